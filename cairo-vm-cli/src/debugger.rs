@@ -0,0 +1,604 @@
+//! Interactive step debugger for Cairo 0 programs
+//!
+//! Drives the [CairoRunner] one instruction at a time and reads commands from an input stream,
+//! which allows stopping the execution at given pcs, source lines or functions and inspecting
+//! the registers and memory of the VM at each stop.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+
+use cairo_vm::cairo_run::{finalize_cairo_run, initialize_cairo_run, CairoRunConfig};
+use cairo_vm::hint_processor::hint_processor_definition::HintProcessor;
+use cairo_vm::serde::deserialize_program::InstructionLocation;
+use cairo_vm::types::exec_scope::ExecutionScopes;
+use cairo_vm::types::program::Program;
+use cairo_vm::types::relocatable::Relocatable;
+use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
+use cairo_vm::vm::errors::vm_exception::{get_location, VmException};
+use cairo_vm::vm::runners::cairo_runner::{CairoRunner, Execution, Progress};
+
+use crate::Error;
+
+const HELP: &str = "\
+Commands:
+  break <target>, b <target>   Set a breakpoint. <target> can be a pc offset (12), a pc (0:12),
+                               a source location (file.cairo:7) or a function/label name (main)
+  delete <id>, d <id>          Remove a breakpoint
+  breakpoints                  List the breakpoints
+  step [n], s [n]              Execute the next n instructions (default 1)
+  continue, c                  Run until a breakpoint is hit or the program ends
+  registers, regs              Print the pc, ap and fp registers
+  memory <addr> [n], x <addr> [n]
+                               Print n memory cells (default 1) starting at <addr>. <addr> can
+                               be a relocatable address (1:5) or a register with an optional
+                               offset (ap, fp-3, ap+1)
+  help, h                      Print this message
+  quit, q                      Abort the execution";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AddressBase {
+    Ap,
+    Fp,
+    Absolute(Relocatable),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Address {
+    base: AddressBase,
+    offset: i32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Break(String),
+    Delete(usize),
+    Breakpoints,
+    Step(usize),
+    Continue,
+    Registers,
+    Memory(Address, usize),
+    Help,
+    Quit,
+}
+
+struct Breakpoint {
+    id: usize,
+    target: String,
+    pcs: Vec<Relocatable>,
+}
+
+/// The state of a debugging session: the set breakpoints and the debug information of the
+/// program used to resolve them.
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    next_breakpoint_id: usize,
+    instruction_locations: HashMap<usize, InstructionLocation>,
+    program_base: Relocatable,
+}
+
+/// The outcome of executing instructions in the debugger
+#[derive(Debug, PartialEq, Eq)]
+enum StopReason {
+    Step,
+    Breakpoint(usize),
+    Finished,
+}
+
+/// Runs a program like [cairo_run](cairo_vm::cairo_run::cairo_run), but stops before the first
+/// instruction and lets the user drive the execution through the commands read from `input`.
+///
+/// Returns `None` if the user aborted the execution.
+pub fn cairo_run_debug(
+    program_content: &[u8],
+    cairo_run_config: &CairoRunConfig,
+    hint_processor: &mut dyn HintProcessor,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> Result<Option<CairoRunner>, Error> {
+    let program = Program::from_bytes(program_content, Some(cairo_run_config.entrypoint))
+        .map_err(CairoRunError::from)?;
+    let (mut cairo_runner, end) =
+        initialize_cairo_run(&program, cairo_run_config, ExecutionScopes::new())?;
+
+    let mut execution = cairo_runner
+        .start_execution(end, hint_processor)
+        .map_err(|err| CairoRunError::from(VmException::from_vm_error(&cairo_runner, err)))?;
+    let mut debugger = Debugger::new(&cairo_runner);
+    if !debugger.run(
+        &mut cairo_runner,
+        &mut execution,
+        hint_processor,
+        input,
+        output,
+    )? {
+        return Ok(None);
+    }
+
+    finalize_cairo_run(&mut cairo_runner, cairo_run_config, hint_processor)?;
+
+    Ok(Some(cairo_runner))
+}
+
+impl Debugger {
+    pub fn new(cairo_runner: &CairoRunner) -> Self {
+        let program_base = cairo_runner.program_base.unwrap_or_default();
+        // Relocating with a zero base keeps the pcs relative to the program segment
+        let instruction_locations = cairo_runner
+            .get_program()
+            .get_relocated_instruction_locations(&[0])
+            .unwrap_or_default();
+        Debugger {
+            breakpoints: Vec::new(),
+            next_breakpoint_id: 1,
+            instruction_locations,
+            program_base,
+        }
+    }
+
+    /// Runs the command loop until the program reaches the end of `execution`.
+    /// Returns false if the user quit before the end of the program.
+    pub fn run(
+        &mut self,
+        cairo_runner: &mut CairoRunner,
+        execution: &mut Execution,
+        hint_processor: &mut dyn HintProcessor,
+        input: &mut dyn BufRead,
+        output: &mut dyn Write,
+    ) -> Result<bool, Error> {
+        self.print_stop(cairo_runner, &StopReason::Step, output)?;
+        let mut line = String::new();
+        loop {
+            write!(output, "(cairo-dbg) ")?;
+            output.flush()?;
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                // Treat the end of the input as a quit command
+                return Ok(false);
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let command = match parse_command(&line) {
+                Ok(command) => command,
+                Err(msg) => {
+                    writeln!(output, "{msg}")?;
+                    continue;
+                }
+            };
+            let stop = match command {
+                Command::Break(target) => {
+                    match self.add_breakpoint(cairo_runner.get_program(), &target) {
+                        Ok(id) => writeln!(output, "Breakpoint {id} set at {target}")?,
+                        Err(msg) => writeln!(output, "{msg}")?,
+                    }
+                    continue;
+                }
+                Command::Delete(id) => {
+                    let len = self.breakpoints.len();
+                    self.breakpoints.retain(|b| b.id != id);
+                    if self.breakpoints.len() == len {
+                        writeln!(output, "No breakpoint number {id}")?;
+                    }
+                    continue;
+                }
+                Command::Breakpoints => {
+                    if self.breakpoints.is_empty() {
+                        writeln!(output, "No breakpoints")?;
+                    }
+                    for breakpoint in self.breakpoints.iter() {
+                        let pcs: Vec<_> = breakpoint.pcs.iter().map(|pc| pc.to_string()).collect();
+                        writeln!(
+                            output,
+                            "{}: {} (pc={})",
+                            breakpoint.id,
+                            breakpoint.target,
+                            pcs.join(", ")
+                        )?;
+                    }
+                    continue;
+                }
+                Command::Registers => {
+                    writeln!(
+                        output,
+                        "pc={} ap={} fp={}",
+                        cairo_runner.vm.get_pc(),
+                        cairo_runner.vm.get_ap(),
+                        cairo_runner.vm.get_fp()
+                    )?;
+                    continue;
+                }
+                Command::Memory(address, len) => {
+                    self.print_memory(cairo_runner, address, len, output)?;
+                    continue;
+                }
+                Command::Help => {
+                    writeln!(output, "{HELP}")?;
+                    continue;
+                }
+                Command::Quit => return Ok(false),
+                Command::Step(n) => {
+                    self.execute(cairo_runner, execution, hint_processor, Some(n))?
+                }
+                Command::Continue => self.execute(cairo_runner, execution, hint_processor, None)?,
+            };
+            self.print_stop(cairo_runner, &stop, output)?;
+            if stop == StopReason::Finished {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Executes `steps` instructions, or until a breakpoint is hit if `steps` is None.
+    /// The execution always stops at its end.
+    fn execute(
+        &self,
+        cairo_runner: &mut CairoRunner,
+        execution: &mut Execution,
+        hint_processor: &mut dyn HintProcessor,
+        steps: Option<usize>,
+    ) -> Result<StopReason, Error> {
+        let breakpoints: HashSet<Relocatable> = self
+            .breakpoints
+            .iter()
+            .flat_map(|b| b.pcs.iter().copied())
+            .collect();
+        let mut remaining_steps = steps;
+        loop {
+            if cairo_runner.vm.get_pc() == execution.end() {
+                return Ok(StopReason::Finished);
+            }
+            if remaining_steps == Some(0) {
+                return Ok(StopReason::Step);
+            }
+            let progress = cairo_runner
+                .resume_execution(execution, 1, hint_processor)
+                .map_err(|err| {
+                    CairoRunError::from(VmException::from_vm_error(cairo_runner, err))
+                })?;
+            remaining_steps = remaining_steps.map(|n| n - 1);
+            let pc = cairo_runner.vm.get_pc();
+            if remaining_steps.is_none()
+                && breakpoints.contains(&pc)
+                && progress == Progress::Running
+            {
+                let id = self
+                    .breakpoints
+                    .iter()
+                    .find(|b| b.pcs.contains(&pc))
+                    .map(|b| b.id)
+                    .unwrap_or_default();
+                return Ok(StopReason::Breakpoint(id));
+            }
+        }
+    }
+
+    fn add_breakpoint(&mut self, program: &Program, target: &str) -> Result<usize, String> {
+        let pcs = self
+            .resolve_target(program, target)?
+            .into_iter()
+            .map(|offset| (self.program_base + offset).map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let id = self.next_breakpoint_id;
+        self.next_breakpoint_id += 1;
+        self.breakpoints.push(Breakpoint {
+            id,
+            target: target.to_string(),
+            pcs,
+        });
+        Ok(id)
+    }
+
+    /// Returns the program segment offsets that correspond to a breakpoint target
+    fn resolve_target(&self, program: &Program, target: &str) -> Result<Vec<usize>, String> {
+        if let Ok(offset) = target.parse::<usize>() {
+            return Ok(vec![offset]);
+        }
+        if let Some(pc) = parse_relocatable(target) {
+            if pc.segment_index != self.program_base.segment_index {
+                return Err(format!("{target} is not in the program segment"));
+            }
+            return Ok(vec![pc.offset]);
+        }
+        if let Some((file, line)) = target
+            .rsplit_once(':')
+            .and_then(|(file, line)| Some((file, line.parse::<u32>().ok()?)))
+        {
+            return self.resolve_source_location(file, line);
+        }
+        let mut pcs: Vec<usize> = program
+            .iter_identifiers()
+            .filter(|(name, identifier)| {
+                matches!(
+                    identifier.type_.as_deref(),
+                    Some("function") | Some("label")
+                ) && (*name == target || name.ends_with(&format!(".{target}")))
+            })
+            .filter_map(|(_, identifier)| identifier.pc)
+            .collect();
+        if pcs.is_empty() {
+            return Err(format!("No function or label named {target}"));
+        }
+        pcs.sort();
+        pcs.dedup();
+        Ok(pcs)
+    }
+
+    /// Returns the first pc of each block of consecutive instructions located at `file:line`
+    fn resolve_source_location(&self, file: &str, line: u32) -> Result<Vec<usize>, String> {
+        if self.instruction_locations.is_empty() {
+            return Err("The program has no debug information".to_string());
+        }
+        let at_line = |pc: &usize| {
+            self.instruction_locations.get(pc).is_some_and(|location| {
+                let filename = &location.inst.input_file.filename;
+                location.inst.start_line == line
+                    && (filename == file || filename.ends_with(&format!("/{file}")))
+            })
+        };
+        let mut all_pcs: Vec<usize> = self.instruction_locations.keys().copied().collect();
+        all_pcs.sort();
+        let pcs: Vec<usize> = all_pcs
+            .iter()
+            .enumerate()
+            .filter(|(i, pc)| at_line(pc) && (*i == 0 || !at_line(&all_pcs[i - 1])))
+            .map(|(_, pc)| *pc)
+            .collect();
+        if pcs.is_empty() {
+            return Err(format!("No instructions found at {file}:{line}"));
+        }
+        Ok(pcs)
+    }
+
+    fn print_stop(
+        &self,
+        cairo_runner: &CairoRunner,
+        reason: &StopReason,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        let pc = cairo_runner.vm.get_pc();
+        match reason {
+            StopReason::Finished => {
+                writeln!(
+                    output,
+                    "Program finished after {} steps",
+                    cairo_runner.vm.get_current_step()
+                )?;
+                return Ok(());
+            }
+            StopReason::Breakpoint(id) => writeln!(output, "Breakpoint {id} hit")?,
+            StopReason::Step => {}
+        }
+        writeln!(
+            output,
+            "Stopped at pc={pc} (step {})",
+            cairo_runner.vm.get_current_step()
+        )?;
+        if pc.segment_index == 0 {
+            if let Some(location) = get_location(pc.offset, cairo_runner, None) {
+                writeln!(output, "{}", location.to_string_with_content(""))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn print_memory(
+        &self,
+        cairo_runner: &CairoRunner,
+        address: Address,
+        len: usize,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        let base = match address.base {
+            AddressBase::Ap => cairo_runner.vm.get_ap(),
+            AddressBase::Fp => cairo_runner.vm.get_fp(),
+            AddressBase::Absolute(addr) => addr,
+        };
+        let start = match base + address.offset {
+            Ok(start) => start,
+            Err(err) => {
+                writeln!(output, "{err}")?;
+                return Ok(());
+            }
+        };
+        for i in 0..len {
+            let Ok(addr) = start + i else {
+                break;
+            };
+            match cairo_runner.vm.get_maybe(&addr) {
+                Some(value) => writeln!(output, "{addr}: {value}")?,
+                None => writeln!(output, "{addr}: <unknown>")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let args: Vec<&str> = words.collect();
+    let parse_count = |arg: Option<&&str>| match arg {
+        Some(n) => n
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid number: {n}")),
+        None => Ok(1),
+    };
+    let command = match (name, args.as_slice()) {
+        ("break" | "b", [target]) => Command::Break(target.to_string()),
+        ("delete" | "d", [id]) => Command::Delete(
+            id.parse()
+                .map_err(|_| format!("Invalid breakpoint: {id}"))?,
+        ),
+        ("breakpoints", []) => Command::Breakpoints,
+        ("step" | "s", [] | [_]) => Command::Step(parse_count(args.first())?),
+        ("continue" | "c", []) => Command::Continue,
+        ("registers" | "regs", []) => Command::Registers,
+        ("memory" | "x", [address, ..]) if args.len() <= 2 => {
+            Command::Memory(parse_address(address)?, parse_count(args.get(1))?)
+        }
+        ("help" | "h", []) => Command::Help,
+        ("quit" | "q", []) => Command::Quit,
+        _ => {
+            return Err(format!(
+                "Invalid command: {}. Type `help` for a list of commands",
+                line.trim()
+            ))
+        }
+    };
+    Ok(command)
+}
+
+fn parse_relocatable(s: &str) -> Option<Relocatable> {
+    let (segment_index, offset) = s.split_once(':')?;
+    Some(Relocatable::from((
+        segment_index.parse::<isize>().ok()?,
+        offset.parse::<usize>().ok()?,
+    )))
+}
+
+fn parse_address(s: &str) -> Result<Address, String> {
+    if let Some(addr) = parse_relocatable(s) {
+        return Ok(Address {
+            base: AddressBase::Absolute(addr),
+            offset: 0,
+        });
+    }
+    let (base, rest) = match s.get(..2) {
+        Some("ap") => (AddressBase::Ap, &s[2..]),
+        Some("fp") => (AddressBase::Fp, &s[2..]),
+        _ => return Err(format!("Invalid address: {s}")),
+    };
+    let offset = if rest.is_empty() {
+        Some(0)
+    } else if let Some(n) = rest.strip_prefix('+') {
+        n.parse::<i32>().ok()
+    } else if rest.starts_with('-') {
+        rest.parse::<i32>().ok()
+    } else {
+        None
+    }
+    .ok_or_else(|| format!("Invalid address: {s}"))?;
+    Ok(Address { base, offset })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+    use cairo_vm::types::layout_name::LayoutName;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("b 12", Command::Break("12".to_string()))]
+    #[case("break fibonacci.cairo:11", Command::Break("fibonacci.cairo:11".to_string()))]
+    #[case("delete 2", Command::Delete(2))]
+    #[case("s", Command::Step(1))]
+    #[case("step 10", Command::Step(10))]
+    #[case("c", Command::Continue)]
+    #[case("regs", Command::Registers)]
+    #[case("x ap-3 3", Command::Memory(Address { base: AddressBase::Ap, offset: -3 }, 3))]
+    #[case("memory fp+1", Command::Memory(Address { base: AddressBase::Fp, offset: 1 }, 1))]
+    #[case("x 1:4 2", Command::Memory(Address { base: AddressBase::Absolute((1, 4).into()), offset: 0 }, 2))]
+    #[case("q", Command::Quit)]
+    fn parse_valid_commands(#[case] line: &str, #[case] expected: Command) {
+        assert_eq!(parse_command(line), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("break")]
+    #[case("step two")]
+    #[case("x ap*2")]
+    #[case("x sp")]
+    #[case("unknown")]
+    fn parse_invalid_commands(#[case] line: &str) {
+        assert_matches!(parse_command(line), Err(_));
+    }
+
+    fn run_debug_session(commands: &str, proof_mode: bool) -> (Option<CairoRunner>, String) {
+        let program_path = if proof_mode {
+            "../cairo_programs/proof_programs/fibonacci.json"
+        } else {
+            "../cairo_programs/fibonacci.json"
+        };
+        let program_content = std::fs::read(program_path).unwrap();
+        let cairo_run_config = CairoRunConfig {
+            proof_mode,
+            relocate_mem: true,
+            trace_enabled: true,
+            ..Default::default()
+        };
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut output = Vec::new();
+        let runner = cairo_run_debug(
+            &program_content,
+            &cairo_run_config,
+            &mut hint_processor,
+            &mut commands.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        (runner, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn debug_continue_until_end() {
+        let (runner, output) = run_debug_session("c\n", false);
+        assert!(runner.is_some());
+        assert!(output.contains("Program finished"));
+    }
+
+    #[test]
+    fn debug_break_at_function() {
+        let (runner, output) = run_debug_session("b fib\nc\nc\nd 1\nc\n", false);
+        assert!(runner.is_some());
+        assert_eq!(output.matches("Breakpoint 1 hit").count(), 2);
+        assert!(output.contains("fibonacci.cairo:11"));
+    }
+
+    #[test]
+    fn debug_break_at_source_line_and_pc() {
+        let (_, output) = run_debug_session("b fibonacci.cairo:16\nb 0\nbreakpoints\n", false);
+        assert!(output.contains("Breakpoint 1 set at fibonacci.cairo:16"));
+        assert!(output.contains("Breakpoint 2 set at 0"));
+        assert!(output.contains("2: 0 (pc=0:0)"));
+    }
+
+    #[test]
+    fn debug_step_and_inspect() {
+        let (runner, output) = run_debug_session("s 3\nregs\nx fp-2 2\nc\n", true);
+        assert!(runner.is_some());
+        assert!(output.contains("(step 3)"));
+        assert!(output.contains("pc="));
+        assert!(output.contains("ap="));
+    }
+
+    #[test]
+    fn debug_run_is_set_up_like_cairo_run() {
+        let program_content =
+            std::fs::read("../cairo_programs/manually_compiled/valid_program_b.json").unwrap();
+        let cairo_run_config = CairoRunConfig {
+            layout: LayoutName::all_cairo,
+            coverage: true,
+            ..Default::default()
+        };
+        let runner = cairo_run_debug(
+            &program_content,
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+            &mut "c\n".as_bytes(),
+            &mut Vec::new(),
+        )
+        .unwrap()
+        .unwrap();
+        // Coverage needs the trace, even though it wasn't enabled
+        assert!(runner.relocated_trace.is_some());
+    }
+
+    #[test]
+    fn debug_quit() {
+        let (runner, output) = run_debug_session("b unknown_function\nq\n", false);
+        assert!(runner.is_none());
+        assert!(output.contains("No function or label named unknown_function"));
+    }
+}
@@ -0,0 +1,261 @@
+//! Inspection of the binary trace and memory files written by a run
+//!
+//! Prints ranges of the trace and memory, as text or JSON, so that the files fed to the prover can
+//! be checked and diffed. The pcs of the trace can be resolved to their source location through
+//! the compiled program.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+
+use cairo_vm::cairo_run::{read_encoded_memory, read_encoded_trace};
+use cairo_vm::serde::deserialize_program::InstructionLocation;
+use cairo_vm::types::program::Program;
+use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
+use cairo_vm::Felt252;
+use serde::Serialize;
+
+use crate::Error;
+
+// The program segment is the first one, relocated right after the reserved address 0
+const PROGRAM_BASE: usize = 1;
+
+#[derive(Debug, Serialize)]
+struct TraceStep {
+    step: usize,
+    pc: usize,
+    ap: usize,
+    fp: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MemoryCell {
+    address: usize,
+    value: Felt252,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Inspection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace: Option<Vec<TraceStep>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<Vec<MemoryCell>>,
+}
+
+/// Files and ranges to inspect
+pub(crate) struct InspectArgs<'a> {
+    pub trace_file: Option<&'a Path>,
+    pub memory_file: Option<&'a Path>,
+    pub program_file: Option<&'a Path>,
+    pub steps: Range<usize>,
+    pub addresses: Range<usize>,
+    pub json: bool,
+}
+
+/// Parses a range of steps or addresses: `start..end`, `start..`, `..end` or a single `index`
+pub(crate) fn parse_range(value: &str) -> Result<Range<usize>, String> {
+    let parse_index = |index: &str| {
+        index
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid index: {index}"))
+    };
+    // Missing bounds default to the start and end of the file
+    let parse_bound = |bound: &str, default: usize| match bound {
+        "" => Ok(default),
+        _ => parse_index(bound),
+    };
+    match value.split_once("..") {
+        Some((start, end)) => Ok(parse_bound(start, 0)?..parse_bound(end, usize::MAX)?),
+        None => {
+            let index = parse_index(value)?;
+            Ok(index..index.saturating_add(1))
+        }
+    }
+}
+
+pub(crate) fn inspect(args: &InspectArgs, output: &mut dyn Write) -> Result<(), Error> {
+    let locations = match args.program_file {
+        Some(program_file) => {
+            let program_content = std::fs::read(program_file)?;
+            let program =
+                Program::from_bytes(&program_content, None).map_err(CairoRunError::from)?;
+            program.get_relocated_instruction_locations(&[PROGRAM_BASE])
+        }
+        None => None,
+    };
+
+    let mut inspection = Inspection::default();
+    if let Some(trace_file) = args.trace_file {
+        let trace = read_encoded_trace(&std::fs::read(trace_file)?)?;
+        let steps = clamp(&args.steps, trace.len());
+        inspection.trace = Some(
+            trace[steps.clone()]
+                .iter()
+                .zip(steps)
+                .map(|(entry, step)| TraceStep {
+                    step,
+                    pc: entry.pc,
+                    ap: entry.ap,
+                    fp: entry.fp,
+                    location: source_location(locations.as_ref(), entry.pc),
+                })
+                .collect(),
+        );
+    }
+    if let Some(memory_file) = args.memory_file {
+        let memory = read_encoded_memory(&std::fs::read(memory_file)?, None)?;
+        let addresses = clamp(&args.addresses, memory.len());
+        inspection.memory = Some(
+            memory[addresses.clone()]
+                .iter()
+                .zip(addresses)
+                .filter_map(|(value, address)| value.map(|value| MemoryCell { address, value }))
+                .collect(),
+        );
+    }
+
+    if args.json {
+        serde_json::to_writer_pretty(&mut *output, &inspection).map_err(std::io::Error::from)?;
+        writeln!(output)?;
+    } else {
+        print_inspection(&inspection, output)?;
+    }
+    Ok(())
+}
+
+fn clamp(range: &Range<usize>, len: usize) -> Range<usize> {
+    let end = range.end.min(len);
+    range.start.min(end)..end
+}
+
+fn source_location(
+    locations: Option<&HashMap<usize, InstructionLocation>>,
+    pc: usize,
+) -> Option<String> {
+    locations?
+        .get(&pc)
+        .map(|location| location.inst.to_string(""))
+}
+
+fn print_inspection(inspection: &Inspection, output: &mut dyn Write) -> Result<(), Error> {
+    if let Some(ref trace) = inspection.trace {
+        writeln!(output, "Trace:")?;
+        for step in trace {
+            write!(
+                output,
+                "  step {}: pc={} ap={} fp={}",
+                step.step, step.pc, step.ap, step.fp
+            )?;
+            match step.location {
+                Some(ref location) => writeln!(output, "  {location}")?,
+                None => writeln!(output)?,
+            }
+        }
+    }
+    if let Some(ref memory) = inspection.memory {
+        writeln!(output, "Memory:")?;
+        for cell in memory {
+            writeln!(output, "  {}: {}", cell.address, cell.value)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("3..10", 3..10)]
+    #[case("3..", 3..usize::MAX)]
+    #[case("..10", 0..10)]
+    #[case("..", 0..usize::MAX)]
+    #[case("7", 7..8)]
+    fn parse_valid_ranges(#[case] value: &str, #[case] expected: Range<usize>) {
+        assert_eq!(parse_range(value), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("a..3")]
+    #[case("1..-2")]
+    #[case("")]
+    fn parse_invalid_ranges(#[case] value: &str) {
+        assert_matches!(parse_range(value), Err(_));
+    }
+
+    fn run_inspect(steps: Range<usize>, addresses: Range<usize>, json: bool) -> String {
+        let args = InspectArgs {
+            trace_file: Some(Path::new(
+                "../cairo_programs/trace_memory/cairo_trace_struct",
+            )),
+            memory_file: Some(Path::new(
+                "../cairo_programs/trace_memory/cairo_memory_struct",
+            )),
+            program_file: None,
+            steps,
+            addresses,
+            json,
+        };
+        let mut output = Vec::new();
+        inspect(&args, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn inspect_trace_and_memory() {
+        assert_eq!(
+            run_inspect(0..usize::MAX, 0..usize::MAX, false),
+            "\
+Trace:
+  step 0: pc=1 ap=4 fp=4
+Memory:
+  1: 2345108766317314046
+  2: 4
+  3: 4
+"
+        );
+    }
+
+    #[test]
+    fn inspect_ranges() {
+        assert_eq!(
+            run_inspect(1..5, 2..3, false),
+            "\
+Trace:
+Memory:
+  2: 4
+"
+        );
+    }
+
+    #[test]
+    fn inspect_json() {
+        let output: serde_json::Value =
+            serde_json::from_str(&run_inspect(0..usize::MAX, 3..usize::MAX, true)).unwrap();
+        assert_eq!(
+            output,
+            serde_json::json!({
+                "trace": [{ "step": 0, "pc": 1, "ap": 4, "fp": 4 }],
+                "memory": [{ "address": 3, "value": "0x4" }],
+            })
+        );
+    }
+
+    #[test]
+    fn inspect_missing_file() {
+        let args = InspectArgs {
+            trace_file: Some(Path::new("../missing/trace")),
+            memory_file: None,
+            program_file: None,
+            steps: 0..usize::MAX,
+            addresses: 0..usize::MAX,
+            json: false,
+        };
+        assert_matches!(inspect(&args, &mut Vec::new()), Err(Error::IO(_)));
+    }
+}
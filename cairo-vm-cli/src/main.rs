@@ -2,11 +2,16 @@
 #![forbid(unsafe_code)]
 use bincode::enc::write::Writer;
 use cairo_vm::air_public_input::PublicInputError;
-use cairo_vm::cairo_run::{self, EncodeTraceError};
+use cairo_vm::cairo_run::{self, DecodeTraceError, EncodeTraceError};
+use cairo_vm::coverage::{Coverage, CoverageError};
 use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+use cairo_vm::hint_processor::hint_support::check_hints;
 #[cfg(feature = "with_tracer")]
 use cairo_vm::serde::deserialize_program::DebugInfo;
+use cairo_vm::types::layout::CairoLayoutParams;
 use cairo_vm::types::layout_name::LayoutName;
+use cairo_vm::types::program::Program;
+use cairo_vm::vm::decoding::disassembler::disassemble;
 use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
 use cairo_vm::vm::errors::trace_errors::TraceError;
 use cairo_vm::vm::errors::vm_errors::VirtualMachineError;
@@ -14,27 +19,41 @@ use cairo_vm::vm::runners::cairo_pie::CairoPie;
 #[cfg(feature = "with_tracer")]
 use cairo_vm::vm::runners::cairo_runner::CairoRunner;
 use cairo_vm::vm::runners::cairo_runner::RunResources;
+use cairo_vm::vm::runners::layout_selection;
 #[cfg(feature = "with_tracer")]
 use cairo_vm_tracer::error::trace_data_errors::TraceDataError;
 #[cfg(feature = "with_tracer")]
 use cairo_vm_tracer::tracer::run_tracer;
-use clap::{Parser, ValueHint};
+use clap::{Parser, Subcommand, ValueEnum, ValueHint};
 use std::io::{self, Write};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[cfg(feature = "with_mimalloc")]
 use mimalloc::MiMalloc;
 
+mod debugger;
+mod inspect;
+
 #[cfg(feature = "with_mimalloc")]
 #[global_allocator]
 static ALLOC: MiMalloc = MiMalloc;
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
-    #[clap(value_parser, value_hint=ValueHint::FilePath)]
-    filename: PathBuf,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(value_parser, value_hint=ValueHint::FilePath, required = true)]
+    filename: Option<PathBuf>,
     #[clap(long = "trace_file", value_parser)]
     trace_file: Option<PathBuf>,
     #[structopt(long = "print_output")]
@@ -43,8 +62,10 @@ struct Args {
     entrypoint: String,
     #[structopt(long = "memory_file")]
     memory_file: Option<PathBuf>,
-    #[clap(long = "layout", default_value = "plain", value_enum)]
-    layout: LayoutName,
+    #[clap(long = "layout", default_value = "plain", value_parser = parse_layout)]
+    layout: LayoutArg,
+    #[clap(long = "cairo_layout_params_file", required_if_eq("layout", "dynamic"))]
+    cairo_layout_params_file: Option<PathBuf>,
     #[structopt(long = "proof_mode")]
     proof_mode: bool,
     #[structopt(long = "secure_run")]
@@ -75,6 +96,64 @@ struct Args {
         conflicts_with_all = ["proof_mode", "air_private_input", "air_public_input"]
     )]
     run_from_cairo_pie: bool,
+    #[structopt(long = "debug", conflicts_with = "run_from_cairo_pie")]
+    debug: bool,
+    #[clap(long = "profile_output", value_parser, conflicts_with = "debug")]
+    profile_output: Option<PathBuf>,
+    #[clap(long = "hint_profile", value_parser, conflicts_with = "debug")]
+    hint_profile: Option<usize>,
+    #[clap(long = "hint_log", value_parser, conflicts_with = "debug")]
+    hint_log: Option<PathBuf>,
+    #[clap(long = "coverage_file", value_parser)]
+    coverage_file: Option<PathBuf>,
+    #[structopt(long = "check_hints", conflicts_with = "run_from_cairo_pie")]
+    check_hints: bool,
+}
+
+/// A layout name, or `auto` to pick the cheapest layout the program fits in
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LayoutArg {
+    Auto,
+    Name(LayoutName),
+}
+
+fn parse_layout(value: &str) -> Result<LayoutArg, String> {
+    match value {
+        "auto" => Ok(LayoutArg::Auto),
+        _ => LayoutName::from_str(value, false).map(LayoutArg::Name),
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the instructions of a compiled program as CASM
+    Disasm {
+        #[clap(value_parser, value_hint=ValueHint::FilePath)]
+        filename: PathBuf,
+    },
+    /// Print ranges of the binary trace and memory files written by a run
+    Inspect {
+        #[clap(
+            long = "trace_file",
+            value_parser,
+            required_unless_present = "memory_file"
+        )]
+        trace_file: Option<PathBuf>,
+        #[clap(long = "memory_file", value_parser)]
+        memory_file: Option<PathBuf>,
+        /// Compiled program the files were produced by, to resolve the pcs of the trace to their source location
+        #[clap(long = "program", value_parser, value_hint=ValueHint::FilePath)]
+        program: Option<PathBuf>,
+        /// Steps of the trace to print, such as `10..20`, `10..` or `10`
+        #[clap(long = "steps", value_parser = inspect::parse_range, default_value = "..")]
+        steps: Range<usize>,
+        /// Memory addresses to print, such as `10..20`, `10..` or `10`
+        #[clap(long = "addresses", value_parser = inspect::parse_range, default_value = "..")]
+        addresses: Range<usize>,
+        /// Print as JSON
+        #[clap(long = "json")]
+        json: bool,
+    },
 }
 
 #[derive(Debug, Error)]
@@ -88,12 +167,20 @@ enum Error {
     #[error(transparent)]
     EncodeTrace(#[from] EncodeTraceError),
     #[error(transparent)]
+    DecodeTrace(#[from] DecodeTraceError),
+    #[error(transparent)]
     VirtualMachine(#[from] VirtualMachineError),
     #[error(transparent)]
     Trace(#[from] TraceError),
     #[error(transparent)]
     PublicInput(#[from] PublicInputError),
     #[error(transparent)]
+    Coverage(#[from] CoverageError),
+    #[error("The program has {0} unsupported hints")]
+    UnsupportedHints(usize),
+    #[error("The program doesn't fit in any layout")]
+    NoFittingLayout,
+    #[error(transparent)]
     #[cfg(feature = "with_tracer")]
     TraceData(#[from] TraceDataError),
 }
@@ -157,31 +244,181 @@ fn start_tracer(cairo_runner: &CairoRunner) -> Result<(), TraceDataError> {
     Ok(())
 }
 
+// Adds up the coverage with the one already in the file, if any, so that it accumulates over many runs
+fn write_coverage(coverage: &Coverage, path: &Path) -> Result<(), Error> {
+    let mut total = match std::fs::read_to_string(path) {
+        Ok(lcov) => Coverage::from_lcov(&lcov)?,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Coverage::new(),
+        Err(error) => return Err(error.into()),
+    };
+    total.merge(coverage);
+    std::fs::write(path, total.to_lcov())?;
+    Ok(())
+}
+
+// Picks the cheapest layout for the program out of the resources used by an execution mode run
+// with the all_cairo layout, or the ones recorded in the Cairo PIE
+fn select_layout(
+    args: &Args,
+    filename: &Path,
+    dynamic_layout_params: Option<&CairoLayoutParams>,
+) -> Result<LayoutName, Error> {
+    let selection = if args.run_from_cairo_pie {
+        let pie = CairoPie::read_zip_file(filename)?;
+        layout_selection::select_layout(
+            &pie.execution_resources,
+            &pie.metadata.program.builtins,
+            pie.get_perm_range_check_limits(),
+            dynamic_layout_params,
+        )
+    } else {
+        let program_content = std::fs::read(filename)?;
+        let cairo_run_config = cairo_run::CairoRunConfig {
+            entrypoint: &args.entrypoint,
+            layout: LayoutName::all_cairo,
+            secure_run: args.secure_run,
+            allow_missing_builtins: args.allow_missing_builtins,
+            ..Default::default()
+        };
+        let cairo_runner = cairo_run::cairo_run(
+            &program_content,
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )?;
+        cairo_runner.select_layout(dynamic_layout_params)
+    }
+    .map_err(CairoRunError::from)?;
+
+    for fit in selection.fits.iter() {
+        match fit.trace_length {
+            Some(trace_length) => eprintln!(
+                "{}: {trace_length} steps, {} units per step",
+                fit.layout, fit.units_per_step
+            ),
+            None => eprintln!("{}: doesn't fit", fit.layout),
+        }
+    }
+    let layout = selection.best().ok_or(Error::NoFittingLayout)?.layout;
+    eprintln!("Selected layout: {layout}");
+    Ok(layout)
+}
+
+fn run_command(command: Command) -> Result<(), Error> {
+    match command {
+        Command::Disasm { filename } => {
+            let program_content = std::fs::read(filename)?;
+            let program =
+                Program::from_bytes(&program_content, None).map_err(CairoRunError::from)?;
+            print!("{}", disassemble(&program));
+        }
+        Command::Inspect {
+            trace_file,
+            memory_file,
+            program,
+            steps,
+            addresses,
+            json,
+        } => {
+            let args = inspect::InspectArgs {
+                trace_file: trace_file.as_deref(),
+                memory_file: memory_file.as_deref(),
+                program_file: program.as_deref(),
+                steps,
+                addresses,
+                json,
+            };
+            inspect::inspect(&args, &mut io::stdout().lock())?;
+        }
+    }
+    Ok(())
+}
+
 fn run(args: impl Iterator<Item = String>) -> Result<(), Error> {
-    let args = Args::try_parse_from(args)?;
+    let mut args = Args::try_parse_from(args)?;
+    let filename = match (args.command.take(), args.filename.take()) {
+        (Some(command), _) => return run_command(command),
+        (None, Some(filename)) => filename,
+        (None, None) => unreachable!("the filename is required without a subcommand"),
+    };
+
+    if args.check_hints {
+        let program_content = std::fs::read(&filename)?;
+        let program = Program::from_bytes(&program_content, Some(&args.entrypoint))
+            .map_err(CairoRunError::from)?;
+        let unsupported = check_hints(&program, &BuiltinHintProcessor::new_empty());
+        if !unsupported.is_empty() {
+            for hint in unsupported.iter() {
+                eprintln!("{hint}\n");
+            }
+            return Err(Error::UnsupportedHints(unsupported.len()));
+        }
+    }
 
     let trace_enabled = args.trace_file.is_some() || args.air_public_input.is_some();
 
+    // The air public input and the tracer need the whole trace in memory
+    #[cfg(not(feature = "with_tracer"))]
+    let stream_trace = args.trace_file.is_some() && args.air_public_input.is_none();
+    #[cfg(feature = "with_tracer")]
+    let stream_trace = args.trace_file.is_some() && args.air_public_input.is_none() && !args.tracer;
+
+    let dynamic_layout_params = match args.cairo_layout_params_file {
+        Some(ref file)
+            if matches!(
+                args.layout,
+                LayoutArg::Auto | LayoutArg::Name(LayoutName::dynamic)
+            ) =>
+        {
+            Some(CairoLayoutParams::from_file(file)?)
+        }
+        _ => None,
+    };
+
+    let layout = match args.layout {
+        LayoutArg::Name(layout) => layout,
+        LayoutArg::Auto => select_layout(&args, &filename, dynamic_layout_params.as_ref())?,
+    };
+
     let cairo_run_config = cairo_run::CairoRunConfig {
         entrypoint: &args.entrypoint,
         trace_enabled,
+        stream_trace,
+        coverage: args.coverage_file.is_some(),
         relocate_mem: args.memory_file.is_some() || args.air_public_input.is_some(),
-        layout: args.layout,
+        layout,
+        dynamic_layout_params,
         proof_mode: args.proof_mode,
         secure_run: args.secure_run,
         allow_missing_builtins: args.allow_missing_builtins,
+        profiling: args.profile_output.is_some(),
+        hint_profiling: args.hint_profile.is_some(),
+        hint_log_file: args.hint_log.as_deref(),
         ..Default::default()
     };
 
     let mut cairo_runner = match if args.run_from_cairo_pie {
-        let pie = CairoPie::read_zip_file(&args.filename)?;
+        let pie = CairoPie::read_zip_file(&filename)?;
         let mut hint_processor = BuiltinHintProcessor::new(
             Default::default(),
             RunResources::new(pie.execution_resources.n_steps),
         );
         cairo_run::cairo_run_pie(&pie, &cairo_run_config, &mut hint_processor)
+    } else if args.debug {
+        let program_content = std::fs::read(&filename).map_err(Error::IO)?;
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        match debugger::cairo_run_debug(
+            &program_content,
+            &cairo_run_config,
+            &mut hint_processor,
+            &mut io::stdin().lock(),
+            &mut io::stdout(),
+        )? {
+            Some(runner) => Ok(runner),
+            // The execution was aborted by the user
+            None => return Ok(()),
+        }
     } else {
-        let program_content = std::fs::read(args.filename).map_err(Error::IO)?;
+        let program_content = std::fs::read(&filename).map_err(Error::IO)?;
         let mut hint_processor = BuiltinHintProcessor::new_empty();
         cairo_run::cairo_run(&program_content, &cairo_run_config, &mut hint_processor)
     } {
@@ -199,16 +436,16 @@ fn run(args: impl Iterator<Item = String>) -> Result<(), Error> {
     }
 
     if let Some(ref trace_path) = args.trace_file {
-        let relocated_trace = cairo_runner
-            .relocated_trace
-            .as_ref()
-            .ok_or(Error::Trace(TraceError::TraceNotRelocated))?;
-
         let trace_file = std::fs::File::create(trace_path)?;
         let mut trace_writer =
             FileWriter::new(io::BufWriter::with_capacity(3 * 1024 * 1024, trace_file));
 
-        cairo_run::write_encoded_trace(relocated_trace, &mut trace_writer)?;
+        match cairo_runner.relocated_trace {
+            Some(ref relocated_trace) => {
+                cairo_run::write_encoded_trace(relocated_trace, &mut trace_writer)?
+            }
+            None => cairo_runner.write_relocated_trace(&mut trace_writer)?,
+        }
         trace_writer.flush()?;
     }
 
@@ -256,6 +493,22 @@ fn run(args: impl Iterator<Item = String>) -> Result<(), Error> {
         std::fs::write(file_path, json)?;
     }
 
+    if let Some(ref profile_path) = args.profile_output {
+        if let Some(profile) = cairo_runner.get_profile() {
+            profile.write_to_file(profile_path)?;
+        }
+    }
+
+    if let Some(top) = args.hint_profile {
+        if let Some(hint_profiler) = cairo_runner.vm.get_hint_profiler() {
+            print!("{}", hint_profiler.summary(top));
+        }
+    }
+
+    if let Some(ref coverage_path) = args.coverage_file {
+        write_coverage(&cairo_runner.get_coverage()?, coverage_path)?;
+    }
+
     if let Some(ref file_name) = args.cairo_pie_output {
         let file_path = Path::new(file_name);
         cairo_runner
@@ -293,8 +546,145 @@ mod tests {
         assert_matches!(run(args), Err(Error::Cli(_)));
     }
 
+    #[test]
+    fn test_run_disasm() {
+        let args = [
+            "cairo-vm-cli",
+            "disasm",
+            "../cairo_programs/manually_compiled/valid_program_b.json",
+        ];
+        assert_matches!(run(args.into_iter().map(String::from)), Ok(()));
+    }
+
+    #[rstest]
+    #[case(["cairo-vm-cli", "inspect", "--trace_file", "../cairo_programs/trace_memory/cairo_trace_struct"].as_slice())]
+    #[case(["cairo-vm-cli", "inspect", "--memory_file", "../cairo_programs/trace_memory/cairo_memory_struct", "--addresses", "2..", "--json"].as_slice())]
+    #[case(["cairo-vm-cli", "inspect", "--trace_file", "../cairo_programs/trace_memory/cairo_trace_struct", "--memory_file", "../cairo_programs/trace_memory/cairo_memory_struct", "--program", "../cairo_programs/manually_compiled/valid_program_b.json", "--steps", "0"].as_slice())]
+    fn test_run_inspect(#[case] args: &[&str]) {
+        let args = args.iter().cloned().map(String::from);
+        assert_matches!(run(args), Ok(()));
+    }
+
+    #[rstest]
+    #[case(["cairo-vm-cli", "inspect"].as_slice())]
+    #[case(["cairo-vm-cli", "inspect", "--trace_file", "../cairo_programs/trace_memory/cairo_trace_struct", "--steps", "a..b"].as_slice())]
+    fn test_run_inspect_invalid_args(#[case] args: &[&str]) {
+        let args = args.iter().cloned().map(String::from);
+        assert_matches!(run(args), Err(Error::Cli(_)));
+    }
+
+    #[test]
+    fn test_run_inspect_invalid_file() {
+        let args = [
+            "cairo-vm-cli",
+            "inspect",
+            "--memory_file",
+            "../cairo_programs/trace_memory/cairo_trace_struct",
+        ];
+        assert_matches!(
+            run(args.into_iter().map(String::from)),
+            Err(Error::DecodeTrace(DecodeTraceError::MemoryLength(24)))
+        );
+    }
+
+    #[test]
+    fn test_run_coverage_with_trace_file() {
+        let dir = std::env::temp_dir();
+        let trace_path = dir.join(format!("cairo-vm-cli-trace-{}", std::process::id()));
+        let coverage_path = dir.join(format!("cairo-vm-cli-coverage-{}.lcov", std::process::id()));
+        let _ = std::fs::remove_file(&coverage_path);
+        let args = [
+            "cairo-vm-cli",
+            "../cairo_programs/manually_compiled/valid_program_b.json",
+            "--layout",
+            "all_cairo",
+            "--trace_file",
+            trace_path.to_str().unwrap(),
+            "--coverage_file",
+            coverage_path.to_str().unwrap(),
+        ];
+        let result = run(args.into_iter().map(String::from));
+        let lcov = std::fs::read_to_string(&coverage_path);
+        let _ = std::fs::remove_file(&trace_path);
+        let _ = std::fs::remove_file(&coverage_path);
+        assert_matches!(result, Ok(()));
+        // Every executed line has a non-zero count
+        assert!(lcov
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.strip_prefix("DA:"))
+            .any(|line_data| !line_data.ends_with(",0")));
+    }
+
+    #[test]
+    fn test_run_check_hints_supported() {
+        let args = [
+            "cairo-vm-cli",
+            "../cairo_programs/manually_compiled/valid_program_b.json",
+            "--layout",
+            "all_cairo",
+            "--check_hints",
+        ];
+        assert_matches!(run(args.into_iter().map(String::from)), Ok(()));
+    }
+
+    #[test]
+    fn test_run_hint_profile() {
+        let args = [
+            "cairo-vm-cli",
+            "../cairo_programs/manually_compiled/valid_program_b.json",
+            "--layout",
+            "all_cairo",
+            "--hint_profile",
+            "5",
+            "--hint_log",
+            "/dev/null",
+        ];
+        assert_matches!(run(args.into_iter().map(String::from)), Ok(()));
+    }
+
+    #[rstest]
+    #[case(["cairo-vm-cli", "../cairo_programs/manually_compiled/valid_program_b.json", "--layout", "auto"].as_slice())]
+    #[case(["cairo-vm-cli", "../cairo_programs/manually_compiled/valid_program_b.json", "--layout", "auto", "--cairo_layout_params_file", "../vm/src/tests/cairo_layout_params_file.json"].as_slice())]
+    fn test_run_auto_layout(#[case] args: &[&str]) {
+        let args = args.iter().cloned().map(String::from);
+        assert_matches!(run(args), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_layout() {
+        assert_eq!(parse_layout("auto"), Ok(LayoutArg::Auto));
+        assert_eq!(
+            parse_layout("small"),
+            Ok(LayoutArg::Name(LayoutName::small))
+        );
+        assert!(parse_layout("broken_layout").is_err());
+    }
+
+    #[test]
+    fn test_run_check_hints_unsupported() {
+        let args = [
+            "cairo-vm-cli",
+            "../cairo_programs/manually_compiled/valid_program_a.json",
+            "--check_hints",
+        ];
+        assert_matches!(
+            run(args.into_iter().map(String::from)),
+            Err(Error::UnsupportedHints(1))
+        );
+    }
+
+    #[rstest]
+    #[case(["cairo-vm-cli", "disasm"].as_slice())]
+    #[case(["cairo-vm-cli", "disasm", "../cairo_programs/fibonacci.json", "--layout", "all_cairo"].as_slice())]
+    fn test_run_disasm_invalid_args(#[case] args: &[&str]) {
+        let args = args.iter().cloned().map(String::from);
+        assert_matches!(run(args), Err(Error::Cli(_)));
+    }
+
     #[rstest]
     #[case(["cairo-vm-cli", "--layout", "broken_layout", "../cairo_programs/fibonacci.json"].as_slice())]
+    #[case(["cairo-vm-cli", "--layout", "dynamic", "../cairo_programs/fibonacci.json"].as_slice())]
     fn test_run_invalid_args(#[case] args: &[&str]) {
         let args = args.iter().cloned().map(String::from);
         assert_matches!(run(args), Err(Error::Cli(_)));
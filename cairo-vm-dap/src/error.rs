@@ -0,0 +1,14 @@
+use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DapError {
+    #[error("Failed to interact with the client stream")]
+    IO(#[from] std::io::Error),
+    #[error("Failed to (de)serialize a protocol message: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid protocol message: {0}")]
+    Protocol(String),
+    #[error(transparent)]
+    Runner(#[from] CairoRunError),
+}
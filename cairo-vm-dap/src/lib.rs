@@ -0,0 +1,4 @@
+pub mod error;
+pub mod protocol;
+pub mod server;
+pub mod session;
@@ -0,0 +1,8 @@
+#![forbid(unsafe_code)]
+use std::io;
+
+use cairo_vm_dap::{error::DapError, server::DapServer};
+
+fn main() -> Result<(), DapError> {
+    DapServer::new(io::stdin().lock(), io::stdout()).run()
+}
@@ -0,0 +1,145 @@
+//! Base protocol of the Debug Adapter Protocol
+//!
+//! Messages are JSON objects preceded by a `Content-Length` header, see
+//! <https://microsoft.github.io/debug-adapter-protocol/overview#base-protocol>
+
+use std::io::{BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::DapError;
+
+/// A request sent by the client
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Request {
+    pub seq: i64,
+    pub command: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OutgoingMessage {
+    Response {
+        seq: i64,
+        request_seq: i64,
+        success: bool,
+        command: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        body: Option<Value>,
+    },
+    Event {
+        seq: i64,
+        event: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        body: Option<Value>,
+    },
+}
+
+/// Reads the next request from the client.
+/// Returns `None` if the stream was closed.
+pub fn read_request(reader: &mut dyn BufRead) -> Result<Option<Request>, DapError> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            // An empty line ends the header part, skip it if no header was read yet
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(value.trim().parse::<usize>().map_err(|_| {
+                    DapError::Protocol(format!("Invalid Content-Length: {}", value.trim()))
+                })?);
+            }
+        }
+    }
+    let mut content = vec![0; content_length.unwrap_or_default()];
+    reader.read_exact(&mut content)?;
+    Ok(Some(serde_json::from_slice(&content)?))
+}
+
+/// Writes a message to the client
+pub fn write_message(writer: &mut dyn Write, message: &OutgoingMessage) -> Result<(), DapError> {
+    let content = serde_json::to_vec(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n", content.len())?;
+    writer.write_all(&content)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use serde_json::json;
+
+    #[test]
+    fn read_request_ok() {
+        let content = r#"{"seq":1,"type":"request","command":"initialize","arguments":{"adapterID":"cairo"}}"#;
+        let message = format!("Content-Length: {}\r\n\r\n{content}", content.len());
+        let request = read_request(&mut message.as_bytes()).unwrap().unwrap();
+        assert_eq!(
+            request,
+            Request {
+                seq: 1,
+                command: "initialize".to_string(),
+                arguments: json!({"adapterID": "cairo"}),
+            }
+        );
+    }
+
+    #[test]
+    fn read_request_without_arguments() {
+        let content = r#"{"seq":3,"type":"request","command":"threads"}"#;
+        let message = format!("Content-Length: {}\r\n\r\n{content}", content.len());
+        let request = read_request(&mut message.as_bytes()).unwrap().unwrap();
+        assert_eq!(request.command, "threads");
+        assert_eq!(request.arguments, Value::Null);
+    }
+
+    #[test]
+    fn read_request_end_of_stream() {
+        assert_matches!(read_request(&mut "".as_bytes()), Ok(None));
+    }
+
+    #[test]
+    fn read_request_invalid_content_length() {
+        let message = "Content-Length: ten\r\n\r\n{}";
+        assert_matches!(
+            read_request(&mut message.as_bytes()),
+            Err(DapError::Protocol(_))
+        );
+    }
+
+    #[test]
+    fn write_event() {
+        let mut output = Vec::new();
+        write_message(
+            &mut output,
+            &OutgoingMessage::Event {
+                seq: 1,
+                event: "initialized".to_string(),
+                body: None,
+            },
+        )
+        .unwrap();
+        let content = r#"{"type":"event","seq":1,"event":"initialized"}"#;
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("Content-Length: {}\r\n\r\n{content}", content.len())
+        );
+    }
+}
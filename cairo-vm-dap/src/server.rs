@@ -0,0 +1,545 @@
+//! Debug Adapter Protocol server
+//!
+//! Handles the requests of a DAP client for a single Cairo program. The program can either be
+//! launched by the client through the `launch` request, or provided by the embedder as an
+//! already initialized [CairoRunner] that the client connects to with the `attach` request.
+
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+use cairo_vm::types::layout_name::LayoutName;
+use cairo_vm::types::program::Program;
+use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
+use cairo_vm::vm::runners::cairo_runner::CairoRunner;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::DapError;
+use crate::protocol::{read_request, write_message, OutgoingMessage, Request};
+use crate::session::{Frame, ReferenceIdentifiers, ResumeMode, Session, StopReason};
+
+// Cairo programs are single threaded
+const THREAD_ID: i64 = 1;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchArguments {
+    program: PathBuf,
+    #[serde(default = "default_layout")]
+    layout: LayoutName,
+    #[serde(default)]
+    proof_mode: bool,
+    entrypoint: Option<String>,
+    #[serde(default)]
+    stop_on_entry: bool,
+}
+
+fn default_layout() -> LayoutName {
+    LayoutName::plain
+}
+
+#[derive(Deserialize)]
+struct Source {
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct SourceBreakpoint {
+    line: u32,
+}
+
+#[derive(Deserialize)]
+struct SetBreakpointsArguments {
+    source: Source,
+    #[serde(default)]
+    breakpoints: Vec<SourceBreakpoint>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FrameArguments {
+    frame_id: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VariablesArguments {
+    variables_reference: usize,
+}
+
+pub struct DapServer<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    seq: i64,
+    session: Option<Session>,
+    stop_on_entry: bool,
+    terminated: bool,
+}
+
+impl<R: BufRead, W: Write> DapServer<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        DapServer {
+            input,
+            output,
+            seq: 0,
+            session: None,
+            stop_on_entry: false,
+            terminated: false,
+        }
+    }
+
+    /// Creates a server for an already initialized runner, which the client can debug through
+    /// the `attach` request. The execution stops before the first instruction.
+    pub fn attach(input: R, output: W, session: Session) -> Self {
+        DapServer {
+            session: Some(session),
+            stop_on_entry: true,
+            ..Self::new(input, output)
+        }
+    }
+
+    /// Serves the client requests until it disconnects
+    pub fn run(&mut self) -> Result<(), DapError> {
+        while let Some(request) = read_request(&mut self.input)? {
+            let disconnect = matches!(request.command.as_str(), "disconnect" | "terminate");
+            match self.handle_request(&request) {
+                Ok(body) => self.send_response(&request, Ok(body))?,
+                Err(DapError::IO(err)) => return Err(DapError::IO(err)),
+                Err(err) => self.send_response(&request, Err(err.to_string()))?,
+            }
+            if disconnect {
+                break;
+            }
+            self.after_response(&request)?;
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, request: &Request) -> Result<Option<Value>, DapError> {
+        let body = match request.command.as_str() {
+            "initialize" => json!({ "supportsConfigurationDoneRequest": true }),
+            "launch" => {
+                let args: LaunchArguments = serde_json::from_value(request.arguments.clone())?;
+                self.launch(args)?;
+                return Ok(None);
+            }
+            "attach" => {
+                if self.session.is_none() {
+                    return Err(DapError::Protocol("No runner to attach to".to_string()));
+                }
+                return Ok(None);
+            }
+            "setBreakpoints" => {
+                let args: SetBreakpointsArguments =
+                    serde_json::from_value(request.arguments.clone())?;
+                let lines: Vec<u32> = args.breakpoints.iter().map(|b| b.line).collect();
+                let breakpoints: Vec<Value> = self
+                    .session_mut()?
+                    .set_breakpoints(&args.source.path, &lines)
+                    .into_iter()
+                    .map(|(line, verified)| json!({ "verified": verified, "line": line }))
+                    .collect();
+                json!({ "breakpoints": breakpoints })
+            }
+            "threads" => json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+            "stackTrace" => {
+                let session = self.session()?;
+                let frames: Vec<Value> = session
+                    .stack_frames()
+                    .iter()
+                    .enumerate()
+                    .map(|(id, frame)| stack_frame_json(session, id, frame))
+                    .collect();
+                json!({ "stackFrames": frames, "totalFrames": frames.len() })
+            }
+            "scopes" => {
+                let args: FrameArguments = serde_json::from_value(request.arguments.clone())?;
+                json!({ "scopes": [
+                    {
+                        "name": "Locals",
+                        "variablesReference": args.frame_id * 2 + 1,
+                        "expensive": false,
+                    },
+                    {
+                        "name": "Registers",
+                        "variablesReference": args.frame_id * 2 + 2,
+                        "expensive": false,
+                    },
+                ]})
+            }
+            "variables" => {
+                let args: VariablesArguments = serde_json::from_value(request.arguments.clone())?;
+                json!({ "variables": self.variables(args.variables_reference)? })
+            }
+            "continue" => json!({ "allThreadsContinued": true }),
+            "configurationDone"
+            | "next"
+            | "stepIn"
+            | "stepOut"
+            | "pause"
+            | "setExceptionBreakpoints"
+            | "disconnect"
+            | "terminate" => return Ok(None),
+            command => {
+                return Err(DapError::Protocol(format!(
+                    "Unsupported command: {command}"
+                )))
+            }
+        };
+        Ok(Some(body))
+    }
+
+    /// Sends the events that follow the response of a request
+    fn after_response(&mut self, request: &Request) -> Result<(), DapError> {
+        let mode = match request.command.as_str() {
+            "launch" | "attach" if self.session.is_some() => {
+                return self.send_event("initialized", None);
+            }
+            "configurationDone" if self.stop_on_entry => {
+                return self.send_stopped("entry");
+            }
+            "configurationDone" | "continue" => ResumeMode::Continue,
+            "next" => ResumeMode::Next,
+            "stepIn" => ResumeMode::StepIn,
+            "stepOut" => ResumeMode::StepOut,
+            _ => return Ok(()),
+        };
+        if self.terminated {
+            return Ok(());
+        }
+        let Some(session) = self.session.as_mut() else {
+            return Ok(());
+        };
+        match session.resume(mode) {
+            Ok(StopReason::Step) => self.send_stopped("step"),
+            Ok(StopReason::Breakpoint) => self.send_stopped("breakpoint"),
+            Ok(StopReason::Finished) => self.finish(),
+            Err(err) => {
+                self.send_output("stderr", &format!("{err}\n"))?;
+                self.terminate(1)
+            }
+        }
+    }
+
+    fn launch(&mut self, args: LaunchArguments) -> Result<(), DapError> {
+        let program_json = std::fs::read(&args.program)?;
+        let entrypoint = args.entrypoint.as_deref().unwrap_or("main");
+        let program =
+            Program::from_bytes(&program_json, Some(entrypoint)).map_err(CairoRunError::from)?;
+        let references = ReferenceIdentifiers::from_program_json(&program_json)?;
+
+        let mut runner = CairoRunner::new(&program, args.layout, args.proof_mode, false)
+            .map_err(CairoRunError::from)?;
+        let end = runner
+            .initialize(args.proof_mode)
+            .map_err(CairoRunError::from)?;
+
+        self.session = Some(Session::new(
+            runner,
+            end,
+            Box::new(BuiltinHintProcessor::new_empty()),
+            references,
+        )?);
+        self.stop_on_entry = args.stop_on_entry;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), DapError> {
+        if let Some(output) = self.session.as_mut().and_then(Session::output) {
+            self.send_output("stdout", &format!("Program Output:\n{output}"))?;
+        }
+        self.terminate(0)
+    }
+
+    fn terminate(&mut self, exit_code: i64) -> Result<(), DapError> {
+        self.terminated = true;
+        self.send_event("exited", Some(json!({ "exitCode": exit_code })))?;
+        self.send_event("terminated", None)
+    }
+
+    fn variables(&self, variables_reference: usize) -> Result<Vec<Value>, DapError> {
+        let session = self.session()?;
+        let frame_id = variables_reference.saturating_sub(1) / 2;
+        let Some(frame) = session.stack_frames().get(frame_id).cloned() else {
+            return Err(DapError::Protocol(format!(
+                "Invalid variables reference: {variables_reference}"
+            )));
+        };
+        if variables_reference % 2 == 1 {
+            return Ok(session
+                .variables(&frame)
+                .into_iter()
+                .map(|v| {
+                    json!({
+                        "name": v.name,
+                        "value": v.value,
+                        "type": v.cairo_type,
+                        "variablesReference": 0,
+                    })
+                })
+                .collect());
+        }
+        let mut registers = vec![("pc", frame.pc), ("fp", frame.fp)];
+        // The value of ap is only known for the current frame
+        if frame_id == 0 {
+            registers.push(("ap", session.runner().vm.get_ap()));
+        }
+        Ok(registers
+            .into_iter()
+            .map(|(name, value)| {
+                json!({ "name": name, "value": value.to_string(), "variablesReference": 0 })
+            })
+            .collect())
+    }
+
+    fn session(&self) -> Result<&Session, DapError> {
+        self.session
+            .as_ref()
+            .ok_or_else(|| DapError::Protocol("No program was launched".to_string()))
+    }
+
+    fn session_mut(&mut self) -> Result<&mut Session, DapError> {
+        self.session
+            .as_mut()
+            .ok_or_else(|| DapError::Protocol("No program was launched".to_string()))
+    }
+
+    fn next_seq(&mut self) -> i64 {
+        self.seq += 1;
+        self.seq
+    }
+
+    fn send_response(
+        &mut self,
+        request: &Request,
+        result: Result<Option<Value>, String>,
+    ) -> Result<(), DapError> {
+        let seq = self.next_seq();
+        let (success, message, body) = match result {
+            Ok(body) => (true, None, body),
+            Err(message) => (false, Some(message), None),
+        };
+        write_message(
+            &mut self.output,
+            &OutgoingMessage::Response {
+                seq,
+                request_seq: request.seq,
+                success,
+                command: request.command.clone(),
+                message,
+                body,
+            },
+        )
+    }
+
+    fn send_event(&mut self, event: &str, body: Option<Value>) -> Result<(), DapError> {
+        let seq = self.next_seq();
+        write_message(
+            &mut self.output,
+            &OutgoingMessage::Event {
+                seq,
+                event: event.to_string(),
+                body,
+            },
+        )
+    }
+
+    fn send_stopped(&mut self, reason: &str) -> Result<(), DapError> {
+        self.send_event(
+            "stopped",
+            Some(json!({
+                "reason": reason,
+                "threadId": THREAD_ID,
+                "allThreadsStopped": true,
+            })),
+        )
+    }
+
+    fn send_output(&mut self, category: &str, output: &str) -> Result<(), DapError> {
+        self.send_event(
+            "output",
+            Some(json!({ "category": category, "output": output })),
+        )
+    }
+}
+
+fn stack_frame_json(session: &Session, id: usize, frame: &Frame) -> Value {
+    let name = session
+        .function_name(frame.pc)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("pc={}", frame.pc));
+    match session.location(frame.pc) {
+        Some(location) => json!({
+            "id": id,
+            "name": name,
+            "source": { "path": location.input_file.filename },
+            "line": location.start_line,
+            "column": location.start_col,
+        }),
+        None => json!({ "id": id, "name": name, "line": 0, "column": 0 }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn request(seq: i64, command: &str, arguments: Value) -> String {
+        let content = json!({
+            "seq": seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        format!("Content-Length: {}\r\n\r\n{content}", content.len())
+    }
+
+    fn run_session(requests: &[String]) -> Vec<Value> {
+        let input = requests.concat();
+        let mut output = Vec::new();
+        DapServer::new(input.as_bytes(), &mut output).run().unwrap();
+        let mut reader = output.as_slice();
+        let mut messages = Vec::new();
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 0 {
+            let length: usize = line
+                .trim()
+                .strip_prefix("Content-Length: ")
+                .unwrap()
+                .parse()
+                .unwrap();
+            let mut separator = String::new();
+            reader.read_line(&mut separator).unwrap();
+            let (content, rest) = reader.split_at(length);
+            messages.push(serde_json::from_slice(content).unwrap());
+            reader = rest;
+            line.clear();
+        }
+        messages
+    }
+
+    fn events<'a>(messages: &'a [Value], event: &str) -> Vec<&'a Value> {
+        messages
+            .iter()
+            .filter(|m| m["type"] == "event" && m["event"] == event)
+            .collect()
+    }
+
+    fn response<'a>(messages: &'a [Value], command: &str) -> &'a Value {
+        messages
+            .iter()
+            .find(|m| m["type"] == "response" && m["command"] == command)
+            .unwrap()
+    }
+
+    #[test]
+    fn unsupported_command() {
+        let messages = run_session(&[request(1, "evaluate", json!({}))]);
+        assert_eq!(messages[0]["success"], false);
+        assert_eq!(messages[0]["request_seq"], 1);
+    }
+
+    #[test]
+    fn attach_without_runner() {
+        let messages = run_session(&[request(1, "attach", json!({}))]);
+        assert_eq!(messages[0]["success"], false);
+        assert!(events(&messages, "initialized").is_empty());
+    }
+
+    #[test]
+    fn launch_and_run_until_end() {
+        let messages = run_session(&[
+            request(1, "initialize", json!({ "adapterID": "cairo" })),
+            request(
+                2,
+                "launch",
+                json!({ "program": "../cairo_programs/fibonacci.json" }),
+            ),
+            request(3, "configurationDone", json!({})),
+            request(4, "disconnect", json!({})),
+        ]);
+        assert_eq!(response(&messages, "launch")["success"], true);
+        assert_eq!(events(&messages, "initialized").len(), 1);
+        assert!(events(&messages, "stopped").is_empty());
+        assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], 0);
+        assert_eq!(events(&messages, "terminated").len(), 1);
+    }
+
+    #[test]
+    fn breakpoint_stack_trace_and_variables() {
+        let messages = run_session(&[
+            request(1, "initialize", json!({ "adapterID": "cairo" })),
+            request(
+                2,
+                "launch",
+                json!({ "program": "../cairo_programs/fibonacci.json" }),
+            ),
+            request(
+                3,
+                "setBreakpoints",
+                json!({
+                    "source": { "path": "/home/user/cairo-vm/cairo_programs/fibonacci.cairo" },
+                    "breakpoints": [{ "line": 16 }, { "line": 1 }],
+                }),
+            ),
+            request(4, "configurationDone", json!({})),
+            request(5, "stackTrace", json!({ "threadId": THREAD_ID })),
+            request(6, "scopes", json!({ "frameId": 0 })),
+            request(7, "variables", json!({ "variablesReference": 1 })),
+            request(8, "variables", json!({ "variablesReference": 2 })),
+            request(9, "disconnect", json!({})),
+        ]);
+        let breakpoints = &response(&messages, "setBreakpoints")["body"]["breakpoints"];
+        assert_eq!(breakpoints[0]["verified"], true);
+        assert_eq!(breakpoints[1]["verified"], false);
+
+        assert_eq!(
+            events(&messages, "stopped")[0]["body"]["reason"],
+            "breakpoint"
+        );
+
+        let frames = &response(&messages, "stackTrace")["body"]["stackFrames"];
+        assert_eq!(frames[0]["name"], "__main__.fib");
+        assert_eq!(frames[0]["line"], 16);
+        assert_eq!(frames[1]["name"], "__main__.main");
+        assert_eq!(frames.as_array().unwrap().len(), 2);
+
+        let variables = &response(&messages, "variables")["body"]["variables"];
+        let n = variables
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|v| v["name"] == "n")
+            .unwrap();
+        assert_eq!(n["value"], "10");
+        assert_eq!(n["type"], "felt");
+    }
+
+    #[test]
+    fn step_commands() {
+        let messages = run_session(&[
+            request(1, "initialize", json!({ "adapterID": "cairo" })),
+            request(
+                2,
+                "launch",
+                json!({
+                    "program": "../cairo_programs/fibonacci.json",
+                    "stopOnEntry": true,
+                }),
+            ),
+            request(3, "configurationDone", json!({})),
+            request(4, "stepIn", json!({ "threadId": THREAD_ID })),
+            request(5, "next", json!({ "threadId": THREAD_ID })),
+            request(6, "stepOut", json!({ "threadId": THREAD_ID })),
+            request(7, "continue", json!({ "threadId": THREAD_ID })),
+            request(8, "disconnect", json!({})),
+        ]);
+        let stopped = events(&messages, "stopped");
+        assert_eq!(stopped[0]["body"]["reason"], "entry");
+        assert_eq!(stopped[1]["body"]["reason"], "step");
+        assert_eq!(events(&messages, "terminated").len(), 1);
+    }
+}
@@ -0,0 +1,455 @@
+//! State of a program being debugged
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use cairo_vm::hint_processor::hint_processor_definition::HintProcessor;
+use cairo_vm::serde::deserialize_program::{
+    InstructionLocation, Location, OffsetValue, Reference, ValueAddress,
+};
+use cairo_vm::types::builtin_name::BuiltinName;
+use cairo_vm::types::instruction::Register;
+use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
+use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
+use cairo_vm::vm::errors::vm_exception::VmException;
+use cairo_vm::vm::runners::cairo_runner::{CairoRunner, Execution, Progress};
+use cairo_vm::vm::vm_core::VirtualMachine;
+use cairo_vm::Felt252;
+use serde::Deserialize;
+
+/// How far the execution should go when resumed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeMode {
+    /// Run until a breakpoint is hit
+    Continue,
+    /// Run until the source line changes, stepping into function calls
+    StepIn,
+    /// Run until the source line changes in the current function or one of its callers
+    Next,
+    /// Run until the current function returns
+    StepOut,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Step,
+    Breakpoint,
+    Finished,
+}
+
+/// A function frame of the Cairo call stack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub pc: Relocatable,
+    pub fp: Relocatable,
+}
+
+/// A variable visible in a frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    pub cairo_type: String,
+}
+
+#[derive(Deserialize)]
+struct ReferenceIdentifier {
+    #[serde(rename = "type")]
+    type_: Option<String>,
+    #[serde(default)]
+    references: Vec<Reference>,
+}
+
+#[derive(Deserialize)]
+struct ProgramIdentifiers {
+    identifiers: HashMap<String, ReferenceIdentifier>,
+}
+
+/// The reference identifiers of a compiled program, grouped by their full name.
+///
+/// The [Program](cairo_vm::types::program::Program) only keeps the references used by hints, so
+/// the names of the variables of each function are read from the compiled program instead.
+#[derive(Debug, Default, Clone)]
+pub struct ReferenceIdentifiers(HashMap<String, Vec<Reference>>);
+
+impl ReferenceIdentifiers {
+    pub fn from_program_json(program_json: &[u8]) -> Result<Self, serde_json::Error> {
+        let program: ProgramIdentifiers = serde_json::from_slice(program_json)?;
+        Ok(ReferenceIdentifiers(
+            program
+                .identifiers
+                .into_iter()
+                .filter(|(_, identifier)| identifier.type_.as_deref() == Some("reference"))
+                .map(|(name, identifier)| (name, identifier.references))
+                .collect(),
+        ))
+    }
+}
+
+pub struct Session {
+    runner: CairoRunner,
+    execution: Execution,
+    hint_processor: Box<dyn HintProcessor>,
+    instruction_locations: HashMap<usize, InstructionLocation>,
+    // Function names indexed by their starting pc
+    functions: BTreeMap<usize, String>,
+    references: ReferenceIdentifiers,
+    // Breakpoint pcs indexed by source path
+    breakpoints: HashMap<String, HashSet<Relocatable>>,
+}
+
+impl Session {
+    /// Creates a session from an initialized runner, which will be run until `end`.
+    pub fn new(
+        mut runner: CairoRunner,
+        end: Relocatable,
+        mut hint_processor: Box<dyn HintProcessor>,
+        references: ReferenceIdentifiers,
+    ) -> Result<Self, CairoRunError> {
+        let execution = runner.start_execution(end, hint_processor.as_mut())?;
+        // Relocating with a zero base keeps the pcs relative to the program segment
+        let instruction_locations = runner
+            .get_program()
+            .get_relocated_instruction_locations(&[0])
+            .unwrap_or_default();
+        let functions = runner
+            .get_program()
+            .iter_identifiers()
+            .filter(|(_, identifier)| identifier.type_.as_deref() == Some("function"))
+            .filter_map(|(name, identifier)| Some((identifier.pc?, name.to_string())))
+            .collect();
+        Ok(Session {
+            runner,
+            execution,
+            hint_processor,
+            instruction_locations,
+            functions,
+            references,
+            breakpoints: HashMap::new(),
+        })
+    }
+
+    pub fn runner(&self) -> &CairoRunner {
+        &self.runner
+    }
+
+    /// Returns the program output, if the program uses the output builtin
+    pub fn output(&mut self) -> Option<String> {
+        let vm = &mut self.runner.vm;
+        if !vm
+            .get_builtin_runners()
+            .iter()
+            .any(|b| b.name() == BuiltinName::output)
+        {
+            return None;
+        }
+        let mut output = String::new();
+        vm.write_output(&mut output).ok()?;
+        Some(output)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.runner.vm.get_pc() == self.execution.end()
+    }
+
+    /// Replaces the breakpoints of a source file.
+    /// Returns the lines, and whether an instruction was found at each of them.
+    pub fn set_breakpoints(&mut self, source_path: &str, lines: &[u32]) -> Vec<(u32, bool)> {
+        let program_base = self.runner.program_base.unwrap_or_default();
+        let mut pcs = HashSet::new();
+        let mut result = Vec::new();
+        for line in lines {
+            let line_pcs = self.pcs_at_line(source_path, *line);
+            result.push((*line, !line_pcs.is_empty()));
+            pcs.extend(
+                line_pcs
+                    .into_iter()
+                    .filter_map(|offset| (program_base + offset).ok()),
+            );
+        }
+        self.breakpoints.insert(source_path.to_string(), pcs);
+        result
+    }
+
+    /// Returns the first pc of each block of consecutive instructions located at the given line
+    fn pcs_at_line(&self, source_path: &str, line: u32) -> Vec<usize> {
+        let at_line = |pc: &usize| {
+            self.instruction_locations.get(pc).is_some_and(|location| {
+                location.inst.start_line == line
+                    && is_same_file(source_path, &location.inst.input_file.filename)
+            })
+        };
+        let mut all_pcs: Vec<usize> = self.instruction_locations.keys().copied().collect();
+        all_pcs.sort();
+        all_pcs
+            .iter()
+            .enumerate()
+            .filter(|(i, pc)| at_line(pc) && (*i == 0 || !at_line(&all_pcs[i - 1])))
+            .map(|(_, pc)| *pc)
+            .collect()
+    }
+
+    /// Returns the source location of the instruction at `pc`
+    pub fn location(&self, pc: Relocatable) -> Option<&Location> {
+        if pc.segment_index != 0 {
+            return None;
+        }
+        self.instruction_locations
+            .get(&pc.offset)
+            .map(|location| &location.inst)
+    }
+
+    /// Returns the name of the function containing `pc`
+    pub fn function_name(&self, pc: Relocatable) -> Option<&str> {
+        if pc.segment_index != 0 {
+            return None;
+        }
+        self.functions
+            .range(..=pc.offset)
+            .next_back()
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Resumes the execution until the condition given by `mode` is met, a breakpoint is hit or
+    /// the program ends.
+    pub fn resume(&mut self, mode: ResumeMode) -> Result<StopReason, CairoRunError> {
+        let start_fp = self.runner.vm.get_fp();
+        let start_line = line(&self.instruction_locations, self.runner.vm.get_pc());
+        loop {
+            if self.is_finished() {
+                return Ok(StopReason::Finished);
+            }
+            let progress = self
+                .runner
+                .resume_execution(&mut self.execution, 1, self.hint_processor.as_mut())
+                .map_err(|err| VmException::from_vm_error(&self.runner, err))?;
+            if progress == Progress::Finished {
+                return Ok(StopReason::Finished);
+            }
+            let pc = self.runner.vm.get_pc();
+            if self.breakpoints.values().any(|pcs| pcs.contains(&pc)) {
+                return Ok(StopReason::Breakpoint);
+            }
+            // Callee frames always have a greater fp than their callers
+            let fp = self.runner.vm.get_fp();
+            let line = line(&self.instruction_locations, pc);
+            let line_changed = line.is_some() && line != start_line;
+            let stop = match mode {
+                ResumeMode::Continue => false,
+                ResumeMode::StepIn => line_changed,
+                ResumeMode::Next => (line_changed && fp <= start_fp) || fp < start_fp,
+                ResumeMode::StepOut => fp < start_fp,
+            };
+            if stop {
+                return Ok(StopReason::Step);
+            }
+        }
+    }
+
+    /// Returns the call stack, most recent call first
+    pub fn stack_frames(&self) -> Vec<Frame> {
+        let vm = &self.runner.vm;
+        let mut frames = vec![Frame {
+            pc: vm.get_pc(),
+            fp: vm.get_fp(),
+        }];
+        frames.extend(
+            vm.get_traceback_entries()
+                .into_iter()
+                .rev()
+                .map(|(fp, pc)| Frame { pc, fp }),
+        );
+        frames
+    }
+
+    /// Returns the variables of the function executing `frame`, resolved from the references
+    /// available at the frame's pc
+    pub fn variables(&self, frame: &Frame) -> Vec<Variable> {
+        let Some(function) = self.function_name(frame.pc) else {
+            return Vec::new();
+        };
+        let prefix = format!("{function}.");
+        let mut variables: Vec<Variable> = self
+            .references
+            .0
+            .iter()
+            .filter_map(|(full_name, references)| {
+                let name = full_name.strip_prefix(&prefix)?;
+                if name.contains('.') {
+                    return None;
+                }
+                // Take the latest definition of the reference before the frame's pc
+                let reference = references
+                    .iter()
+                    .filter(|r| r.pc.is_some_and(|pc| pc <= frame.pc.offset))
+                    .max_by_key(|r| r.pc)?;
+                let value =
+                    match eval_reference(&self.runner.vm, &reference.value_address, frame.fp) {
+                        Ok(Some(value)) => value.to_string(),
+                        Ok(None) => "<unknown>".to_string(),
+                        Err(msg) => msg.to_string(),
+                    };
+                Some(Variable {
+                    name: name.to_string(),
+                    value,
+                    cairo_type: reference.value_address.value_type.clone(),
+                })
+            })
+            .collect();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+        variables
+    }
+}
+
+/// Returns the source file and line of the instruction at `pc`
+fn line(
+    instruction_locations: &HashMap<usize, InstructionLocation>,
+    pc: Relocatable,
+) -> Option<(&str, u32)> {
+    if pc.segment_index != 0 {
+        return None;
+    }
+    instruction_locations.get(&pc.offset).map(|location| {
+        (
+            location.inst.input_file.filename.as_str(),
+            location.inst.start_line,
+        )
+    })
+}
+
+/// Computes the value of a reference using `fp` as the frame pointer.
+/// References based on ap can't be computed outside of the instruction that defines them.
+fn eval_reference(
+    vm: &VirtualMachine,
+    value_address: &ValueAddress,
+    fp: Relocatable,
+) -> Result<Option<MaybeRelocatable>, &'static str> {
+    let offset_value = |offset: &OffsetValue| match offset {
+        OffsetValue::Immediate(f) => Ok(Some(f.into())),
+        OffsetValue::Value(v) => Ok(Some(Felt252::from(*v).into())),
+        OffsetValue::Reference(Register::AP, _, _) => Err("<ap-based reference>"),
+        OffsetValue::Reference(Register::FP, offset, deref) => {
+            let Ok(addr) = fp + *offset else {
+                return Ok(None);
+            };
+            if *deref {
+                Ok(vm.get_maybe(&addr))
+            } else {
+                Ok(Some(addr.into()))
+            }
+        }
+    };
+    let (Some(offset1), Some(offset2)) = (
+        offset_value(&value_address.offset1)?,
+        offset_value(&value_address.offset2)?,
+    ) else {
+        return Ok(None);
+    };
+    let Ok(mut value) = offset1.add(&offset2) else {
+        return Ok(None);
+    };
+    if value_address.inner_dereference && value_address.outer_dereference {
+        let Some(inner) = vm.get_maybe(&value) else {
+            return Ok(None);
+        };
+        value = inner;
+    }
+    if value_address.inner_dereference || value_address.outer_dereference {
+        return Ok(vm.get_maybe(&value));
+    }
+    Ok(Some(value))
+}
+
+/// Source paths sent by the client are absolute, while the compiler stores them relative to the
+/// directory it was run from.
+fn is_same_file(source_path: &str, filename: &str) -> bool {
+    source_path == filename || Path::new(source_path).ends_with(filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_same_file_relative_path() {
+        assert!(is_same_file(
+            "/home/user/cairo-vm/cairo_programs/fibonacci.cairo",
+            "cairo_programs/fibonacci.cairo"
+        ));
+        assert!(is_same_file("fibonacci.cairo", "fibonacci.cairo"));
+        assert!(!is_same_file(
+            "/home/user/cairo-vm/cairo_programs/fibonacci.cairo",
+            "bonacci.cairo"
+        ));
+    }
+
+    #[test]
+    fn reference_identifiers_from_program_json() {
+        let program_json = br#"{
+            "identifiers": {
+                "__main__.fib.n": {
+                    "cairo_type": "felt",
+                    "full_name": "__main__.fib.n",
+                    "references": [
+                        {
+                            "ap_tracking_data": {"group": 3, "offset": 0},
+                            "pc": 9,
+                            "value": "[cast(fp + (-3), felt*)]"
+                        }
+                    ],
+                    "type": "reference"
+                },
+                "__main__.fib": {"decorators": [], "pc": 9, "type": "function"}
+            }
+        }"#;
+        let references = ReferenceIdentifiers::from_program_json(program_json).unwrap();
+        assert_eq!(references.0.len(), 1);
+        let reference = &references.0["__main__.fib.n"][0];
+        assert_eq!(reference.pc, Some(9));
+        assert_eq!(
+            reference.value_address.offset1,
+            OffsetValue::Reference(Register::FP, -3, false)
+        );
+        assert!(reference.value_address.outer_dereference);
+    }
+
+    #[test]
+    fn eval_fp_based_reference() {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.insert_value(Relocatable::from((1, 2)), Felt252::from(10))
+            .unwrap();
+        let value_address = ValueAddress {
+            offset1: OffsetValue::Reference(Register::FP, -3, false),
+            offset2: OffsetValue::Value(0),
+            outer_dereference: true,
+            inner_dereference: false,
+            value_type: "felt".to_string(),
+        };
+        assert_eq!(
+            eval_reference(&vm, &value_address, Relocatable::from((1, 5))),
+            Ok(Some(Felt252::from(10).into()))
+        );
+        assert_eq!(
+            eval_reference(&vm, &value_address, Relocatable::from((1, 6))),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn eval_ap_based_reference() {
+        let vm = VirtualMachine::new(false);
+        let value_address = ValueAddress {
+            offset1: OffsetValue::Reference(Register::AP, -1, false),
+            offset2: OffsetValue::Value(0),
+            outer_dereference: true,
+            inner_dereference: false,
+            value_type: "felt".to_string(),
+        };
+        assert_eq!(
+            eval_reference(&vm, &value_address, Relocatable::from((1, 5))),
+            Err("<ap-based reference>")
+        );
+    }
+}
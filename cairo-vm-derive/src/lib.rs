@@ -0,0 +1,115 @@
+//! Derive macros for writing cairo-vm hints
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Implements `cairo_vm::hint_processor::builtin_hint_processor::hint_ids::HintIds` for a struct
+/// with named fields, each field being loaded from (and stored into) the ids variable of the same
+/// name. Fields must implement `HintIdsField`: `Felt252`, `Relocatable` and `MaybeRelocatable` are
+/// read by the hint, `Out<T>` is written by it.
+///
+/// The ids variable of a field can be renamed with `#[hint_ids(name = "other_name")]`.
+///
+/// ```ignore
+/// #[derive(HintIds)]
+/// struct Ids {
+///     x: Felt252,
+///     ptr: Relocatable,
+///     #[hint_ids(name = "res")]
+///     result: Out<Felt252>,
+/// }
+/// ```
+#[proc_macro_derive(HintIds, attributes(hint_ids))]
+pub fn derive_hint_ids(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_hint_ids(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_hint_ids(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "HintIds can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "HintIds can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut loads = Vec::new();
+    let mut stores = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("fields are named");
+        let mut name = ident.to_string();
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("hint_ids"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported hint_ids attribute, expected `name`"))
+                }
+            })?;
+        }
+        loads.push(quote! {
+            #ident: HintIdsField::load(#name, vm, ids_data, ap_tracking)?
+        });
+        stores.push(quote! {
+            HintIdsField::store(&self.#ident, #name, vm, ids_data, ap_tracking)?;
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        const _: () = {
+            use ::cairo_vm::{
+                hint_processor::{
+                    builtin_hint_processor::hint_ids::{HintIds, HintIdsField},
+                    hint_processor_definition::HintReference,
+                },
+                serde::deserialize_program::ApTracking,
+                stdlib::{collections::HashMap, string::String},
+                vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
+            };
+
+            impl #impl_generics HintIds for #ident #ty_generics #where_clause {
+                fn load(
+                    vm: &VirtualMachine,
+                    ids_data: &HashMap<String, HintReference>,
+                    ap_tracking: &ApTracking,
+                ) -> ::core::result::Result<Self, HintError> {
+                    ::core::result::Result::Ok(Self {
+                        #(#loads,)*
+                    })
+                }
+
+                fn store(
+                    &self,
+                    vm: &mut VirtualMachine,
+                    ids_data: &HashMap<String, HintReference>,
+                    ap_tracking: &ApTracking,
+                ) -> ::core::result::Result<(), HintError> {
+                    #(#stores)*
+                    ::core::result::Result::Ok(())
+                }
+            }
+        };
+    })
+}
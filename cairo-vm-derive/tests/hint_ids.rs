@@ -0,0 +1,145 @@
+use std::{collections::HashMap, rc::Rc};
+
+use assert_matches::assert_matches;
+use cairo_vm::{
+    any_box,
+    hint_processor::{
+        builtin_hint_processor::{
+            builtin_hint_processor_definition::{
+                BuiltinHintProcessor, HintFunc, HintProcessorData,
+            },
+            hint_ids::{HintIds, Out},
+        },
+        hint_processor_definition::{HintProcessorLogic, HintReference},
+    },
+    serde::deserialize_program::ApTracking,
+    types::{
+        exec_scope::ExecutionScopes,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{
+        errors::{hint_errors::HintError, memory_errors::MemoryError},
+        vm_core::VirtualMachine,
+    },
+    Felt252,
+};
+use cairo_vm_derive::HintIds;
+
+#[derive(Debug, HintIds)]
+struct Ids {
+    x: Felt252,
+    ptr: Relocatable,
+    value: MaybeRelocatable,
+    #[hint_ids(name = "res")]
+    result: Out<Felt252>,
+}
+
+// Returns a vm whose frame holds x = 5, ptr = 2:0 and value = 7, and the ids data pointing
+// at them and at the cell after them, res
+fn vm_with_ids() -> (VirtualMachine, HashMap<String, HintReference>) {
+    let mut vm = VirtualMachine::new(false);
+    for _ in 0..3 {
+        vm.add_memory_segment();
+    }
+    vm.set_fp(4);
+    vm.insert_value(Relocatable::from((1, 0)), Felt252::from(5))
+        .unwrap();
+    vm.insert_value(Relocatable::from((1, 1)), Relocatable::from((2, 0)))
+        .unwrap();
+    vm.insert_value(Relocatable::from((1, 2)), Felt252::from(7))
+        .unwrap();
+    let ids_data = ["x", "ptr", "value", "res"]
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| (name.to_string(), HintReference::new_simple(i as i32 - 4)))
+        .collect();
+    (vm, ids_data)
+}
+
+#[test]
+fn load_and_store_ids() {
+    let (mut vm, ids_data) = vm_with_ids();
+    let mut ids = Ids::load(&vm, &ids_data, &ApTracking::default()).unwrap();
+    assert_eq!(ids.x, Felt252::from(5));
+    assert_eq!(ids.ptr, Relocatable::from((2, 0)));
+    assert_eq!(ids.value, MaybeRelocatable::from(7));
+    assert_eq!(ids.result.get(), None);
+
+    ids.result.set(ids.x * Felt252::from(2));
+    ids.store(&mut vm, &ids_data, &ApTracking::default())
+        .unwrap();
+    assert_eq!(
+        vm.get_integer(Relocatable::from((1, 3))).unwrap().as_ref(),
+        &Felt252::from(10)
+    );
+}
+
+#[test]
+fn load_missing_id() {
+    let (vm, mut ids_data) = vm_with_ids();
+    ids_data.remove("res");
+    assert_matches!(
+        Ids::load(&vm, &ids_data, &ApTracking::default()),
+        Err(HintError::UnknownIdentifier(name)) if name.as_ref() == "res"
+    );
+}
+
+#[test]
+fn load_mistyped_id() {
+    let (vm, mut ids_data) = vm_with_ids();
+    // Make ptr point at x
+    ids_data.insert("ptr".to_string(), HintReference::new_simple(-4));
+    assert_matches!(
+        Ids::load(&vm, &ids_data, &ApTracking::default()),
+        Err(HintError::IdentifierNotRelocatable(name)) if name.as_ref() == "ptr"
+    );
+}
+
+#[test]
+fn store_into_written_cell() {
+    let (mut vm, ids_data) = vm_with_ids();
+    vm.insert_value(Relocatable::from((1, 3)), Felt252::from(7))
+        .unwrap();
+    let mut ids = Ids::load(&vm, &ids_data, &ApTracking::default()).unwrap();
+    ids.result.set(Felt252::from(6));
+    assert_matches!(
+        ids.store(&mut vm, &ids_data, &ApTracking::default()),
+        Err(HintError::Memory(MemoryError::InconsistentMemory(_)))
+    );
+}
+
+#[test]
+fn run_extra_hint_with_ids() {
+    let (mut vm, ids_data) = vm_with_ids();
+    let mut hint_processor = BuiltinHintProcessor::new_empty();
+    hint_processor.add_hint(
+        "ids.res = ids.x + ids.value".to_string(),
+        Rc::new(HintFunc(Box::new(|vm, _, ids_data, ap_tracking, _| {
+            let mut ids = Ids::load(vm, ids_data, ap_tracking)?;
+            let value = match ids.value {
+                MaybeRelocatable::Int(value) => value,
+                MaybeRelocatable::RelocatableValue(_) => {
+                    return Err(HintError::IdentifierNotInteger("value".into()))
+                }
+            };
+            ids.result.set(ids.x + value);
+            ids.store(vm, ids_data, ap_tracking)
+        }))),
+    );
+    let hint_data = any_box!(HintProcessorData::new_default(
+        "ids.res = ids.x + ids.value".to_string(),
+        ids_data,
+    ));
+    hint_processor
+        .execute_hint(
+            &mut vm,
+            &mut ExecutionScopes::new(),
+            &hint_data,
+            &HashMap::new(),
+        )
+        .unwrap();
+    assert_eq!(
+        vm.get_integer(Relocatable::from((1, 3))).unwrap().as_ref(),
+        &Felt252::from(12)
+    );
+}
@@ -34,12 +34,16 @@ use cairo_lang_utils::{
     bigint::BigIntAsHex, casts::IntoOrPanic, unordered_hash_map::UnorderedHashMap,
 };
 use cairo_vm::{
-    hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor,
+    hint_processor::cairo_1_hint_processor::{
+        hint_processor::Cairo1HintProcessor, syscall_handler::InMemorySyscallHandler,
+    },
     math_utils::signed_felt,
-    serde::deserialize_program::{ApTracking, FlowTrackingData, HintParams, ReferenceManager},
+    serde::deserialize_program::{
+        ApTracking, FlowTrackingData, HintParams, Identifier, ReferenceManager,
+    },
     types::{
-        builtin_name::BuiltinName, layout_name::LayoutName, program::Program,
-        relocatable::MaybeRelocatable,
+        builtin_name::BuiltinName, layout::CairoLayoutParams, layout_name::LayoutName,
+        program::Program, relocatable::MaybeRelocatable,
     },
     vm::{
         errors::{runner_errors::RunnerError, vm_errors::VirtualMachineError},
@@ -86,6 +90,8 @@ pub struct Cairo1RunConfig<'a> {
     pub relocate_mem: bool,
     /// Cairo layout chosen for the run
     pub layout: LayoutName,
+    /// Params used to build the layout, required when `layout` is `LayoutName::dynamic`
+    pub dynamic_layout_params: Option<CairoLayoutParams>,
     /// Run in proof_mode
     pub proof_mode: bool,
     /// Should be true if either air_public_input or cairo_pie_output are needed
@@ -93,6 +99,11 @@ pub struct Cairo1RunConfig<'a> {
     pub finalize_builtins: bool,
     /// Appends the return and input values to the output segment. This is performed by default when running in proof_mode
     pub append_return_values: bool,
+    /// Record the steps and builtin instances used by each function, see `CairoRunner::get_profile`
+    pub profiling: bool,
+    /// Handler executing the Starknet syscalls made by the program. Clones of the handler share
+    /// its state, so a clone can be kept to inspect the storage, events and messages after the run
+    pub syscall_handler: Option<InMemorySyscallHandler>,
 }
 
 impl Default for Cairo1RunConfig<'_> {
@@ -103,9 +114,12 @@ impl Default for Cairo1RunConfig<'_> {
             trace_enabled: false,
             relocate_mem: false,
             layout: LayoutName::plain,
+            dynamic_layout_params: None,
             proof_mode: false,
             finalize_builtins: false,
             append_return_values: false,
+            profiling: false,
+            syscall_handler: None,
         }
     }
 }
@@ -201,6 +215,9 @@ pub fn cairo_run_program(
         RunResources::default(),
         cairo_run_config.copy_to_output(),
     );
+    if let Some(syscall_handler) = cairo_run_config.syscall_handler.clone() {
+        hint_processor = hint_processor.with_syscall_handler(syscall_handler);
+    }
 
     let data: Vec<MaybeRelocatable> = instructions
         .flat_map(|inst| inst.assemble().encode())
@@ -208,6 +225,12 @@ pub fn cairo_run_program(
         .map(MaybeRelocatable::from)
         .collect();
 
+    let identifiers = function_identifiers(
+        sierra_program,
+        &casm_program,
+        entry_code.current_code_offset,
+    );
+
     let program = if cairo_run_config.proof_mode {
         Program::new_for_proof(
             builtins.clone(),
@@ -220,7 +243,7 @@ pub fn cairo_run_program(
             ReferenceManager {
                 references: Vec::new(),
             },
-            HashMap::new(),
+            identifiers,
             vec![],
             None,
         )?
@@ -233,7 +256,7 @@ pub fn cairo_run_program(
             ReferenceManager {
                 references: Vec::new(),
             },
-            HashMap::new(),
+            identifiers,
             vec![],
             None,
         )?
@@ -248,11 +271,15 @@ pub fn cairo_run_program(
     let mut runner = CairoRunner::new_v2(
         &program,
         cairo_run_config.layout,
+        cairo_run_config.dynamic_layout_params.clone(),
         runner_mode,
         cairo_run_config.trace_enabled,
     )?;
     let end = runner.initialize(cairo_run_config.proof_mode)?;
     load_arguments(&mut runner, &cairo_run_config, main_func, initial_gas)?;
+    if cairo_run_config.profiling {
+        runner.vm.enable_profiler();
+    }
 
     // Run it until the end / infinite loop in proof_mode
     runner.run_until_pc(end, &mut hint_processor)?;
@@ -363,6 +390,38 @@ fn build_hints_vec<'b>(
     (hints, program_hints)
 }
 
+/// Returns a `function` identifier for each sierra function, pointing to its first instruction once placed after the entry code
+fn function_identifiers(
+    sierra_program: &SierraProgram,
+    casm_program: &CairoProgram,
+    code_offset: usize,
+) -> HashMap<String, Identifier> {
+    sierra_program
+        .funcs
+        .iter()
+        .filter_map(|func| {
+            let name = func.id.debug_name.as_ref()?.to_string();
+            let pc = code_offset
+                + casm_program
+                    .debug_info
+                    .sierra_statement_info
+                    .get(func.entry_point.0)?
+                    .start_offset;
+            Some((
+                name.clone(),
+                Identifier {
+                    pc: Some(pc),
+                    type_: Some("function".to_string()),
+                    value: None,
+                    full_name: Some(name),
+                    members: None,
+                    cairo_type: None,
+                },
+            ))
+        })
+        .collect()
+}
+
 /// Finds first function ending with `name_suffix`.
 fn find_function<'a>(
     sierra_program: &'a SierraProgram,
@@ -1667,6 +1726,38 @@ mod tests {
         assert_eq!(hash_a, hash_b)
     }
 
+    #[test]
+    fn run_program_with_syscall_handler() {
+        let sierra_program =
+            compile_to_sierra("../cairo_programs/cairo-1-programs/storage_syscalls.cairo");
+        let mut syscall_handler = InMemorySyscallHandler::new();
+        syscall_handler.set_storage_at(Felt252::ZERO, Felt252::from(7), Felt252::ONE);
+        let cairo_run_config = Cairo1RunConfig {
+            layout: LayoutName::all_cairo,
+            serialize_output: true,
+            syscall_handler: Some(syscall_handler.clone()),
+            ..Default::default()
+        };
+        let (_, _, serialized_output) =
+            cairo_run_program(&sierra_program, cairo_run_config).unwrap();
+        assert_eq!(serialized_output, Some("43".to_string()));
+        assert_eq!(
+            syscall_handler.storage_at(Felt252::ZERO, Felt252::from(7)),
+            Felt252::from(43)
+        );
+    }
+
+    #[test]
+    fn run_program_with_syscalls_without_handler() {
+        let sierra_program =
+            compile_to_sierra("../cairo_programs/cairo-1-programs/storage_syscalls.cairo");
+        let cairo_run_config = Cairo1RunConfig {
+            layout: LayoutName::all_cairo,
+            ..Default::default()
+        };
+        assert!(cairo_run_program(&sierra_program, cairo_run_config).is_err());
+    }
+
     #[rstest]
     fn check_output_segment_contains_program_ouput_and_input(
         #[values(true, false)] proof_mode: bool,
@@ -5,8 +5,11 @@ use cairo_lang_compiler::{
     compile_prepared_db, db::RootDatabase, project::setup_project, CompilerConfig,
 };
 use cairo_vm::{
-    air_public_input::PublicInputError, types::layout_name::LayoutName,
-    vm::errors::trace_errors::TraceError, Felt252,
+    air_public_input::PublicInputError,
+    hint_processor::cairo_1_hint_processor::syscall_handler::InMemorySyscallHandler,
+    types::{layout::CairoLayoutParams, layout_name::LayoutName},
+    vm::errors::trace_errors::TraceError,
+    Felt252,
 };
 use clap::{Parser, ValueHint};
 use itertools::Itertools;
@@ -26,6 +29,8 @@ struct Args {
     memory_file: Option<PathBuf>,
     #[clap(long = "layout", default_value = "plain", value_enum)]
     layout: LayoutName,
+    #[clap(long = "cairo_layout_params_file", required_if_eq("layout", "dynamic"))]
+    cairo_layout_params_file: Option<PathBuf>,
     #[clap(long = "proof_mode", value_parser)]
     proof_mode: bool,
     #[clap(long = "air_public_input", requires = "proof_mode")]
@@ -58,6 +63,10 @@ struct Args {
         conflicts_with_all = ["proof_mode", "air_private_input", "air_public_input"]
     )]
     append_return_values: bool,
+    #[clap(long = "profile_output", value_parser)]
+    profile_output: Option<PathBuf>,
+    #[clap(long = "in_memory_syscalls", value_parser)]
+    in_memory_syscalls: bool,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -153,15 +162,25 @@ fn run(args: impl Iterator<Item = String>) -> Result<Option<String>, Error> {
         args.args = process_args(&std::fs::read_to_string(filename)?).unwrap();
     }
 
+    let dynamic_layout_params = match args.cairo_layout_params_file {
+        Some(file) if args.layout == LayoutName::dynamic => {
+            Some(CairoLayoutParams::from_file(&file)?)
+        }
+        _ => None,
+    };
+
     let cairo_run_config = Cairo1RunConfig {
         proof_mode: args.proof_mode,
         serialize_output: args.print_output,
         relocate_mem: args.memory_file.is_some() || args.air_public_input.is_some(),
         layout: args.layout,
+        dynamic_layout_params,
         trace_enabled: args.trace_file.is_some() || args.air_public_input.is_some(),
         args: &args.args.0,
         finalize_builtins: args.air_public_input.is_some() || args.cairo_pie_output.is_some(),
         append_return_values: args.append_return_values,
+        profiling: args.profile_output.is_some(),
+        syscall_handler: args.in_memory_syscalls.then(InMemorySyscallHandler::new),
     };
 
     // Try to parse the file as a sierra program
@@ -221,6 +240,12 @@ fn run(args: impl Iterator<Item = String>) -> Result<Option<String>, Error> {
         std::fs::write(file_path, json)?;
     }
 
+    if let Some(ref profile_path) = args.profile_output {
+        if let Some(profile) = runner.get_profile() {
+            profile.write_to_file(profile_path)?;
+        }
+    }
+
     if let Some(ref file_path) = args.cairo_pie_output {
         runner.get_cairo_pie()?.write_zip_file(file_path)?
     }
@@ -542,4 +567,18 @@ mod tests {
         let args = args.iter().cloned().map(String::from);
         assert_matches!(run(args), Err(Error::ArgumentsSizeMismatch { expected, actual }) if expected == 2 && actual == 3);
     }
+
+    #[test]
+    fn test_run_in_memory_syscalls() {
+        let args = [
+            "cairo1-run",
+            "../cairo_programs/cairo-1-programs/storage_syscalls.cairo",
+            "--layout",
+            "all_cairo",
+            "--print_output",
+            "--in_memory_syscalls",
+        ];
+        let args = args.iter().cloned().map(String::from);
+        assert_matches!(run(args), Ok(Some(res)) if res == "42");
+    }
 }
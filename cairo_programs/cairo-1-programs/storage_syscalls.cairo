@@ -0,0 +1,10 @@
+use starknet::{SyscallResultTrait, storage_access::storage_address_from_base_and_offset};
+use starknet::storage_access::storage_base_address_const;
+use starknet::syscalls::{storage_read_syscall, storage_write_syscall};
+
+fn main() -> felt252 {
+    let address = storage_address_from_base_and_offset(storage_base_address_const::<7>(), 0);
+    let value = storage_read_syscall(0, address).unwrap_syscall();
+    storage_write_syscall(0, address, value + 42).unwrap_syscall();
+    storage_read_syscall(0, address).unwrap_syscall()
+}
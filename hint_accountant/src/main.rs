@@ -1,12 +1,13 @@
 #![deny(warnings)]
 #![forbid(unsafe_code)]
-use std::fs::{self, File};
-use std::io::BufReader;
+use std::fs;
 
 use cairo_vm::stdlib::collections::{HashMap, HashSet};
 use cairo_vm::{
     hint_processor::{
-        builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        builtin_hint_processor::{
+            builtin_hint_processor_definition::BuiltinHintProcessor, hint_whitelist::HintWhitelist,
+        },
         hint_processor_definition::HintProcessorLogic,
     },
     serde::deserialize_program::ApTracking,
@@ -14,22 +15,6 @@ use cairo_vm::{
     vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
 };
 
-use serde::Deserialize;
-use serde_json::Value;
-
-#[derive(Deserialize)]
-struct AllowedHintExpression {
-    #[serde(rename(deserialize = "allowed_expressions"))]
-    _allowed_expressions: Option<Value>,
-    hint_lines: Vec<Box<str>>,
-}
-
-#[derive(Deserialize)]
-struct Whitelist {
-    #[serde(rename(deserialize = "allowed_reference_expressions_for_hint"))]
-    allowed_hint_expressions: Vec<AllowedHintExpression>,
-}
-
 const CAIRO_LANG_PATH: &str = "cairo-lang/src/starkware/starknet/security/whitelists";
 
 fn run() {
@@ -37,16 +22,14 @@ fn run() {
     let whitelist_paths = fs::read_dir(CAIRO_LANG_PATH).expect(
         "Failed to read whitelist directory from cairo-lang, did you forget to clone it?\n",
     );
-    let mut whitelists = Vec::new();
+    let mut whitelist = HintWhitelist::new();
     for path in whitelist_paths {
         let path = path.expect("Failed to get path").path();
         if path.to_str().unwrap_or_default().ends_with(".json") {
-            let file = File::open(path).expect("Failed to open whitelist file");
-            let mut reader = BufReader::new(file);
-
-            let whitelist_file: Whitelist =
-                serde_json::from_reader(&mut reader).expect("Failed to parse whitelist");
-            whitelists.push(whitelist_file.allowed_hint_expressions);
+            let bytes = fs::read(path).expect("Failed to read whitelist file");
+            let whitelist_file =
+                HintWhitelist::from_json(&bytes).expect("Failed to parse whitelist");
+            whitelist.extend(whitelist_file.iter());
         }
     }
     let mut vm = VirtualMachine::new(false);
@@ -58,10 +41,8 @@ fn run() {
         ExecutionScopes::new(),
         HashMap::new(),
     );
-    let missing_hints: HashSet<_> = whitelists
-        .into_iter()
-        .flatten()
-        .map(|ahe| ahe.hint_lines.join("\n"))
+    let missing_hints: HashSet<_> = whitelist
+        .iter()
         .filter(|h| {
             let hint_data = hint_executor
                 .compile_hint(h, &ap_tracking_data, &reference_ids, &references)
@@ -7,6 +7,7 @@ use crate::{
         collections::HashMap,
         prelude::{String, Vec},
     },
+    types::layout::CairoLayoutParams,
     vm::{
         errors::{trace_errors::TraceError, vm_errors::VirtualMachineError},
         trace::trace_entry::RelocatedTraceEntry,
@@ -89,7 +90,6 @@ impl From<(usize, usize)> for MemorySegmentAddresses {
     }
 }
 
-#[allow(clippy::manual_non_exhaustive)]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PublicInput<'a> {
     pub layout: &'a str,
@@ -98,14 +98,16 @@ pub struct PublicInput<'a> {
     pub n_steps: usize,
     pub memory_segments: HashMap<&'a str, MemorySegmentAddresses>,
     pub public_memory: Vec<PublicMemoryEntry>,
-    #[serde(skip_deserializing)] // This is set to None by default so we can skip it
-    dynamic_params: (),
+    /// Params used to build the layout, only present when running with the dynamic layout
+    #[serde(default)]
+    pub dynamic_params: Option<CairoLayoutParams>,
 }
 
 impl<'a> PublicInput<'a> {
     pub fn new(
         memory: &[Option<Felt252>],
         layout: &'a str,
+        dynamic_params: Option<&CairoLayoutParams>,
         public_memory_addresses: &[(usize, usize)],
         memory_segment_addresses: HashMap<&'static str, (usize, usize)>,
         trace: &[RelocatedTraceEntry],
@@ -134,7 +136,7 @@ impl<'a> PublicInput<'a> {
 
         Ok(PublicInput {
             layout,
-            dynamic_params: (),
+            dynamic_params: dynamic_params.cloned(),
             rc_min,
             rc_max,
             n_steps: trace.len(),
@@ -219,4 +221,38 @@ mod tests {
             deserialized_public_input.public_memory
         );
     }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    #[case(None)]
+    #[case(Some(serde_json::from_slice(include_bytes!("tests/cairo_layout_params_file.json")).unwrap()))]
+    fn serialize_and_deserialize_dynamic_params(#[case] dynamic_params: Option<CairoLayoutParams>) {
+        let trace = [
+            RelocatedTraceEntry {
+                pc: 1,
+                ap: 5,
+                fp: 5,
+            },
+            RelocatedTraceEntry {
+                pc: 3,
+                ap: 7,
+                fp: 5,
+            },
+        ];
+        let public_input = PublicInput::new(
+            &[None, Some(Felt252::from(10))],
+            "dynamic",
+            dynamic_params.as_ref(),
+            &[(1, 0)],
+            HashMap::new(),
+            &trace,
+            (0, 1),
+        )
+        .unwrap();
+        assert_eq!(public_input.dynamic_params, dynamic_params);
+        let serialized_public_input = public_input.serialize_json().unwrap();
+        let deserialized_public_input: PublicInput =
+            serde_json::from_str(&serialized_public_input).unwrap();
+        assert_eq!(deserialized_public_input.dynamic_params, dynamic_params);
+    }
 }
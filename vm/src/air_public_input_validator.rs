@@ -0,0 +1,667 @@
+//! Checks that an AIR public input is consistent with the trace, memory and private input of the
+//! run that produced it, so that errors are caught before spending time on a proof.
+
+use crate::stdlib::{collections::HashMap, prelude::*};
+use crate::Felt252;
+use core::fmt;
+use num_traits::ToPrimitive;
+use thiserror_no_std::Error;
+
+use crate::{
+    air_private_input::{AirPrivateInput, PrivateInput},
+    air_public_input::PublicInput,
+    types::{builtin_name::BuiltinName, layout_name::LayoutName, program::Program},
+    vm::{
+        decoding::decoder::decode_instruction,
+        runners::{
+            builtin_runner::{
+                BuiltinRunner, RangeCheckBuiltinRunner, RC_N_PARTS_96, RC_N_PARTS_STANDARD,
+            },
+            cairo_runner::{CairoRunner, RunnerMode},
+        },
+        trace::trace_entry::RelocatedTraceEntry,
+    },
+};
+
+#[cfg(feature = "std")]
+use {
+    crate::{
+        air_private_input::AirPrivateInputSerializable,
+        cairo_run::{read_encoded_memory, read_encoded_trace, DecodeTraceError},
+    },
+    std::{fs, io, path::Path},
+};
+
+/// An inconsistency found in an AIR public input
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PublicInputDiagnostic {
+    #[error("Segment {segment}: begin address {begin_addr} is after the stop pointer {stop_ptr}")]
+    SegmentBeginAfterStop {
+        segment: String,
+        begin_addr: usize,
+        stop_ptr: usize,
+    },
+    #[error("Segment {segment}: addresses {begin_addr}..{stop_ptr} are outside of the relocated memory, which holds addresses 1..{memory_end}")]
+    SegmentOutOfMemory {
+        segment: String,
+        begin_addr: usize,
+        stop_ptr: usize,
+        memory_end: usize,
+    },
+    #[error("Public memory at address {address}: public input has {} but memory has {}", Value(.public_value), Value(.memory_value))]
+    PublicMemoryMismatch {
+        address: usize,
+        public_value: Option<Felt252>,
+        memory_value: Option<Felt252>,
+    },
+    #[error("n_steps {0} is not a power of two")]
+    NStepsNotPowerOfTwo(usize),
+    #[error("n_steps {n_steps} doesn't match the trace length {trace_length}")]
+    NStepsTraceMismatch { n_steps: usize, trace_length: usize },
+    #[error("Step {step}: no instruction can be decoded at pc {pc}")]
+    InvalidInstruction { step: usize, pc: usize },
+    #[error("Range check limits ({rc_min}, {rc_max}) don't match the ({expected_rc_min}, {expected_rc_max}) used by the trace and range check segments")]
+    RangeCheckLimitsMismatch {
+        rc_min: isize,
+        rc_max: isize,
+        expected_rc_min: isize,
+        expected_rc_max: isize,
+    },
+    #[error("Layout {layout} can't be built: {error}")]
+    InvalidLayout { layout: String, error: String },
+    #[error("Segment {segment} is not a builtin of layout {layout}")]
+    SegmentNotInLayout { segment: String, layout: String },
+    #[error("Builtin {builtin} uses {used} cells but layout {layout} allocates {allocated} for {n_steps} steps")]
+    BuiltinSegmentTooLarge {
+        builtin: BuiltinName,
+        used: usize,
+        allocated: usize,
+        layout: String,
+        n_steps: usize,
+    },
+    #[error("Private input of builtin {0} has no segment in the public input")]
+    PrivateInputWithoutSegment(BuiltinName),
+    #[error("Private input of builtin {builtin}, instance {index}: address {address} is outside of the builtin's segment")]
+    PrivateInputOutOfSegment {
+        builtin: BuiltinName,
+        index: usize,
+        address: usize,
+    },
+    #[error("Private input of builtin {builtin}, instance {index}: address {address} has {} in the private input but memory has {}", Value(&Some(*.private_value)), Value(.memory_value))]
+    PrivateInputMismatch {
+        builtin: BuiltinName,
+        index: usize,
+        address: usize,
+        private_value: Felt252,
+        memory_value: Option<Felt252>,
+    },
+}
+
+// Displays a memory value, which may be missing
+struct Value<'a>(&'a Option<Felt252>);
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(value) => write!(f, "{value}"),
+            None => write!(f, "no value"),
+        }
+    }
+}
+
+/// Checks that the public input is consistent with the relocated trace and memory of the run, and
+/// with its private input if given:
+/// * The segments' begin and stop addresses are inside the relocated memory
+/// * The public memory entries match the memory
+/// * `n_steps` is a power of two that matches the trace length
+/// * `rc_min` and `rc_max` match the instruction offsets in the trace and the values of the range
+///   check segments
+/// * The builtin segments fit in the cells the layout allocates for `n_steps` steps
+/// * The private input's builtin instances match the memory
+///
+/// Returns every inconsistency found, the public input is valid if there are none.
+pub fn validate_public_input(
+    public_input: &PublicInput,
+    trace: &[RelocatedTraceEntry],
+    memory: &[Option<Felt252>],
+    private_input: Option<&AirPrivateInput>,
+) -> Vec<PublicInputDiagnostic> {
+    let mut diagnostics = Vec::new();
+    check_segments(public_input, memory, &mut diagnostics);
+    check_public_memory(public_input, memory, &mut diagnostics);
+    check_n_steps(public_input, trace, &mut diagnostics);
+    check_range_check_limits(public_input, trace, memory, &mut diagnostics);
+    if let Some(builtin_runners) = layout_builtins(public_input, &mut diagnostics) {
+        check_builtin_ratios(public_input, &builtin_runners, &mut diagnostics);
+        if let Some(private_input) = private_input {
+            check_private_input(
+                public_input,
+                private_input,
+                &builtin_runners,
+                memory,
+                &mut diagnostics,
+            );
+        }
+    }
+    diagnostics
+}
+
+/// Reads the files written by a proof mode run and validates them with [`validate_public_input`].
+/// The trace and memory files are read with [`read_encoded_trace`] and [`read_encoded_memory`], and
+/// the public and private inputs are in the JSON format used by the prover.
+#[cfg(feature = "std")]
+pub fn validate_public_input_files(
+    trace_path: &Path,
+    memory_path: &Path,
+    public_input_path: &Path,
+    private_input_path: Option<&Path>,
+) -> io::Result<Vec<PublicInputDiagnostic>> {
+    let invalid_data = |error: DecodeTraceError| io::Error::new(io::ErrorKind::InvalidData, error);
+    let trace = read_encoded_trace(&fs::read(trace_path)?).map_err(invalid_data)?;
+    let memory = read_encoded_memory(&fs::read(memory_path)?, None).map_err(invalid_data)?;
+    let public_input = fs::read_to_string(public_input_path)?;
+    let public_input: PublicInput = serde_json::from_str(&public_input)?;
+    let private_input = private_input_path
+        .map(|path| -> io::Result<AirPrivateInput> {
+            let private_input: AirPrivateInputSerializable =
+                serde_json::from_slice(&fs::read(path)?)?;
+            Ok(private_input.into())
+        })
+        .transpose()?;
+    Ok(validate_public_input(
+        &public_input,
+        &trace,
+        &memory,
+        private_input.as_ref(),
+    ))
+}
+
+fn check_segments(
+    public_input: &PublicInput,
+    memory: &[Option<Felt252>],
+    diagnostics: &mut Vec<PublicInputDiagnostic>,
+) {
+    let mut segments: Vec<_> = public_input.memory_segments.iter().collect();
+    segments.sort_by_key(|(_, segment)| (segment.begin_addr, segment.stop_ptr));
+    for (name, segment) in segments {
+        let (begin_addr, stop_ptr) = (segment.begin_addr, segment.stop_ptr);
+        if begin_addr > stop_ptr {
+            diagnostics.push(PublicInputDiagnostic::SegmentBeginAfterStop {
+                segment: name.to_string(),
+                begin_addr,
+                stop_ptr,
+            });
+        }
+        // Relocated memory starts at address 1, and builtin stop pointers point past their
+        // segment's last cell. Empty segments, such as those of unused builtins, may be placed
+        // past the last cell written
+        if begin_addr == 0 || (begin_addr < stop_ptr && stop_ptr > memory.len()) {
+            diagnostics.push(PublicInputDiagnostic::SegmentOutOfMemory {
+                segment: name.to_string(),
+                begin_addr,
+                stop_ptr,
+                memory_end: memory.len(),
+            });
+        }
+    }
+}
+
+fn check_public_memory(
+    public_input: &PublicInput,
+    memory: &[Option<Felt252>],
+    diagnostics: &mut Vec<PublicInputDiagnostic>,
+) {
+    for entry in &public_input.public_memory {
+        let memory_value = memory.get(entry.address).copied().flatten();
+        if entry.value != memory_value {
+            diagnostics.push(PublicInputDiagnostic::PublicMemoryMismatch {
+                address: entry.address,
+                public_value: entry.value,
+                memory_value,
+            });
+        }
+    }
+}
+
+fn check_n_steps(
+    public_input: &PublicInput,
+    trace: &[RelocatedTraceEntry],
+    diagnostics: &mut Vec<PublicInputDiagnostic>,
+) {
+    if !public_input.n_steps.is_power_of_two() {
+        diagnostics.push(PublicInputDiagnostic::NStepsNotPowerOfTwo(
+            public_input.n_steps,
+        ));
+    }
+    if public_input.n_steps != trace.len() {
+        diagnostics.push(PublicInputDiagnostic::NStepsTraceMismatch {
+            n_steps: public_input.n_steps,
+            trace_length: trace.len(),
+        });
+    }
+}
+
+// Replays the range check limits kept by the vm: the biased offsets of each executed instruction,
+// and the parts the range check builtins split their values into
+fn check_range_check_limits(
+    public_input: &PublicInput,
+    trace: &[RelocatedTraceEntry],
+    memory: &[Option<Felt252>],
+    diagnostics: &mut Vec<PublicInputDiagnostic>,
+) {
+    const OFFSET_BIAS: isize = 1 << 15;
+    let mut rc_values = Vec::new();
+    let mut valid_trace = true;
+    for (step, entry) in trace.iter().enumerate() {
+        let instruction = memory
+            .get(entry.pc)
+            .copied()
+            .flatten()
+            .and_then(|word| word.to_u64())
+            .and_then(|word| decode_instruction(word).ok());
+        match instruction {
+            Some(instruction) => rc_values.extend(
+                [instruction.off0, instruction.off1, instruction.off2]
+                    .map(|offset| offset + OFFSET_BIAS),
+            ),
+            None => {
+                diagnostics.push(PublicInputDiagnostic::InvalidInstruction { step, pc: entry.pc });
+                valid_trace = false;
+            }
+        }
+    }
+    // The limits can't be replayed without every instruction
+    if !valid_trace {
+        return;
+    }
+
+    for (name, segment) in &public_input.memory_segments {
+        let parts: fn(&Felt252) -> Vec<usize> = match BuiltinName::from_str(name) {
+            Some(BuiltinName::range_check) => |value| {
+                RangeCheckBuiltinRunner::<RC_N_PARTS_STANDARD>::range_check_parts(value).collect()
+            },
+            Some(BuiltinName::range_check96) => {
+                |value| RangeCheckBuiltinRunner::<RC_N_PARTS_96>::range_check_parts(value).collect()
+            }
+            _ => continue,
+        };
+        let cells = memory
+            .get(segment.begin_addr..segment.stop_ptr)
+            .unwrap_or_default();
+        for value in cells.iter().flatten() {
+            rc_values.extend(parts(value).into_iter().map(|part| part as isize));
+        }
+    }
+
+    let expected_rc_min = rc_values.iter().copied().min().unwrap_or_default();
+    let expected_rc_max = rc_values.iter().copied().max().unwrap_or_default();
+    if (public_input.rc_min, public_input.rc_max) != (expected_rc_min, expected_rc_max) {
+        diagnostics.push(PublicInputDiagnostic::RangeCheckLimitsMismatch {
+            rc_min: public_input.rc_min,
+            rc_max: public_input.rc_max,
+            expected_rc_min,
+            expected_rc_max,
+        });
+    }
+}
+
+// Returns the builtins of the public input's layout, as created for a proof mode run
+fn layout_builtins(
+    public_input: &PublicInput,
+    diagnostics: &mut Vec<PublicInputDiagnostic>,
+) -> Option<Vec<BuiltinRunner>> {
+    let invalid_layout = |error: String| PublicInputDiagnostic::InvalidLayout {
+        layout: public_input.layout.to_string(),
+        error,
+    };
+    let Some(layout) = LayoutName::ALL
+        .into_iter()
+        .find(|layout| layout.to_str() == public_input.layout)
+    else {
+        diagnostics.push(invalid_layout("unknown layout".to_string()));
+        return None;
+    };
+    let runner = CairoRunner::new_v2(
+        &Program::default(),
+        layout,
+        public_input.dynamic_params.clone(),
+        RunnerMode::ProofModeCanonical,
+        false,
+    )
+    .and_then(|mut runner| runner.initialize_builtins(false).map(|_| runner));
+    match runner {
+        Ok(runner) => Some(runner.vm.builtin_runners),
+        Err(error) => {
+            diagnostics.push(invalid_layout(error.to_string()));
+            None
+        }
+    }
+}
+
+fn check_builtin_ratios(
+    public_input: &PublicInput,
+    builtin_runners: &[BuiltinRunner],
+    diagnostics: &mut Vec<PublicInputDiagnostic>,
+) {
+    let builtins: HashMap<_, _> = builtin_runners
+        .iter()
+        .map(|builtin| (builtin.name().to_str(), builtin))
+        .collect();
+    let mut segments: Vec<_> = public_input.memory_segments.iter().collect();
+    segments.sort_by_key(|(_, segment)| segment.begin_addr);
+    for (name, segment) in segments {
+        if matches!(*name, "program" | "execution") {
+            continue;
+        }
+        let Some(builtin) = builtins.get(name) else {
+            diagnostics.push(PublicInputDiagnostic::SegmentNotInLayout {
+                segment: name.to_string(),
+                layout: public_input.layout.to_string(),
+            });
+            continue;
+        };
+        // Builtins without a ratio, such as the output builtin, take up as many cells as they use
+        let Some(ratio) = builtin.ratio().filter(|ratio| *ratio != 0) else {
+            continue;
+        };
+        let used = segment.stop_ptr.saturating_sub(segment.begin_addr);
+        let allocated =
+            builtin.cells_per_instance() as usize * (public_input.n_steps / ratio as usize);
+        if used > allocated {
+            diagnostics.push(PublicInputDiagnostic::BuiltinSegmentTooLarge {
+                builtin: builtin.name(),
+                used,
+                allocated,
+                layout: public_input.layout.to_string(),
+                n_steps: public_input.n_steps,
+            });
+        }
+    }
+}
+
+fn check_private_input(
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+    builtin_runners: &[BuiltinRunner],
+    memory: &[Option<Felt252>],
+    diagnostics: &mut Vec<PublicInputDiagnostic>,
+) {
+    let mut builtins: Vec<_> = private_input.0.iter().collect();
+    builtins.sort_by_key(|(name, _)| name.to_str());
+    for (name, inputs) in builtins {
+        let Some(segment) = public_input.memory_segments.get(name.to_str()) else {
+            diagnostics.push(PublicInputDiagnostic::PrivateInputWithoutSegment(*name));
+            continue;
+        };
+        // Missing builtins are reported by the ratio checks
+        let Some(builtin) = builtin_runners
+            .iter()
+            .find(|builtin| builtin.name() == *name)
+        else {
+            continue;
+        };
+        let cells_per_instance = builtin.cells_per_instance() as usize;
+        for input in inputs {
+            let Some((index, cells)) = instance_input_cells(input) else {
+                continue;
+            };
+            let instance_addr = segment.begin_addr + index * cells_per_instance;
+            for (offset, private_value) in cells.into_iter().enumerate() {
+                let address = instance_addr + offset;
+                if address >= segment.stop_ptr {
+                    diagnostics.push(PublicInputDiagnostic::PrivateInputOutOfSegment {
+                        builtin: *name,
+                        index,
+                        address,
+                    });
+                    break;
+                }
+                let memory_value = memory.get(address).copied().flatten();
+                if memory_value != Some(private_value) {
+                    diagnostics.push(PublicInputDiagnostic::PrivateInputMismatch {
+                        builtin: *name,
+                        index,
+                        address,
+                        private_value,
+                        memory_value,
+                    });
+                }
+            }
+        }
+    }
+}
+
+// Returns the index of a builtin instance and the values of its input cells, in memory order.
+// Mod builtin instances span several segments and are not checked
+fn instance_input_cells(input: &PrivateInput) -> Option<(usize, Vec<Felt252>)> {
+    Some(match input {
+        PrivateInput::Value(input) => (input.index, vec![input.value]),
+        PrivateInput::Pair(input) => (input.index, vec![input.x, input.y]),
+        PrivateInput::EcOp(input) => (
+            input.index,
+            vec![input.p_x, input.p_y, input.q_x, input.q_y, input.m],
+        ),
+        PrivateInput::PoseidonState(input) => (
+            input.index,
+            vec![input.input_s0, input.input_s1, input.input_s2],
+        ),
+        PrivateInput::KeccakState(input) => (
+            input.index,
+            vec![
+                input.input_s0,
+                input.input_s1,
+                input.input_s2,
+                input.input_s3,
+                input.input_s4,
+                input.input_s5,
+                input.input_s6,
+                input.input_s7,
+            ],
+        ),
+        PrivateInput::Signature(input) => (input.index, vec![input.pubkey, input.msg]),
+        PrivateInput::Mod(_) => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use rstest::rstest;
+
+    #[cfg(feature = "std")]
+    use crate::{
+        cairo_run::{cairo_run_program, CairoRunConfig},
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        vm::decoding::assembler::assemble_for_proof,
+    };
+
+    // Writes 7 to the output and range checks 300
+    #[cfg(feature = "std")]
+    const PROOF_PROGRAM: &str = "
+        %builtins output range_check
+        __start__:
+            ap += 2
+            call rel main
+        __end__:
+            jmp rel 0
+        main:
+            [ap + 0] = 7, ap++
+            [ap + -1] = [[fp + -4] + 0]
+            [ap + 0] = 300, ap++
+            [ap + -1] = [[fp + -3] + 0]
+            [ap + 0] = [fp + -4] + 1, ap++
+            [ap + 0] = [fp + -3] + 1, ap++
+            ret
+    ";
+
+    #[cfg(feature = "std")]
+    fn run_proof_program(layout: LayoutName) -> CairoRunner {
+        let config = CairoRunConfig {
+            proof_mode: true,
+            relocate_mem: true,
+            trace_enabled: true,
+            layout,
+            ..Default::default()
+        };
+        cairo_run_program(
+            &assemble_for_proof(PROOF_PROGRAM).unwrap(),
+            &config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap()
+    }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    #[case(LayoutName::small)]
+    #[case(LayoutName::recursive)]
+    #[case(LayoutName::all_cairo)]
+    fn validate_public_input_of_proof_run(#[case] layout: LayoutName) {
+        let runner = run_proof_program(layout);
+        let public_input = runner.get_air_public_input().unwrap();
+        let private_input = runner.get_air_private_input();
+        assert_eq!(
+            validate_public_input(
+                &public_input,
+                runner.relocated_trace.as_ref().unwrap(),
+                &runner.relocated_memory,
+                Some(&private_input),
+            ),
+            vec![]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn validate_tampered_public_input() {
+        let runner = run_proof_program(LayoutName::all_cairo);
+        let trace = runner.relocated_trace.as_ref().unwrap();
+        let memory = &runner.relocated_memory;
+        let mut public_input = runner.get_air_public_input().unwrap();
+        let n_steps = public_input.n_steps;
+        public_input.n_steps = n_steps + 1;
+        public_input.rc_max += 1;
+        public_input.public_memory[0].value = Some(Felt252::from(42));
+        let address = public_input.public_memory[0].address;
+        let execution = public_input.memory_segments.get_mut("execution").unwrap();
+        execution.stop_ptr = memory.len() + 1;
+        let execution_begin = execution.begin_addr;
+
+        assert_eq!(
+            validate_public_input(&public_input, trace, memory, None),
+            vec![
+                PublicInputDiagnostic::SegmentOutOfMemory {
+                    segment: "execution".to_string(),
+                    begin_addr: execution_begin,
+                    stop_ptr: memory.len() + 1,
+                    memory_end: memory.len(),
+                },
+                PublicInputDiagnostic::PublicMemoryMismatch {
+                    address,
+                    public_value: Some(Felt252::from(42)),
+                    memory_value: memory[address],
+                },
+                PublicInputDiagnostic::NStepsNotPowerOfTwo(n_steps + 1),
+                PublicInputDiagnostic::NStepsTraceMismatch {
+                    n_steps: n_steps + 1,
+                    trace_length: n_steps,
+                },
+                PublicInputDiagnostic::RangeCheckLimitsMismatch {
+                    rc_min: public_input.rc_min,
+                    rc_max: public_input.rc_max,
+                    expected_rc_min: public_input.rc_min,
+                    expected_rc_max: public_input.rc_max - 1,
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn validate_builtin_segment_too_large() {
+        let runner = run_proof_program(LayoutName::all_cairo);
+        let mut public_input = runner.get_air_public_input().unwrap();
+        public_input.n_steps = 1;
+        let diagnostics = validate_public_input(
+            &public_input,
+            runner.relocated_trace.as_ref().unwrap(),
+            &runner.relocated_memory,
+            None,
+        );
+        assert!(
+            diagnostics.contains(&PublicInputDiagnostic::BuiltinSegmentTooLarge {
+                builtin: BuiltinName::range_check,
+                used: 1,
+                allocated: 0,
+                layout: "all_cairo".to_string(),
+                n_steps: 1,
+            })
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn validate_private_input_mismatch() {
+        let runner = run_proof_program(LayoutName::all_cairo);
+        let public_input = runner.get_air_public_input().unwrap();
+        let mut private_input = runner.get_air_private_input();
+        let Some(PrivateInput::Value(input)) = private_input
+            .0
+            .get_mut(&BuiltinName::range_check)
+            .and_then(|inputs| inputs.first_mut())
+        else {
+            panic!("missing range check private input");
+        };
+        input.value = Felt252::from(301);
+        let address = public_input.memory_segments["range_check"].begin_addr;
+        assert_eq!(
+            validate_public_input(
+                &public_input,
+                runner.relocated_trace.as_ref().unwrap(),
+                &runner.relocated_memory,
+                Some(&private_input),
+            ),
+            vec![PublicInputDiagnostic::PrivateInputMismatch {
+                builtin: BuiltinName::range_check,
+                index: 0,
+                address,
+                private_value: Felt252::from(301),
+                memory_value: Some(Felt252::from(300)),
+            }]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn validate_unknown_layout() {
+        let runner = run_proof_program(LayoutName::all_cairo);
+        let mut public_input = runner.get_air_public_input().unwrap();
+        public_input.layout = "no_layout";
+        assert_eq!(
+            validate_public_input(
+                &public_input,
+                runner.relocated_trace.as_ref().unwrap(),
+                &runner.relocated_memory,
+                None,
+            ),
+            vec![PublicInputDiagnostic::InvalidLayout {
+                layout: "no_layout".to_string(),
+                error: "unknown layout".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn display_missing_memory_value() {
+        assert_eq!(
+            PublicInputDiagnostic::PublicMemoryMismatch {
+                address: 3,
+                public_value: Some(Felt252::from(7)),
+                memory_value: None,
+            }
+            .to_string(),
+            "Public memory at address 3: public input has 7 but memory has no value"
+        );
+    }
+}
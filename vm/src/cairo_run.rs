@@ -1,12 +1,23 @@
+#[cfg(feature = "std")]
+use crate::vm::{errors::trace_errors::TraceError, trace::trace_sink::FileTraceSink};
 use crate::{
     hint_processor::hint_processor_definition::HintProcessor,
-    types::{builtin_name::BuiltinName, layout_name::LayoutName, program::Program},
+    stdlib::prelude::*,
+    types::{
+        builtin_name::BuiltinName, layout::CairoLayoutParams, layout_name::LayoutName,
+        program::Program, relocatable::Relocatable,
+    },
     vm::{
         errors::{
             cairo_run_errors::CairoRunError, runner_errors::RunnerError, vm_exception::VmException,
         },
-        runners::{cairo_pie::CairoPie, cairo_runner::CairoRunner},
+        runners::{
+            builtin_runner::CustomBuiltin,
+            cairo_pie::CairoPie,
+            cairo_runner::{CairoRunner, RunnerMode},
+        },
         security::verify_secure_runner,
+        trace::trace_entry::RelocatedTraceEntry,
     },
 };
 
@@ -16,6 +27,8 @@ use bincode::enc::write::Writer;
 use thiserror_no_std::Error;
 
 use crate::types::exec_scope::ExecutionScopes;
+#[cfg(feature = "profiler")]
+use crate::vm::{errors::vm_errors::VirtualMachineError, hint_profiler::HintProfiler};
 #[cfg(feature = "test_utils")]
 use arbitrary::{self, Arbitrary};
 
@@ -26,10 +39,33 @@ pub struct CairoRunConfig<'a> {
     pub trace_enabled: bool,
     pub relocate_mem: bool,
     pub layout: LayoutName,
+    /// Params used to build the layout, required when `layout` is [`LayoutName::dynamic`]
+    pub dynamic_layout_params: Option<CairoLayoutParams>,
     pub proof_mode: bool,
     pub secure_run: Option<bool>,
     pub disable_trace_padding: bool,
     pub allow_missing_builtins: Option<bool>,
+    /// Builtins implemented outside of the vm, used by the programs that declare them in execution mode.
+    /// See [`CairoRunner::add_custom_builtin`]
+    #[cfg_attr(feature = "test_utils", arbitrary(value = Vec::new()))]
+    pub custom_builtins: Vec<Box<dyn CustomBuiltin>>,
+    /// Hands the trace over to a [`FileTraceSink`] during the run instead of keeping it in memory,
+    /// it can then be written with [`CairoRunner::write_relocated_trace`]. Requires `trace_enabled`
+    #[cfg(feature = "std")]
+    pub stream_trace: bool,
+    /// Keeps the trace of the run in memory, so that the lines it executed can be read with
+    /// [`CairoRunner::get_coverage`]. Enables the trace and takes precedence over `stream_trace`
+    pub coverage: bool,
+    /// Enables the vm's profiler, see [`CairoRunner::get_profile`]
+    #[cfg(feature = "profiler")]
+    pub profiling: bool,
+    /// Enables the vm's hint profiler, see [`HintProfiler`]
+    #[cfg(feature = "profiler")]
+    pub hint_profiling: bool,
+    /// Logs each hint execution as a JSON line to this file, enables the hint profiler
+    #[cfg(all(feature = "profiler", feature = "std"))]
+    #[cfg_attr(feature = "test_utils", arbitrary(value = None))]
+    pub hint_log_file: Option<&'a std::path::Path>,
 }
 
 impl<'a> Default for CairoRunConfig<'a> {
@@ -39,12 +75,51 @@ impl<'a> Default for CairoRunConfig<'a> {
             trace_enabled: false,
             relocate_mem: false,
             layout: LayoutName::plain,
+            dynamic_layout_params: None,
             proof_mode: false,
             secure_run: None,
             disable_trace_padding: false,
             allow_missing_builtins: None,
+            custom_builtins: Vec::new(),
+            #[cfg(feature = "std")]
+            stream_trace: false,
+            coverage: false,
+            #[cfg(feature = "profiler")]
+            profiling: false,
+            #[cfg(feature = "profiler")]
+            hint_profiling: false,
+            #[cfg(all(feature = "profiler", feature = "std"))]
+            hint_log_file: None,
+        }
+    }
+}
+
+impl<'a> CairoRunConfig<'a> {
+    /// The mode the runner is created with for this config
+    pub fn runner_mode(&self) -> RunnerMode {
+        if self.proof_mode {
+            RunnerMode::ProofModeCanonical
+        } else {
+            RunnerMode::ExecutionMode
         }
     }
+
+    fn trace_enabled(&self) -> bool {
+        self.trace_enabled || self.coverage
+    }
+
+    #[cfg(feature = "std")]
+    fn stream_trace(&self) -> bool {
+        self.stream_trace && !self.coverage
+    }
+
+    fn secure_run(&self) -> bool {
+        self.secure_run.unwrap_or(!self.proof_mode)
+    }
+
+    fn allow_missing_builtins(&self) -> bool {
+        self.allow_missing_builtins.unwrap_or(self.proof_mode)
+    }
 }
 
 /// Runs a program with a customized execution scope.
@@ -54,30 +129,62 @@ pub fn cairo_run_program_with_initial_scope(
     hint_processor: &mut dyn HintProcessor,
     exec_scopes: ExecutionScopes,
 ) -> Result<CairoRunner, CairoRunError> {
-    let secure_run = cairo_run_config
-        .secure_run
-        .unwrap_or(!cairo_run_config.proof_mode);
+    let (mut cairo_runner, end) = initialize_cairo_run(program, cairo_run_config, exec_scopes)?;
 
-    let allow_missing_builtins = cairo_run_config
-        .allow_missing_builtins
-        .unwrap_or(cairo_run_config.proof_mode);
+    cairo_runner
+        .run_until_pc(end, hint_processor)
+        .map_err(|err| VmException::from_vm_error(&cairo_runner, err))?;
+
+    finalize_cairo_run(&mut cairo_runner, cairo_run_config, hint_processor)?;
 
-    let mut cairo_runner = CairoRunner::new(
+    Ok(cairo_runner)
+}
+
+/// Creates the runner of a program and initializes it as configured, returning it along with the
+/// pc at which the execution ends.
+///
+/// Together with [`finalize_cairo_run`], it allows driving the execution in between, e.g. one step
+/// at a time, while setting up the run like [`cairo_run_program`] does.
+pub fn initialize_cairo_run(
+    program: &Program,
+    cairo_run_config: &CairoRunConfig,
+    exec_scopes: ExecutionScopes,
+) -> Result<(CairoRunner, Relocatable), CairoRunError> {
+    let mut cairo_runner = CairoRunner::new_v2(
         program,
         cairo_run_config.layout,
-        cairo_run_config.proof_mode,
-        cairo_run_config.trace_enabled,
+        cairo_run_config.dynamic_layout_params.clone(),
+        cairo_run_config.runner_mode(),
+        cairo_run_config.trace_enabled(),
     )?;
 
     cairo_runner.exec_scopes = exec_scopes;
+    add_custom_builtins(&mut cairo_runner, cairo_run_config);
+    #[cfg(feature = "std")]
+    if cairo_run_config.stream_trace() {
+        set_file_trace_sink(&mut cairo_runner)?;
+    }
 
-    let end = cairo_runner.initialize(allow_missing_builtins)?;
+    let end = cairo_runner.initialize(cairo_run_config.allow_missing_builtins())?;
     // check step calculation
 
-    cairo_runner
-        .run_until_pc(end, hint_processor)
-        .map_err(|err| VmException::from_vm_error(&cairo_runner, err))?;
+    #[cfg(feature = "profiler")]
+    if cairo_run_config.profiling {
+        cairo_runner.vm.enable_profiler();
+    }
+    #[cfg(feature = "profiler")]
+    set_hint_profiler(&mut cairo_runner, cairo_run_config)?;
+
+    Ok((cairo_runner, end))
+}
 
+/// Ends a run set up by [`initialize_cairo_run`] once its execution reached the end pc, verifying
+/// and relocating it as configured.
+pub fn finalize_cairo_run(
+    cairo_runner: &mut CairoRunner,
+    cairo_run_config: &CairoRunConfig,
+    hint_processor: &mut dyn HintProcessor,
+) -> Result<(), CairoRunError> {
     if cairo_run_config.proof_mode {
         cairo_runner.run_for_steps(1, hint_processor)?;
     }
@@ -86,18 +193,56 @@ pub fn cairo_run_program_with_initial_scope(
         false,
         hint_processor,
     )?;
+    #[cfg(all(feature = "profiler", feature = "std"))]
+    flush_hint_log(cairo_runner)?;
 
     cairo_runner.vm.verify_auto_deductions()?;
-    cairo_runner.read_return_values(allow_missing_builtins)?;
+    cairo_runner.read_return_values(cairo_run_config.allow_missing_builtins())?;
     if cairo_run_config.proof_mode {
         cairo_runner.finalize_segments()?;
     }
-    if secure_run {
-        verify_secure_runner(&cairo_runner, true, None)?;
+    if cairo_run_config.secure_run() {
+        verify_secure_runner(cairo_runner, true, None)?;
     }
     cairo_runner.relocate(cairo_run_config.relocate_mem)?;
 
-    Ok(cairo_runner)
+    Ok(())
+}
+
+#[cfg(feature = "profiler")]
+fn set_hint_profiler(
+    cairo_runner: &mut CairoRunner,
+    cairo_run_config: &CairoRunConfig,
+) -> Result<(), VirtualMachineError> {
+    #[cfg(feature = "std")]
+    if let Some(path) = cairo_run_config.hint_log_file {
+        let file =
+            std::fs::File::create(path).map_err(|err| VirtualMachineError::Other(err.into()))?;
+        let log = Box::new(std::io::BufWriter::new(file));
+        cairo_runner
+            .vm
+            .set_hint_profiler(HintProfiler::new().with_log(log));
+        return Ok(());
+    }
+    if cairo_run_config.hint_profiling {
+        cairo_runner.vm.set_hint_profiler(HintProfiler::new());
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "profiler", feature = "std"))]
+fn flush_hint_log(cairo_runner: &mut CairoRunner) -> Result<(), VirtualMachineError> {
+    match cairo_runner.vm.hint_profiler.as_mut() {
+        Some(hint_profiler) => hint_profiler.flush(),
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "std")]
+fn set_file_trace_sink(cairo_runner: &mut CairoRunner) -> Result<(), TraceError> {
+    let sink = FileTraceSink::new()
+        .map_err(|err| TraceError::SinkFailure(err.to_string().into_boxed_str()))?;
+    cairo_runner.vm.set_trace_sink(Box::new(sink))
 }
 
 pub fn cairo_run_program(
@@ -118,7 +263,16 @@ pub fn cairo_run(
     cairo_run_config: &CairoRunConfig,
     hint_processor: &mut dyn HintProcessor,
 ) -> Result<CairoRunner, CairoRunError> {
-    let program = Program::from_bytes(program_content, Some(cairo_run_config.entrypoint))?;
+    let custom_builtins: Vec<_> = cairo_run_config
+        .custom_builtins
+        .iter()
+        .map(|builtin| builtin.name())
+        .collect();
+    let program = Program::from_bytes_with_custom_builtins(
+        program_content,
+        Some(cairo_run_config.entrypoint),
+        &custom_builtins,
+    )?;
 
     cairo_run_program(&program, cairo_run_config, hint_processor)
 }
@@ -148,12 +302,19 @@ pub fn cairo_run_pie(
     let allow_missing_builtins = cairo_run_config.allow_missing_builtins.unwrap_or_default();
 
     let program = Program::from_stripped_program(&pie.metadata.program);
-    let mut cairo_runner = CairoRunner::new(
+    let mut cairo_runner = CairoRunner::new_v2(
         &program,
         cairo_run_config.layout,
-        false,
-        cairo_run_config.trace_enabled,
+        cairo_run_config.dynamic_layout_params.clone(),
+        RunnerMode::ExecutionMode,
+        cairo_run_config.trace_enabled(),
     )?;
+    add_custom_builtins(&mut cairo_runner, cairo_run_config);
+
+    #[cfg(feature = "std")]
+    if cairo_run_config.stream_trace() {
+        set_file_trace_sink(&mut cairo_runner)?;
+    }
 
     let end = cairo_runner.initialize(allow_missing_builtins)?;
     cairo_runner.vm.finalize_segments_by_cairo_pie(pie);
@@ -180,6 +341,13 @@ pub fn cairo_run_pie(
         .segments
         .load_pie_memory(&pie.memory, n_extra_segments)?;
 
+    #[cfg(feature = "profiler")]
+    if cairo_run_config.profiling {
+        cairo_runner.vm.enable_profiler();
+    }
+    #[cfg(feature = "profiler")]
+    set_hint_profiler(&mut cairo_runner, cairo_run_config)?;
+
     cairo_runner
         .run_until_pc(end, hint_processor)
         .map_err(|err| VmException::from_vm_error(&cairo_runner, err))?;
@@ -189,6 +357,8 @@ pub fn cairo_run_pie(
         false,
         hint_processor,
     )?;
+    #[cfg(all(feature = "profiler", feature = "std"))]
+    flush_hint_log(&mut cairo_runner)?;
 
     cairo_runner.vm.verify_auto_deductions()?;
     cairo_runner.read_return_values(allow_missing_builtins)?;
@@ -203,6 +373,12 @@ pub fn cairo_run_pie(
     Ok(cairo_runner)
 }
 
+fn add_custom_builtins(cairo_runner: &mut CairoRunner, cairo_run_config: &CairoRunConfig) {
+    for builtin in &cairo_run_config.custom_builtins {
+        cairo_runner.add_custom_builtin(builtin.clone());
+    }
+}
+
 #[cfg(feature = "test_utils")]
 pub fn cairo_run_fuzzed_program(
     program: Program,
@@ -220,11 +396,12 @@ pub fn cairo_run_fuzzed_program(
         .allow_missing_builtins
         .unwrap_or(cairo_run_config.proof_mode);
 
-    let mut cairo_runner = CairoRunner::new(
+    let mut cairo_runner = CairoRunner::new_v2(
         &program,
         cairo_run_config.layout,
-        cairo_run_config.proof_mode,
-        cairo_run_config.trace_enabled,
+        cairo_run_config.dynamic_layout_params.clone(),
+        cairo_run_config.runner_mode(),
+        cairo_run_config.trace_enabled(),
     )?;
 
     let _end = cairo_runner.initialize(allow_missing_builtins)?;
@@ -260,7 +437,7 @@ pub struct EncodeTraceError(usize, bincode::error::EncodeError);
 /// Bincode encodes to little endian by default and each trace entry is composed of
 /// 3 usize values that are padded to always reach 64 bit size.
 pub fn write_encoded_trace(
-    relocated_trace: &[crate::vm::trace::trace_entry::RelocatedTraceEntry],
+    relocated_trace: &[RelocatedTraceEntry],
     dest: &mut impl Writer,
 ) -> Result<(), EncodeTraceError> {
     for (i, entry) in relocated_trace.iter().enumerate() {
@@ -299,10 +476,76 @@ pub fn write_encoded_memory(
     Ok(())
 }
 
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DecodeTraceError {
+    #[error("Trace length {0} is not a multiple of the {TRACE_ENTRY_SIZE} bytes of an entry")]
+    TraceLength(usize),
+    #[error("Memory length {0} is not a multiple of the {MEMORY_CELL_SIZE} bytes of a cell")]
+    MemoryLength(usize),
+    #[error("Memory address {0} is encoded more than once")]
+    DuplicateAddress(usize),
+    #[error("Memory address {0} is greater than the maximum address {1}")]
+    AddressOutOfBounds(u64, usize),
+}
+
+// Sizes of a trace entry and of a memory cell in their binary representations
+const TRACE_ENTRY_SIZE: usize = 3 * 8;
+const MEMORY_CELL_SIZE: usize = 8 + 32;
+
+/// Reads the trace binary representation written by [`write_encoded_trace`].
+pub fn read_encoded_trace(bytes: &[u8]) -> Result<Vec<RelocatedTraceEntry>, DecodeTraceError> {
+    if bytes.len() % TRACE_ENTRY_SIZE != 0 {
+        return Err(DecodeTraceError::TraceLength(bytes.len()));
+    }
+    let word = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().unwrap()) as usize;
+    Ok(bytes
+        .chunks_exact(TRACE_ENTRY_SIZE)
+        .map(|entry| RelocatedTraceEntry {
+            ap: word(&entry[..8]),
+            fp: word(&entry[8..16]),
+            pc: word(&entry[16..]),
+        })
+        .collect())
+}
+
+/// Reads the relocated memory binary representation written by [`write_encoded_memory`].
+///
+/// Addresses without a cell in the binary representation are left empty. As the returned memory
+/// holds every address up to the greatest one, `max_address` can be used to reject files that
+/// would make it too large.
+pub fn read_encoded_memory(
+    bytes: &[u8],
+    max_address: Option<usize>,
+) -> Result<Vec<Option<Felt252>>, DecodeTraceError> {
+    if bytes.len() % MEMORY_CELL_SIZE != 0 {
+        return Err(DecodeTraceError::MemoryLength(bytes.len()));
+    }
+    // The memory can't hold usize::MAX + 1 addresses
+    let max_address = max_address.unwrap_or(usize::MAX - 1);
+    let mut relocated_memory = Vec::new();
+    for cell in bytes.chunks_exact(MEMORY_CELL_SIZE) {
+        let encoded_address = u64::from_le_bytes(cell[..8].try_into().unwrap());
+        let address = usize::try_from(encoded_address)
+            .ok()
+            .filter(|address| *address <= max_address)
+            .ok_or(DecodeTraceError::AddressOutOfBounds(
+                encoded_address,
+                max_address,
+            ))?;
+        let value = Felt252::from_bytes_le(cell[8..].try_into().unwrap());
+        if relocated_memory.len() <= address {
+            relocated_memory.resize(address + 1, None);
+        }
+        if relocated_memory[address].replace(value).is_some() {
+            return Err(DecodeTraceError::DuplicateAddress(address));
+        }
+    }
+    Ok(relocated_memory)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::stdlib::prelude::*;
     use crate::vm::runners::cairo_runner::RunResources;
     use crate::Felt252;
     use crate::{
@@ -312,6 +555,7 @@ mod tests {
         },
         utils::test_utils::*,
     };
+    use assert_matches::assert_matches;
     use bincode::enc::write::SliceWriter;
 
     use rstest::rstest;
@@ -447,6 +691,129 @@ mod tests {
         assert_eq!(*expected_encoded_memory, buffer);
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_binary_trace_file() {
+        let encoded_trace = include_bytes!("../../cairo_programs/trace_memory/cairo_trace_struct");
+        let trace = read_encoded_trace(encoded_trace).unwrap();
+        assert_eq!(
+            trace,
+            vec![RelocatedTraceEntry {
+                pc: 1,
+                ap: 4,
+                fp: 4
+            }]
+        );
+
+        let mut buffer = [0; 24];
+        write_encoded_trace(&trace, &mut SliceWriter::new(&mut buffer)).unwrap();
+        assert_eq!(buffer, *encoded_trace);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_binary_memory_file() {
+        let encoded_memory =
+            include_bytes!("../../cairo_programs/trace_memory/cairo_memory_struct");
+        let relocated_memory = read_encoded_memory(encoded_memory, None).unwrap();
+        assert_eq!(relocated_memory[0], None);
+        assert_eq!(relocated_memory.iter().flatten().count(), 3);
+
+        let mut buffer = [0; 120];
+        write_encoded_memory(&relocated_memory, &mut SliceWriter::new(&mut buffer)).unwrap();
+        assert_eq!(buffer, *encoded_memory);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_invalid_binary_files() {
+        let encoded_trace = include_bytes!("../../cairo_programs/trace_memory/cairo_trace_struct");
+        let encoded_memory =
+            include_bytes!("../../cairo_programs/trace_memory/cairo_memory_struct");
+        assert_eq!(
+            read_encoded_trace(&encoded_trace[1..]),
+            Err(DecodeTraceError::TraceLength(23))
+        );
+        assert_eq!(
+            read_encoded_memory(&encoded_memory[1..], None),
+            Err(DecodeTraceError::MemoryLength(119))
+        );
+        let duplicated_memory = [&encoded_memory[..40], &encoded_memory[..40]].concat();
+        assert_eq!(
+            read_encoded_memory(&duplicated_memory, None),
+            Err(DecodeTraceError::DuplicateAddress(1))
+        );
+        let out_of_bounds_memory = [&17u64.to_le_bytes()[..], &encoded_memory[8..40]].concat();
+        assert_eq!(
+            read_encoded_memory(&out_of_bounds_memory, Some(16)),
+            Err(DecodeTraceError::AddressOutOfBounds(17, 16))
+        );
+        let max_address_memory = [&16u64.to_le_bytes()[..], &encoded_memory[8..40]].concat();
+        assert_eq!(
+            read_encoded_memory(&max_address_memory, Some(16))
+                .unwrap()
+                .len(),
+            17
+        );
+        let overflowing_memory = [&u64::MAX.to_le_bytes()[..], &encoded_memory[8..40]].concat();
+        assert_matches!(
+            read_encoded_memory(&overflowing_memory, None),
+            Err(DecodeTraceError::AddressOutOfBounds(u64::MAX, _))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_binary_memory_file_of_proof_run() {
+        let program = crate::vm::decoding::assembler::assemble_for_proof(
+            "
+            %builtins output range_check
+            __start__:
+                ap += 2
+                call rel main
+            __end__:
+                jmp rel 0
+            main:
+                %{
+                    memory[ap] = segments.add()
+                %}
+                ap += 1
+                [ap + 0] = 7, ap++
+                [ap + -1] = [[fp + -4] + 0]
+                [ap + -1] = [[ap + -2] + 0]
+                [ap + 0] = [fp + -4] + 1, ap++
+                [ap + 0] = [fp + -3], ap++
+                ret
+            ",
+        )
+        .unwrap();
+        let cairo_run_config = CairoRunConfig {
+            proof_mode: true,
+            relocate_mem: true,
+            layout: LayoutName::all_cairo,
+            ..Default::default()
+        };
+        let runner = cairo_run_program(
+            &program,
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let relocated_memory = &runner.relocated_memory;
+        let n_cells = relocated_memory.iter().flatten().count();
+        // The builtin segments are sized to their allocated units and are mostly left unwritten,
+        // leaving holes before the segment added by the hint
+        assert!(relocated_memory.len() > 16 * n_cells);
+
+        let mut buffer = vec![0; n_cells * MEMORY_CELL_SIZE];
+        write_encoded_memory(relocated_memory, &mut SliceWriter::new(&mut buffer)).unwrap();
+        let read_memory = read_encoded_memory(&buffer, None).unwrap();
+        assert_eq!(read_memory[..], relocated_memory[..read_memory.len()]);
+        assert!(relocated_memory[read_memory.len()..]
+            .iter()
+            .all(Option::is_none));
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn run_with_no_trace() {
@@ -464,6 +831,26 @@ mod tests {
         assert!(cairo_runner.relocated_trace.is_none());
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cairo_run_with_coverage() {
+        let cairo_run_config = CairoRunConfig {
+            layout: LayoutName::all_cairo,
+            #[cfg(feature = "std")]
+            stream_trace: true,
+            coverage: true,
+            ..Default::default()
+        };
+        let runner = cairo_run(
+            include_bytes!("../../cairo_programs/manually_compiled/valid_program_b.json"),
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let coverage = runner.get_coverage().unwrap();
+        assert_matches!(coverage.get_line_count("main1.cairo", 14), Some(count) if count > 0);
+    }
+
     #[rstest]
     #[case(include_bytes!("../../cairo_programs/fibonacci.json"))]
     #[case(include_bytes!("../../cairo_programs/integration.json"))]
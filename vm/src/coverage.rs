@@ -0,0 +1,346 @@
+//! Source level code coverage of Cairo 0 programs, built from the trace of their runs and exported
+//! in lcov's tracefile format.
+
+use crate::stdlib::{
+    collections::{BTreeMap, HashMap},
+    prelude::*,
+};
+use thiserror_no_std::Error;
+
+use crate::{
+    serde::deserialize_program::{HintParams, Location},
+    types::program::Program,
+    vm::{errors::trace_errors::TraceError, runners::cairo_runner::CairoRunner},
+};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CoverageError {
+    #[error(transparent)]
+    Trace(#[from] TraceError),
+    #[error("The program has no debug info to map pcs to source lines")]
+    NoDebugInfo,
+    #[error("Invalid lcov record at line {0}: {1}")]
+    InvalidLcovRecord(usize, String),
+}
+
+/// Execution counts of the source lines of one or more runs.
+///
+/// Lines are counted both for the Cairo instructions and for the hint bodies run before them.
+/// Lines with instructions that were never run are kept with a count of zero.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    // filename -> line -> count
+    files: BTreeMap<String, BTreeMap<u32, u64>>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the lines executed by a run, the runner must have been created with the trace enabled
+    pub fn add_run(&mut self, runner: &CairoRunner) -> Result<(), CoverageError> {
+        let trace = runner
+            .vm
+            .trace
+            .as_ref()
+            .ok_or(TraceError::TraceNotEnabled)?;
+        let mut pc_counts = HashMap::<usize, u64>::new();
+        for entry in trace.iter().filter(|entry| entry.pc.segment_index == 0) {
+            *pc_counts.entry(entry.pc.offset).or_default() += 1;
+        }
+        self.add_pc_counts(runner.get_program(), &pc_counts)
+    }
+
+    fn add_pc_counts(
+        &mut self,
+        program: &Program,
+        pc_counts: &HashMap<usize, u64>,
+    ) -> Result<(), CoverageError> {
+        let locations = program
+            .shared_program_data
+            .instruction_locations
+            .as_ref()
+            .ok_or(CoverageError::NoDebugInfo)?;
+        let hints =
+            BTreeMap::<usize, Vec<HintParams>>::from(&program.shared_program_data.hints_collection);
+
+        // Lines with several instructions count as executed as often as their most executed one
+        let mut run = Coverage::new();
+        for (pc, location) in locations.iter() {
+            let count = pc_counts.get(pc).copied().unwrap_or_default();
+            run.mark_lines(
+                &location.inst,
+                location.inst.start_line..=location.inst.end_line,
+                count,
+            );
+            // Hint locations are listed in the same order as the hints of the pc
+            for (hint_location, hint) in location
+                .hints
+                .iter()
+                .zip(hints.get(pc).into_iter().flatten())
+            {
+                let first_line =
+                    hint_location.location.start_line + hint_location.n_prefix_newlines;
+                let n_lines = hint.code.lines().count().max(1) as u32;
+                run.mark_lines(
+                    &hint_location.location,
+                    first_line..=first_line + n_lines - 1,
+                    count,
+                );
+            }
+        }
+        self.merge(&run);
+        Ok(())
+    }
+
+    fn mark_lines(&mut self, location: &Location, lines: impl Iterator<Item = u32>, count: u64) {
+        let file = self
+            .files
+            .entry(location.input_file.filename.clone())
+            .or_default();
+        for line in lines {
+            let line_count = file.entry(line).or_default();
+            *line_count = (*line_count).max(count);
+        }
+    }
+
+    /// Adds up the line counts of another coverage, as obtained from a different run
+    pub fn merge(&mut self, other: &Coverage) {
+        for (filename, lines) in other.files.iter() {
+            let file = self.files.entry(filename.clone()).or_default();
+            for (line, count) in lines.iter() {
+                *file.entry(*line).or_default() += count;
+            }
+        }
+    }
+
+    /// Returns the execution count of a line, or None if no instruction or hint maps to it
+    pub fn get_line_count(&self, filename: &str, line: u32) -> Option<u64> {
+        self.files.get(filename)?.get(&line).copied()
+    }
+
+    /// Returns the coverage in lcov's tracefile format
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::new();
+        for (filename, lines) in self.files.iter() {
+            lcov.push_str(&format!("TN:\nSF:{filename}\n"));
+            for (line, count) in lines.iter() {
+                lcov.push_str(&format!("DA:{line},{count}\n"));
+            }
+            let hit = lines.values().filter(|count| **count != 0).count();
+            lcov.push_str(&format!("LF:{}\nLH:{hit}\nend_of_record\n", lines.len()));
+        }
+        lcov
+    }
+
+    /// Parses the line counts of an lcov tracefile, such as one written by [`Coverage::to_lcov`].
+    /// Records other than the source file and line counts are ignored.
+    pub fn from_lcov(lcov: &str) -> Result<Self, CoverageError> {
+        let mut coverage = Coverage::new();
+        let mut current_file = None;
+        for (i, record) in lcov.lines().enumerate() {
+            let invalid = || CoverageError::InvalidLcovRecord(i + 1, record.to_string());
+            if let Some(filename) = record.strip_prefix("SF:") {
+                current_file = Some(coverage.files.entry(filename.to_string()).or_default());
+            } else if let Some(line_data) = record.strip_prefix("DA:") {
+                let file = current_file.as_mut().ok_or_else(invalid)?;
+                // The line data may be followed by a checksum
+                let mut fields = line_data.split(',');
+                let line = fields.next().and_then(|l| l.parse().ok());
+                let count = fields.next().and_then(|c| c.parse::<u64>().ok());
+                let (Some(line), Some(count)) = (line, count) else {
+                    return Err(invalid());
+                };
+                *file.entry(line).or_default() += count;
+            } else if record == "end_of_record" {
+                current_file = None;
+            }
+        }
+        Ok(coverage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        serde::deserialize_program::{
+            ApTracking, FlowTrackingData, HintLocation, InputFile, InstructionLocation,
+            ReferenceManager,
+        },
+        types::layout_name::LayoutName,
+        Felt252,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn location(filename: &str, start_line: u32, end_line: u32) -> Location {
+        Location {
+            end_line,
+            end_col: 0,
+            input_file: InputFile {
+                filename: filename.to_string(),
+            },
+            parent_location: None,
+            start_line,
+            start_col: 0,
+        }
+    }
+
+    fn program_with_hint() -> Program {
+        let hint = HintParams {
+            code: "a = 1\nb = 2".to_string(),
+            accessible_scopes: vec![],
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking::default(),
+                reference_ids: HashMap::new(),
+            },
+        };
+        Program::new(
+            vec![],
+            vec![Felt252::ZERO.into(); 3],
+            None,
+            HashMap::from([(1, vec![hint])]),
+            ReferenceManager {
+                references: Vec::new(),
+            },
+            HashMap::new(),
+            vec![],
+            Some(HashMap::from([
+                (
+                    0,
+                    InstructionLocation {
+                        inst: location("main.cairo", 2, 2),
+                        hints: vec![],
+                    },
+                ),
+                (
+                    1,
+                    InstructionLocation {
+                        inst: location("main.cairo", 3, 4),
+                        hints: vec![HintLocation {
+                            location: location("main.cairo", 5, 8),
+                            n_prefix_newlines: 1,
+                        }],
+                    },
+                ),
+                (
+                    2,
+                    InstructionLocation {
+                        inst: location("lib.cairo", 10, 10),
+                        hints: vec![],
+                    },
+                ),
+            ])),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn coverage_marks_instruction_and_hint_lines() {
+        let mut coverage = Coverage::new();
+        coverage
+            .add_pc_counts(&program_with_hint(), &HashMap::from([(0, 1), (1, 3)]))
+            .unwrap();
+        assert_eq!(coverage.get_line_count("main.cairo", 2), Some(1));
+        assert_eq!(coverage.get_line_count("main.cairo", 3), Some(3));
+        assert_eq!(coverage.get_line_count("main.cairo", 4), Some(3));
+        assert_eq!(coverage.get_line_count("main.cairo", 5), None);
+        assert_eq!(coverage.get_line_count("main.cairo", 6), Some(3));
+        assert_eq!(coverage.get_line_count("main.cairo", 7), Some(3));
+        assert_eq!(coverage.get_line_count("main.cairo", 8), None);
+        assert_eq!(coverage.get_line_count("lib.cairo", 10), Some(0));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn coverage_to_lcov() {
+        let mut coverage = Coverage::new();
+        coverage
+            .add_pc_counts(&program_with_hint(), &HashMap::from([(0, 1)]))
+            .unwrap();
+        assert_eq!(
+            coverage.to_lcov(),
+            "TN:\nSF:lib.cairo\nDA:10,0\nLF:1\nLH:0\nend_of_record\n\
+             TN:\nSF:main.cairo\nDA:2,1\nDA:3,0\nDA:4,0\nDA:6,0\nDA:7,0\nLF:5\nLH:1\nend_of_record\n"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn coverage_merge_and_lcov_roundtrip() {
+        let program = program_with_hint();
+        let mut coverage = Coverage::new();
+        coverage
+            .add_pc_counts(&program, &HashMap::from([(0, 1)]))
+            .unwrap();
+        let mut other = Coverage::new();
+        other
+            .add_pc_counts(&program, &HashMap::from([(0, 2), (2, 1)]))
+            .unwrap();
+        coverage.merge(&other);
+        assert_eq!(coverage.get_line_count("main.cairo", 2), Some(3));
+        assert_eq!(coverage.get_line_count("main.cairo", 3), Some(0));
+        assert_eq!(coverage.get_line_count("lib.cairo", 10), Some(1));
+        assert_eq!(Coverage::from_lcov(&coverage.to_lcov()), Ok(coverage));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn from_lcov_invalid_record() {
+        assert_matches!(
+            Coverage::from_lcov("TN:\nSF:a.cairo\nDA:1\n"),
+            Err(CoverageError::InvalidLcovRecord(3, record)) if record == "DA:1"
+        );
+        assert_matches!(
+            Coverage::from_lcov("DA:1,1\n"),
+            Err(CoverageError::InvalidLcovRecord(1, _))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn coverage_of_run() {
+        let program = Program::from_bytes(
+            include_bytes!("../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runner = CairoRunner::new(&program, LayoutName::all_cairo, false, true).unwrap();
+        let end = runner.initialize(false).unwrap();
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+
+        let coverage = runner.get_coverage().unwrap();
+        let executed: u64 = coverage.files["main1.cairo"].values().sum();
+        assert!(executed > 0);
+        let mut twice = coverage.clone();
+        twice.merge(&coverage);
+        assert_eq!(
+            twice.get_line_count("main1.cairo", 14),
+            coverage
+                .get_line_count("main1.cairo", 14)
+                .map(|count| count * 2)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn coverage_without_trace() {
+        let program = Program::from_bytes(
+            include_bytes!("../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let runner = CairoRunner::new(&program, LayoutName::all_cairo, false, false).unwrap();
+        assert_eq!(
+            runner.get_coverage(),
+            Err(CoverageError::Trace(TraceError::TraceNotEnabled))
+        );
+    }
+}
@@ -0,0 +1,1500 @@
+//! Hints of cairo-lang's simple bootloader (`starkware/cairo/bootloaders/simple_bootloader`),
+//! which runs a list of tasks given as Cairo PIEs and outputs, for each one of them, its output
+//! size, its program hash and its output.
+//!
+//! The bootloader input is read from the initial scope of the run, built with
+//! [`simple_bootloader_initial_scope`] and passed to
+//! [`cairo_run_program_with_initial_scope`](crate::cairo_run::cairo_run_program_with_initial_scope).
+//! PIE tasks don't run hints, their memory is loaded as is, so only the bootloader's hints need to
+//! be supported. Tasks running a program with its hints are rejected when the bootloader calls
+//! them.
+
+use crate::stdlib::{borrow::Cow, collections::HashMap, prelude::*};
+
+use crate::Felt252;
+use crate::{
+    any_box,
+    hint_processor::{
+        builtin_hint_processor::hint_utils::{
+            get_integer_from_var_name, get_ptr_from_var_name, get_relocatable_from_var_name,
+            insert_value_from_var_name,
+        },
+        hint_processor_definition::HintReference,
+    },
+    program_hash::compute_program_hash_chain,
+    serde::deserialize_program::ApTracking,
+    types::{
+        builtin_name::BuiltinName,
+        exec_scope::ExecutionScopes,
+        program::Program,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{
+        errors::{
+            hint_errors::HintError, memory_errors::MemoryError, vm_errors::VirtualMachineError,
+        },
+        runners::{
+            builtin_runner::OutputBuiltinRunner,
+            cairo_pie::{BuiltinAdditionalData, CairoPie, Pages, StrippedProgram},
+        },
+        vm_core::VirtualMachine,
+    },
+};
+use num_traits::ToPrimitive;
+use serde::Serialize;
+
+/// Version of the bootloader, part of the hash of every task
+const BOOTLOADER_VERSION: usize = 0;
+/// Builtins supported by the bootloader, in the order of its `BuiltinData` struct
+const ALL_BUILTINS: [BuiltinName; 11] = [
+    BuiltinName::output,
+    BuiltinName::pedersen,
+    BuiltinName::range_check,
+    BuiltinName::ecdsa,
+    BuiltinName::bitwise,
+    BuiltinName::ec_op,
+    BuiltinName::keccak,
+    BuiltinName::poseidon,
+    BuiltinName::range_check96,
+    BuiltinName::add_mod,
+    BuiltinName::mul_mod,
+];
+/// Offset of `builtin_list` in the bootloader's `ProgramHeader` struct
+const PROGRAM_HEADER_BUILTINS_OFFSET: usize = 4;
+/// Output builtin attribute holding the fact topology of a program
+const GPS_FACT_TOPOLOGY: &str = "gps_fact_topology";
+/// The bootloader's function calling each task, between its `call_task` and `ret_pc_label` labels
+const EXECUTE_TASK: &str =
+    "starkware.cairo.bootloaders.simple_bootloader.execute_task.execute_task";
+/// Scope variable holding the offset from the `call_task` label to the `ret_pc_label` label
+const RET_PC_OFFSET: &str = "ret_pc_offset";
+
+/// Task run by the simple bootloader
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Task {
+    /// Replays the execution of a Cairo PIE
+    CairoPie(Box<CairoPie>),
+    /// Runs a program along with its hints. Not supported yet, the bootloader fails when calling it
+    RunProgram(Program),
+}
+
+impl Task {
+    fn get_program(&self) -> Result<Cow<'_, StrippedProgram>, HintError> {
+        match self {
+            Task::CairoPie(pie) => Ok(Cow::Borrowed(&pie.metadata.program)),
+            Task::RunProgram(program) => program
+                .get_stripped_program()
+                .map(Cow::Owned)
+                .map_err(|err| HintError::CustomHint(err.to_string().into_boxed_str())),
+        }
+    }
+
+    fn cairo_pie(&self) -> Result<&CairoPie, HintError> {
+        match self {
+            Task::CairoPie(pie) => Ok(pie),
+            Task::RunProgram(_) => Err(HintError::CustomHint(
+                "RunProgramTask is not supported, tasks must be given as Cairo PIEs".into(),
+            )),
+        }
+    }
+}
+
+impl From<CairoPie> for Task {
+    fn from(pie: CairoPie) -> Self {
+        Task::CairoPie(Box::new(pie))
+    }
+}
+
+/// Input of the simple bootloader
+#[derive(Clone, Debug, Default)]
+pub struct SimpleBootloaderInput {
+    /// Tasks to run, in order
+    pub tasks: Vec<Task>,
+    /// Leaves the whole output in a single page instead of adding a page per task page
+    pub single_page: bool,
+    /// File to write the fact topologies of the tasks to, as JSON
+    #[cfg(feature = "std")]
+    pub fact_topologies_path: Option<std::path::PathBuf>,
+}
+
+/// Describes how the fact of a task is computed from its output: `page_sizes` splits the output
+/// into pages, and `tree_structure` describes how they are merged into a Merkle tree
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FactTopology {
+    pub tree_structure: Vec<usize>,
+    pub page_sizes: Vec<usize>,
+}
+
+/// Builds the initial scope of a run of the simple bootloader `program` on `input`. Along with the
+/// input, it holds the position of the return address of each task, read from the labels of the
+/// bootloader's `execute_task` function.
+pub fn simple_bootloader_initial_scope(
+    program: &Program,
+    input: SimpleBootloaderInput,
+) -> Result<ExecutionScopes, HintError> {
+    let label_pc = |label: &str| {
+        let name = format!("{EXECUTE_TASK}.{label}");
+        program
+            .get_identifier(&name)
+            .and_then(|identifier| identifier.pc)
+            .ok_or_else(|| HintError::UnknownIdentifier(name.into_boxed_str()))
+    };
+    let ret_pc_offset = label_pc("ret_pc_label")?
+        .checked_sub(label_pc("call_task")?)
+        .ok_or_else(|| HintError::AssertionFailed("ret_pc_label comes before call_task".into()))?;
+    let mut exec_scopes = ExecutionScopes::new();
+    exec_scopes.insert_value("program_input", input);
+    exec_scopes.insert_value(RET_PC_OFFSET, ret_pc_offset);
+    Ok(exec_scopes)
+}
+
+/*
+Implements hint:
+%{
+    from starkware.cairo.bootloaders.simple_bootloader.objects import SimpleBootloaderInput
+    simple_bootloader_input = SimpleBootloaderInput.Schema().load(program_input)
+%}
+*/
+pub fn load_simple_bootloader_input(exec_scopes: &mut ExecutionScopes) -> Result<(), HintError> {
+    let input: SimpleBootloaderInput = exec_scopes.get("program_input")?;
+    exec_scopes.insert_value("simple_bootloader_input", input);
+    Ok(())
+}
+
+/*
+Implements hint:
+%{
+    n_tasks = len(simple_bootloader_input.tasks)
+    memory[ids.output_ptr] = n_tasks
+
+    # Task range checks are located right after simple bootloader validation range checks, and
+    # this is validated later in this function.
+    ids.task_range_check_ptr = ids.range_check_ptr + ids.BuiltinData.SIZE * n_tasks
+
+    # A list of fact_toplogies that instruct how to generate the fact from the program output
+    # for each task.
+    fact_topologies = []
+%}
+*/
+pub fn prepare_task_range_checks(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let n_tasks = exec_scopes
+        .get_ref::<SimpleBootloaderInput>("simple_bootloader_input")?
+        .tasks
+        .len();
+    let output_ptr = get_ptr_from_var_name("output_ptr", vm, ids_data, ap_tracking)?;
+    vm.insert_value(output_ptr, Felt252::from(n_tasks))?;
+
+    let range_check_ptr = get_ptr_from_var_name("range_check_ptr", vm, ids_data, ap_tracking)?;
+    let task_range_check_ptr = (range_check_ptr + ALL_BUILTINS.len() * n_tasks)?;
+    insert_value_from_var_name(
+        "task_range_check_ptr",
+        task_range_check_ptr,
+        vm,
+        ids_data,
+        ap_tracking,
+    )?;
+
+    exec_scopes.insert_value("fact_topologies", Vec::<FactTopology>::new());
+    Ok(())
+}
+
+/*
+Implements hint:
+%{ tasks = simple_bootloader_input.tasks %}
+*/
+pub fn set_tasks_variable(exec_scopes: &mut ExecutionScopes) -> Result<(), HintError> {
+    let tasks = exec_scopes
+        .get_ref::<SimpleBootloaderInput>("simple_bootloader_input")?
+        .tasks
+        .clone();
+    exec_scopes.insert_value("tasks", tasks);
+    Ok(())
+}
+
+/*
+Implements hint:
+%{
+    from starkware.cairo.bootloaders.simple_bootloader.objects import Task
+
+    # Pass current task to execute_task.
+    task_id = len(tasks) - ids.n_tasks
+    task = tasks[task_id].load_task()
+%}
+*/
+pub fn set_current_task(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let n_tasks = get_integer_from_var_name("n_tasks", vm, ids_data, ap_tracking)?;
+    let tasks = exec_scopes.get_ref::<Vec<Task>>("tasks")?;
+    let task = n_tasks
+        .to_usize()
+        .and_then(|n_tasks| tasks.len().checked_sub(n_tasks))
+        .and_then(|task_id| tasks.get(task_id))
+        .ok_or_else(|| {
+            HintError::AssertionFailed(
+                format!(
+                    "n_tasks = {n_tasks} is out of range for {} tasks",
+                    tasks.len()
+                )
+                .into_boxed_str(),
+            )
+        })?
+        .clone();
+    exec_scopes.insert_value("task", task);
+    Ok(())
+}
+
+/*
+Implements hint:
+%{ ids.program_data_ptr = program_data_base = segments.add() %}
+*/
+pub fn allocate_program_data_segment(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let program_data_base = vm.add_memory_segment();
+    insert_value_from_var_name(
+        "program_data_ptr",
+        program_data_base,
+        vm,
+        ids_data,
+        ap_tracking,
+    )?;
+    exec_scopes.insert_value("program_data_base", program_data_base);
+    Ok(())
+}
+
+/*
+Implements hint:
+%{
+    from starkware.cairo.bootloaders.simple_bootloader.utils import load_program
+
+    # Call load_program to load the program header and code to memory.
+    program_address, program_data_size = load_program(
+        task=task, memory=memory, program_header=ids.program_header,
+        builtins_offset=ids.ProgramHeader.builtin_list)
+    segments.finalize(program_data_base.segment_index, program_data_size)
+%}
+*/
+pub fn load_program(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let program = exec_scopes.get_ref::<Task>("task")?.get_program()?;
+    let program_header = get_ptr_from_var_name("program_header", vm, ids_data, ap_tracking)?;
+
+    // The header ends with the builtin list, the data length doesn't count itself
+    let header_size = PROGRAM_HEADER_BUILTINS_OFFSET + program.builtins.len();
+    let data_length = header_size - 1 + program.data.len();
+    vm.insert_value(program_header, Felt252::from(data_length))?;
+    vm.insert_value((program_header + 2)?, Felt252::from(program.main))?;
+    vm.insert_value((program_header + 3)?, Felt252::from(program.builtins.len()))?;
+    let builtins: Vec<MaybeRelocatable> = program
+        .builtins
+        .iter()
+        .map(|builtin| Felt252::from_bytes_be_slice(builtin.to_str().as_bytes()).into())
+        .collect();
+    vm.load_data(
+        (program_header + PROGRAM_HEADER_BUILTINS_OFFSET)?,
+        &builtins,
+    )?;
+    let program_address = (program_header + header_size)?;
+    vm.load_data(program_address, &program.data)?;
+
+    let program_data_size = header_size + program.data.len();
+    let program_data_base = exec_scopes.get::<Relocatable>("program_data_base")?;
+    vm.segments.finalize(
+        Some(program_data_size),
+        program_data_base.segment_index as usize,
+        None,
+    );
+    exec_scopes.insert_value("program_address", program_address);
+    Ok(())
+}
+
+/*
+Implements hint:
+%{
+    # Validate hash.
+    from starkware.cairo.bootloaders.hash_program import compute_program_hash_chain
+
+    assert memory[ids.output_ptr + 1] == compute_program_hash_chain(task.get_program()), \
+      'Computed hash does not match input.'
+%}
+*/
+pub fn validate_hash(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let program = exec_scopes.get_ref::<Task>("task")?.get_program()?;
+    let output_ptr = get_ptr_from_var_name("output_ptr", vm, ids_data, ap_tracking)?;
+    let program_hash = vm.get_integer((output_ptr + 1)?)?;
+    let computed_hash = compute_program_hash_chain(&program, BOOTLOADER_VERSION)
+        .map_err(|err| HintError::CustomHint(err.to_string().into_boxed_str()))?;
+    if program_hash.as_ref() != &Felt252::from_bytes_be(&computed_hash.to_bytes_be()) {
+        return Err(HintError::AssertionFailed(
+            "Computed hash does not match input.".into(),
+        ));
+    }
+    Ok(())
+}
+
+/*
+Implements hint:
+%{
+    # Sanity check.
+    assert ids.program_address == program_address
+%}
+*/
+pub fn assert_program_address(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let ids_program_address = get_ptr_from_var_name("program_address", vm, ids_data, ap_tracking)?;
+    let program_address = exec_scopes.get::<Relocatable>("program_address")?;
+    if ids_program_address != program_address {
+        return Err(HintError::AssertionFailed(
+            format!("ids.program_address = {ids_program_address} != {program_address}")
+                .into_boxed_str(),
+        ));
+    }
+    Ok(())
+}
+
+/*
+Implements hint:
+%{
+    from starkware.cairo.bootloaders.simple_bootloader.objects import (
+        CairoPieTask,
+        RunProgramTask,
+        Task,
+    )
+    from starkware.cairo.bootloaders.simple_bootloader.utils import (
+        load_cairo_pie,
+        prepare_output_runner,
+    )
+
+    assert isinstance(task, Task)
+    n_builtins = len(task.get_program().builtins)
+    new_task_locals = {}
+    if isinstance(task, RunProgramTask):
+        new_task_locals['program_input'] = task.program_input
+        new_task_locals['WITH_BOOTLOADER'] = True
+
+        vm_load_program(task.program, program_address)
+    elif isinstance(task, CairoPieTask):
+        ret_pc = ids.ret_pc_label.instruction_offset_ - ids.call_task.instruction_offset_ + pc
+        load_cairo_pie(
+            task=task.cairo_pie, memory=memory, segments=segments,
+            program_address=program_address, execution_segment_address= ap - n_builtins,
+            builtin_runners=builtin_runners, ret_fp=fp, ret_pc=ret_pc)
+    else:
+        raise NotImplementedError(f'Unexpected task type: {type(task).__name__}.')
+
+    output_runner_data = prepare_output_runner(
+        task=task,
+        output_builtin=output_builtin,
+        output_ptr=ids.pre_execution_builtin_ptrs.output)
+    vm_enter_scope(new_task_locals)
+%}
+Only PIE tasks are supported, they don't need any output runner data.
+*/
+pub fn call_task(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+) -> Result<(), HintError> {
+    let task = exec_scopes.get_ref::<Task>("task")?.cairo_pie()?;
+    let program_address = exec_scopes.get::<Relocatable>("program_address")?;
+    let n_builtins = task.metadata.program.builtins.len();
+    let ret_fp = vm.get_fp();
+    // The hint runs at the call_task label
+    let ret_pc = (vm.get_pc() + exec_scopes.get::<usize>(RET_PC_OFFSET)?)?;
+    let execution_segment_address = (vm.get_ap() - n_builtins)?;
+    load_cairo_pie(
+        vm,
+        task,
+        program_address,
+        execution_segment_address,
+        ret_fp,
+        ret_pc,
+    )?;
+    exec_scopes.enter_scope(HashMap::new());
+    Ok(())
+}
+
+/*
+Implements hint:
+%{
+    from starkware.cairo.bootloaders.simple_bootloader.utils import get_task_fact_topology
+
+    # Add the fact topology of the current task to 'fact_topologies'.
+    output_start = ids.pre_execution_builtin_ptrs.output
+    output_end = ids.return_builtin_ptrs.output
+    fact_topologies.append(get_task_fact_topology(
+        output_size=output_end - output_start,
+        task=task,
+        output_builtin=output_builtin,
+        output_runner_data=output_runner_data,
+    ))
+%}
+*/
+pub fn append_fact_topologies(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    // `output` is the first member of BuiltinData
+    let output_start = vm.get_relocatable(get_relocatable_from_var_name(
+        "pre_execution_builtin_ptrs",
+        vm,
+        ids_data,
+        ap_tracking,
+    )?)?;
+    let output_end = vm.get_relocatable(get_relocatable_from_var_name(
+        "return_builtin_ptrs",
+        vm,
+        ids_data,
+        ap_tracking,
+    )?)?;
+    let output_size = (output_end - output_start)?;
+    let fact_topology = get_cairo_pie_fact_topology(
+        exec_scopes.get_ref::<Task>("task")?.cairo_pie()?,
+        output_size,
+    )?;
+    exec_scopes
+        .get_mut_list_ref::<FactTopology>("fact_topologies")?
+        .push(fact_topology);
+    Ok(())
+}
+
+/*
+Implements hint:
+%{
+    from starkware.cairo.bootloaders.simple_bootloader.utils import write_return_builtins
+
+    # Fill the values of all builtin pointers after executing the task.
+    builtins = task.get_program().builtins
+    write_return_builtins(
+        memory=memory, return_builtins_addr=ids.return_builtin_ptrs.address_,
+        used_builtins=builtins, used_builtins_addr=ids.used_builtins_addr,
+        pre_execution_builtins_addr=ids.pre_execution_builtin_ptrs.address_, task=task)
+
+    vm_enter_scope({'n_selected_builtins': n_builtins})
+%}
+*/
+pub fn write_return_builtins(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let task = exec_scopes.get_ref::<Task>("task")?.cairo_pie()?;
+    let return_builtins_addr =
+        get_relocatable_from_var_name("return_builtin_ptrs", vm, ids_data, ap_tracking)?;
+    let used_builtins_addr =
+        get_ptr_from_var_name("used_builtins_addr", vm, ids_data, ap_tracking)?;
+    let pre_execution_builtins_addr =
+        get_relocatable_from_var_name("pre_execution_builtin_ptrs", vm, ids_data, ap_tracking)?;
+
+    let used_builtins = &task.metadata.program.builtins;
+    let mut used_builtin_offset = 0;
+    for (index, builtin) in ALL_BUILTINS.iter().enumerate() {
+        let pre_execution_addr = (pre_execution_builtins_addr + index)?;
+        let pre_execution_ptr = vm
+            .get_maybe(&pre_execution_addr)
+            .ok_or_else(|| MemoryError::UnknownMemoryCell(Box::new(pre_execution_addr)))?;
+        let return_ptr = if used_builtins.contains(builtin) {
+            let return_ptr = vm.get_relocatable((used_builtins_addr + used_builtin_offset)?)?;
+            used_builtin_offset += 1;
+            let segment_size = task
+                .metadata
+                .builtin_segments
+                .get(builtin)
+                .map(|segment| segment.size);
+            let used_size = pre_execution_ptr
+                .get_relocatable()
+                .and_then(|pre_execution_ptr| (return_ptr - pre_execution_ptr).ok());
+            if segment_size.is_none() || segment_size != used_size {
+                return Err(HintError::AssertionFailed(
+                    "Builtin usage is inconsistent with the CairoPie.".into(),
+                ));
+            }
+            return_ptr.into()
+        } else {
+            // The builtin is unused, hence its value is the same as before calling the program
+            pre_execution_ptr
+        };
+        vm.insert_value((return_builtins_addr + index)?, return_ptr)?;
+    }
+
+    let n_builtins = Felt252::from(used_builtins.len());
+    exec_scopes.enter_scope(HashMap::from([(
+        String::from("n_selected_builtins"),
+        any_box!(n_builtins),
+    )]));
+    Ok(())
+}
+
+/*
+Implements hint:
+%{ vm_enter_scope({'n_selected_builtins': ids.n_selected_builtins}) %}
+*/
+pub fn select_builtins_enter_scope(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let n_selected_builtins =
+        get_integer_from_var_name("n_selected_builtins", vm, ids_data, ap_tracking)?;
+    exec_scopes.enter_scope(HashMap::from([(
+        String::from("n_selected_builtins"),
+        any_box!(n_selected_builtins),
+    )]));
+    Ok(())
+}
+
+/*
+Implements hint:
+%{
+    # A builtin should be selected iff its encoding appears in the selected encodings list
+    # and the list wasn't exhausted.
+    # Note that testing inclusion by a single comparison is possible since the lists are sorted.
+    ids.select_builtin = int(
+      n_selected_builtins > 0 and memory[ids.selected_encodings] == memory[ids.all_encodings])
+    if ids.select_builtin:
+      n_selected_builtins = n_selected_builtins - 1
+%}
+*/
+pub fn select_builtin(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let n_selected_builtins = exec_scopes.get_mut_ref::<Felt252>("n_selected_builtins")?;
+    let select_builtin = *n_selected_builtins != Felt252::ZERO && {
+        let selected_encodings =
+            get_ptr_from_var_name("selected_encodings", vm, ids_data, ap_tracking)?;
+        let all_encodings = get_ptr_from_var_name("all_encodings", vm, ids_data, ap_tracking)?;
+        vm.get_maybe(&selected_encodings) == vm.get_maybe(&all_encodings)
+    };
+    if select_builtin {
+        *n_selected_builtins -= Felt252::ONE;
+    }
+    insert_value_from_var_name(
+        "select_builtin",
+        Felt252::from(select_builtin),
+        vm,
+        ids_data,
+        ap_tracking,
+    )
+}
+
+/*
+Implements hint:
+%{
+    # Dump fact topologies to a json file.
+    from starkware.cairo.bootloaders.simple_bootloader.utils import (
+        configure_fact_topologies,
+        write_to_fact_topologies_file,
+    )
+
+    # The task-related output is prefixed by a single word that contains the number of tasks.
+    tasks_output_start = output_builtin.base + 1
+
+    if not simple_bootloader_input.single_page:
+        # Configure the memory pages in the output builtin, based on fact_topologies.
+        configure_fact_topologies(
+            fact_topologies=fact_topologies, output_start=tasks_output_start,
+            output_builtin=output_builtin,
+        )
+
+    if simple_bootloader_input.fact_topologies_path is not None:
+        write_to_fact_topologies_file(
+            fact_topologies_path=simple_bootloader_input.fact_topologies_path,
+            fact_topologies=fact_topologies,
+        )
+%}
+*/
+pub fn configure_fact_topologies(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+) -> Result<(), HintError> {
+    let input = exec_scopes.get_ref::<SimpleBootloaderInput>("simple_bootloader_input")?;
+    let fact_topologies = exec_scopes.get_list_ref::<FactTopology>("fact_topologies")?;
+    let output_builtin = vm.get_output_builtin_mut()?;
+    let tasks_output_start = Relocatable::from((output_builtin.base() as isize, 1));
+
+    if !input.single_page {
+        add_fact_topologies_pages(fact_topologies, tasks_output_start, output_builtin)?;
+    }
+
+    #[cfg(feature = "std")]
+    if let Some(ref path) = input.fact_topologies_path {
+        write_fact_topologies_file(path, fact_topologies)?;
+    }
+    Ok(())
+}
+
+/// Writes the memory of `pie` into the bootloader's memory, with its program at
+/// `program_address` and its execution segment at `execution_segment_address`, so that calling
+/// its main function replays its execution.
+/// Its builtin segments are placed where the builtin pointers given to it point to, its return fp
+/// and pc are the given ones, and its extra segments are allocated as new segments.
+fn load_cairo_pie(
+    vm: &mut VirtualMachine,
+    pie: &CairoPie,
+    program_address: Relocatable,
+    execution_segment_address: Relocatable,
+    ret_fp: Relocatable,
+    ret_pc: Relocatable,
+) -> Result<(), HintError> {
+    let metadata = &pie.metadata;
+    let mut segment_offsets: HashMap<isize, Relocatable> = [
+        (metadata.program_segment.index, program_address),
+        (metadata.execution_segment.index, execution_segment_address),
+        (metadata.ret_fp_segment.index, ret_fp),
+        (metadata.ret_pc_segment.index, ret_pc),
+    ]
+    .into_iter()
+    .collect();
+
+    let pie_memory: HashMap<Relocatable, &MaybeRelocatable> = pie
+        .memory
+        .0
+        .iter()
+        .map(|((segment_index, offset), value)| {
+            (Relocatable::from((*segment_index as isize, *offset)), value)
+        })
+        .collect();
+    for (index, builtin) in metadata.program.builtins.iter().enumerate() {
+        let builtin_segment = metadata.builtin_segments.get(builtin).ok_or_else(|| {
+            HintError::AssertionFailed(
+                format!("The CairoPie has no segment for builtin {builtin}").into_boxed_str(),
+            )
+        })?;
+        // Check that the builtin segment starts at the expected address
+        let builtin_ptr = pie_memory.get(&Relocatable::from((
+            metadata.execution_segment.index,
+            index,
+        )));
+        if builtin_ptr != Some(&&MaybeRelocatable::from((builtin_segment.index, 0))) {
+            return Err(HintError::AssertionFailed(
+                format!("The CairoPie's {builtin} builtin pointer doesn't start its segment")
+                    .into_boxed_str(),
+            ));
+        }
+        segment_offsets.insert(
+            builtin_segment.index,
+            vm.get_relocatable((execution_segment_address + index)?)?,
+        );
+    }
+    for segment in metadata.extra_segments.iter() {
+        segment_offsets.insert(segment.index, vm.add_memory_segment());
+    }
+
+    let relocate_address = |address: Relocatable| -> Result<Relocatable, HintError> {
+        let base = segment_offsets.get(&address.segment_index).ok_or_else(|| {
+            HintError::AssertionFailed(
+                format!(
+                    "The CairoPie uses the unknown segment {}",
+                    address.segment_index
+                )
+                .into_boxed_str(),
+            )
+        })?;
+        Ok((*base + address.offset)?)
+    };
+
+    // Signatures must be added before the signed messages and public keys are written
+    if let Some(BuiltinAdditionalData::Signature(signatures)) =
+        pie.additional_data.0.get(&BuiltinName::ecdsa)
+    {
+        let signatures = signatures
+            .iter()
+            .map(|(address, signature)| Ok((relocate_address(*address)?, signature)))
+            .collect::<Result<Vec<_>, HintError>>()?;
+        let signature_builtin = vm.get_signature_builtin()?;
+        for (address, signature) in signatures {
+            signature_builtin.add_signature(address, signature)?;
+        }
+    }
+
+    for (address, value) in pie_memory {
+        let value: MaybeRelocatable = match value {
+            MaybeRelocatable::RelocatableValue(value) => relocate_address(*value)?.into(),
+            MaybeRelocatable::Int(value) => (*value).into(),
+        };
+        vm.insert_value(relocate_address(address)?, value)?;
+    }
+    Ok(())
+}
+
+/// Builds the fact topology of a PIE task from the pages and the `gps_fact_topology` attribute
+/// of its output builtin
+fn get_cairo_pie_fact_topology(
+    pie: &CairoPie,
+    output_size: usize,
+) -> Result<FactTopology, HintError> {
+    let output_data = match pie.additional_data.0.get(&BuiltinName::output) {
+        Some(BuiltinAdditionalData::Output(output_data)) => output_data,
+        _ => {
+            return Err(HintError::AssertionFailed(
+                "The CairoPie has no output builtin data".into(),
+            ))
+        }
+    };
+    let tree_structure = match output_data.attributes.get(GPS_FACT_TOPOLOGY) {
+        Some(tree_structure) => {
+            if tree_structure.is_empty()
+                || tree_structure.len() % 2 != 0
+                || tree_structure.len() > 10
+            {
+                return Err(HintError::AssertionFailed(
+                    format!("Invalid tree structure specified in the gps_fact_topology attribute: {tree_structure:?}")
+                        .into_boxed_str(),
+                ));
+            }
+            tree_structure.clone()
+        }
+        None => {
+            if !output_data.pages.is_empty() {
+                return Err(HintError::AssertionFailed(
+                    "Tree structure must be specified when there are multiple pages".into(),
+                ));
+            }
+            vec![1, 0]
+        }
+    };
+    Ok(FactTopology {
+        tree_structure,
+        page_sizes: get_page_sizes(output_size, &output_data.pages)?,
+    })
+}
+
+/// Returns the sizes of the pages of an output of size `output_size`, page 0 being everything
+/// before page 1. Pages must be consecutive and cover the rest of the output.
+fn get_page_sizes(output_size: usize, pages: &Pages) -> Result<Vec<usize>, HintError> {
+    let mut page_ids: Vec<&usize> = pages.keys().collect();
+    page_ids.sort();
+
+    let mut page0_size = output_size;
+    let mut expected_page_start = None;
+    let mut page_sizes = Vec::with_capacity(pages.len() + 1);
+    for (expected_page_id, page_id) in (1..).zip(page_ids) {
+        let page = &pages[page_id];
+        let valid_start = if *page_id == 1 {
+            page0_size = page.start;
+            page.start <= output_size
+        } else {
+            expected_page_start == Some(page.start)
+        };
+        if *page_id != expected_page_id || !valid_start || page.size > output_size {
+            return Err(HintError::AssertionFailed(
+                format!(
+                    "Invalid output page {page_id}: start {}, size {}",
+                    page.start, page.size
+                )
+                .into_boxed_str(),
+            ));
+        }
+        expected_page_start = Some(page.start + page.size);
+        page_sizes.push(page.size);
+    }
+    if expected_page_start.is_some_and(|page_end| page_end != output_size) {
+        return Err(HintError::AssertionFailed(
+            "The output pages don't cover the whole output".into(),
+        ));
+    }
+    page_sizes.insert(0, page0_size);
+    Ok(page_sizes)
+}
+
+/// Adds an output page for each page of each task, from page 1 on, skipping the output size and
+/// program hash at the start of each task's output
+fn add_fact_topologies_pages(
+    fact_topologies: &[FactTopology],
+    mut output_start: Relocatable,
+    output_builtin: &mut OutputBuiltinRunner,
+) -> Result<(), HintError> {
+    let mut page_id = 1;
+    for fact_topology in fact_topologies {
+        output_start = (output_start + 2)?;
+        for page_size in fact_topology.page_sizes.iter() {
+            output_builtin
+                .add_page(page_id, output_start, *page_size)
+                .map_err(VirtualMachineError::from)?;
+            page_id += 1;
+            output_start = (output_start + *page_size)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+fn write_fact_topologies_file(
+    path: &std::path::Path,
+    fact_topologies: &[FactTopology],
+) -> Result<(), HintError> {
+    #[derive(Serialize)]
+    struct FactTopologiesFile<'a> {
+        fact_topologies: &'a [FactTopology],
+    }
+
+    let file = std::fs::File::create(path).map_err(|err| {
+        HintError::CustomHint(
+            format!("Failed to create fact topologies file: {err}").into_boxed_str(),
+        )
+    })?;
+    serde_json::to_writer(
+        std::io::BufWriter::new(file),
+        &FactTopologiesFile { fact_topologies },
+    )
+    .map_err(|err| {
+        HintError::CustomHint(
+            format!("Failed to write fact topologies file: {err}").into_boxed_str(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        any_box,
+        hint_processor::{
+            builtin_hint_processor::{
+                builtin_hint_processor_definition::{BuiltinHintProcessor, HintProcessorData},
+                hint_code,
+            },
+            hint_processor_definition::HintProcessorLogic,
+        },
+        serde::deserialize_program::Identifier,
+        stdlib::sync::Arc,
+        types::builtin_name::BuiltinName,
+        utils::test_utils::*,
+        vm::runners::{
+            cairo_pie::{
+                CairoPieAdditionalData, CairoPieMemory, CairoPieMetadata, CairoPieVersion,
+                OutputBuiltinAdditionalData, PublicMemoryPage, SegmentInfo, StrippedProgram,
+            },
+            cairo_runner::ExecutionResources,
+        },
+    };
+    use assert_matches::assert_matches;
+    use rstest::rstest;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    // A PIE of a program that writes 7 to its output: program in segment 0, execution in
+    // segment 1, output in segment 2 and return fp and pc in segments 3 and 4
+    fn output_pie() -> CairoPie {
+        let data: Vec<MaybeRelocatable> = vec![
+            Felt252::from(0x400380007ffc8000_u64).into(),
+            Felt252::from(7).into(),
+            Felt252::from(0x482680017ffc8000_u64).into(),
+        ];
+        CairoPie {
+            metadata: CairoPieMetadata {
+                program: StrippedProgram {
+                    data: data.clone(),
+                    builtins: vec![BuiltinName::output],
+                    main: 0,
+                    prime: (),
+                },
+                program_segment: SegmentInfo::from((0, 3)),
+                execution_segment: SegmentInfo::from((1, 4)),
+                ret_fp_segment: SegmentInfo::from((3, 0)),
+                ret_pc_segment: SegmentInfo::from((4, 0)),
+                builtin_segments: HashMap::from([(BuiltinName::output, SegmentInfo::from((2, 1)))]),
+                extra_segments: vec![],
+            },
+            memory: CairoPieMemory(
+                data.into_iter()
+                    .enumerate()
+                    .map(|(i, value)| ((0, i), value))
+                    .chain([
+                        ((1, 0), MaybeRelocatable::from((2, 0))),
+                        ((1, 1), MaybeRelocatable::from((3, 0))),
+                        ((1, 2), MaybeRelocatable::from((4, 0))),
+                        ((1, 3), MaybeRelocatable::from((2, 1))),
+                        ((2, 0), MaybeRelocatable::from(7)),
+                    ])
+                    .collect(),
+            ),
+            execution_resources: ExecutionResources::default(),
+            additional_data: CairoPieAdditionalData(HashMap::from([(
+                BuiltinName::output,
+                BuiltinAdditionalData::Output(OutputBuiltinAdditionalData {
+                    pages: HashMap::new(),
+                    attributes: HashMap::new(),
+                }),
+            )])),
+            version: CairoPieVersion { cairo_pie: () },
+        }
+    }
+
+    fn bootloader_input(tasks: Vec<CairoPie>) -> SimpleBootloaderInput {
+        SimpleBootloaderInput {
+            tasks: tasks.into_iter().map(Task::from).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn label(pc: usize) -> Identifier {
+        Identifier {
+            pc: Some(pc),
+            type_: Some(String::from("label")),
+            value: None,
+            full_name: None,
+            members: None,
+            cairo_type: None,
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn initial_scope_holds_ret_pc_offset() {
+        let mut program = program!();
+        Arc::get_mut(&mut program.shared_program_data)
+            .unwrap()
+            .identifiers = HashMap::from([
+            (format!("{EXECUTE_TASK}.call_task"), label(10)),
+            (format!("{EXECUTE_TASK}.ret_pc_label"), label(12)),
+        ]);
+        let exec_scopes =
+            simple_bootloader_initial_scope(&program, bootloader_input(vec![output_pie()]))
+                .unwrap();
+        assert_matches!(exec_scopes.get::<usize>(RET_PC_OFFSET), Ok(2));
+        assert_matches!(
+            exec_scopes.get_ref::<SimpleBootloaderInput>("program_input"),
+            Ok(input) if input.tasks == [Task::from(output_pie())]
+        );
+
+        assert_matches!(
+            simple_bootloader_initial_scope(&program!(), SimpleBootloaderInput::default()),
+            Err(HintError::UnknownIdentifier(name)) if name.ends_with(".ret_pc_label")
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn load_input_and_prepare_task_range_checks() {
+        let mut vm = vm!();
+        vm.set_fp(3);
+        vm.segments = segments![((1, 0), (2, 0)), ((1, 1), (3, 0))];
+        add_segments!(vm, 2);
+        let mut exec_scopes = scope![(
+            "program_input",
+            bootloader_input(vec![output_pie(), output_pie()])
+        )];
+        let ids_data = ids_data!["output_ptr", "range_check_ptr", "task_range_check_ptr"];
+        assert_matches!(
+            run_hint!(
+                vm,
+                HashMap::new(),
+                hint_code::SIMPLE_BOOTLOADER_LOAD_INPUT,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data,
+                hint_code::SIMPLE_BOOTLOADER_PREPARE_TASK_RANGE_CHECKS,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![vm.segments.memory, ((2, 0), 2), ((1, 2), (3, 22))];
+        assert_matches!(
+            exec_scopes.get_list_ref::<FactTopology>("fact_topologies"),
+            Ok(fact_topologies) if fact_topologies.is_empty()
+        );
+    }
+
+    #[rstest]
+    #[case(2, 0)]
+    #[case(1, 1)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn set_current_task(#[case] n_tasks: usize, #[case] task_id: usize) {
+        let mut vm = vm!();
+        vm.set_fp(1);
+        vm.segments = segments![((1, 0), n_tasks)];
+        let mut tasks = vec![output_pie(), output_pie()];
+        tasks[1].metadata.program.main = 1;
+        let mut exec_scopes = scope![("simple_bootloader_input", bootloader_input(tasks.clone()))];
+        assert_matches!(
+            run_hint!(
+                vm,
+                HashMap::new(),
+                hint_code::SIMPLE_BOOTLOADER_SET_TASKS_VARIABLE,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["n_tasks"],
+                hint_code::SIMPLE_BOOTLOADER_SET_CURRENT_TASK,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        assert_matches!(
+            exec_scopes.get_ref::<Task>("task"),
+            Ok(Task::CairoPie(task)) if **task == tasks[task_id]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn set_current_task_out_of_range() {
+        let mut vm = vm!();
+        vm.set_fp(1);
+        vm.segments = segments![((1, 0), 3)];
+        let mut exec_scopes = scope![("tasks", vec![Task::from(output_pie())])];
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["n_tasks"],
+                hint_code::SIMPLE_BOOTLOADER_SET_CURRENT_TASK,
+                &mut exec_scopes
+            ),
+            Err(HintError::AssertionFailed(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn allocate_and_load_program() {
+        let mut vm = vm!();
+        vm.set_fp(3);
+        add_segments!(vm, 2);
+        let mut exec_scopes = scope![("task", Task::from(output_pie()))];
+        assert_matches!(
+            run_hint!(
+                vm,
+                non_continuous_ids_data![("program_data_ptr", -3)],
+                hint_code::EXECUTE_TASK_ALLOCATE_PROGRAM_DATA_SEGMENT,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![vm.segments.memory, ((1, 0), (2, 0))];
+        // program_header = program_data_ptr
+        vm.insert_value(Relocatable::from((1, 1)), Relocatable::from((2, 0)))
+            .unwrap();
+        assert_matches!(
+            run_hint!(
+                vm,
+                non_continuous_ids_data![("program_header", -2), ("program_address", -1)],
+                hint_code::EXECUTE_TASK_LOAD_PROGRAM,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![
+            vm.segments.memory,
+            ((2, 0), 7),
+            ((2, 2), 0),
+            ((2, 3), 1),
+            ((2, 4), 0x6f7574707574_u64),
+            ((2, 5), 0x400380007ffc8000_u64),
+            ((2, 6), 7),
+            ((2, 7), 0x482680017ffc8000_u64)
+        ];
+        assert_eq!(vm.segments.segment_sizes.get(&2), Some(&8));
+        // The bootloader version is written by the bootloader itself
+        assert_eq!(vm.get_maybe(&Relocatable::from((2, 1))), None);
+
+        vm.insert_value(Relocatable::from((1, 2)), Relocatable::from((2, 5)))
+            .unwrap();
+        assert_matches!(
+            run_hint!(
+                vm,
+                non_continuous_ids_data![("program_address", -1)],
+                hint_code::EXECUTE_TASK_ASSERT_PROGRAM_ADDRESS,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        vm.set_fp(2);
+        assert_matches!(
+            run_hint!(
+                vm,
+                non_continuous_ids_data![("program_address", -1)],
+                hint_code::EXECUTE_TASK_ASSERT_PROGRAM_ADDRESS,
+                &mut exec_scopes
+            ),
+            Err(HintError::AssertionFailed(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_hash() {
+        let task = output_pie();
+        let program_hash = compute_program_hash_chain(&task.metadata.program, 0).unwrap();
+        let program_hash = Felt252::from_bytes_be(&program_hash.to_bytes_be());
+        let mut vm = vm!();
+        vm.set_fp(1);
+        vm.segments = segments![((1, 0), (2, 0))];
+        add_segments!(vm, 1);
+        vm.insert_value(Relocatable::from((2, 1)), program_hash)
+            .unwrap();
+        let mut exec_scopes = scope![("task", Task::from(task))];
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["output_ptr"],
+                hint_code::EXECUTE_TASK_VALIDATE_HASH,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+
+        let mut other_task = output_pie();
+        other_task.metadata.program.main = 2;
+        let mut exec_scopes = scope![("task", Task::from(other_task))];
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data!["output_ptr"],
+                hint_code::EXECUTE_TASK_VALIDATE_HASH,
+                &mut exec_scopes
+            ),
+            Err(HintError::AssertionFailed(bx)) if bx.as_ref() == "Computed hash does not match input."
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn call_task_loads_cairo_pie() {
+        let mut vm = vm!();
+        // Program loaded at 2:4, output builtin at 3:2, the task is called at 0:0 and returns to
+        // 0:2, with its builtin pointer at 1:1
+        vm.segments = segments![((1, 1), (3, 2))];
+        add_segments!(vm, 2);
+        vm.set_pc(Relocatable::from((0, 0)));
+        vm.set_fp(1);
+        vm.set_ap(2);
+        let mut exec_scopes = scope![
+            ("task", Task::from(output_pie())),
+            ("program_address", Relocatable::from((2, 4))),
+            (RET_PC_OFFSET, 2_usize)
+        ];
+        assert_matches!(
+            run_hint!(
+                vm,
+                HashMap::new(),
+                hint_code::EXECUTE_TASK_CALL_TASK,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![
+            vm.segments.memory,
+            ((2, 4), 0x400380007ffc8000_u64),
+            ((2, 5), 7),
+            ((2, 6), 0x482680017ffc8000_u64),
+            ((1, 1), (3, 2)),
+            ((1, 2), (1, 1)),
+            ((1, 3), (0, 2)),
+            ((1, 4), (3, 3)),
+            ((3, 2), 7)
+        ];
+        // The task runs in its own scope
+        assert_eq!(exec_scopes.data.len(), 2);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn call_task_missing_builtin_segment() {
+        let mut vm = vm!();
+        vm.segments = segments![((1, 1), (3, 2))];
+        vm.set_ap(2);
+        let mut task = output_pie();
+        task.metadata.builtin_segments.clear();
+        let mut exec_scopes = scope![
+            ("task", Task::from(task)),
+            ("program_address", Relocatable::from((2, 4))),
+            (RET_PC_OFFSET, 2_usize)
+        ];
+        assert_matches!(
+            run_hint!(
+                vm,
+                HashMap::new(),
+                hint_code::EXECUTE_TASK_CALL_TASK,
+                &mut exec_scopes
+            ),
+            Err(HintError::AssertionFailed(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn call_task_rejects_run_program_task() {
+        let mut vm = vm!();
+        let mut exec_scopes = scope![
+            ("task", Task::RunProgram(program!())),
+            ("program_address", Relocatable::from((2, 4))),
+            (RET_PC_OFFSET, 2_usize)
+        ];
+        assert_matches!(
+            run_hint!(
+                vm,
+                HashMap::new(),
+                hint_code::EXECUTE_TASK_CALL_TASK,
+                &mut exec_scopes
+            ),
+            Err(HintError::CustomHint(message)) if message.contains("RunProgramTask is not supported")
+        );
+    }
+
+    #[rstest]
+    #[case((2, 3), Ok(()))]
+    #[case((2, 4), Err(()))]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn write_return_builtins(#[case] output_end: (isize, usize), #[case] expected: Result<(), ()>) {
+        let mut vm = vm!();
+        // pre_execution_builtin_ptrs at 1:0, return_builtin_ptrs at 1:11, used_builtins_addr at
+        // 1:22, and the task's return values at 1:23
+        add_segments!(vm, 2 + ALL_BUILTINS.len());
+        for (index, segment_index) in (0..ALL_BUILTINS.len()).zip(2..) {
+            let ptr = Relocatable::from((segment_index, if index == 0 { 2 } else { 0 }));
+            vm.insert_value(Relocatable::from((1, index)), ptr).unwrap();
+        }
+        vm.insert_value(Relocatable::from((1, 22)), Relocatable::from((1, 23)))
+            .unwrap();
+        vm.insert_value(Relocatable::from((1, 23)), Relocatable::from(output_end))
+            .unwrap();
+        vm.set_fp(23);
+        let ids_data = non_continuous_ids_data![
+            ("pre_execution_builtin_ptrs", -23),
+            ("return_builtin_ptrs", -12),
+            ("used_builtins_addr", -1)
+        ];
+        let mut exec_scopes = scope![("task", Task::from(output_pie()))];
+        let result = run_hint!(
+            vm,
+            ids_data,
+            hint_code::EXECUTE_TASK_WRITE_RETURN_BUILTINS,
+            &mut exec_scopes
+        );
+        if expected.is_err() {
+            assert_matches!(
+                result,
+                Err(HintError::AssertionFailed(bx)) if bx.as_ref() == "Builtin usage is inconsistent with the CairoPie."
+            );
+            return;
+        }
+        assert_matches!(result, Ok(()));
+        // Only the output builtin is used, the other pointers are copied as is
+        check_memory![vm.segments.memory, ((1, 11), (2, 3))];
+        for (index, segment_index) in (1..ALL_BUILTINS.len()).zip(3..) {
+            assert_eq!(
+                vm.get_relocatable(Relocatable::from((1, 11 + index))),
+                Ok(Relocatable::from((segment_index, 0)))
+            );
+        }
+        assert_matches!(
+            exec_scopes.get::<Felt252>("n_selected_builtins"),
+            Ok(n) if n == Felt252::ONE
+        );
+    }
+
+    #[rstest]
+    #[case(1, 5, 0, 1)]
+    #[case(1, 6, 1, 0)]
+    #[case(0, 5, 0, 0)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn select_builtin(
+        #[case] n_selected_builtins: usize,
+        #[case] selected_encoding: usize,
+        #[case] n_selected_builtins_after: usize,
+        #[case] selected: usize,
+    ) {
+        let mut vm = vm!();
+        vm.segments = segments![
+            ((1, 0), n_selected_builtins),
+            ((1, 1), (2, 0)),
+            ((1, 2), (2, 1)),
+            ((2, 0), 5),
+            ((2, 1), selected_encoding)
+        ];
+        vm.set_fp(4);
+        let ids_data = ids_data![
+            "n_selected_builtins",
+            "all_encodings",
+            "selected_encodings",
+            "select_builtin"
+        ];
+        let mut exec_scopes = scope![];
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data.clone(),
+                hint_code::SELECT_BUILTINS_ENTER_SCOPE,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data,
+                hint_code::INNER_SELECT_BUILTINS_SELECT_BUILTIN,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![vm.segments.memory, ((1, 3), selected)];
+        assert_matches!(
+            exec_scopes.get::<Felt252>("n_selected_builtins"),
+            Ok(n) if n == Felt252::from(n_selected_builtins_after)
+        );
+    }
+
+    #[rstest]
+    #[case(5, &[], Ok(vec![5]))]
+    #[case(5, &[(1, 2, 3)], Ok(vec![2, 3]))]
+    #[case(6, &[(1, 0, 2), (2, 2, 4)], Ok(vec![0, 2, 4]))]
+    #[case(5, &[(1, 2, 2)], Err(()))]
+    #[case(6, &[(1, 0, 2), (2, 3, 3)], Err(()))]
+    #[case(5, &[(2, 0, 5)], Err(()))]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_page_sizes(
+        #[case] output_size: usize,
+        #[case] pages: &[(usize, usize, usize)],
+        #[case] expected: Result<Vec<usize>, ()>,
+    ) {
+        let pages = pages
+            .iter()
+            .map(|(id, start, size)| {
+                (
+                    *id,
+                    PublicMemoryPage {
+                        start: *start,
+                        size: *size,
+                    },
+                )
+            })
+            .collect();
+        let page_sizes = super::get_page_sizes(output_size, &pages);
+        match expected {
+            Ok(expected) => assert_eq!(page_sizes.unwrap(), expected),
+            Err(()) => assert_matches!(page_sizes, Err(HintError::AssertionFailed(_))),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cairo_pie_fact_topology() {
+        let mut pie = output_pie();
+        assert_eq!(
+            get_cairo_pie_fact_topology(&pie, 3).unwrap(),
+            FactTopology {
+                tree_structure: vec![1, 0],
+                page_sizes: vec![3],
+            }
+        );
+
+        let pages = HashMap::from([(1, PublicMemoryPage { start: 1, size: 2 })]);
+        pie.additional_data.0.insert(
+            BuiltinName::output,
+            BuiltinAdditionalData::Output(OutputBuiltinAdditionalData {
+                pages: pages.clone(),
+                attributes: HashMap::new(),
+            }),
+        );
+        assert_matches!(
+            get_cairo_pie_fact_topology(&pie, 3),
+            Err(HintError::AssertionFailed(_))
+        );
+        pie.additional_data.0.insert(
+            BuiltinName::output,
+            BuiltinAdditionalData::Output(OutputBuiltinAdditionalData {
+                pages,
+                attributes: HashMap::from([(GPS_FACT_TOPOLOGY.to_string(), vec![2, 1, 0, 2])]),
+            }),
+        );
+        assert_eq!(
+            get_cairo_pie_fact_topology(&pie, 3).unwrap(),
+            FactTopology {
+                tree_structure: vec![2, 1, 0, 2],
+                page_sizes: vec![1, 2],
+            }
+        );
+    }
+
+    #[rstest]
+    #[case(false, vec![(1, 3, 1), (2, 6, 2), (3, 8, 1)])]
+    #[case(true, vec![])]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn configure_fact_topologies(
+        #[case] single_page: bool,
+        #[case] expected_pages: Vec<(usize, usize, usize)>,
+    ) {
+        let mut vm = vm!();
+        vm.builtin_runners = vec![OutputBuiltinRunner::new(true).into()];
+        add_segments!(vm, 1);
+        let mut exec_scopes = scope![
+            (
+                "simple_bootloader_input",
+                SimpleBootloaderInput {
+                    single_page,
+                    ..Default::default()
+                }
+            ),
+            (
+                "fact_topologies",
+                vec![
+                    FactTopology {
+                        tree_structure: vec![1, 0],
+                        page_sizes: vec![1],
+                    },
+                    FactTopology {
+                        tree_structure: vec![2, 1, 0, 2],
+                        page_sizes: vec![2, 1],
+                    },
+                ]
+            )
+        ];
+        assert_matches!(
+            run_hint!(
+                vm,
+                HashMap::new(),
+                hint_code::SIMPLE_BOOTLOADER_CONFIGURE_FACT_TOPOLOGIES,
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        let expected_pages: Pages = expected_pages
+            .into_iter()
+            .map(|(id, start, size)| (id, PublicMemoryPage { start, size }))
+            .collect();
+        assert_eq!(vm.get_output_builtin_mut().unwrap().pages, expected_pages);
+    }
+}
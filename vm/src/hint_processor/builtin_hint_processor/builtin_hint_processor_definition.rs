@@ -22,6 +22,7 @@ use super::{
         pack::*,
     },
 };
+use crate::any_box;
 use crate::Felt252;
 use crate::{
     hint_processor::{
@@ -39,6 +40,12 @@ use crate::{
             blake2s_utils::{
                 blake2s_add_uint256, blake2s_add_uint256_bigend, compute_blake2s, finalize_blake2s,
             },
+            bootloader::{
+                allocate_program_data_segment, append_fact_topologies, assert_program_address,
+                call_task, configure_fact_topologies, load_program, load_simple_bootloader_input,
+                prepare_task_range_checks, select_builtin, select_builtins_enter_scope,
+                set_current_task, set_tasks_variable, validate_hash, write_return_builtins,
+            },
             cairo_keccak::keccak_hints::{
                 block_permutation_v1, block_permutation_v2, cairo_keccak_finalize_v1,
                 cairo_keccak_finalize_v2, cairo_keccak_is_full_word, compare_bytes_in_word_nondet,
@@ -52,6 +59,7 @@ use crate::{
             find_element_hint::{find_element, search_sorted_lower},
             garaga::get_felt_bitlenght,
             hint_code,
+            hint_whitelist::HintWhitelist,
             keccak_utils::{
                 split_input, split_n_bytes, split_output, split_output_mid_low_high, unsafe_keccak,
                 unsafe_keccak_finalize,
@@ -107,13 +115,18 @@ use crate::{
                 verify_multiplicity_body, verify_usort,
             },
         },
-        hint_processor_definition::HintReference,
+        hint_processor_definition::{get_ids_data, HintReference},
+        hint_processor_utils::normalize_hint_code,
     },
     serde::deserialize_program::ApTracking,
-    stdlib::{any::Any, collections::HashMap, prelude::*, rc::Rc},
+    stdlib::{any::Any, borrow::Cow, collections::HashMap, prelude::*, rc::Rc},
     types::exec_scope::ExecutionScopes,
-    vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
+    vm::{
+        errors::{hint_errors::HintError, vm_errors::VirtualMachineError},
+        vm_core::VirtualMachine,
+    },
 };
+use lazy_static::lazy_static;
 
 #[cfg(feature = "test_utils")]
 use crate::hint_processor::builtin_hint_processor::skip_next_instruction::skip_next_instruction;
@@ -158,23 +171,42 @@ pub struct HintFunc(
 pub struct BuiltinHintProcessor {
     pub extra_hints: HashMap<String, Rc<HintFunc>>,
     run_resources: RunResources,
+    whitelist: Option<HintWhitelist>,
 }
 impl BuiltinHintProcessor {
     pub fn new_empty() -> Self {
         BuiltinHintProcessor {
             extra_hints: HashMap::new(),
             run_resources: RunResources::default(),
+            whitelist: None,
         }
     }
 
     pub fn new(extra_hints: HashMap<String, Rc<HintFunc>>, run_resources: RunResources) -> Self {
         BuiltinHintProcessor {
-            extra_hints,
+            extra_hints: extra_hints
+                .into_iter()
+                .map(|(code, hint_func)| (normalize_hint_code(&code).into_owned(), hint_func))
+                .collect(),
             run_resources,
+            whitelist: None,
         }
     }
 
+    /// Restricts the hints this processor compiles, extra hints included, to those of the
+    /// whitelist, using the references it allows. Compiling any other hint fails with
+    /// `VirtualMachineError::HintNotWhitelisted`.
+    pub fn with_whitelist(mut self, whitelist: HintWhitelist) -> Self {
+        self.whitelist = Some(whitelist);
+        self
+    }
+
+    /// Adds a hint, its code being matched regardless of its formatting like builtin hints are
     pub fn add_hint(&mut self, hint_code: String, hint_func: Rc<HintFunc>) {
+        let hint_code = match normalize_hint_code(&hint_code) {
+            Cow::Borrowed(_) => hint_code,
+            Cow::Owned(normalized) => normalized,
+        };
         self.extra_hints.insert(hint_code, hint_func);
     }
 }
@@ -191,7 +223,9 @@ impl HintProcessorLogic for BuiltinHintProcessor {
             .downcast_ref::<HintProcessorData>()
             .ok_or(HintError::WrongHintData)?;
 
-        if let Some(hint_func) = self.extra_hints.get(&hint_data.code) {
+        // Already normalized by compile_hint, unless the hint data was built by someone else
+        let code = normalize_hint_code(&hint_data.code);
+        if let Some(hint_func) = self.extra_hints.get(code.as_ref()) {
             return hint_func.0(
                 vm,
                 exec_scopes,
@@ -200,683 +234,808 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                 constants,
             );
         }
-        match &*hint_data.code {
-            hint_code::ADD_SEGMENT => add_segment(vm),
-            hint_code::IS_NN => is_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::IS_NN_OUT_OF_RANGE => {
-                is_nn_out_of_range(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::ASSERT_LE_FELT => assert_le_felt(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                constants,
-            ),
-            hint_code::ASSERT_LE_FELT_EXCLUDED_2 => assert_le_felt_excluded_2(exec_scopes),
-            hint_code::ASSERT_LE_FELT_EXCLUDED_1 => assert_le_felt_excluded_1(vm, exec_scopes),
-            hint_code::ASSERT_LE_FELT_EXCLUDED_0 => assert_le_felt_excluded_0(vm, exec_scopes),
-            hint_code::IS_LE_FELT => is_le_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::ASSERT_250_BITS => {
-                assert_250_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::IS_250_BITS => is_250_bits(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::IS_ADDR_BOUNDED => {
-                is_addr_bounded(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::IS_POSITIVE => is_positive(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::SPLIT_INT_ASSERT_RANGE => {
-                split_int_assert_range(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::SPLIT_INT => split_int(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::ASSERT_NOT_EQUAL => {
-                assert_not_equal(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::ASSERT_NN => assert_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::SQRT => sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::ASSERT_NOT_ZERO => {
-                assert_not_zero(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::IS_QUAD_RESIDUE => {
-                is_quad_residue(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::VM_EXIT_SCOPE => exit_scope(exec_scopes),
-            hint_code::MEMCPY_ENTER_SCOPE => {
-                memcpy_enter_scope(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::MEMSET_ENTER_SCOPE => {
-                memset_enter_scope(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::MEMCPY_CONTINUE_COPYING => memset_step_loop(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "continue_copying",
-            ),
-            hint_code::MEMSET_CONTINUE_LOOP => memset_step_loop(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "continue_loop",
-            ),
-            hint_code::SPLIT_FELT => {
-                split_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::UNSIGNED_DIV_REM => {
-                unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::SIGNED_DIV_REM => {
-                signed_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::ASSERT_LT_FELT => {
-                assert_lt_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::FIND_ELEMENT => {
-                find_element(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::SEARCH_SORTED_LOWER => {
-                search_sorted_lower(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::POW => pow(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::SET_ADD => set_add(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::DICT_NEW => dict_new(vm, exec_scopes),
-            hint_code::DICT_READ => {
-                dict_read(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::DICT_WRITE => {
-                dict_write(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::DEFAULT_DICT_NEW => {
-                default_dict_new(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::SQUASH_DICT_INNER_FIRST_ITERATION => squash_dict_inner_first_iteration(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::USORT_ENTER_SCOPE => usort_enter_scope(exec_scopes),
-            hint_code::USORT_BODY => {
-                usort_body(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::USORT_VERIFY => {
-                verify_usort(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::USORT_VERIFY_MULTIPLICITY_ASSERT => verify_multiplicity_assert(exec_scopes),
-            hint_code::USORT_VERIFY_MULTIPLICITY_BODY => verify_multiplicity_body(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::BLAKE2S_COMPUTE => {
-                compute_blake2s(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::VERIFY_ZERO_V1 | hint_code::VERIFY_ZERO_V2 => verify_zero(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P,
-            ),
-            hint_code::VERIFY_ZERO_V3 => verify_zero(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P_V2,
-            ),
-            hint_code::VERIFY_ZERO_EXTERNAL_SECP => verify_zero_with_external_const(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::NONDET_BIGINT3_V1 | hint_code::NONDET_BIGINT3_V2 => {
-                nondet_bigint3(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::REDUCE_V1 => {
-                reduce_v1(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::REDUCE_V2 => {
-                reduce_v2(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::REDUCE_ED25519 => {
-                ed25519_reduce(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::BLAKE2S_FINALIZE | hint_code::BLAKE2S_FINALIZE_V2 => {
-                finalize_blake2s(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::BLAKE2S_FINALIZE_V3 => {
-                finalize_blake2s_v3(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::BLAKE2S_ADD_UINT256 => {
-                blake2s_add_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::BLAKE2S_ADD_UINT256_BIGEND => {
-                blake2s_add_uint256_bigend(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UNSAFE_KECCAK => {
-                unsafe_keccak(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UNSAFE_KECCAK_FINALIZE => {
-                unsafe_keccak_finalize(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::SQUASH_DICT_INNER_SKIP_LOOP => squash_dict_inner_skip_loop(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::SQUASH_DICT_INNER_CHECK_ACCESS_INDEX => {
-                squash_dict_inner_check_access_index(
-                    vm,
-                    exec_scopes,
-                    &hint_data.ids_data,
-                    &hint_data.ap_tracking,
-                )
-            }
-            hint_code::SQUASH_DICT_INNER_CONTINUE_LOOP => squash_dict_inner_continue_loop(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::SQUASH_DICT_INNER_ASSERT_LEN_KEYS => {
-                squash_dict_inner_assert_len_keys(exec_scopes)
-            }
-            hint_code::SQUASH_DICT_INNER_LEN_ASSERT => squash_dict_inner_len_assert(exec_scopes),
-            hint_code::SQUASH_DICT_INNER_USED_ACCESSES_ASSERT => {
-                squash_dict_inner_used_accesses_assert(
-                    vm,
-                    exec_scopes,
-                    &hint_data.ids_data,
-                    &hint_data.ap_tracking,
-                )
-            }
-            hint_code::SQUASH_DICT_INNER_NEXT_KEY => squash_dict_inner_next_key(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::SQUASH_DICT => {
-                squash_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::VM_ENTER_SCOPE => enter_scope(exec_scopes),
-            hint_code::DICT_UPDATE => {
-                dict_update(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::DICT_SQUASH_COPY_DICT => {
-                dict_squash_copy_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::DICT_SQUASH_UPDATE_PTR => {
-                dict_squash_update_ptr(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UINT256_ADD => {
-                uint256_add(vm, &hint_data.ids_data, &hint_data.ap_tracking, false)
-            }
-            hint_code::UINT256_ADD_LOW => {
-                uint256_add(vm, &hint_data.ids_data, &hint_data.ap_tracking, true)
-            }
-            hint_code::UINT128_ADD => uint128_add(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::UINT256_SUB => uint256_sub(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::SPLIT_64 => split_64(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::UINT256_SQRT => {
-                uint256_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking, false)
-            }
-            hint_code::UINT256_SQRT_FELT => {
-                uint256_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking, true)
-            }
-            hint_code::UINT256_SIGNED_NN => {
-                uint256_signed_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UINT256_UNSIGNED_DIV_REM => {
-                uint256_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UINT256_EXPANDED_UNSIGNED_DIV_REM => {
-                uint256_expanded_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::BIGINT_TO_UINT256 => {
-                bigint_to_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::IS_ZERO_PACK_V1 | hint_code::IS_ZERO_PACK_V2 => {
-                is_zero_pack(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::IS_ZERO_NONDET | hint_code::IS_ZERO_INT => is_zero_nondet(vm, exec_scopes),
-            hint_code::IS_ZERO_PACK_EXTERNAL_SECP_V1 | hint_code::IS_ZERO_PACK_EXTERNAL_SECP_V2 => {
-                is_zero_pack_external_secp(
-                    vm,
-                    exec_scopes,
-                    &hint_data.ids_data,
-                    &hint_data.ap_tracking,
-                )
-            }
-            hint_code::IS_ZERO_PACK_ED25519 => {
-                ed25519_is_zero_pack(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::IS_ZERO_ASSIGN_SCOPE_VARS => is_zero_assign_scope_variables(exec_scopes),
-            hint_code::IS_ZERO_ASSIGN_SCOPE_VARS_EXTERNAL_SECP => {
-                is_zero_assign_scope_variables_external_const(exec_scopes)
-            }
-            hint_code::IS_ZERO_ASSIGN_SCOPE_VARS_ED25519 => {
-                ed25519_is_zero_assign_scope_vars(exec_scopes)
-            }
-            hint_code::DIV_MOD_N_PACKED_DIVMOD_V1 => div_mod_n_packed_divmod(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::GET_FELT_BIT_LENGTH => {
-                get_felt_bitlenght(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::BIGINT_PACK_DIV_MOD => bigint_pack_div_mod_hint(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::BIGINT_SAFE_DIV => {
-                bigint_safe_div_hint(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::DIV_MOD_N_PACKED_DIVMOD_EXTERNAL_N => div_mod_n_packed_external_n(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::DIV_MOD_N_SAFE_DIV => div_mod_n_safe_div(exec_scopes, "a", "b", 0),
-            hint_code::DIV_MOD_N_SAFE_DIV_PLUS_ONE => div_mod_n_safe_div(exec_scopes, "a", "b", 1),
-            hint_code::GET_POINT_FROM_X => get_point_from_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                constants,
-            ),
-            hint_code::EC_NEGATE => ec_negate_import_secp_p(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::EC_NEGATE_EMBEDDED_SECP => ec_negate_embedded_secp_p(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::EC_DOUBLE_SLOPE_V1 => compute_doubling_slope(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "point",
-                &SECP_P,
-                &ALPHA,
-            ),
-            hint_code::EC_DOUBLE_SLOPE_V2 => compute_doubling_slope(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "point",
-                &SECP_P_V2,
-                &ALPHA_V2,
-            ),
-            hint_code::EC_DOUBLE_SLOPE_V3 => compute_doubling_slope(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "pt",
-                &SECP_P,
-                &ALPHA,
-            ),
-            hint_code::EC_DOUBLE_SLOPE_V4 => compute_doubling_slope(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "point",
-                &SECP256R1_P,
-                &SECP256R1_ALPHA,
-            ),
-            hint_code::EC_DOUBLE_SLOPE_EXTERNAL_CONSTS => compute_doubling_slope_external_consts(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::COMPUTE_SLOPE_V1 => compute_slope_and_assing_secp_p(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "point0",
-                "point1",
-                &SECP_P,
-            ),
-            hint_code::SQUARE_SLOPE_X_MOD_P => {
-                square_slope_minus_xs(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        match BUILTIN_HINTS.get(code.as_ref()) {
+            Some(hint_func) => hint_func(vm, exec_scopes, hint_data, constants),
+            None => Err(HintError::UnknownHint(
+                hint_data.code.clone().into_boxed_str(),
+            )),
+        }
+    }
+
+    fn compile_hint(
+        &self,
+        hint_code: &str,
+        ap_tracking_data: &ApTracking,
+        reference_ids: &HashMap<String, usize>,
+        references: &[HintReference],
+    ) -> Result<Box<dyn Any>, VirtualMachineError> {
+        let code = normalize_hint_code(hint_code).into_owned();
+        if let Some(ref whitelist) = self.whitelist {
+            if !whitelist.allows(&code, ap_tracking_data, reference_ids, references) {
+                return Err(VirtualMachineError::HintNotWhitelisted(Box::new((
+                    None,
+                    code.into_boxed_str(),
+                ))));
             }
-            hint_code::COMPUTE_SLOPE_V2 => compute_slope_and_assing_secp_p(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "point0",
-                "point1",
-                &SECP_P_V2,
-            ),
-            hint_code::COMPUTE_SLOPE_SECP256R1_V1 => compute_slope(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "point0",
-                "point1",
-                "SECP_P",
-            ),
-            hint_code::COMPUTE_SLOPE_SECP256R1_V2 => compute_slope(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "point0",
-                "point1",
-                "SECP256R1_P",
-            ),
-            hint_code::IMPORT_SECP256R1_P => import_secp256r1_p(exec_scopes),
-            hint_code::COMPUTE_SLOPE_WHITELIST => compute_slope_and_assing_secp_p(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                "pt0",
-                "pt1",
-                &SECP_P,
-            ),
-            hint_code::EC_DOUBLE_ASSIGN_NEW_X_V1 => ec_double_assign_new_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P,
-                "point",
+        }
+        Ok(any_box!(HintProcessorData {
+            code,
+            ap_tracking: ap_tracking_data.clone(),
+            ids_data: get_ids_data(reference_ids, references)?,
+        }))
+    }
+
+    fn is_hint_supported(&self, hint_data: &Box<dyn Any>) -> bool {
+        hint_data
+            .downcast_ref::<HintProcessorData>()
+            .is_some_and(|hint_data| {
+                let code = normalize_hint_code(&hint_data.code);
+                self.extra_hints.contains_key(code.as_ref())
+                    || BUILTIN_HINTS.contains_key(code.as_ref())
+            })
+    }
+}
+
+type BuiltinHintFn = fn(
+    &mut VirtualMachine,
+    &mut ExecutionScopes,
+    &HintProcessorData,
+    &HashMap<String, Felt252>,
+) -> Result<(), HintError>;
+
+lazy_static! {
+    // Maps the normalized code of each builtin hint to its implementation
+    static ref BUILTIN_HINTS: HashMap<String, BuiltinHintFn> = builtin_hints()
+        .into_iter()
+        .flat_map(|(codes, hint_func)| {
+            codes
+                .iter()
+                .map(move |code| (normalize_hint_code(code).into_owned(), hint_func))
+        })
+        .collect();
+}
+
+// Builds the list of builtin hints from match-like arms, pairing the codes of each arm with a
+// function that runs its body with the given arguments in scope
+macro_rules! builtin_hints {
+    (|$vm:ident, $exec_scopes:ident, $hint_data:ident, $constants:ident| {
+        $($(#[$meta:meta])* $($code:path)|+ => $body:expr,)*
+    }) => {
+        vec![$(
+            $(#[$meta])*
+            (
+                &[$($code),+][..],
+                (|$vm: &mut VirtualMachine,
+                  $exec_scopes: &mut ExecutionScopes,
+                  $hint_data: &HintProcessorData,
+                  $constants: &HashMap<String, Felt252>| $body) as BuiltinHintFn,
             ),
-            hint_code::EC_DOUBLE_ASSIGN_NEW_X_V2 => ec_double_assign_new_x_v2(
+        )*]
+    };
+}
+
+#[allow(unused_variables)]
+fn builtin_hints() -> Vec<(&'static [&'static str], BuiltinHintFn)> {
+    builtin_hints!(|vm, exec_scopes, hint_data, constants| {
+        hint_code::ADD_SEGMENT => add_segment(vm),
+        hint_code::IS_NN => is_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::IS_NN_OUT_OF_RANGE => {
+            is_nn_out_of_range(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_LE_FELT => assert_le_felt(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            constants,
+        ),
+        hint_code::ASSERT_LE_FELT_EXCLUDED_2 => assert_le_felt_excluded_2(exec_scopes),
+        hint_code::ASSERT_LE_FELT_EXCLUDED_1 => assert_le_felt_excluded_1(vm, exec_scopes),
+        hint_code::ASSERT_LE_FELT_EXCLUDED_0 => assert_le_felt_excluded_0(vm, exec_scopes),
+        hint_code::IS_LE_FELT => is_le_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::ASSERT_250_BITS => {
+            assert_250_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::IS_250_BITS => is_250_bits(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::IS_ADDR_BOUNDED => {
+            is_addr_bounded(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::IS_POSITIVE => is_positive(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::SPLIT_INT_ASSERT_RANGE => {
+            split_int_assert_range(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SPLIT_INT => split_int(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::ASSERT_NOT_EQUAL => {
+            assert_not_equal(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_NN => assert_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::SQRT => sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::ASSERT_NOT_ZERO => {
+            assert_not_zero(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::IS_QUAD_RESIDUE => {
+            is_quad_residue(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::VM_EXIT_SCOPE => exit_scope(exec_scopes),
+        hint_code::MEMCPY_ENTER_SCOPE => {
+            memcpy_enter_scope(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::MEMSET_ENTER_SCOPE => {
+            memset_enter_scope(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::MEMCPY_CONTINUE_COPYING => memset_step_loop(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "continue_copying",
+        ),
+        hint_code::MEMSET_CONTINUE_LOOP => memset_step_loop(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "continue_loop",
+        ),
+        hint_code::SPLIT_FELT => {
+            split_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::UNSIGNED_DIV_REM => {
+            unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SIGNED_DIV_REM => {
+            signed_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_LT_FELT => {
+            assert_lt_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::FIND_ELEMENT => {
+            find_element(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SEARCH_SORTED_LOWER => {
+            search_sorted_lower(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::POW => pow(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::SET_ADD => set_add(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::DICT_NEW => dict_new(vm, exec_scopes),
+        hint_code::DICT_READ => {
+            dict_read(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DICT_WRITE => {
+            dict_write(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DEFAULT_DICT_NEW => {
+            default_dict_new(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SQUASH_DICT_INNER_FIRST_ITERATION => squash_dict_inner_first_iteration(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::USORT_ENTER_SCOPE => usort_enter_scope(exec_scopes),
+        hint_code::USORT_BODY => {
+            usort_body(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::USORT_VERIFY => {
+            verify_usort(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::USORT_VERIFY_MULTIPLICITY_ASSERT => verify_multiplicity_assert(exec_scopes),
+        hint_code::USORT_VERIFY_MULTIPLICITY_BODY => verify_multiplicity_body(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::BLAKE2S_COMPUTE => {
+            compute_blake2s(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::VERIFY_ZERO_V1 | hint_code::VERIFY_ZERO_V2 => verify_zero(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            &SECP_P,
+        ),
+        hint_code::VERIFY_ZERO_V3 => verify_zero(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            &SECP_P_V2,
+        ),
+        hint_code::VERIFY_ZERO_EXTERNAL_SECP => verify_zero_with_external_const(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::NONDET_BIGINT3_V1 | hint_code::NONDET_BIGINT3_V2 => {
+            nondet_bigint3(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::REDUCE_V1 => {
+            reduce_v1(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::REDUCE_V2 => {
+            reduce_v2(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::REDUCE_ED25519 => {
+            ed25519_reduce(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::BLAKE2S_FINALIZE | hint_code::BLAKE2S_FINALIZE_V2 => {
+            finalize_blake2s(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::BLAKE2S_FINALIZE_V3 => {
+            finalize_blake2s_v3(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::BLAKE2S_ADD_UINT256 => {
+            blake2s_add_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::BLAKE2S_ADD_UINT256_BIGEND => {
+            blake2s_add_uint256_bigend(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UNSAFE_KECCAK => {
+            unsafe_keccak(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UNSAFE_KECCAK_FINALIZE => {
+            unsafe_keccak_finalize(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SQUASH_DICT_INNER_SKIP_LOOP => squash_dict_inner_skip_loop(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::SQUASH_DICT_INNER_CHECK_ACCESS_INDEX => {
+            squash_dict_inner_check_access_index(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
                 &hint_data.ap_tracking,
-                "point",
-            ),
-            hint_code::EC_DOUBLE_ASSIGN_NEW_X_V3 => ec_double_assign_new_x(
+            )
+        },
+        hint_code::SQUASH_DICT_INNER_CONTINUE_LOOP => squash_dict_inner_continue_loop(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::SQUASH_DICT_INNER_ASSERT_LEN_KEYS => {
+            squash_dict_inner_assert_len_keys(exec_scopes)
+        },
+        hint_code::SQUASH_DICT_INNER_LEN_ASSERT => squash_dict_inner_len_assert(exec_scopes),
+        hint_code::SQUASH_DICT_INNER_USED_ACCESSES_ASSERT => {
+            squash_dict_inner_used_accesses_assert(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
                 &hint_data.ap_tracking,
-                &SECP_P_V2,
-                "point",
-            ),
-            hint_code::EC_DOUBLE_ASSIGN_NEW_X_V4 => ec_double_assign_new_x(
+            )
+        },
+        hint_code::SQUASH_DICT_INNER_NEXT_KEY => squash_dict_inner_next_key(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::SQUASH_DICT => {
+            squash_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::VM_ENTER_SCOPE => enter_scope(exec_scopes),
+        hint_code::DICT_UPDATE => {
+            dict_update(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DICT_SQUASH_COPY_DICT => {
+            dict_squash_copy_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DICT_SQUASH_UPDATE_PTR => {
+            dict_squash_update_ptr(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT256_ADD => {
+            uint256_add(vm, &hint_data.ids_data, &hint_data.ap_tracking, false)
+        },
+        hint_code::UINT256_ADD_LOW => {
+            uint256_add(vm, &hint_data.ids_data, &hint_data.ap_tracking, true)
+        },
+        hint_code::UINT128_ADD => uint128_add(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::UINT256_SUB => uint256_sub(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::SPLIT_64 => split_64(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::UINT256_SQRT => {
+            uint256_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking, false)
+        },
+        hint_code::UINT256_SQRT_FELT => {
+            uint256_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking, true)
+        },
+        hint_code::UINT256_SIGNED_NN => {
+            uint256_signed_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT256_UNSIGNED_DIV_REM => {
+            uint256_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT256_EXPANDED_UNSIGNED_DIV_REM => {
+            uint256_expanded_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::BIGINT_TO_UINT256 => {
+            bigint_to_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::IS_ZERO_PACK_V1 | hint_code::IS_ZERO_PACK_V2 => {
+            is_zero_pack(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::IS_ZERO_NONDET | hint_code::IS_ZERO_INT => is_zero_nondet(vm, exec_scopes),
+        hint_code::IS_ZERO_PACK_EXTERNAL_SECP_V1 | hint_code::IS_ZERO_PACK_EXTERNAL_SECP_V2 => {
+            is_zero_pack_external_secp(
                 vm,
                 exec_scopes,
                 &hint_data.ids_data,
                 &hint_data.ap_tracking,
-                &SECP_P,
-                "pt",
-            ),
-            hint_code::EC_DOUBLE_ASSIGN_NEW_Y => ec_double_assign_new_y(exec_scopes),
-            hint_code::KECCAK_WRITE_ARGS => {
-                keccak_write_args(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::COMPARE_BYTES_IN_WORD_NONDET => compare_bytes_in_word_nondet(
-                vm,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                constants,
-            ),
-            hint_code::SHA256_MAIN_CONSTANT_INPUT_LENGTH => sha256_main_constant_input_length(
-                vm,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                constants,
-            ),
-            hint_code::SHA256_MAIN_ARBITRARY_INPUT_LENGTH => sha256_main_arbitrary_input_length(
+            )
+        },
+        hint_code::IS_ZERO_PACK_ED25519 => {
+            ed25519_is_zero_pack(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::IS_ZERO_ASSIGN_SCOPE_VARS => is_zero_assign_scope_variables(exec_scopes),
+        hint_code::IS_ZERO_ASSIGN_SCOPE_VARS_EXTERNAL_SECP => {
+            is_zero_assign_scope_variables_external_const(exec_scopes)
+        },
+        hint_code::IS_ZERO_ASSIGN_SCOPE_VARS_ED25519 => {
+            ed25519_is_zero_assign_scope_vars(exec_scopes)
+        },
+        hint_code::DIV_MOD_N_PACKED_DIVMOD_V1 => div_mod_n_packed_divmod(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::GET_FELT_BIT_LENGTH => {
+            get_felt_bitlenght(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::BIGINT_PACK_DIV_MOD => bigint_pack_div_mod_hint(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::BIGINT_SAFE_DIV => {
+            bigint_safe_div_hint(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DIV_MOD_N_PACKED_DIVMOD_EXTERNAL_N => div_mod_n_packed_external_n(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::DIV_MOD_N_SAFE_DIV => div_mod_n_safe_div(exec_scopes, "a", "b", 0),
+        hint_code::DIV_MOD_N_SAFE_DIV_PLUS_ONE => div_mod_n_safe_div(exec_scopes, "a", "b", 1),
+        hint_code::GET_POINT_FROM_X => get_point_from_x(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            constants,
+        ),
+        hint_code::EC_NEGATE => ec_negate_import_secp_p(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::EC_NEGATE_EMBEDDED_SECP => ec_negate_embedded_secp_p(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::EC_DOUBLE_SLOPE_V1 => compute_doubling_slope(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "point",
+            &SECP_P,
+            &ALPHA,
+        ),
+        hint_code::EC_DOUBLE_SLOPE_V2 => compute_doubling_slope(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "point",
+            &SECP_P_V2,
+            &ALPHA_V2,
+        ),
+        hint_code::EC_DOUBLE_SLOPE_V3 => compute_doubling_slope(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "pt",
+            &SECP_P,
+            &ALPHA,
+        ),
+        hint_code::EC_DOUBLE_SLOPE_V4 => compute_doubling_slope(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "point",
+            &SECP256R1_P,
+            &SECP256R1_ALPHA,
+        ),
+        hint_code::EC_DOUBLE_SLOPE_EXTERNAL_CONSTS => compute_doubling_slope_external_consts(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::COMPUTE_SLOPE_V1 => compute_slope_and_assing_secp_p(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "point0",
+            "point1",
+            &SECP_P,
+        ),
+        hint_code::SQUARE_SLOPE_X_MOD_P => {
+            square_slope_minus_xs(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::COMPUTE_SLOPE_V2 => compute_slope_and_assing_secp_p(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "point0",
+            "point1",
+            &SECP_P_V2,
+        ),
+        hint_code::COMPUTE_SLOPE_SECP256R1_V1 => compute_slope(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "point0",
+            "point1",
+            "SECP_P",
+        ),
+        hint_code::COMPUTE_SLOPE_SECP256R1_V2 => compute_slope(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "point0",
+            "point1",
+            "SECP256R1_P",
+        ),
+        hint_code::IMPORT_SECP256R1_P => import_secp256r1_p(exec_scopes),
+        hint_code::COMPUTE_SLOPE_WHITELIST => compute_slope_and_assing_secp_p(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "pt0",
+            "pt1",
+            &SECP_P,
+        ),
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V1 => ec_double_assign_new_x(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            &SECP_P,
+            "point",
+        ),
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V2 => ec_double_assign_new_x_v2(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            "point",
+        ),
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V3 => ec_double_assign_new_x(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            &SECP_P_V2,
+            "point",
+        ),
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V4 => ec_double_assign_new_x(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            &SECP_P,
+            "pt",
+        ),
+        hint_code::EC_DOUBLE_ASSIGN_NEW_Y => ec_double_assign_new_y(exec_scopes),
+        hint_code::KECCAK_WRITE_ARGS => {
+            keccak_write_args(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::COMPARE_BYTES_IN_WORD_NONDET => compare_bytes_in_word_nondet(
+            vm,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            constants,
+        ),
+        hint_code::SHA256_MAIN_CONSTANT_INPUT_LENGTH => sha256_main_constant_input_length(
+            vm,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            constants,
+        ),
+        hint_code::SHA256_MAIN_ARBITRARY_INPUT_LENGTH => sha256_main_arbitrary_input_length(
+            vm,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            constants,
+        ),
+        hint_code::SHA256_INPUT => {
+            sha256_input(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SHA256_FINALIZE => {
+            sha256_finalize(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::CAIRO_KECCAK_INPUT_IS_FULL_WORD => {
+            cairo_keccak_is_full_word(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::COMPARE_KECCAK_FULL_RATE_IN_BYTES_NONDET => {
+            compare_keccak_full_rate_in_bytes_nondet(
                 vm,
                 &hint_data.ids_data,
                 &hint_data.ap_tracking,
                 constants,
-            ),
-            hint_code::SHA256_INPUT => {
-                sha256_input(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::SHA256_FINALIZE => {
-                sha256_finalize(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::CAIRO_KECCAK_INPUT_IS_FULL_WORD => {
-                cairo_keccak_is_full_word(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::COMPARE_KECCAK_FULL_RATE_IN_BYTES_NONDET => {
-                compare_keccak_full_rate_in_bytes_nondet(
-                    vm,
-                    &hint_data.ids_data,
-                    &hint_data.ap_tracking,
-                    constants,
-                )
-            }
-            hint_code::BLOCK_PERMUTATION | hint_code::BLOCK_PERMUTATION_WHITELIST_V1 => {
-                block_permutation_v1(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::BLOCK_PERMUTATION_WHITELIST_V2 => {
-                block_permutation_v2(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::CAIRO_KECCAK_FINALIZE_V1 => {
-                cairo_keccak_finalize_v1(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::CAIRO_KECCAK_FINALIZE_V2 => {
-                cairo_keccak_finalize_v2(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::FAST_EC_ADD_ASSIGN_NEW_X => fast_ec_add_assign_new_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P,
-                "point0",
-                "point1",
-            ),
-            hint_code::FAST_EC_ADD_ASSIGN_NEW_X_V2 => fast_ec_add_assign_new_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P_V2,
-                "point0",
-                "point1",
-            ),
-            hint_code::FAST_EC_ADD_ASSIGN_NEW_X_V3 => fast_ec_add_assign_new_x(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-                &SECP_P,
-                "pt0",
-                "pt1",
-            ),
-            hint_code::FAST_EC_ADD_ASSIGN_NEW_Y => fast_ec_add_assign_new_y(exec_scopes),
-            hint_code::EC_MUL_INNER => {
-                ec_mul_inner(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::RELOCATE_SEGMENT => {
-                relocate_segment(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::TEMPORARY_ARRAY => {
-                temporary_array(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::VERIFY_ECDSA_SIGNATURE => {
-                verify_ecdsa_signature(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::SPLIT_OUTPUT_0 => {
-                split_output(vm, &hint_data.ids_data, &hint_data.ap_tracking, 0)
-            }
-            hint_code::SPLIT_OUTPUT_1 => {
-                split_output(vm, &hint_data.ids_data, &hint_data.ap_tracking, 1)
-            }
-            hint_code::SPLIT_INPUT_3 => {
-                split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 3, 1)
-            }
-            hint_code::SPLIT_INPUT_6 => {
-                split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 6, 2)
-            }
-            hint_code::SPLIT_INPUT_9 => {
-                split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 9, 3)
-            }
-            hint_code::SPLIT_INPUT_12 => {
-                split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 12, 4)
-            }
-            hint_code::SPLIT_INPUT_15 => {
-                split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 15, 5)
-            }
-            hint_code::SPLIT_N_BYTES => {
-                split_n_bytes(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::SPLIT_OUTPUT_MID_LOW_HIGH => {
-                split_output_mid_low_high(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::NONDET_N_GREATER_THAN_10 => {
-                n_greater_than_10(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::NONDET_N_GREATER_THAN_2 => {
-                n_greater_than_2(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::NONDET_ELEMENTS_OVER_TEN => {
-                elements_over_x(vm, &hint_data.ids_data, &hint_data.ap_tracking, 10)
-            }
-            hint_code::NONDET_ELEMENTS_OVER_TWO => {
-                elements_over_x(vm, &hint_data.ids_data, &hint_data.ap_tracking, 2)
-            }
-            hint_code::RANDOM_EC_POINT => {
-                random_ec_point_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::CHAINED_EC_OP_RANDOM_EC_POINT => {
-                chained_ec_op_random_ec_point_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::RECOVER_Y => recover_y_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::PACK_MODN_DIV_MODN => {
-                pack_modn_div_modn(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::XS_SAFE_DIV => div_mod_n_safe_div(exec_scopes, "x", "s", 0),
-            hint_code::UINT384_UNSIGNED_DIV_REM => {
-                uint384_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UINT384_SPLIT_128 => {
-                uint384_split_128(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::ADD_NO_UINT384_CHECK => {
-                add_no_uint384_check(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
-            }
-            hint_code::UINT384_SQRT => {
-                uint384_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UNSIGNED_DIV_REM_UINT768_BY_UINT384
-            | hint_code::UNSIGNED_DIV_REM_UINT768_BY_UINT384_STRIPPED => {
-                unsigned_div_rem_uint768_by_uint384(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::SUB_REDUCED_A_AND_REDUCED_B => {
-                sub_reduced_a_and_reduced_b(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UINT384_GET_SQUARE_ROOT => {
-                u384_get_square_root(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UINT256_GET_SQUARE_ROOT => {
-                u256_get_square_root(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UINT384_SIGNED_NN => {
-                uint384_signed_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::UINT384_DIV => uint384_div(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::UINT256_MUL_DIV_MOD => {
-                uint256_mul_div_mod(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::IMPORT_SECP256R1_ALPHA => import_secp256r1_alpha(exec_scopes),
-            hint_code::IMPORT_SECP256R1_N => import_secp256r1_n(exec_scopes),
-            hint_code::UINT512_UNSIGNED_DIV_REM => {
-                uint512_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::HI_MAX_BITLEN => {
-                hi_max_bitlen(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::QUAD_BIT => quad_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::INV_MOD_P_UINT256 => {
-                inv_mod_p_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::INV_MOD_P_UINT512 => {
-                inv_mod_p_uint512(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::DI_BIT => di_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::EXAMPLE_BLAKE2S_COMPRESS => {
-                example_blake2s_compress(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::EC_RECOVER_DIV_MOD_N_PACKED => ec_recover_divmod_n_packed(
-                vm,
-                exec_scopes,
-                &hint_data.ids_data,
-                &hint_data.ap_tracking,
-            ),
-            hint_code::EC_RECOVER_SUB_A_B => {
-                ec_recover_sub_a_b(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::A_B_BITAND_1 => {
-                a_b_bitand_1(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::ASSERT_LE_FELT_V_0_6 => {
-                assert_le_felt_v_0_6(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::ASSERT_LE_FELT_V_0_8 => {
-                assert_le_felt_v_0_8(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::EC_RECOVER_PRODUCT_MOD => {
-                ec_recover_product_mod(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::EC_RECOVER_PRODUCT_DIV_M => ec_recover_product_div_m(exec_scopes),
-            hint_code::SPLIT_XX => split_xx(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            hint_code::RUN_P_CIRCUIT => {
-                run_p_mod_circuit(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::RUN_P_CIRCUIT_WITH_LARGE_BATCH_SIZE => {
-                run_p_mod_circuit_with_large_batch_size(
-                    vm,
-                    &hint_data.ids_data,
-                    &hint_data.ap_tracking,
-                    constants,
-                )
-            }
-            #[cfg(feature = "test_utils")]
-            hint_code::SKIP_NEXT_INSTRUCTION => skip_next_instruction(vm),
-            #[cfg(feature = "test_utils")]
-            hint_code::PRINT_FELT => print_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            #[cfg(feature = "test_utils")]
-            hint_code::PRINT_ARR => print_array(vm, &hint_data.ids_data, &hint_data.ap_tracking),
-            #[cfg(feature = "test_utils")]
-            hint_code::PRINT_DICT => {
-                print_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
-            hint_code::EXCESS_BALANCE => excess_balance_hint(
+            )
+        },
+        hint_code::BLOCK_PERMUTATION | hint_code::BLOCK_PERMUTATION_WHITELIST_V1 => {
+            block_permutation_v1(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::BLOCK_PERMUTATION_WHITELIST_V2 => {
+            block_permutation_v2(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::CAIRO_KECCAK_FINALIZE_V1 => {
+            cairo_keccak_finalize_v1(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::CAIRO_KECCAK_FINALIZE_V2 => {
+            cairo_keccak_finalize_v2(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_X => fast_ec_add_assign_new_x(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            &SECP_P,
+            "point0",
+            "point1",
+        ),
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_X_V2 => fast_ec_add_assign_new_x(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            &SECP_P_V2,
+            "point0",
+            "point1",
+        ),
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_X_V3 => fast_ec_add_assign_new_x(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            &SECP_P,
+            "pt0",
+            "pt1",
+        ),
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_Y => fast_ec_add_assign_new_y(exec_scopes),
+        hint_code::EC_MUL_INNER => {
+            ec_mul_inner(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::RELOCATE_SEGMENT => {
+            relocate_segment(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::TEMPORARY_ARRAY => {
+            temporary_array(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::VERIFY_ECDSA_SIGNATURE => {
+            verify_ecdsa_signature(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SPLIT_OUTPUT_0 => {
+            split_output(vm, &hint_data.ids_data, &hint_data.ap_tracking, 0)
+        },
+        hint_code::SPLIT_OUTPUT_1 => {
+            split_output(vm, &hint_data.ids_data, &hint_data.ap_tracking, 1)
+        },
+        hint_code::SPLIT_INPUT_3 => {
+            split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 3, 1)
+        },
+        hint_code::SPLIT_INPUT_6 => {
+            split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 6, 2)
+        },
+        hint_code::SPLIT_INPUT_9 => {
+            split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 9, 3)
+        },
+        hint_code::SPLIT_INPUT_12 => {
+            split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 12, 4)
+        },
+        hint_code::SPLIT_INPUT_15 => {
+            split_input(vm, &hint_data.ids_data, &hint_data.ap_tracking, 15, 5)
+        },
+        hint_code::SPLIT_N_BYTES => {
+            split_n_bytes(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::SPLIT_OUTPUT_MID_LOW_HIGH => {
+            split_output_mid_low_high(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::NONDET_N_GREATER_THAN_10 => {
+            n_greater_than_10(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::NONDET_N_GREATER_THAN_2 => {
+            n_greater_than_2(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::NONDET_ELEMENTS_OVER_TEN => {
+            elements_over_x(vm, &hint_data.ids_data, &hint_data.ap_tracking, 10)
+        },
+        hint_code::NONDET_ELEMENTS_OVER_TWO => {
+            elements_over_x(vm, &hint_data.ids_data, &hint_data.ap_tracking, 2)
+        },
+        hint_code::RANDOM_EC_POINT => {
+            random_ec_point_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::CHAINED_EC_OP_RANDOM_EC_POINT => {
+            chained_ec_op_random_ec_point_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::RECOVER_Y => recover_y_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::PACK_MODN_DIV_MODN => {
+            pack_modn_div_modn(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::XS_SAFE_DIV => div_mod_n_safe_div(exec_scopes, "x", "s", 0),
+        hint_code::UINT384_UNSIGNED_DIV_REM => {
+            uint384_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT384_SPLIT_128 => {
+            uint384_split_128(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ADD_NO_UINT384_CHECK => {
+            add_no_uint384_check(vm, &hint_data.ids_data, &hint_data.ap_tracking, constants)
+        },
+        hint_code::UINT384_SQRT => {
+            uint384_sqrt(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UNSIGNED_DIV_REM_UINT768_BY_UINT384
+        | hint_code::UNSIGNED_DIV_REM_UINT768_BY_UINT384_STRIPPED => {
+            unsigned_div_rem_uint768_by_uint384(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SUB_REDUCED_A_AND_REDUCED_B => {
+            sub_reduced_a_and_reduced_b(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT384_GET_SQUARE_ROOT => {
+            u384_get_square_root(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT256_GET_SQUARE_ROOT => {
+            u256_get_square_root(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT384_SIGNED_NN => {
+            uint384_signed_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::UINT384_DIV => uint384_div(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::UINT256_MUL_DIV_MOD => {
+            uint256_mul_div_mod(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::IMPORT_SECP256R1_ALPHA => import_secp256r1_alpha(exec_scopes),
+        hint_code::IMPORT_SECP256R1_N => import_secp256r1_n(exec_scopes),
+        hint_code::UINT512_UNSIGNED_DIV_REM => {
+            uint512_unsigned_div_rem(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::HI_MAX_BITLEN => {
+            hi_max_bitlen(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::QUAD_BIT => quad_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::INV_MOD_P_UINT256 => {
+            inv_mod_p_uint256(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::INV_MOD_P_UINT512 => {
+            inv_mod_p_uint512(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::DI_BIT => di_bit(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::EXAMPLE_BLAKE2S_COMPRESS => {
+            example_blake2s_compress(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EC_RECOVER_DIV_MOD_N_PACKED => ec_recover_divmod_n_packed(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::EC_RECOVER_SUB_A_B => {
+            ec_recover_sub_a_b(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::A_B_BITAND_1 => {
+            a_b_bitand_1(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_LE_FELT_V_0_6 => {
+            assert_le_felt_v_0_6(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::ASSERT_LE_FELT_V_0_8 => {
+            assert_le_felt_v_0_8(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EC_RECOVER_PRODUCT_MOD => {
+            ec_recover_product_mod(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EC_RECOVER_PRODUCT_DIV_M => ec_recover_product_div_m(exec_scopes),
+        hint_code::SPLIT_XX => split_xx(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        hint_code::RUN_P_CIRCUIT => {
+            run_p_mod_circuit(vm, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::RUN_P_CIRCUIT_WITH_LARGE_BATCH_SIZE => {
+            run_p_mod_circuit_with_large_batch_size(
                 vm,
                 &hint_data.ids_data,
                 &hint_data.ap_tracking,
                 constants,
-                exec_scopes,
-            ),
-            code => Err(HintError::UnknownHint(code.to_string().into_boxed_str())),
-        }
-    }
+            )
+        },
+        #[cfg(feature = "test_utils")]
+        hint_code::SKIP_NEXT_INSTRUCTION => skip_next_instruction(vm),
+        #[cfg(feature = "test_utils")]
+        hint_code::PRINT_FELT => print_felt(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        #[cfg(feature = "test_utils")]
+        hint_code::PRINT_ARR => print_array(vm, &hint_data.ids_data, &hint_data.ap_tracking),
+        #[cfg(feature = "test_utils")]
+        hint_code::PRINT_DICT => {
+            print_dict(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EXCESS_BALANCE => excess_balance_hint(
+            vm,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+            constants,
+            exec_scopes,
+        ),
+        hint_code::SIMPLE_BOOTLOADER_LOAD_INPUT => load_simple_bootloader_input(exec_scopes),
+        hint_code::SIMPLE_BOOTLOADER_PREPARE_TASK_RANGE_CHECKS => prepare_task_range_checks(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::SIMPLE_BOOTLOADER_SET_TASKS_VARIABLE => set_tasks_variable(exec_scopes),
+        hint_code::SIMPLE_BOOTLOADER_SET_CURRENT_TASK => {
+            set_current_task(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SIMPLE_BOOTLOADER_CONFIGURE_FACT_TOPOLOGIES => {
+            configure_fact_topologies(vm, exec_scopes)
+        },
+        hint_code::EXECUTE_TASK_ALLOCATE_PROGRAM_DATA_SEGMENT => allocate_program_data_segment(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::EXECUTE_TASK_LOAD_PROGRAM => {
+            load_program(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EXECUTE_TASK_VALIDATE_HASH => {
+            validate_hash(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EXECUTE_TASK_ASSERT_PROGRAM_ADDRESS => {
+            assert_program_address(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EXECUTE_TASK_CALL_TASK => call_task(vm, exec_scopes),
+        hint_code::EXECUTE_TASK_APPEND_FACT_TOPOLOGIES => {
+            append_fact_topologies(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::EXECUTE_TASK_WRITE_RETURN_BUILTINS => {
+            write_return_builtins(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+        hint_code::SELECT_BUILTINS_ENTER_SCOPE => select_builtins_enter_scope(
+            vm,
+            exec_scopes,
+            &hint_data.ids_data,
+            &hint_data.ap_tracking,
+        ),
+        hint_code::INNER_SELECT_BUILTINS_SELECT_BUILTIN => {
+            select_builtin(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
+        },
+    })
 }
 
 impl ResourceTracker for BuiltinHintProcessor {
@@ -900,11 +1059,12 @@ impl ResourceTracker for BuiltinHintProcessor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::stdlib::any::Any;
+    use crate::stdlib::{any::Any, collections::BTreeMap};
     use crate::types::relocatable::Relocatable;
 
     use crate::{
         any_box,
+        serde::deserialize_program::{FlowTrackingData, HintParams},
         types::{exec_scope::ExecutionScopes, relocatable::MaybeRelocatable},
         utils::test_utils::*,
         vm::{
@@ -979,6 +1139,179 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn builtin_hints_normalize_to_distinct_codes() {
+        // Hints of different arms must not end up sharing the same normalized code
+        let mut arms = HashMap::new();
+        for (arm, (codes, _)) in builtin_hints().into_iter().enumerate() {
+            for code in codes {
+                let previous = arms.insert(normalize_hint_code(code).into_owned(), arm);
+                assert!(previous.is_none() || previous == Some(arm), "{code}");
+            }
+        }
+        assert_eq!(arms.len(), BUILTIN_HINTS.len());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_hint_with_different_formatting() {
+        let hint_code = "\n    memory[ap] = segments.add()  \n";
+        let mut vm = vm!();
+        add_segments!(vm, 1);
+        run_hint!(vm, HashMap::new(), hint_code).expect("Error while executing hint");
+        check_memory![vm.segments.memory, ((1, 0), (1, 0))];
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_extra_hint_with_different_formatting() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_hint(
+            "  if x:\n      y = 1\n".to_string(),
+            Rc::new(HintFunc(Box::new(|vm, _, _, _, _| {
+                vm.add_memory_segment();
+                Ok(())
+            }))),
+        );
+        let mut vm = vm!();
+        let hint_data = any_box!(HintProcessorData::new_default(
+            "if x:\n    y = 1".to_string(),
+            HashMap::new()
+        ));
+        assert_matches!(
+            hint_processor.execute_hint(
+                &mut vm,
+                &mut ExecutionScopes::new(),
+                &hint_data,
+                &HashMap::new()
+            ),
+            Ok(())
+        );
+        assert_eq!(vm.segments.num_segments(), 1);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn compile_hint_with_whitelist() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty()
+            .with_whitelist([hint_code::ADD_SEGMENT, "x = 1"].into_iter().collect());
+        for code in ["x = 1", "y = 2"] {
+            hint_processor.add_hint(
+                code.to_string(),
+                Rc::new(HintFunc(Box::new(|_, _, _, _, _| Ok(())))),
+            );
+        }
+        let compile = |code: &str| {
+            hint_processor.compile_hint(code, &ApTracking::default(), &HashMap::new(), &[])
+        };
+        assert_matches!(compile(hint_code::ADD_SEGMENT), Ok(_));
+        assert_matches!(compile("  x = 1\n"), Ok(_));
+        // Extra hints need to be whitelisted too
+        assert_matches!(
+            compile("y = 2"),
+            Err(VirtualMachineError::HintNotWhitelisted(bx)) if *bx == (None, "y = 2".into())
+        );
+        assert_matches!(
+            compile(hint_code::ASSERT_NN),
+            Err(VirtualMachineError::HintNotWhitelisted(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn compile_hint_with_whitelisted_references() {
+        let whitelist = HintWhitelist::from_json(
+            br#"{
+                "allowed_reference_expressions_for_hint": [
+                    {
+                        "allowed_expressions": [
+                            {"expr": "[cast(fp + (-3), felt*)]", "name": "main.a"}
+                        ],
+                        "hint_lines": [
+                            "from starkware.cairo.common.math_utils import assert_integer",
+                            "assert_integer(ids.a)",
+                            "assert 0 <= ids.a % PRIME < range_check_builtin.bound, f'a = {ids.a} is out of range.'"
+                        ]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let hint_processor = BuiltinHintProcessor::new_empty().with_whitelist(whitelist);
+        let reference = |offset| HintReference {
+            cairo_type: Some("felt".to_string()),
+            ..HintReference::new_simple(offset)
+        };
+        let references = [reference(-3), reference(-4)];
+        let compile = |reference_id: usize| {
+            hint_processor.compile_hint(
+                hint_code::ASSERT_NN,
+                &ApTracking::default(),
+                &HashMap::from([("main.a".to_string(), reference_id)]),
+                &references,
+            )
+        };
+        assert_matches!(compile(0), Ok(_));
+        assert_matches!(compile(1), Err(VirtualMachineError::HintNotWhitelisted(_)));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_hint_data_with_whitelist_names_pc() {
+        let hint = |code: &str| HintParams {
+            code: code.to_string(),
+            accessible_scopes: vec![],
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking::default(),
+                reference_ids: HashMap::new(),
+            },
+        };
+        let program = program!(
+            data = vec_data!((1), (2), (3)),
+            hints = BTreeMap::from([
+                (0, vec![hint(hint_code::ADD_SEGMENT)]),
+                (2, vec![hint(hint_code::ADD_SEGMENT), hint("x = 1")]),
+            ]),
+        );
+        let runner = cairo_runner!(program);
+        let mut hint_processor = BuiltinHintProcessor::new_empty()
+            .with_whitelist([hint_code::ADD_SEGMENT].into_iter().collect());
+        let error = runner
+            .get_hint_data(&[], &mut hint_processor)
+            .err()
+            .unwrap();
+        assert_matches!(
+            error,
+            VirtualMachineError::HintNotWhitelisted(ref bx) if **bx == (Some(2), "x = 1".into())
+        );
+        assert_eq!(
+            error.to_string(),
+            "Hint at pc 2 is not whitelisted:\n%{\nx = 1\n%}"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn is_hint_supported() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_hint(
+            "extra_hint".to_string(),
+            Rc::new(HintFunc(Box::new(|_, _, _, _, _| Ok(())))),
+        );
+        let hint_data = |code: &str| {
+            any_box!(HintProcessorData::new_default(
+                code.to_string(),
+                HashMap::new()
+            ))
+        };
+        assert!(hint_processor.is_hint_supported(&hint_data(hint_code::ADD_SEGMENT)));
+        assert!(hint_processor.is_hint_supported(&hint_data(hint_code::EXCESS_BALANCE)));
+        assert!(hint_processor.is_hint_supported(&hint_data("extra_hint")));
+        assert!(!hint_processor.is_hint_supported(&hint_data("random_invalid_code")));
+        assert!(!hint_processor.is_hint_supported(&any_box!(1_u32)));
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn memcpy_enter_scope_valid() {
@@ -1334,4 +1667,54 @@ mod tests {
         );
         assert_eq!(exec_scopes.data.len(), 3);
     }
+
+    #[test]
+    #[cfg(not(feature = "extensive_hints"))]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn step_hint_stops_between_hints_when_cancelled() {
+        use crate::vm::{
+            errors::vm_errors::VirtualMachineError,
+            runners::cairo_runner::{CancellationFlag, RunLimit},
+        };
+
+        let flag = CancellationFlag::new();
+        let mut hint_processor = BuiltinHintProcessor::new(
+            HashMap::new(),
+            RunResources::default().with_cancellation_flag(flag.clone()),
+        );
+        hint_processor.add_hint(
+            String::from("cancel"),
+            Rc::new(HintFunc(Box::new(move |_, _, _, _, _| {
+                flag.cancel();
+                Ok(())
+            }))),
+        );
+        hint_processor.add_hint(
+            String::from("enter_scope_custom"),
+            Rc::new(HintFunc(Box::new(enter_scope))),
+        );
+        let mut vm = vm!();
+        let exec_scopes = exec_scopes_ref!();
+        let hint_datas = vec![
+            any_box!(HintProcessorData::new_default(
+                String::from("cancel"),
+                HashMap::new()
+            )),
+            any_box!(HintProcessorData::new_default(
+                String::from("enter_scope_custom"),
+                HashMap::new()
+            )),
+        ];
+        assert_matches!(
+            vm.step_hint(
+                &mut hint_processor,
+                exec_scopes,
+                &hint_datas,
+                &HashMap::new()
+            ),
+            Err(VirtualMachineError::RunInterrupted(RunLimit::Cancelled))
+        );
+        // The second hint didn't run
+        assert_eq!(exec_scopes.data.len(), 1);
+    }
 }
@@ -0,0 +1,154 @@
+//! Typed access to the `ids` of a hint.
+//!
+//! A struct whose fields implement [`HintIdsField`] can implement [`HintIds`], usually through
+//! `#[derive(HintIds)]` from the `cairo-vm-derive` crate, to load all the ids a hint reads at once
+//! and store the ones it writes:
+//!
+//! ```ignore
+//! #[derive(HintIds)]
+//! struct Ids {
+//!     x: Felt252,
+//!     ptr: Relocatable,
+//!     res: Out<Felt252>,
+//! }
+//!
+//! let mut ids = Ids::load(vm, ids_data, ap_tracking)?;
+//! ids.res.set(ids.x + 1);
+//! ids.store(vm, ids_data, ap_tracking)?;
+//! ```
+
+use crate::stdlib::{collections::HashMap, prelude::*};
+
+use crate::Felt252;
+use crate::{
+    hint_processor::{
+        builtin_hint_processor::hint_utils::{
+            get_integer_from_var_name, get_maybe_relocatable_from_var_name, get_ptr_from_var_name,
+            get_reference_from_var_name, insert_value_from_var_name,
+        },
+        hint_processor_definition::HintReference,
+    },
+    serde::deserialize_program::ApTracking,
+    types::relocatable::{MaybeRelocatable, Relocatable},
+    vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
+};
+
+/// Set of ids read and written by a hint
+pub trait HintIds: Sized {
+    /// Reads the ids from the vm's memory
+    fn load(
+        vm: &VirtualMachine,
+        ids_data: &HashMap<String, HintReference>,
+        ap_tracking: &ApTracking,
+    ) -> Result<Self, HintError>;
+
+    /// Writes the output ids into the vm's memory
+    fn store(
+        &self,
+        vm: &mut VirtualMachine,
+        ids_data: &HashMap<String, HintReference>,
+        ap_tracking: &ApTracking,
+    ) -> Result<(), HintError>;
+}
+
+/// A value that can be loaded from, and possibly stored into, the ids variable `name`
+pub trait HintIdsField: Sized {
+    fn load(
+        name: &str,
+        vm: &VirtualMachine,
+        ids_data: &HashMap<String, HintReference>,
+        ap_tracking: &ApTracking,
+    ) -> Result<Self, HintError>;
+
+    /// Input values aren't stored back
+    fn store(
+        &self,
+        _name: &str,
+        _vm: &mut VirtualMachine,
+        _ids_data: &HashMap<String, HintReference>,
+        _ap_tracking: &ApTracking,
+    ) -> Result<(), HintError> {
+        Ok(())
+    }
+}
+
+impl HintIdsField for Felt252 {
+    fn load(
+        name: &str,
+        vm: &VirtualMachine,
+        ids_data: &HashMap<String, HintReference>,
+        ap_tracking: &ApTracking,
+    ) -> Result<Self, HintError> {
+        get_integer_from_var_name(name, vm, ids_data, ap_tracking)
+    }
+}
+
+impl HintIdsField for Relocatable {
+    fn load(
+        name: &str,
+        vm: &VirtualMachine,
+        ids_data: &HashMap<String, HintReference>,
+        ap_tracking: &ApTracking,
+    ) -> Result<Self, HintError> {
+        get_ptr_from_var_name(name, vm, ids_data, ap_tracking)
+    }
+}
+
+impl HintIdsField for MaybeRelocatable {
+    fn load(
+        name: &str,
+        vm: &VirtualMachine,
+        ids_data: &HashMap<String, HintReference>,
+        ap_tracking: &ApTracking,
+    ) -> Result<Self, HintError> {
+        get_maybe_relocatable_from_var_name(name, vm, ids_data, ap_tracking)
+    }
+}
+
+/// An ids variable written by the hint. It starts unset, and is only stored if set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Out<T>(Option<T>);
+
+impl<T> Default for Out<T> {
+    fn default() -> Self {
+        Out(None)
+    }
+}
+
+impl<T> Out<T> {
+    pub fn set(&mut self, value: T) {
+        self.0 = Some(value);
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.0.as_ref()
+    }
+}
+
+impl<T: Into<MaybeRelocatable> + Clone> HintIdsField for Out<T> {
+    /// Only checks that the variable exists
+    fn load(
+        name: &str,
+        _vm: &VirtualMachine,
+        ids_data: &HashMap<String, HintReference>,
+        _ap_tracking: &ApTracking,
+    ) -> Result<Self, HintError> {
+        get_reference_from_var_name(name, ids_data)?;
+        Ok(Out(None))
+    }
+
+    fn store(
+        &self,
+        name: &str,
+        vm: &mut VirtualMachine,
+        ids_data: &HashMap<String, HintReference>,
+        ap_tracking: &ApTracking,
+    ) -> Result<(), HintError> {
+        match self.0 {
+            Some(ref value) => {
+                insert_value_from_var_name(name, value.clone(), vm, ids_data, ap_tracking)
+            }
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use crate::stdlib::{collections::HashMap, prelude::*};
+
+use serde::Deserialize;
+
+use crate::{
+    hint_processor::{
+        hint_processor_definition::HintReference, hint_processor_utils::normalize_hint_code,
+    },
+    serde::deserialize_program::{
+        deserialize_value_address, ApTracking, OffsetValue, ValueAddress,
+    },
+    types::instruction::Register,
+};
+
+#[derive(Deserialize)]
+struct AllowedExpression {
+    name: String,
+    #[serde(deserialize_with = "deserialize_value_address")]
+    expr: ValueAddress,
+}
+
+#[derive(Deserialize)]
+struct AllowedHintExpression {
+    allowed_expressions: Vec<AllowedExpression>,
+    hint_lines: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct WhitelistJson {
+    allowed_reference_expressions_for_hint: Vec<AllowedHintExpression>,
+}
+
+/// Set of hints a [`BuiltinHintProcessor`](super::builtin_hint_processor_definition::BuiltinHintProcessor)
+/// is allowed to compile. Codes are normalized, so they match regardless of their formatting.
+///
+/// Hints can also be restricted to the references they use, as in the whitelists loaded with
+/// [`HintWhitelist::from_json`], where each reference a hint uses has to be listed with its name
+/// and its expression at the hint. Hints added with [`HintWhitelist::add_hint`] can use any reference.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HintWhitelist {
+    // Allowed (full name, expression) pairs of each hint's references, `None` if unrestricted
+    hints: HashMap<String, Option<Vec<(String, ValueAddress)>>>,
+}
+
+impl HintWhitelist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a whitelist in the format of the files in
+    /// `cairo-lang/src/starkware/starknet/security/whitelists`.
+    /// The allowed expressions of the entries sharing a hint code are merged
+    pub fn from_json(bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        let whitelist: WhitelistJson = serde_json::from_slice(bytes)?;
+        let mut hints = HashMap::<String, Option<Vec<_>>>::new();
+        for entry in whitelist.allowed_reference_expressions_for_hint {
+            let code = normalize_hint_code(&entry.hint_lines.join("\n")).into_owned();
+            let allowed_expressions = hints.entry(code).or_insert_with(|| Some(Vec::new()));
+            if let Some(allowed_expressions) = allowed_expressions {
+                allowed_expressions.extend(
+                    entry
+                        .allowed_expressions
+                        .into_iter()
+                        .map(|expression| (expression.name, expression.expr)),
+                );
+            }
+        }
+        Ok(HintWhitelist { hints })
+    }
+
+    /// Whitelists a hint, regardless of the references it uses
+    pub fn add_hint(&mut self, hint_code: &str) {
+        self.hints
+            .insert(normalize_hint_code(hint_code).into_owned(), None);
+    }
+
+    pub fn contains(&self, hint_code: &str) -> bool {
+        self.hints
+            .contains_key(normalize_hint_code(hint_code).as_ref())
+    }
+
+    /// Returns true if the hint is whitelisted, and each of the references it uses, given by their
+    /// full name, is allowed with the expression it evaluates to at the hint's ap tracking
+    pub fn allows(
+        &self,
+        hint_code: &str,
+        ap_tracking: &ApTracking,
+        reference_ids: &HashMap<String, usize>,
+        references: &[HintReference],
+    ) -> bool {
+        let Some(allowed_expressions) = self.hints.get(normalize_hint_code(hint_code).as_ref())
+        else {
+            return false;
+        };
+        let Some(allowed_expressions) = allowed_expressions else {
+            return true;
+        };
+        reference_ids.iter().all(|(name, reference_id)| {
+            references
+                .get(*reference_id)
+                .and_then(|reference| expression_at(reference, ap_tracking))
+                .is_some_and(|expression| {
+                    allowed_expressions
+                        .iter()
+                        .any(|allowed| allowed.0 == *name && allowed.1 == expression)
+                })
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.hints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hints.is_empty()
+    }
+
+    /// Iterates over the normalized codes of the whitelisted hints
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.hints.keys().map(String::as_str)
+    }
+}
+
+// Returns the expression of the reference at a hint, with its ap based offsets shifted by the ap
+// increase since the reference was defined. None if ap changed by an unknown amount in between
+fn expression_at(reference: &HintReference, ap_tracking: &ApTracking) -> Option<ValueAddress> {
+    let ap_diff = match &reference.ap_tracking_data {
+        Some(reference_ap_tracking) if reference_ap_tracking.group != ap_tracking.group => {
+            return None
+        }
+        Some(reference_ap_tracking) => {
+            ap_tracking.offset as i32 - reference_ap_tracking.offset as i32
+        }
+        None => 0,
+    };
+    let at_hint = |offset: &OffsetValue| match offset {
+        OffsetValue::Reference(Register::AP, offset, dereference) => {
+            OffsetValue::Reference(Register::AP, offset - ap_diff, *dereference)
+        }
+        offset => offset.clone(),
+    };
+    Some(ValueAddress {
+        offset1: at_hint(&reference.offset1),
+        offset2: at_hint(&reference.offset2),
+        outer_dereference: reference.outer_dereference,
+        inner_dereference: reference.inner_dereference,
+        value_type: reference.cairo_type.clone().unwrap_or_default(),
+    })
+}
+
+impl<S: AsRef<str>> FromIterator<S> for HintWhitelist {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut whitelist = HintWhitelist::new();
+        whitelist.extend(iter);
+        whitelist
+    }
+}
+
+impl<S: AsRef<str>> Extend<S> for HintWhitelist {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        for hint_code in iter {
+            self.add_hint(hint_code.as_ref());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde::deserialize_program::Reference;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn from_json() {
+        let whitelist = HintWhitelist::from_json(
+            br#"{
+                "allowed_reference_expressions_for_hint": [
+                    {
+                        "allowed_expressions": [],
+                        "hint_lines": ["memory[ap] = segments.add()"]
+                    },
+                    {
+                        "allowed_expressions": [
+                            {"expr": "[cast(fp + (-3), felt*)]", "name": "x"}
+                        ],
+                        "hint_lines": [
+                            "from starkware.cairo.common.math_utils import assert_integer",
+                            "assert_integer(ids.x)"
+                        ]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(whitelist.len(), 2);
+        assert!(whitelist.contains("memory[ap] = segments.add()"));
+        assert!(whitelist.contains(
+            "    from starkware.cairo.common.math_utils import assert_integer\n    assert_integer(ids.x)\n"
+        ));
+        assert!(!whitelist.contains("assert_integer(ids.x)"));
+    }
+
+    fn reference(value: &str, ap_tracking_data: ApTracking) -> HintReference {
+        let reference: Reference = serde_json::from_value(serde_json::json!({
+            "ap_tracking_data": {
+                "group": ap_tracking_data.group,
+                "offset": ap_tracking_data.offset
+            },
+            "pc": null,
+            "value": value
+        }))
+        .unwrap();
+        HintReference::from(reference)
+    }
+
+    fn assert_integer_whitelist() -> HintWhitelist {
+        HintWhitelist::from_json(
+            br#"{
+                "allowed_reference_expressions_for_hint": [
+                    {
+                        "allowed_expressions": [
+                            {"expr": "[cast(fp + (-3), felt*)]", "name": "main.x"}
+                        ],
+                        "hint_lines": ["assert_integer(ids.x)"]
+                    },
+                    {
+                        "allowed_expressions": [
+                            {"expr": "[cast(ap + (-5), felt*)]", "name": "other.x"}
+                        ],
+                        "hint_lines": ["assert_integer(ids.x)"]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn allows_whitelisted_references() {
+        let whitelist = assert_integer_whitelist();
+        let ap_tracking = ApTracking {
+            group: 1,
+            offset: 2,
+        };
+        let references = vec![
+            reference("[cast(fp + (-3), felt*)]", ApTracking::new()),
+            // Defined when ap was 2 cells lower, so it's [ap - 5] at the hint
+            reference(
+                "[cast(ap + (-3), felt*)]",
+                ApTracking {
+                    group: 1,
+                    offset: 0,
+                },
+            ),
+        ];
+        let allows = |name: &str, reference_id: usize| {
+            whitelist.allows(
+                "assert_integer(ids.x)",
+                &ap_tracking,
+                &HashMap::from([(name.to_string(), reference_id)]),
+                &references,
+            )
+        };
+        assert!(allows("main.x", 0));
+        assert!(allows("other.x", 1));
+        // The name has to match too
+        assert!(!allows("main.x", 1));
+        assert!(!allows("other.y", 1));
+        // ap changed by an unknown amount since the reference was defined
+        assert!(!whitelist.allows(
+            "assert_integer(ids.x)",
+            &ApTracking {
+                group: 2,
+                offset: 0
+            },
+            &HashMap::from([("other.x".to_string(), 1)]),
+            &references,
+        ));
+        assert!(!whitelist.allows("x = 1", &ap_tracking, &HashMap::new(), &references));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn added_hints_allow_any_reference() {
+        let mut whitelist = assert_integer_whitelist();
+        let references = vec![reference("[cast(fp + (-5), felt*)]", ApTracking::new())];
+        let reference_ids = HashMap::from([("main.y".to_string(), 0)]);
+        assert!(!whitelist.allows(
+            "assert_integer(ids.x)",
+            &ApTracking::new(),
+            &reference_ids,
+            &references
+        ));
+        whitelist.add_hint("assert_integer(ids.x)");
+        assert!(whitelist.allows(
+            "assert_integer(ids.x)",
+            &ApTracking::new(),
+            &reference_ids,
+            &references
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn from_json_invalid() {
+        assert!(HintWhitelist::from_json(br#"{"hint_lines": []}"#).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn extend_whitelist() {
+        let mut whitelist: HintWhitelist = ["x = 1"].into_iter().collect();
+        whitelist.extend(["  y = 2", "x = 1  "]);
+        let mut hints: Vec<_> = whitelist.iter().collect();
+        hints.sort();
+        assert_eq!(hints, vec!["x = 1", "y = 2"]);
+    }
+}
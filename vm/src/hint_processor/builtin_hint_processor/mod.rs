@@ -1,6 +1,7 @@
 pub mod bigint;
 pub mod blake2s_hash;
 pub mod blake2s_utils;
+pub mod bootloader;
 pub mod builtin_hint_processor_definition;
 pub mod cairo_keccak;
 pub mod dict_hint_utils;
@@ -12,7 +13,9 @@ pub mod field_arithmetic;
 pub mod find_element_hint;
 pub mod garaga;
 pub mod hint_code;
+pub mod hint_ids;
 pub mod hint_utils;
+pub mod hint_whitelist;
 pub mod keccak_utils;
 pub mod math_utils;
 pub mod memcpy_hint_utils;
@@ -1,5 +1,6 @@
 use super::dict_manager::DictManagerExecScope;
 use super::hint_processor_utils::*;
+use super::syscall_handler::{execute_syscall, SyscallHandler};
 use crate::any_box;
 use crate::hint_processor::cairo_1_hint_processor::dict_manager::DictSquashExecScope;
 use crate::hint_processor::hint_processor_definition::HintReference;
@@ -57,6 +58,8 @@ pub struct Cairo1HintProcessor {
     /// If set to true, uses a single segment for dictionaries to aid in segment arena validations
     /// WARNING: The program must call the "RelocateAllDictionaries" Cheatcode if the flag is enabled
     segment_arena_validations: bool,
+    /// Executes the Starknet syscalls, required to run contracts making them
+    syscall_handler: Option<Box<dyn SyscallHandler>>,
 }
 
 impl Cairo1HintProcessor {
@@ -69,11 +72,20 @@ impl Cairo1HintProcessor {
             hints: hints.iter().cloned().collect(),
             run_resources,
             segment_arena_validations,
+            syscall_handler: None,
         }
     }
+
+    /// Sets the handler used to execute the Starknet syscalls of the program,
+    /// see [`InMemorySyscallHandler`](super::syscall_handler::InMemorySyscallHandler)
+    pub fn with_syscall_handler(mut self, syscall_handler: impl SyscallHandler + 'static) -> Self {
+        self.syscall_handler = Some(Box::new(syscall_handler));
+        self
+    }
+
     // Runs a single Hint
     pub fn execute(
-        &self,
+        &mut self,
         vm: &mut VirtualMachine,
         exec_scopes: &mut ExecutionScopes,
         hint: &Hint,
@@ -289,12 +301,29 @@ impl Cairo1HintProcessor {
                 }
             }
 
+            Hint::Starknet(StarknetHint::SystemCall { system }) => self.system_call(vm, system),
+
             hint => Err(HintError::UnknownHint(
                 format!("{:?}", hint).into_boxed_str(),
             )),
         }
     }
 
+    fn system_call(
+        &mut self,
+        vm: &mut VirtualMachine,
+        system: &ResOperand,
+    ) -> Result<(), HintError> {
+        let (system_base, system_offset) = extract_buffer(system)?;
+        let syscall_ptr = get_ptr(vm, system_base, &system_offset)?;
+        let syscall_handler = self.syscall_handler.as_deref_mut().ok_or_else(|| {
+            HintError::CustomHint(Box::from(
+                "Running syscalls requires a syscall handler, see Cairo1HintProcessor::with_syscall_handler",
+            ))
+        })?;
+        execute_syscall(syscall_handler, vm, syscall_ptr)
+    }
+
     fn alloc_segment(&self, vm: &mut VirtualMachine, dst: &CellRef) -> Result<(), HintError> {
         let segment = vm.add_memory_segment();
         vm.insert_value(cell_ref_to_relocatable(dst, vm)?, segment)
@@ -1,3 +1,4 @@
 pub mod dict_manager;
 pub mod hint_processor;
 pub mod hint_processor_utils;
+pub mod syscall_handler;
@@ -0,0 +1,1772 @@
+use crate::hint_processor::builtin_hint_processor::secp::secp_utils::{
+    SECP256R1_ALPHA, SECP256R1_P, SECP_P,
+};
+use crate::stdlib::{collections::HashMap, prelude::*};
+use crate::types::{
+    errors::math_errors::MathError,
+    relocatable::{MaybeRelocatable, Relocatable},
+};
+use crate::vm::{errors::hint_errors::HintError, vm_core::VirtualMachine};
+use crate::Felt252;
+use lazy_static::lazy_static;
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{ToPrimitive, Zero};
+// Used by the InMemorySyscallHandler
+#[cfg(feature = "std")]
+use {
+    super::hint_processor::Cairo1HintProcessor,
+    crate::stdlib::sync::{Arc, Mutex, MutexGuard, PoisonError},
+    crate::types::{builtin_name::BuiltinName, layout_name::LayoutName},
+    crate::vm::runners::cairo_runner::{CairoArg, CairoRunner, RunResources},
+    cairo_lang_starknet_classes::casm_contract_class::CasmContractClass,
+};
+
+// Gas costs of each syscall, matching the ones used by cairo-lang-runner
+const STEP: u64 = 100;
+const RANGE_CHECK: u64 = 70;
+const BITWISE: u64 = 594;
+const ENTRY_POINT_INITIAL_BUDGET: u64 = 100 * STEP;
+const ENTRY_POINT: u64 = ENTRY_POINT_INITIAL_BUDGET + 500 * STEP;
+const CALL_CONTRACT_GAS_COST: u64 = 10 * STEP + ENTRY_POINT;
+const LIBRARY_CALL_GAS_COST: u64 = CALL_CONTRACT_GAS_COST;
+const STORAGE_READ_GAS_COST: u64 = 50 * STEP;
+const STORAGE_WRITE_GAS_COST: u64 = 50 * STEP;
+const EMIT_EVENT_GAS_COST: u64 = 10 * STEP;
+const GET_BLOCK_HASH_GAS_COST: u64 = 50 * STEP;
+const GET_EXECUTION_INFO_GAS_COST: u64 = 10 * STEP;
+const SEND_MESSAGE_TO_L1_GAS_COST: u64 = 50 * STEP;
+const KECCAK_ROUND_GAS_COST: u64 = 180000;
+const SHA256_PROCESS_BLOCK_GAS_COST: u64 = 1852 * STEP + 65 * RANGE_CHECK + 1115 * BITWISE;
+const SECP256_ADD_GAS_COST: u64 = 254 * STEP + 29 * RANGE_CHECK;
+const SECP256_GET_POINT_FROM_X_GAS_COST: u64 = 260 * STEP + 29 * RANGE_CHECK;
+const SECP256_GET_XY_GAS_COST: u64 = 24 * STEP + 9 * RANGE_CHECK;
+const SECP256_MUL_GAS_COST: u64 = 121810 * STEP + 10739 * RANGE_CHECK;
+const SECP256_NEW_GAS_COST: u64 = 340 * STEP + 36 * RANGE_CHECK;
+
+// Number of u64 words absorbed by each keccak round
+const KECCAK_RATE: usize = 17;
+// Number of the latest blocks whose hash can't be requested
+#[cfg(feature = "std")]
+const BLOCK_HASH_BUFFER: u64 = 10;
+
+lazy_static! {
+    static ref SECP256K1_B: BigInt = BigInt::from(7);
+    static ref SECP256R1_B: BigInt = BigInt::parse_bytes(
+        b"5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b",
+        16
+    )
+    .unwrap();
+}
+
+/// Error returned by a syscall
+#[derive(Debug)]
+pub enum SyscallError {
+    /// The syscall failed and the calling contract receives the given revert reason
+    Revert(Vec<Felt252>),
+    /// The execution can't continue
+    Hint(HintError),
+}
+
+impl SyscallError {
+    /// Reverts with a single short string as reason
+    pub fn revert(reason: &str) -> Self {
+        SyscallError::Revert(vec![Felt252::from_bytes_be_slice(reason.as_bytes())])
+    }
+}
+
+impl From<HintError> for SyscallError {
+    fn from(error: HintError) -> Self {
+        SyscallError::Hint(error)
+    }
+}
+
+pub type SyscallResult<T> = Result<T, SyscallError>;
+
+/// Block related fields of the `ExecutionInfo`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockInfo {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub sequencer_address: Felt252,
+}
+
+/// Bounds on the amount of a resource a transaction can consume
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceBounds {
+    pub resource: Felt252,
+    pub max_amount: u64,
+    pub max_price_per_unit: u128,
+}
+
+/// Transaction related fields of the `ExecutionInfo`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxInfo {
+    pub version: Felt252,
+    pub account_contract_address: Felt252,
+    pub max_fee: u128,
+    pub signature: Vec<Felt252>,
+    pub transaction_hash: Felt252,
+    pub chain_id: Felt252,
+    pub nonce: Felt252,
+    pub resource_bounds: Vec<ResourceBounds>,
+    pub tip: u128,
+    pub paymaster_data: Vec<Felt252>,
+    pub nonce_data_availability_mode: u32,
+    pub fee_data_availability_mode: u32,
+    pub account_deployment_data: Vec<Felt252>,
+}
+
+/// Value returned by the `get_execution_info` syscall
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionInfo {
+    pub block_info: BlockInfo,
+    pub tx_info: TxInfo,
+    pub caller_address: Felt252,
+    pub contract_address: Felt252,
+    pub entry_point_selector: Felt252,
+}
+
+/// Curves supported by the secp256 syscalls
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SecpCurve {
+    Secp256k1,
+    Secp256r1,
+}
+
+impl SecpCurve {
+    fn prime(&self) -> &'static BigInt {
+        match self {
+            SecpCurve::Secp256k1 => &SECP_P,
+            SecpCurve::Secp256r1 => &SECP256R1_P,
+        }
+    }
+
+    fn alpha(&self) -> BigInt {
+        match self {
+            SecpCurve::Secp256k1 => BigInt::zero(),
+            SecpCurve::Secp256r1 => SECP256R1_ALPHA.clone(),
+        }
+    }
+
+    fn beta(&self) -> &'static BigInt {
+        match self {
+            SecpCurve::Secp256k1 => &SECP256K1_B,
+            SecpCurve::Secp256r1 => &SECP256R1_B,
+        }
+    }
+
+    // Returns y^2 for the given x
+    fn y_squared(&self, x: &BigInt) -> BigInt {
+        (x.pow(3) + self.alpha() * x + self.beta()).mod_floor(self.prime())
+    }
+}
+
+// A point on a secp256 curve, None being the point at infinity
+type SecpPoint = Option<(BigInt, BigInt)>;
+
+/// Points created by the secp256 syscalls of a single execution.
+/// Points are referred to by the contract through their index
+#[derive(Clone, Debug, Default)]
+pub struct SecpPoints {
+    points: HashMap<SecpCurve, Vec<SecpPoint>>,
+}
+
+impl SecpPoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the point (x, y), returning None if it doesn't lie on the curve.
+    /// (0, 0) stands for the point at infinity
+    pub fn new_point(
+        &mut self,
+        curve: SecpCurve,
+        x: BigUint,
+        y: BigUint,
+    ) -> SyscallResult<Option<usize>> {
+        let (x, y) = (BigInt::from(x), BigInt::from(y));
+        if &x >= curve.prime() || &y >= curve.prime() {
+            return Err(SyscallError::revert("Invalid argument"));
+        }
+        if x.is_zero() && y.is_zero() {
+            return Ok(Some(self.push(curve, None)));
+        }
+        if (&y * &y).mod_floor(curve.prime()) != curve.y_squared(&x) {
+            return Ok(None);
+        }
+        Ok(Some(self.push(curve, Some((x, y)))))
+    }
+
+    /// Adds the points p0 and p1
+    pub fn add(&mut self, curve: SecpCurve, p0: usize, p1: usize) -> SyscallResult<usize> {
+        let sum = ec_add(curve, self.get(curve, p0)?, self.get(curve, p1)?);
+        Ok(self.push(curve, sum))
+    }
+
+    /// Multiplies the point p by the given scalar
+    pub fn mul(&mut self, curve: SecpCurve, p: usize, scalar: BigUint) -> SyscallResult<usize> {
+        let point = self.get(curve, p)?.clone();
+        let mut product = None;
+        for i in (0..scalar.bits()).rev() {
+            product = ec_add(curve, &product, &product);
+            if scalar.bit(i) {
+                product = ec_add(curve, &product, &point);
+            }
+        }
+        Ok(self.push(curve, product))
+    }
+
+    /// Creates the point with the given x coordinate and y parity, returning None if there is none
+    pub fn get_point_from_x(
+        &mut self,
+        curve: SecpCurve,
+        x: BigUint,
+        y_parity: bool,
+    ) -> SyscallResult<Option<usize>> {
+        let x = BigInt::from(x);
+        if &x >= curve.prime() {
+            return Err(SyscallError::revert("Invalid argument"));
+        }
+        let y_squared = curve.y_squared(&x);
+        // Both curves have p = 3 mod 4
+        let y = y_squared.modpow(&((curve.prime() + 1_u32) >> 2), curve.prime());
+        if (&y * &y).mod_floor(curve.prime()) != y_squared {
+            return Ok(None);
+        }
+        let y = if y.is_odd() == y_parity {
+            y
+        } else {
+            (curve.prime() - &y).mod_floor(curve.prime())
+        };
+        Ok(Some(self.push(curve, Some((x, y)))))
+    }
+
+    /// Returns the coordinates of the point p, (0, 0) for the point at infinity
+    pub fn get_xy(&self, curve: SecpCurve, p: usize) -> SyscallResult<(BigUint, BigUint)> {
+        Ok(match self.get(curve, p)? {
+            Some((x, y)) => (x.magnitude().clone(), y.magnitude().clone()),
+            None => (BigUint::zero(), BigUint::zero()),
+        })
+    }
+
+    fn get(&self, curve: SecpCurve, p: usize) -> SyscallResult<&SecpPoint> {
+        self.points
+            .get(&curve)
+            .and_then(|points| points.get(p))
+            .ok_or_else(|| {
+                HintError::CustomHint(format!("Invalid {curve:?} point id {p}").into_boxed_str())
+                    .into()
+            })
+    }
+
+    fn push(&mut self, curve: SecpCurve, point: SecpPoint) -> usize {
+        let points = self.points.entry(curve).or_default();
+        points.push(point);
+        points.len() - 1
+    }
+}
+
+fn ec_add(curve: SecpCurve, p0: &SecpPoint, p1: &SecpPoint) -> SecpPoint {
+    let prime = curve.prime();
+    let ((x0, y0), (x1, y1)) = match (p0, p1) {
+        (None, p) | (p, None) => return p.clone(),
+        (Some(p0), Some(p1)) => (p0, p1),
+    };
+    let inv = |value: BigInt| value.modpow(&(prime - 2_u32), prime);
+    let slope = if x0 == x1 {
+        if (y0 + y1).mod_floor(prime).is_zero() {
+            return None;
+        }
+        (BigInt::from(3) * x0 * x0 + curve.alpha()) * inv(BigInt::from(2) * y0)
+    } else {
+        (y1 - y0) * inv((x1 - x0).mod_floor(prime))
+    }
+    .mod_floor(prime);
+    let x = (&slope * &slope - x0 - x1).mod_floor(prime);
+    let y = (slope * (x0 - &x) - y0).mod_floor(prime);
+    Some((x, y))
+}
+
+/// Executes the Starknet syscalls of a Cairo 1 contract.
+/// The [`Cairo1HintProcessor`] reads each request from the syscall segment, charges its gas and
+/// writes back the response, delegating the actual execution to the handler
+pub trait SyscallHandler: Send {
+    fn storage_read(&mut self, address_domain: Felt252, address: Felt252)
+        -> SyscallResult<Felt252>;
+
+    fn storage_write(
+        &mut self,
+        address_domain: Felt252,
+        address: Felt252,
+        value: Felt252,
+    ) -> SyscallResult<()>;
+
+    fn emit_event(&mut self, keys: Vec<Felt252>, data: Vec<Felt252>) -> SyscallResult<()>;
+
+    fn get_execution_info(&mut self) -> SyscallResult<ExecutionInfo>;
+
+    fn get_block_hash(&mut self, block_number: u64) -> SyscallResult<Felt252>;
+
+    fn send_message_to_l1(
+        &mut self,
+        to_address: Felt252,
+        payload: Vec<Felt252>,
+    ) -> SyscallResult<()>;
+
+    /// Calls an entry point of the contract deployed at `contract_address`, returning its retdata.
+    /// The gas used by the call is deducted from `remaining_gas`
+    fn call_contract(
+        &mut self,
+        contract_address: Felt252,
+        entry_point_selector: Felt252,
+        calldata: Vec<Felt252>,
+        remaining_gas: &mut u64,
+    ) -> SyscallResult<Vec<Felt252>>;
+
+    /// Calls an entry point of the class `class_hash` in the context of the current contract,
+    /// returning its retdata. The gas used by the call is deducted from `remaining_gas`
+    fn library_call(
+        &mut self,
+        class_hash: Felt252,
+        entry_point_selector: Felt252,
+        calldata: Vec<Felt252>,
+        remaining_gas: &mut u64,
+    ) -> SyscallResult<Vec<Felt252>>;
+
+    /// Applies the keccak permutation to the input, given in chunks of 17 words, returning the
+    /// low and high halves of the resulting u256
+    fn keccak(&mut self, input: &[u64]) -> SyscallResult<(u128, u128)> {
+        let mut state = [0u64; 25];
+        for chunk in input.chunks(KECCAK_RATE) {
+            for (word, value) in state.iter_mut().zip(chunk) {
+                *word ^= value;
+            }
+            keccak::f1600(&mut state);
+        }
+        Ok((
+            ((state[1] as u128) << 64) + state[0] as u128,
+            ((state[3] as u128) << 64) + state[2] as u128,
+        ))
+    }
+
+    /// Applies the sha256 compression function to the state and block
+    fn sha256_process_block(
+        &mut self,
+        mut state: [u32; 8],
+        block: [u32; 16],
+    ) -> SyscallResult<[u32; 8]> {
+        let block: Vec<u8> = block.iter().flat_map(|word| word.to_be_bytes()).collect();
+        sha2::compress256(
+            &mut state,
+            &[*generic_array::GenericArray::from_slice(&block)],
+        );
+        Ok(state)
+    }
+
+    fn secp_new(
+        &mut self,
+        curve: SecpCurve,
+        x: BigUint,
+        y: BigUint,
+    ) -> SyscallResult<Option<usize>>;
+
+    fn secp_add(&mut self, curve: SecpCurve, p0: usize, p1: usize) -> SyscallResult<usize>;
+
+    fn secp_mul(&mut self, curve: SecpCurve, p: usize, scalar: BigUint) -> SyscallResult<usize>;
+
+    fn secp_get_point_from_x(
+        &mut self,
+        curve: SecpCurve,
+        x: BigUint,
+        y_parity: bool,
+    ) -> SyscallResult<Option<usize>>;
+
+    fn secp_get_xy(&mut self, curve: SecpCurve, p: usize) -> SyscallResult<(BigUint, BigUint)>;
+}
+
+/// Event emitted by a contract
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub from_address: Felt252,
+    pub keys: Vec<Felt252>,
+    pub data: Vec<Felt252>,
+}
+
+/// Message sent by a contract to L1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct L2ToL1Message {
+    pub from_address: Felt252,
+    pub to_address: Felt252,
+    pub payload: Vec<Felt252>,
+}
+
+// State shared by all the calls of an InMemorySyscallHandler
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+struct InMemoryState {
+    storage: HashMap<(Felt252, Felt252), Felt252>,
+    // Previous values of the storage keys written during the ongoing calls, so that the writes of
+    // a reverted call can be undone
+    storage_journal: Vec<((Felt252, Felt252), Option<Felt252>)>,
+    // Number of ongoing calls
+    call_depth: usize,
+    events: Vec<Event>,
+    l2_to_l1_messages: Vec<L2ToL1Message>,
+    block_info: BlockInfo,
+    tx_info: TxInfo,
+    block_hashes: HashMap<u64, Felt252>,
+    classes: HashMap<Felt252, CasmContractClass>,
+    contracts: HashMap<Felt252, Felt252>,
+}
+
+// Lengths of the storage journal, events and messages when a call started
+#[cfg(feature = "std")]
+struct CallCheckpoint {
+    storage_journal_len: usize,
+    events_len: usize,
+    l2_to_l1_messages_len: usize,
+}
+
+#[cfg(feature = "std")]
+impl InMemoryState {
+    fn begin_call(&mut self) -> CallCheckpoint {
+        self.call_depth += 1;
+        CallCheckpoint {
+            storage_journal_len: self.storage_journal.len(),
+            events_len: self.events.len(),
+            l2_to_l1_messages_len: self.l2_to_l1_messages.len(),
+        }
+    }
+
+    // Discards the changes made since the checkpoint if the call reverted
+    fn end_call(&mut self, checkpoint: CallCheckpoint, reverted: bool) {
+        if reverted {
+            // Undo the storage writes in reverse order, so each key gets back its value from
+            // before the call
+            for (key, previous_value) in self
+                .storage_journal
+                .drain(checkpoint.storage_journal_len..)
+                .rev()
+            {
+                match previous_value {
+                    Some(value) => self.storage.insert(key, value),
+                    None => self.storage.remove(&key),
+                };
+            }
+            self.events.truncate(checkpoint.events_len);
+            self.l2_to_l1_messages
+                .truncate(checkpoint.l2_to_l1_messages_len);
+        }
+        self.call_depth -= 1;
+        // The outermost call can't be reverted by this handler anymore
+        if self.call_depth == 0 {
+            self.storage_journal.clear();
+        }
+    }
+}
+
+/// [`SyscallHandler`] keeping the Starknet state in memory, so contracts can be run without a node.
+/// Classes are declared with [`InMemorySyscallHandler::declare_class`] and deployed with
+/// [`InMemorySyscallHandler::deploy_contract`], then called through
+/// [`SyscallHandler::call_contract`]. Nested calls share the state of the handler they
+/// originate from, with the changes of a reverted call being discarded.
+/// Clones of a handler share its state, which is kept behind a [`Mutex`] so the handler can be sent
+/// to other threads, and is only available with the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct InMemorySyscallHandler {
+    state: Arc<Mutex<InMemoryState>>,
+    caller_address: Felt252,
+    contract_address: Felt252,
+    entry_point_selector: Felt252,
+    secp_points: SecpPoints,
+}
+
+#[cfg(feature = "std")]
+impl InMemorySyscallHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Locks the state, which is never kept locked across a nested call. A call panicking while
+    // holding the lock can't leave the state half updated, so the poisoning is ignored
+    fn state(&self) -> MutexGuard<'_, InMemoryState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sets the block info returned by `get_execution_info`
+    pub fn with_block_info(self, block_info: BlockInfo) -> Self {
+        self.state().block_info = block_info;
+        self
+    }
+
+    /// Sets the transaction info returned by `get_execution_info`
+    pub fn with_tx_info(self, tx_info: TxInfo) -> Self {
+        self.state().tx_info = tx_info;
+        self
+    }
+
+    /// Sets the address of the contract performing the calls made through this handler
+    pub fn with_contract_address(mut self, contract_address: Felt252) -> Self {
+        self.contract_address = contract_address;
+        self
+    }
+
+    pub fn set_block_hash(&mut self, block_number: u64, block_hash: Felt252) {
+        self.state().block_hashes.insert(block_number, block_hash);
+    }
+
+    pub fn declare_class(&mut self, class_hash: Felt252, class: CasmContractClass) {
+        self.state().classes.insert(class_hash, class);
+    }
+
+    pub fn deploy_contract(&mut self, contract_address: Felt252, class_hash: Felt252) {
+        self.state().contracts.insert(contract_address, class_hash);
+    }
+
+    /// Returns the value stored by the contract at the given key
+    pub fn storage_at(&self, contract_address: Felt252, key: Felt252) -> Felt252 {
+        self.state()
+            .storage
+            .get(&(contract_address, key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_storage_at(&mut self, contract_address: Felt252, key: Felt252, value: Felt252) {
+        let mut state = self.state();
+        let previous_value = state.storage.insert((contract_address, key), value);
+        if state.call_depth > 0 {
+            state
+                .storage_journal
+                .push(((contract_address, key), previous_value));
+        }
+    }
+
+    pub fn events(&self) -> Vec<Event> {
+        self.state().events.clone()
+    }
+
+    pub fn l2_to_l1_messages(&self) -> Vec<L2ToL1Message> {
+        self.state().l2_to_l1_messages.clone()
+    }
+
+    // Runs the entry point of the class in the given call context, reverting its state changes if
+    // it fails
+    fn run_entry_point(
+        &self,
+        class_hash: Felt252,
+        caller_address: Felt252,
+        contract_address: Felt252,
+        entry_point_selector: Felt252,
+        calldata: Vec<Felt252>,
+        remaining_gas: &mut u64,
+    ) -> SyscallResult<Vec<Felt252>> {
+        let class = self
+            .state()
+            .classes
+            .get(&class_hash)
+            .cloned()
+            .ok_or_else(|| SyscallError::revert("CLASS_HASH_NOT_FOUND"))?;
+        let handler = InMemorySyscallHandler {
+            state: self.state.clone(),
+            caller_address,
+            contract_address,
+            entry_point_selector,
+            secp_points: SecpPoints::new(),
+        };
+
+        let checkpoint = self.state().begin_call();
+        let result = run_class_entry_point(
+            &class,
+            entry_point_selector,
+            calldata,
+            remaining_gas,
+            handler,
+        );
+        let reverted = matches!(result, Err(SyscallError::Revert(_)));
+        self.state().end_call(checkpoint, reverted);
+        result
+    }
+}
+
+#[cfg(feature = "std")]
+fn custom_error(message: String) -> SyscallError {
+    HintError::CustomHint(message.into_boxed_str()).into()
+}
+
+// Runs an external entry point of the class in a new runner, following the Starknet calling
+// convention: the builtins, gas and syscall pointer are passed as implicit arguments, followed by
+// the calldata span
+#[cfg(feature = "std")]
+fn run_class_entry_point(
+    class: &CasmContractClass,
+    entry_point_selector: Felt252,
+    calldata: Vec<Felt252>,
+    remaining_gas: &mut u64,
+    syscall_handler: InMemorySyscallHandler,
+) -> SyscallResult<Vec<Felt252>> {
+    let entry_point = class
+        .entry_points_by_type
+        .external
+        .iter()
+        .find(|entry_point| Felt252::from(&entry_point.selector) == entry_point_selector)
+        .ok_or_else(|| SyscallError::revert("ENTRYPOINT_NOT_FOUND"))?;
+    let builtins = entry_point
+        .builtins
+        .iter()
+        .map(|name| {
+            BuiltinName::from_str(name)
+                .ok_or_else(|| custom_error(format!("Invalid builtin name {name}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let program = class
+        .clone()
+        .try_into()
+        .map_err(|e| custom_error(format!("Failed to load contract class: {e}")))?;
+    let mut runner = CairoRunner::new(&program, LayoutName::all_cairo, false, false)
+        .map_err(|e| custom_error(e.to_string()))?;
+    runner
+        .initialize_function_runner_cairo_1(&builtins)
+        .map_err(|e| custom_error(e.to_string()))?;
+    let mut hint_processor = Cairo1HintProcessor::new(&class.hints, RunResources::default(), false)
+        .with_syscall_handler(syscall_handler);
+
+    // Implicit args
+    let mut args: Vec<CairoArg> = runner
+        .vm
+        .get_builtin_runners()
+        .iter()
+        .filter(|b| builtins.contains(&b.name()))
+        .flat_map(|b| b.initial_stack())
+        .map(CairoArg::from)
+        .collect();
+    let syscall_segment = runner.vm.add_memory_segment();
+    args.push(MaybeRelocatable::from(Felt252::from(*remaining_gas)).into());
+    args.push(MaybeRelocatable::from(syscall_segment).into());
+
+    // Builtin costs are not charged
+    let builtin_costs_ptr = runner.vm.add_memory_segment();
+    runner
+        .vm
+        .load_data(builtin_costs_ptr, &vec![Felt252::ZERO.into(); 5])
+        .map_err(HintError::from)?;
+    let program_len = runner.program.shared_program_data.data.len();
+    let program_extra_data: [MaybeRelocatable; 2] = [
+        Felt252::from(0x208B7FFF7FFF7FFE_u64).into(),
+        builtin_costs_ptr.into(),
+    ];
+    let program_end =
+        (runner.program_base.unwrap_or_default() + program_len).map_err(HintError::Math)?;
+    runner
+        .vm
+        .load_data(program_end, &program_extra_data)
+        .map_err(HintError::from)?;
+
+    // Calldata span
+    let calldata: Vec<MaybeRelocatable> = calldata.into_iter().map(Into::into).collect();
+    let calldata_start = runner.vm.add_memory_segment();
+    let calldata_end = runner
+        .vm
+        .load_data(calldata_start, &calldata)
+        .map_err(HintError::from)?;
+    args.push(MaybeRelocatable::from(calldata_start).into());
+    args.push(MaybeRelocatable::from(calldata_end).into());
+
+    runner
+        .run_from_entrypoint(
+            entry_point.offset,
+            &args.iter().collect::<Vec<_>>(),
+            true,
+            Some(program_len + program_extra_data.len()),
+            &mut hint_processor,
+        )
+        .map_err(|e| custom_error(e.to_string()))?;
+
+    // Return values: gas, syscall pointer, panic flag, retdata start and end
+    let return_values = runner.vm.get_return_values(5).map_err(HintError::from)?;
+    let retdata_start = return_values[3]
+        .get_relocatable()
+        .ok_or_else(|| custom_error("Invalid retdata".to_string()))?;
+    let retdata_end = return_values[4]
+        .get_relocatable()
+        .ok_or_else(|| custom_error("Invalid retdata".to_string()))?;
+    let retdata: Vec<Felt252> = runner
+        .vm
+        .get_integer_range(
+            retdata_start,
+            (retdata_end - retdata_start).map_err(HintError::Math)?,
+        )
+        .map_err(HintError::from)?
+        .into_iter()
+        .map(|value| *value)
+        .collect();
+    *remaining_gas = return_values[0]
+        .get_int()
+        .and_then(|gas| gas.to_u64())
+        .ok_or_else(|| custom_error("Invalid remaining gas".to_string()))?;
+
+    if return_values[2] != MaybeRelocatable::from(0) {
+        let mut revert_reason = retdata;
+        revert_reason.push(Felt252::from_bytes_be_slice(b"ENTRYPOINT_FAILED"));
+        return Err(SyscallError::Revert(revert_reason));
+    }
+    Ok(retdata)
+}
+
+#[cfg(feature = "std")]
+impl SyscallHandler for InMemorySyscallHandler {
+    fn storage_read(
+        &mut self,
+        address_domain: Felt252,
+        address: Felt252,
+    ) -> SyscallResult<Felt252> {
+        if address_domain != Felt252::ZERO {
+            return Err(SyscallError::revert("Unsupported address domain"));
+        }
+        Ok(self.storage_at(self.contract_address, address))
+    }
+
+    fn storage_write(
+        &mut self,
+        address_domain: Felt252,
+        address: Felt252,
+        value: Felt252,
+    ) -> SyscallResult<()> {
+        if address_domain != Felt252::ZERO {
+            return Err(SyscallError::revert("Unsupported address domain"));
+        }
+        let contract_address = self.contract_address;
+        self.set_storage_at(contract_address, address, value);
+        Ok(())
+    }
+
+    fn emit_event(&mut self, keys: Vec<Felt252>, data: Vec<Felt252>) -> SyscallResult<()> {
+        self.state().events.push(Event {
+            from_address: self.contract_address,
+            keys,
+            data,
+        });
+        Ok(())
+    }
+
+    fn get_execution_info(&mut self) -> SyscallResult<ExecutionInfo> {
+        let state = self.state();
+        Ok(ExecutionInfo {
+            block_info: state.block_info.clone(),
+            tx_info: state.tx_info.clone(),
+            caller_address: self.caller_address,
+            contract_address: self.contract_address,
+            entry_point_selector: self.entry_point_selector,
+        })
+    }
+
+    fn get_block_hash(&mut self, block_number: u64) -> SyscallResult<Felt252> {
+        let state = self.state();
+        let current_block_number = state.block_info.block_number;
+        if current_block_number < BLOCK_HASH_BUFFER
+            || block_number > current_block_number - BLOCK_HASH_BUFFER
+        {
+            return Err(SyscallError::revert("Block number out of range"));
+        }
+        state
+            .block_hashes
+            .get(&block_number)
+            .copied()
+            .ok_or_else(|| SyscallError::revert("Block number out of range"))
+    }
+
+    fn send_message_to_l1(
+        &mut self,
+        to_address: Felt252,
+        payload: Vec<Felt252>,
+    ) -> SyscallResult<()> {
+        self.state().l2_to_l1_messages.push(L2ToL1Message {
+            from_address: self.contract_address,
+            to_address,
+            payload,
+        });
+        Ok(())
+    }
+
+    fn call_contract(
+        &mut self,
+        contract_address: Felt252,
+        entry_point_selector: Felt252,
+        calldata: Vec<Felt252>,
+        remaining_gas: &mut u64,
+    ) -> SyscallResult<Vec<Felt252>> {
+        let class_hash = self
+            .state()
+            .contracts
+            .get(&contract_address)
+            .copied()
+            .ok_or_else(|| SyscallError::revert("CONTRACT_NOT_DEPLOYED"))?;
+        self.run_entry_point(
+            class_hash,
+            self.contract_address,
+            contract_address,
+            entry_point_selector,
+            calldata,
+            remaining_gas,
+        )
+    }
+
+    fn library_call(
+        &mut self,
+        class_hash: Felt252,
+        entry_point_selector: Felt252,
+        calldata: Vec<Felt252>,
+        remaining_gas: &mut u64,
+    ) -> SyscallResult<Vec<Felt252>> {
+        // Library calls keep the call context of the current contract
+        self.run_entry_point(
+            class_hash,
+            self.caller_address,
+            self.contract_address,
+            entry_point_selector,
+            calldata,
+            remaining_gas,
+        )
+    }
+
+    fn secp_new(
+        &mut self,
+        curve: SecpCurve,
+        x: BigUint,
+        y: BigUint,
+    ) -> SyscallResult<Option<usize>> {
+        self.secp_points.new_point(curve, x, y)
+    }
+
+    fn secp_add(&mut self, curve: SecpCurve, p0: usize, p1: usize) -> SyscallResult<usize> {
+        self.secp_points.add(curve, p0, p1)
+    }
+
+    fn secp_mul(&mut self, curve: SecpCurve, p: usize, scalar: BigUint) -> SyscallResult<usize> {
+        self.secp_points.mul(curve, p, scalar)
+    }
+
+    fn secp_get_point_from_x(
+        &mut self,
+        curve: SecpCurve,
+        x: BigUint,
+        y_parity: bool,
+    ) -> SyscallResult<Option<usize>> {
+        self.secp_points.get_point_from_x(curve, x, y_parity)
+    }
+
+    fn secp_get_xy(&mut self, curve: SecpCurve, p: usize) -> SyscallResult<(BigUint, BigUint)> {
+        self.secp_points.get_xy(curve, p)
+    }
+}
+
+// Reads a syscall request and writes its response, advancing through the syscall segment
+struct SyscallBuffer {
+    ptr: Relocatable,
+}
+
+impl SyscallBuffer {
+    fn next_felt(&mut self, vm: &VirtualMachine) -> Result<Felt252, HintError> {
+        let value = *vm.get_integer(self.ptr)?;
+        self.ptr += 1;
+        Ok(value)
+    }
+
+    fn next_u64(&mut self, vm: &VirtualMachine) -> Result<u64, HintError> {
+        let value = self.next_felt(vm)?;
+        value
+            .to_u64()
+            .ok_or_else(|| MathError::Felt252ToU64Conversion(Box::new(value)).into())
+    }
+
+    fn next_usize(&mut self, vm: &VirtualMachine) -> Result<usize, HintError> {
+        let value = self.next_felt(vm)?;
+        value
+            .to_usize()
+            .ok_or_else(|| MathError::Felt252ToUsizeConversion(Box::new(value)).into())
+    }
+
+    fn next_u256(&mut self, vm: &VirtualMachine) -> Result<BigUint, HintError> {
+        let low = self.next_felt(vm)?.to_biguint();
+        let high = self.next_felt(vm)?.to_biguint();
+        Ok((high << 128) + low)
+    }
+
+    fn next_ptr(&mut self, vm: &VirtualMachine) -> Result<Relocatable, HintError> {
+        let value = vm.get_relocatable(self.ptr)?;
+        self.ptr += 1;
+        Ok(value)
+    }
+
+    fn next_arr(&mut self, vm: &VirtualMachine) -> Result<Vec<Felt252>, HintError> {
+        let start = self.next_ptr(vm)?;
+        let end = self.next_ptr(vm)?;
+        Ok(vm
+            .get_integer_range(start, (end - start)?)?
+            .into_iter()
+            .map(|value| *value)
+            .collect())
+    }
+
+    fn next_u32_words<const N: usize>(
+        &mut self,
+        vm: &VirtualMachine,
+    ) -> Result<[u32; N], HintError> {
+        let start = self.next_ptr(vm)?;
+        let mut words = [0; N];
+        for (word, value) in words.iter_mut().zip(vm.get_integer_range(start, N)?) {
+            *word = value
+                .to_u32()
+                .ok_or_else(|| MathError::Felt252ToU32Conversion(Box::new(*value)))?;
+        }
+        Ok(words)
+    }
+
+    fn write<T: Into<MaybeRelocatable>>(
+        &mut self,
+        vm: &mut VirtualMachine,
+        value: T,
+    ) -> Result<(), HintError> {
+        vm.insert_value(self.ptr, value)?;
+        self.ptr += 1;
+        Ok(())
+    }
+}
+
+// Writes the values to a new segment, returning its start and end
+fn write_segment(
+    vm: &mut VirtualMachine,
+    values: &[MaybeRelocatable],
+) -> Result<[MaybeRelocatable; 2], HintError> {
+    let start = vm.add_memory_segment();
+    let end = vm.load_data(start, values)?;
+    Ok([start.into(), end.into()])
+}
+
+fn u256_to_felts(value: &BigUint) -> [MaybeRelocatable; 2] {
+    let low = value & BigUint::from(u128::MAX);
+    let high: BigUint = value >> 128;
+    [Felt252::from(low).into(), Felt252::from(high).into()]
+}
+
+fn felts(values: Vec<Felt252>) -> Vec<MaybeRelocatable> {
+    values.into_iter().map(Into::into).collect()
+}
+
+fn deduct_gas(gas: &mut u64, cost: u64) -> SyscallResult<()> {
+    *gas = gas
+        .checked_sub(cost)
+        .ok_or_else(|| SyscallError::revert("Syscall out of gas"))?;
+    Ok(())
+}
+
+fn optional_point(point: Option<usize>) -> Vec<MaybeRelocatable> {
+    match point {
+        Some(id) => vec![1.into(), id.into()],
+        None => vec![0.into(), 0.into()],
+    }
+}
+
+// Writes the execution info to new segments, returning a pointer to it
+fn write_execution_info(
+    vm: &mut VirtualMachine,
+    info: ExecutionInfo,
+) -> Result<Vec<MaybeRelocatable>, HintError> {
+    let block_info: [MaybeRelocatable; 3] = [
+        Felt252::from(info.block_info.block_number).into(),
+        Felt252::from(info.block_info.block_timestamp).into(),
+        info.block_info.sequencer_address.into(),
+    ];
+    let block_info = write_segment(vm, &block_info)?;
+
+    let tx_info = info.tx_info;
+    let resource_bounds: Vec<MaybeRelocatable> = tx_info
+        .resource_bounds
+        .iter()
+        .flat_map(|bounds| {
+            [
+                bounds.resource.into(),
+                Felt252::from(bounds.max_amount).into(),
+                Felt252::from(bounds.max_price_per_unit).into(),
+            ]
+        })
+        .collect();
+    let mut tx_info_data: Vec<MaybeRelocatable> = vec![
+        tx_info.version.into(),
+        tx_info.account_contract_address.into(),
+        Felt252::from(tx_info.max_fee).into(),
+    ];
+    tx_info_data.extend(write_segment(vm, &felts(tx_info.signature))?);
+    tx_info_data.extend([
+        tx_info.transaction_hash.into(),
+        tx_info.chain_id.into(),
+        tx_info.nonce.into(),
+    ]);
+    tx_info_data.extend(write_segment(vm, &resource_bounds)?);
+    tx_info_data.push(Felt252::from(tx_info.tip).into());
+    tx_info_data.extend(write_segment(vm, &felts(tx_info.paymaster_data))?);
+    tx_info_data.extend([
+        Felt252::from(tx_info.nonce_data_availability_mode).into(),
+        Felt252::from(tx_info.fee_data_availability_mode).into(),
+    ]);
+    tx_info_data.extend(write_segment(vm, &felts(tx_info.account_deployment_data))?);
+    let tx_info = write_segment(vm, &tx_info_data)?;
+
+    let [execution_info, _] = write_segment(
+        vm,
+        &[
+            block_info[0].clone(),
+            tx_info[0].clone(),
+            info.caller_address.into(),
+            info.contract_address.into(),
+            info.entry_point_selector.into(),
+        ],
+    )?;
+    Ok(vec![execution_info])
+}
+
+/// Executes the syscall whose request starts at `syscall_ptr`, writing the response right after it.
+/// Requests hold the selector, the available gas and the syscall arguments, and responses the
+/// remaining gas, a failure flag and either the results or the revert reason.
+pub(crate) fn execute_syscall(
+    syscall_handler: &mut dyn SyscallHandler,
+    vm: &mut VirtualMachine,
+    syscall_ptr: Relocatable,
+) -> Result<(), HintError> {
+    let mut buffer = SyscallBuffer { ptr: syscall_ptr };
+    let selector = buffer.next_felt(vm)?.to_bytes_be();
+    let selector = core::str::from_utf8(&selector)
+        .map_err(|_| HintError::CustomHint(Box::from("failed to parse syscall selector")))?
+        .trim_start_matches('\0');
+    let mut gas = buffer.next_u64(vm)?;
+
+    let secp_curve = if selector.starts_with("Secp256k1") {
+        SecpCurve::Secp256k1
+    } else {
+        SecpCurve::Secp256r1
+    };
+    let result: SyscallResult<Vec<MaybeRelocatable>> = match selector {
+        "StorageRead" => {
+            let address_domain = buffer.next_felt(vm)?;
+            let address = buffer.next_felt(vm)?;
+            deduct_gas(&mut gas, STORAGE_READ_GAS_COST)
+                .and_then(|_| syscall_handler.storage_read(address_domain, address))
+                .map(|value| vec![value.into()])
+        }
+        "StorageWrite" => {
+            let address_domain = buffer.next_felt(vm)?;
+            let address = buffer.next_felt(vm)?;
+            let value = buffer.next_felt(vm)?;
+            deduct_gas(&mut gas, STORAGE_WRITE_GAS_COST)
+                .and_then(|_| syscall_handler.storage_write(address_domain, address, value))
+                .map(|_| vec![])
+        }
+        "EmitEvent" => {
+            let keys = buffer.next_arr(vm)?;
+            let data = buffer.next_arr(vm)?;
+            deduct_gas(&mut gas, EMIT_EVENT_GAS_COST)
+                .and_then(|_| syscall_handler.emit_event(keys, data))
+                .map(|_| vec![])
+        }
+        "GetExecutionInfo" => match deduct_gas(&mut gas, GET_EXECUTION_INFO_GAS_COST)
+            .and_then(|_| syscall_handler.get_execution_info())
+        {
+            Ok(info) => Ok(write_execution_info(vm, info)?),
+            Err(error) => Err(error),
+        },
+        "GetBlockHash" => {
+            let block_number = buffer.next_u64(vm)?;
+            deduct_gas(&mut gas, GET_BLOCK_HASH_GAS_COST)
+                .and_then(|_| syscall_handler.get_block_hash(block_number))
+                .map(|hash| vec![hash.into()])
+        }
+        "SendMessageToL1" => {
+            let to_address = buffer.next_felt(vm)?;
+            let payload = buffer.next_arr(vm)?;
+            deduct_gas(&mut gas, SEND_MESSAGE_TO_L1_GAS_COST)
+                .and_then(|_| syscall_handler.send_message_to_l1(to_address, payload))
+                .map(|_| vec![])
+        }
+        "CallContract" | "LibraryCall" => {
+            let target = buffer.next_felt(vm)?;
+            let entry_point_selector = buffer.next_felt(vm)?;
+            let calldata = buffer.next_arr(vm)?;
+            let retdata = if selector == "CallContract" {
+                deduct_gas(&mut gas, CALL_CONTRACT_GAS_COST).and_then(|_| {
+                    syscall_handler.call_contract(target, entry_point_selector, calldata, &mut gas)
+                })
+            } else {
+                deduct_gas(&mut gas, LIBRARY_CALL_GAS_COST).and_then(|_| {
+                    syscall_handler.library_call(target, entry_point_selector, calldata, &mut gas)
+                })
+            };
+            match retdata {
+                Ok(retdata) => Ok(write_segment(vm, &felts(retdata))?.to_vec()),
+                Err(error) => Err(error),
+            }
+        }
+        "Keccak" => {
+            let input = buffer.next_arr(vm)?;
+            if input.len() % KECCAK_RATE != 0 {
+                Err(SyscallError::revert("Invalid keccak input size"))
+            } else {
+                let input = input
+                    .iter()
+                    .map(|word| {
+                        word.to_u64()
+                            .ok_or_else(|| MathError::Felt252ToU64Conversion(Box::new(*word)))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let rounds = (input.len() / KECCAK_RATE) as u64;
+                deduct_gas(&mut gas, rounds * KECCAK_ROUND_GAS_COST)
+                    .and_then(|_| syscall_handler.keccak(&input))
+                    .map(|(low, high)| vec![Felt252::from(low).into(), Felt252::from(high).into()])
+            }
+        }
+        "Sha256ProcessBlock" => {
+            let state = buffer.next_u32_words::<8>(vm)?;
+            let block = buffer.next_u32_words::<16>(vm)?;
+            match deduct_gas(&mut gas, SHA256_PROCESS_BLOCK_GAS_COST)
+                .and_then(|_| syscall_handler.sha256_process_block(state, block))
+            {
+                Ok(state) => {
+                    let state: Vec<MaybeRelocatable> = state
+                        .iter()
+                        .map(|word| Felt252::from(*word).into())
+                        .collect();
+                    Ok(vec![write_segment(vm, &state)?[0].clone()])
+                }
+                Err(error) => Err(error),
+            }
+        }
+        "Secp256k1New" | "Secp256r1New" => {
+            let x = buffer.next_u256(vm)?;
+            let y = buffer.next_u256(vm)?;
+            deduct_gas(&mut gas, SECP256_NEW_GAS_COST)
+                .and_then(|_| syscall_handler.secp_new(secp_curve, x, y))
+                .map(optional_point)
+        }
+        "Secp256k1Add" | "Secp256r1Add" => {
+            let p0 = buffer.next_usize(vm)?;
+            let p1 = buffer.next_usize(vm)?;
+            deduct_gas(&mut gas, SECP256_ADD_GAS_COST)
+                .and_then(|_| syscall_handler.secp_add(secp_curve, p0, p1))
+                .map(|id| vec![id.into()])
+        }
+        "Secp256k1Mul" | "Secp256r1Mul" => {
+            let p = buffer.next_usize(vm)?;
+            let scalar = buffer.next_u256(vm)?;
+            deduct_gas(&mut gas, SECP256_MUL_GAS_COST)
+                .and_then(|_| syscall_handler.secp_mul(secp_curve, p, scalar))
+                .map(|id| vec![id.into()])
+        }
+        "Secp256k1GetPointFromX" | "Secp256r1GetPointFromX" => {
+            let x = buffer.next_u256(vm)?;
+            let y_parity = buffer.next_felt(vm)? != Felt252::ZERO;
+            deduct_gas(&mut gas, SECP256_GET_POINT_FROM_X_GAS_COST)
+                .and_then(|_| syscall_handler.secp_get_point_from_x(secp_curve, x, y_parity))
+                .map(optional_point)
+        }
+        "Secp256k1GetXy" | "Secp256r1GetXy" => {
+            let p = buffer.next_usize(vm)?;
+            deduct_gas(&mut gas, SECP256_GET_XY_GAS_COST)
+                .and_then(|_| syscall_handler.secp_get_xy(secp_curve, p))
+                .map(|(x, y)| {
+                    let mut xy = u256_to_felts(&x).to_vec();
+                    xy.extend(u256_to_felts(&y));
+                    xy
+                })
+        }
+        _ => return Err(HintError::UnknownHint(selector.into())),
+    };
+
+    match result {
+        Ok(values) => {
+            buffer.write(vm, Felt252::from(gas))?;
+            buffer.write(vm, Felt252::ZERO)?;
+            for value in values {
+                buffer.write(vm, value)?;
+            }
+        }
+        Err(SyscallError::Revert(revert_reason)) => {
+            buffer.write(vm, Felt252::from(gas))?;
+            buffer.write(vm, Felt252::ONE)?;
+            for value in write_segment(vm, &felts(revert_reason))? {
+                buffer.write(vm, value)?;
+            }
+        }
+        Err(SyscallError::Hint(error)) => return Err(error),
+    }
+    Ok(())
+}
+
+// The syscalls are tested through the InMemorySyscallHandler, which requires std
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::types::exec_scope::ExecutionScopes;
+    use crate::utils::test_utils::*;
+    use assert_matches::assert_matches;
+    use cairo_lang_casm::{
+        hints::{Hint, StarknetHint},
+        operand::{CellRef, Register, ResOperand},
+    };
+    use rstest::rstest;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    const GAS: u64 = 1_000_000_000;
+
+    fn selector(name: &str) -> MaybeRelocatable {
+        Felt252::from_bytes_be_slice(name.as_bytes()).into()
+    }
+
+    // Writes the request to a new segment and executes it, returning the vm and the start of the
+    // response
+    fn run_syscall(
+        handler: &mut dyn SyscallHandler,
+        request: &[MaybeRelocatable],
+        vm: &mut VirtualMachine,
+    ) -> Result<Relocatable, HintError> {
+        let syscall_ptr = vm.add_memory_segment();
+        vm.load_data(syscall_ptr, request)?;
+        execute_syscall(handler, vm, syscall_ptr)?;
+        Ok((syscall_ptr + request.len()).unwrap())
+    }
+
+    fn read_range(vm: &VirtualMachine, ptr: Relocatable, size: usize) -> Vec<MaybeRelocatable> {
+        vm.get_continuous_range(ptr, size).unwrap()
+    }
+
+    fn span(vm: &VirtualMachine, start: &MaybeRelocatable, end: &MaybeRelocatable) -> Vec<Felt252> {
+        let start = start.get_relocatable().unwrap();
+        let end = end.get_relocatable().unwrap();
+        vm.get_integer_range(start, (end - start).unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|value| *value)
+            .collect()
+    }
+
+    fn alloc_span(vm: &mut VirtualMachine, values: &[Felt252]) -> [MaybeRelocatable; 2] {
+        write_segment(vm, &felts(values.to_vec())).unwrap()
+    }
+
+    fn u256(value: &BigUint) -> [MaybeRelocatable; 2] {
+        u256_to_felts(value)
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn storage_write_and_read() {
+        let mut vm = vm!();
+        let mut handler = InMemorySyscallHandler::new().with_contract_address(Felt252::from(3));
+        let write = [
+            selector("StorageWrite"),
+            Felt252::from(GAS).into(),
+            Felt252::ZERO.into(),
+            Felt252::from(7).into(),
+            Felt252::from(42).into(),
+        ];
+        let ptr = run_syscall(&mut handler, &write, &mut vm).unwrap();
+        assert_eq!(
+            read_range(&vm, ptr, 2),
+            vec![
+                Felt252::from(GAS - STORAGE_WRITE_GAS_COST).into(),
+                Felt252::ZERO.into()
+            ]
+        );
+        assert_eq!(
+            handler.storage_at(Felt252::from(3), Felt252::from(7)),
+            Felt252::from(42)
+        );
+
+        let read = [
+            selector("StorageRead"),
+            Felt252::from(GAS).into(),
+            Felt252::ZERO.into(),
+            Felt252::from(7).into(),
+        ];
+        let ptr = run_syscall(&mut handler, &read, &mut vm).unwrap();
+        assert_eq!(
+            read_range(&vm, ptr, 3),
+            vec![
+                Felt252::from(GAS - STORAGE_READ_GAS_COST).into(),
+                Felt252::ZERO.into(),
+                Felt252::from(42).into()
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::unsupported_address_domain(GAS, Felt252::ONE, "Unsupported address domain")]
+    #[case::out_of_gas(STORAGE_READ_GAS_COST - 1, Felt252::ZERO, "Syscall out of gas")]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn storage_read_reverts(
+        #[case] gas: u64,
+        #[case] address_domain: Felt252,
+        #[case] reason: &str,
+    ) {
+        let mut vm = vm!();
+        let mut handler = InMemorySyscallHandler::new();
+        let read = [
+            selector("StorageRead"),
+            Felt252::from(gas).into(),
+            address_domain.into(),
+            Felt252::from(7).into(),
+        ];
+        let ptr = run_syscall(&mut handler, &read, &mut vm).unwrap();
+        let response = read_range(&vm, ptr, 4);
+        assert_eq!(response[1], Felt252::ONE.into());
+        assert_eq!(
+            span(&vm, &response[2], &response[3]),
+            vec![Felt252::from_bytes_be_slice(reason.as_bytes())]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn emit_event_and_send_message_to_l1() {
+        let mut vm = vm!();
+        let mut handler = InMemorySyscallHandler::new().with_contract_address(Felt252::from(3));
+        let keys = alloc_span(&mut vm, &[Felt252::from(1)]);
+        let data = alloc_span(&mut vm, &[Felt252::from(2), Felt252::from(3)]);
+        let mut emit_event = vec![selector("EmitEvent"), Felt252::from(GAS).into()];
+        emit_event.extend(keys);
+        emit_event.extend(data);
+        run_syscall(&mut handler, &emit_event, &mut vm).unwrap();
+
+        let payload = alloc_span(&mut vm, &[Felt252::from(4)]);
+        let mut send_message = vec![
+            selector("SendMessageToL1"),
+            Felt252::from(GAS).into(),
+            Felt252::from(5).into(),
+        ];
+        send_message.extend(payload);
+        run_syscall(&mut handler, &send_message, &mut vm).unwrap();
+
+        assert_eq!(
+            handler.events(),
+            vec![Event {
+                from_address: Felt252::from(3),
+                keys: vec![Felt252::from(1)],
+                data: vec![Felt252::from(2), Felt252::from(3)],
+            }]
+        );
+        assert_eq!(
+            handler.l2_to_l1_messages(),
+            vec![L2ToL1Message {
+                from_address: Felt252::from(3),
+                to_address: Felt252::from(5),
+                payload: vec![Felt252::from(4)],
+            }]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_execution_info() {
+        let mut vm = vm!();
+        let mut handler = InMemorySyscallHandler::new()
+            .with_contract_address(Felt252::from(3))
+            .with_block_info(BlockInfo {
+                block_number: 100,
+                block_timestamp: 200,
+                sequencer_address: Felt252::from(300),
+            })
+            .with_tx_info(TxInfo {
+                version: Felt252::ONE,
+                signature: vec![Felt252::from(10), Felt252::from(11)],
+                chain_id: Felt252::from(12),
+                ..Default::default()
+            });
+        let request = [selector("GetExecutionInfo"), Felt252::from(GAS).into()];
+        let ptr = run_syscall(&mut handler, &request, &mut vm).unwrap();
+        let response = read_range(&vm, ptr, 3);
+        assert_eq!(response[1], Felt252::ZERO.into());
+
+        let execution_info = read_range(&vm, response[2].get_relocatable().unwrap(), 5);
+        assert_eq!(execution_info[3], Felt252::from(3).into());
+        let block_info = read_range(&vm, execution_info[0].get_relocatable().unwrap(), 3);
+        assert_eq!(
+            block_info,
+            vec![
+                Felt252::from(100).into(),
+                Felt252::from(200).into(),
+                Felt252::from(300).into()
+            ]
+        );
+        let tx_info = read_range(&vm, execution_info[1].get_relocatable().unwrap(), 17);
+        assert_eq!(tx_info[0], Felt252::ONE.into());
+        assert_eq!(
+            span(&vm, &tx_info[3], &tx_info[4]),
+            vec![Felt252::from(10), Felt252::from(11)]
+        );
+        assert_eq!(tx_info[6], Felt252::from(12).into());
+    }
+
+    #[rstest]
+    #[case(80, Ok(Felt252::from(8080)))]
+    #[case(95, Err(()))]
+    #[case(81, Err(()))]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_block_hash(#[case] block_number: u64, #[case] expected: Result<Felt252, ()>) {
+        let mut handler = InMemorySyscallHandler::new().with_block_info(BlockInfo {
+            block_number: 100,
+            ..Default::default()
+        });
+        handler.set_block_hash(80, Felt252::from(8080));
+        handler.set_block_hash(95, Felt252::from(9595));
+        assert_eq!(
+            handler.get_block_hash(block_number).map_err(|_| ()),
+            expected
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn keccak_empty_input() {
+        let mut vm = vm!();
+        // Padded empty message
+        let mut input = vec![Felt252::ZERO; KECCAK_RATE];
+        input[0] = Felt252::ONE;
+        input[16] = Felt252::from(0x8000000000000000_u64);
+        let mut request = vec![selector("Keccak"), Felt252::from(GAS).into()];
+        request.extend(alloc_span(&mut vm, &input));
+        let ptr = run_syscall(&mut InMemorySyscallHandler::new(), &request, &mut vm).unwrap();
+        assert_eq!(
+            read_range(&vm, ptr, 4),
+            vec![
+                Felt252::from(GAS - KECCAK_ROUND_GAS_COST).into(),
+                Felt252::ZERO.into(),
+                Felt252::from(255231405766226377092371489783609217733_u128).into(),
+                Felt252::from(149727777142906939876210071365441945829_u128).into(),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn keccak_invalid_input_size() {
+        let mut vm = vm!();
+        let mut request = vec![selector("Keccak"), Felt252::from(GAS).into()];
+        request.extend(alloc_span(&mut vm, &[Felt252::ONE]));
+        let ptr = run_syscall(&mut InMemorySyscallHandler::new(), &request, &mut vm).unwrap();
+        let response = read_range(&vm, ptr, 4);
+        assert_eq!(response[1], Felt252::ONE.into());
+        assert_eq!(
+            span(&vm, &response[2], &response[3]),
+            vec![Felt252::from_bytes_be_slice(b"Invalid keccak input size")]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn sha256_process_block_abc() {
+        let mut vm = vm!();
+        let iv: [u32; 8] = [
+            0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+            0x5be0cd19,
+        ];
+        // "abc" padded to a single block
+        let mut block = [0_u32; 16];
+        block[0] = 0x61626380;
+        block[15] = 24;
+        let to_felts = |words: &[u32]| -> Vec<Felt252> {
+            words.iter().map(|word| Felt252::from(*word)).collect()
+        };
+        let [state_ptr, _] = alloc_span(&mut vm, &to_felts(&iv));
+        let [block_ptr, _] = alloc_span(&mut vm, &to_felts(&block));
+        let request = [
+            selector("Sha256ProcessBlock"),
+            Felt252::from(GAS).into(),
+            state_ptr,
+            block_ptr,
+        ];
+        let ptr = run_syscall(&mut InMemorySyscallHandler::new(), &request, &mut vm).unwrap();
+        let response = read_range(&vm, ptr, 3);
+        assert_eq!(response[1], Felt252::ZERO.into());
+        let digest = vm
+            .get_integer_range(response[2].get_relocatable().unwrap(), 8)
+            .unwrap()
+            .into_iter()
+            .map(|word| *word)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            digest,
+            to_felts(&[
+                0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
+                0xf20015ad
+            ])
+        );
+    }
+
+    fn secp256k1_generator() -> (BigUint, BigUint) {
+        (
+            BigUint::parse_bytes(
+                b"79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+                16,
+            )
+            .unwrap(),
+            BigUint::parse_bytes(
+                b"483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8",
+                16,
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn secp256k1_syscalls() {
+        let mut vm = vm!();
+        let mut handler = InMemorySyscallHandler::new();
+        let (gx, gy) = secp256k1_generator();
+
+        let mut new = vec![selector("Secp256k1New"), Felt252::from(GAS).into()];
+        new.extend(u256(&gx));
+        new.extend(u256(&gy));
+        let ptr = run_syscall(&mut handler, &new, &mut vm).unwrap();
+        assert_eq!(
+            read_range(&vm, ptr, 4)[1..],
+            [Felt252::ZERO.into(), 1.into(), 0.into()]
+        );
+
+        let add = [
+            selector("Secp256k1Add"),
+            Felt252::from(GAS).into(),
+            0.into(),
+            0.into(),
+        ];
+        let ptr = run_syscall(&mut handler, &add, &mut vm).unwrap();
+        assert_eq!(read_range(&vm, ptr, 3)[2], 1.into());
+
+        let mut mul = vec![
+            selector("Secp256k1Mul"),
+            Felt252::from(GAS).into(),
+            0.into(),
+        ];
+        mul.extend(u256(&BigUint::from(2_u32)));
+        let ptr = run_syscall(&mut handler, &mul, &mut vm).unwrap();
+        assert_eq!(read_range(&vm, ptr, 3)[2], 2.into());
+
+        let double_x = BigUint::parse_bytes(
+            b"c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+            16,
+        )
+        .unwrap();
+        let double_y = BigUint::parse_bytes(
+            b"1ae168fea63dc339a3c58419466ceaeef7f632653266d0e1236431a950cfe52a",
+            16,
+        )
+        .unwrap();
+        for p in [1_usize, 2] {
+            let get_xy = [
+                selector("Secp256k1GetXy"),
+                Felt252::from(GAS).into(),
+                p.into(),
+            ];
+            let ptr = run_syscall(&mut handler, &get_xy, &mut vm).unwrap();
+            let mut expected = u256(&double_x).to_vec();
+            expected.extend(u256(&double_y));
+            assert_eq!(read_range(&vm, ptr, 6)[2..], expected);
+        }
+
+        let mut get_point_from_x = vec![
+            selector("Secp256k1GetPointFromX"),
+            Felt252::from(GAS).into(),
+        ];
+        get_point_from_x.extend(u256(&gx));
+        get_point_from_x.push(Felt252::ZERO.into());
+        let ptr = run_syscall(&mut handler, &get_point_from_x, &mut vm).unwrap();
+        assert_eq!(read_range(&vm, ptr, 4)[2..], [1.into(), 3.into()]);
+        assert_eq!(
+            handler.secp_get_xy(SecpCurve::Secp256k1, 3).unwrap(),
+            (gx, gy)
+        );
+    }
+
+    #[rstest]
+    #[case::secp256k1(SecpCurve::Secp256k1)]
+    #[case::secp256r1(SecpCurve::Secp256r1)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn secp_points_arithmetic(#[case] curve: SecpCurve) {
+        let mut points = SecpPoints::new();
+        let p = points
+            .get_point_from_x(curve, BigUint::from(5_u32), true)
+            .unwrap()
+            .or_else(|| {
+                points
+                    .get_point_from_x(curve, BigUint::from(6_u32), true)
+                    .unwrap()
+            })
+            .unwrap();
+        let (x, y) = points.get_xy(curve, p).unwrap();
+        assert!(y.bit(0));
+        assert_eq!(points.new_point(curve, x, y).unwrap(), Some(p + 1));
+
+        // 3P = P + 2P
+        let double = points.add(curve, p, p).unwrap();
+        let triple = points.add(curve, p, double).unwrap();
+        let product = points.mul(curve, p, BigUint::from(3_u32)).unwrap();
+        assert_eq!(
+            points.get_xy(curve, triple).unwrap(),
+            points.get_xy(curve, product).unwrap()
+        );
+
+        // P + (-P) is the point at infinity
+        let (x, y) = points.get_xy(curve, p).unwrap();
+        let neg_y = curve.prime().magnitude() - y;
+        let neg = points.new_point(curve, x, neg_y).unwrap().unwrap();
+        let infinity = points.add(curve, p, neg).unwrap();
+        assert_eq!(
+            points.get_xy(curve, infinity).unwrap(),
+            (BigUint::zero(), BigUint::zero())
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn secp_new_point_not_on_curve() {
+        let mut points = SecpPoints::new();
+        assert_matches!(
+            points.new_point(
+                SecpCurve::Secp256k1,
+                BigUint::from(1_u32),
+                BigUint::from(1_u32)
+            ),
+            Ok(None)
+        );
+        assert_matches!(
+            points.new_point(
+                SecpCurve::Secp256k1,
+                SECP_P.magnitude().clone(),
+                BigUint::zero()
+            ),
+            Err(SyscallError::Revert(_))
+        );
+        assert_matches!(
+            points.get_xy(SecpCurve::Secp256r1, 0),
+            Err(SyscallError::Hint(HintError::CustomHint(_)))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn call_contract_not_deployed() {
+        let mut vm = vm!();
+        let mut request = vec![
+            selector("CallContract"),
+            Felt252::from(GAS).into(),
+            Felt252::from(3).into(),
+            Felt252::from(4).into(),
+        ];
+        request.extend(alloc_span(&mut vm, &[]));
+        let ptr = run_syscall(&mut InMemorySyscallHandler::new(), &request, &mut vm).unwrap();
+        let response = read_range(&vm, ptr, 4);
+        assert_eq!(
+            response[0],
+            Felt252::from(GAS - CALL_CONTRACT_GAS_COST).into()
+        );
+        assert_eq!(response[1], Felt252::ONE.into());
+        assert_eq!(
+            span(&vm, &response[2], &response[3]),
+            vec![Felt252::from_bytes_be_slice(b"CONTRACT_NOT_DEPLOYED")]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn reverted_call_discards_its_changes() {
+        let mut handler = InMemorySyscallHandler::new();
+        let (contract, key, new_key) = (Felt252::from(3), Felt252::from(7), Felt252::from(8));
+        handler.set_storage_at(contract, key, Felt252::ONE);
+
+        let outer = handler.state().begin_call();
+        handler.set_storage_at(contract, key, Felt252::TWO);
+        let inner = handler.state().begin_call();
+        handler.set_storage_at(contract, key, Felt252::from(3));
+        handler.set_storage_at(contract, key, Felt252::from(4));
+        handler.set_storage_at(contract, new_key, Felt252::from(5));
+        handler.emit_event(vec![], vec![]).unwrap();
+        handler.state().end_call(inner, true);
+
+        assert_eq!(handler.storage_at(contract, key), Felt252::TWO);
+        assert!(!handler.state().storage.contains_key(&(contract, new_key)));
+        assert!(handler.events().is_empty());
+
+        handler.state().end_call(outer, false);
+        assert_eq!(handler.storage_at(contract, key), Felt252::TWO);
+        assert!(handler.state().storage_journal.is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn unknown_syscall() {
+        let mut vm = vm!();
+        let request = [
+            selector("ReplaceClass"),
+            Felt252::from(GAS).into(),
+            Felt252::ONE.into(),
+        ];
+        assert_matches!(
+            run_syscall(&mut InMemorySyscallHandler::new(), &request, &mut vm),
+            Err(HintError::UnknownHint(selector)) if selector.as_ref() == "ReplaceClass"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hint_processor_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<InMemorySyscallHandler>();
+        assert_send::<Cairo1HintProcessor>();
+    }
+
+    #[rstest]
+    #[case::with_handler(true)]
+    #[case::without_handler(false)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn system_call_hint(#[case] with_handler: bool) {
+        let mut vm = vm!();
+        vm.segments = segments![((1, 0), (2, 0))];
+        vm.set_fp(0);
+        add_segments!(vm, 1);
+        vm.load_data(
+            Relocatable::from((2, 0)),
+            &[
+                selector("StorageRead"),
+                Felt252::from(GAS).into(),
+                Felt252::ZERO.into(),
+                Felt252::ONE.into(),
+            ],
+        )
+        .unwrap();
+        let mut hint_processor = Cairo1HintProcessor::new(&[], RunResources::default(), false);
+        if with_handler {
+            let mut handler = InMemorySyscallHandler::new();
+            handler.set_storage_at(Felt252::ZERO, Felt252::ONE, Felt252::from(5));
+            hint_processor = hint_processor.with_syscall_handler(handler);
+        }
+        let hint = Hint::Starknet(StarknetHint::SystemCall {
+            system: ResOperand::Deref(CellRef {
+                register: Register::FP,
+                offset: 0,
+            }),
+        });
+        let result = hint_processor.execute(&mut vm, &mut ExecutionScopes::new(), &hint);
+        if with_handler {
+            assert_matches!(result, Ok(()));
+            assert_eq!(
+                vm.get_integer(Relocatable::from((2, 6))).unwrap().as_ref(),
+                &Felt252::from(5)
+            );
+        } else {
+            assert_matches!(result, Err(HintError::CustomHint(_)));
+        }
+    }
+}
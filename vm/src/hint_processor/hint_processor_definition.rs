@@ -51,6 +51,13 @@ pub trait HintProcessorLogic {
         }))
     }
 
+    // Returns whether the hint which's data was created by compile_hint can be executed by this processor, without executing it
+    // Used to find unsupported hints before running a program, see `hint_processor::hint_support`.
+    // The default implementation assumes that every hint that compiles is supported
+    fn is_hint_supported(&self, _hint_data: &Box<dyn Any>) -> bool {
+        true
+    }
+
     #[cfg(feature = "extensive_hints")]
     // Executes the hint which's data is provided by a dynamic structure previously created by compile_hint
     // Also returns a map of hints to be loaded after the current hint is executed
@@ -77,7 +84,7 @@ pub type HintExtension = HashMap<Relocatable, Vec<Box<dyn Any>>>;
 pub trait HintProcessor: HintProcessorLogic + ResourceTracker {}
 impl<T> HintProcessor for T where T: HintProcessorLogic + ResourceTracker {}
 
-fn get_ids_data(
+pub(crate) fn get_ids_data(
     reference_ids: &HashMap<String, usize>,
     references: &[HintReference],
 ) -> Result<HashMap<String, HintReference>, VirtualMachineError> {
@@ -1,4 +1,4 @@
-use crate::stdlib::boxed::Box;
+use crate::stdlib::{borrow::Cow, boxed::Box, prelude::*};
 
 use crate::{
     serde::deserialize_program::{ApTracking, OffsetValue},
@@ -15,6 +15,45 @@ use crate::Felt252;
 
 use num_traits::ToPrimitive;
 
+/// Normalizes the formatting of a hint's code, so that hints that only differ in whitespace are
+/// matched as the same hint: trailing whitespace and blank leading and trailing lines are
+/// removed, and the lines are dedented by their common indentation.
+pub fn normalize_hint_code(code: &str) -> Cow<str> {
+    let lines: Vec<&str> = code.lines().map(str::trim_end).collect();
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+    let (Some(first), Some(last)) = (first, last) else {
+        return Cow::Borrowed("");
+    };
+    let lines = &lines[first..=last];
+    let indent = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or_default();
+    // Most hints are already normalized, in which case the code is borrowed as is
+    let unchanged = indent == 0
+        && first == 0
+        && lines.len() == last + 1
+        && !code.ends_with('\n')
+        && !code.contains('\r')
+        && lines
+            .iter()
+            .zip(code.lines())
+            .all(|(trimmed, line)| trimmed.len() == line.len());
+    if unchanged {
+        return Cow::Borrowed(code);
+    }
+    Cow::Owned(
+        lines
+            .iter()
+            .map(|line| line.get(indent..).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
 /// Inserts value into the address of the given ids variable
 pub fn insert_value_from_reference(
     value: impl Into<MaybeRelocatable>,
@@ -166,6 +205,7 @@ mod tests {
 
     use crate::{relocatable, utils::test_utils::*, vm::vm_memory::memory::Memory};
     use assert_matches::assert_matches;
+    use rstest::rstest;
 
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::*;
@@ -395,4 +435,25 @@ mod tests {
             Felt252::THREE
         );
     }
+
+    #[rstest]
+    #[case("memory[ap] = segments.add()", "memory[ap] = segments.add()")]
+    #[case("  memory[ap] = segments.add()  \n", "memory[ap] = segments.add()")]
+    #[case(
+        "\n    if x:\n        y = 1  \n    z = 2\n\n",
+        "if x:\n    y = 1\nz = 2"
+    )]
+    #[case("a = 1\r\n\r\nb = 2", "a = 1\n\nb = 2")]
+    #[case(" \n\t\n", "")]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn normalize_hint_code_cases(#[case] code: &str, #[case] expected: &str) {
+        assert_eq!(normalize_hint_code(code), expected);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn normalize_hint_code_borrows_normalized_code() {
+        assert_matches!(normalize_hint_code("a = 1\n    b = 2"), Cow::Borrowed(_));
+        assert_matches!(normalize_hint_code("a = 1\n"), Cow::Owned(_));
+    }
 }
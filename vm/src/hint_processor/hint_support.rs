@@ -0,0 +1,215 @@
+//! Static check of the hints of a program, which finds the hints a hint processor can't run
+//! before the execution reaches them.
+
+use crate::stdlib::{fmt, prelude::*};
+
+use crate::{
+    hint_processor::hint_processor_definition::HintProcessorLogic,
+    serde::deserialize_program::Location, types::program::Program,
+};
+
+/// A hint of a program that the hint processor can't execute
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedHint {
+    pub pc: usize,
+    pub code: String,
+    /// Location of the hint in the source code, if the program has debug info
+    pub location: Option<Location>,
+    /// Error returned by `compile_hint`, if the hint couldn't be compiled
+    pub compile_error: Option<String>,
+}
+
+impl fmt::Display for UnsupportedHint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self.compile_error {
+            Some(ref error) => format!("Hint at pc {} failed to compile: {error}", self.pc),
+            None => format!("Unsupported hint at pc {}", self.pc),
+        };
+        match self.location {
+            Some(ref location) => writeln!(f, "{}", location.to_string(&message))?,
+            None => writeln!(f, "{message}")?,
+        }
+        write!(f, "%{{\n{}\n%}}", self.code)
+    }
+}
+
+/// Compiles every hint of the program with the hint processor and returns those it can't
+/// execute, sorted by pc. Nothing is executed.
+pub fn check_hints(
+    program: &Program,
+    hint_processor: &dyn HintProcessorLogic,
+) -> Vec<UnsupportedHint> {
+    let shared_program_data = &program.shared_program_data;
+    let references = &shared_program_data.reference_manager;
+    let mut unsupported = Vec::new();
+    for (pc, hints) in program.iter_hints() {
+        for (index, hint) in hints.iter().enumerate() {
+            let compile_error = match hint_processor.compile_hint(
+                &hint.code,
+                &hint.flow_tracking_data.ap_tracking,
+                &hint.flow_tracking_data.reference_ids,
+                references,
+            ) {
+                Ok(hint_data) if hint_processor.is_hint_supported(&hint_data) => continue,
+                Ok(_) => None,
+                Err(error) => Some(error.to_string()),
+            };
+            // Hint locations are listed in the same order as the hints of the pc
+            let location = shared_program_data
+                .instruction_locations
+                .as_ref()
+                .and_then(|locations| locations.get(&pc))
+                .and_then(|location| location.hints.get(index))
+                .map(|hint_location| hint_location.location.clone());
+            unsupported.push(UnsupportedHint {
+                pc,
+                code: hint.code.clone(),
+                location,
+                compile_error,
+            });
+        }
+    }
+    // Hints are stored in a map with the extensive_hints feature
+    unsupported.sort_by_key(|hint| hint.pc);
+    unsupported
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::{
+            BuiltinHintProcessor, HintFunc,
+        },
+        serde::deserialize_program::{
+            ApTracking, FlowTrackingData, HintLocation, HintParams, InputFile, InstructionLocation,
+        },
+        stdlib::{
+            collections::{BTreeMap, HashMap},
+            rc::Rc,
+        },
+        utils::test_utils::*,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn hint(code: &str) -> HintParams {
+        HintParams {
+            code: code.to_string(),
+            accessible_scopes: vec![],
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking::default(),
+                reference_ids: HashMap::new(),
+            },
+        }
+    }
+
+    fn location(line: u32) -> Location {
+        Location {
+            end_line: line,
+            end_col: 3,
+            input_file: InputFile {
+                filename: "main.cairo".to_string(),
+            },
+            parent_location: None,
+            start_line: line,
+            start_col: 5,
+        }
+    }
+
+    fn program_with_hints() -> Program {
+        program!(
+            data = vec_data!((1), (2), (3)),
+            hints = BTreeMap::from([
+                (0, vec![hint("memory[ap] = segments.add()"), hint("x = 1")]),
+                (2, vec![hint("y = 2")]),
+            ]),
+            instruction_locations = Some(HashMap::from([(
+                0,
+                InstructionLocation {
+                    inst: location(1),
+                    hints: vec![
+                        HintLocation {
+                            location: location(2),
+                            n_prefix_newlines: 1,
+                        },
+                        HintLocation {
+                            location: location(5),
+                            n_prefix_newlines: 1,
+                        },
+                    ],
+                },
+            )])),
+        )
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_hints_reports_unknown_hints() {
+        let unsupported = check_hints(&program_with_hints(), &BuiltinHintProcessor::new_empty());
+        assert_eq!(
+            unsupported,
+            vec![
+                UnsupportedHint {
+                    pc: 0,
+                    code: "x = 1".to_string(),
+                    location: Some(location(5)),
+                    compile_error: None,
+                },
+                UnsupportedHint {
+                    pc: 2,
+                    code: "y = 2".to_string(),
+                    location: None,
+                    compile_error: None,
+                },
+            ]
+        );
+        assert_eq!(
+            unsupported[0].to_string(),
+            "main.cairo:5:5: Unsupported hint at pc 0\n%{\nx = 1\n%}"
+        );
+        assert_eq!(
+            unsupported[1].to_string(),
+            "Unsupported hint at pc 2\n%{\ny = 2\n%}"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_hints_with_extra_hints() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        for code in ["x = 1", "y = 2"] {
+            hint_processor.add_hint(
+                code.to_string(),
+                Rc::new(HintFunc(Box::new(|_, _, _, _, _| Ok(())))),
+            );
+        }
+        assert_eq!(check_hints(&program_with_hints(), &hint_processor), vec![]);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_hints_reports_compile_errors() {
+        let program = program!(
+            data = vec_data!((1)),
+            hints = BTreeMap::from([(
+                0,
+                vec![HintParams {
+                    flow_tracking_data: FlowTrackingData {
+                        ap_tracking: ApTracking::default(),
+                        // There is no reference 3
+                        reference_ids: HashMap::from([("__main__.x".to_string(), 3)]),
+                    },
+                    ..hint("memory[ap] = segments.add()")
+                }],
+            )]),
+        );
+        let unsupported = check_hints(&program, &BuiltinHintProcessor::new_empty());
+        assert_eq!(unsupported.len(), 1);
+        assert!(unsupported[0].compile_error.is_some());
+        assert!(unsupported[0]
+            .to_string()
+            .starts_with("Hint at pc 0 failed to compile: "));
+    }
+}
@@ -3,11 +3,13 @@
 //! ## Feature Flags
 //! - `std`: Enables usage of the [`std`] standard library. Enabled by default.
 //! - `test_utils`: Enables the following to help with tests (not enabled by default):
-//!    - [`Hooks`](crate::vm::hooks::Hooks) support for the [VirtualMachine](vm::vm_core::VirtualMachine);
+//!    - the `hooks` feature;
 //!    - the `print_*` family of hints;
 //!    - the `skip_next_instruction()` hints;
 //!    - implementations of [`arbitrary::Arbitrary`](https://docs.rs/arbitrary/latest/arbitrary/) for some structs.
+//! - `hooks`: Enables [`Hooks`](crate::vm::hooks::Hooks) support for the [VirtualMachine](vm::vm_core::VirtualMachine), to run custom code at different stages of the execution. Not enabled by default.
 //! - `cairo-1-hints`: Enable hints that were introduced in Cairo 1. Not enabled by default.
+//! - `profiler`: Enables the [`Profiler`](crate::vm::profiler::Profiler), which attributes executed steps and builtin instances to Cairo functions. Not enabled by default.
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![deny(warnings)]
@@ -54,7 +56,9 @@ pub mod stdlib {
 
 pub mod air_private_input;
 pub mod air_public_input;
+pub mod air_public_input_validator;
 pub mod cairo_run;
+pub mod coverage;
 pub mod hint_processor;
 pub mod math_utils;
 pub mod program_hash;
@@ -13,7 +13,7 @@ use crate::{
         prelude::*,
         sync::Arc,
     },
-    types::builtin_name::BuiltinName,
+    types::builtin_name::{BuiltinName, CustomBuiltinName},
     utils::CAIRO_PRIME,
 };
 
@@ -431,6 +431,36 @@ pub fn deserialize_and_parse_program(
     parse_program_json(program_json, entrypoint)
 }
 
+/// Deserializes and parses a program whose builtins may include the given builtins implemented
+/// outside of the vm, see [`CustomBuiltin`](crate::vm::runners::builtin_runner::CustomBuiltin).
+/// Other builtin names unknown to the vm fail to parse.
+pub fn deserialize_and_parse_program_with_custom_builtins(
+    reader: &[u8],
+    entrypoint: Option<&str>,
+    custom_builtins: &[CustomBuiltinName],
+) -> Result<Program, ProgramError> {
+    if custom_builtins.is_empty() {
+        return deserialize_and_parse_program(reader, entrypoint);
+    }
+    // Custom builtins don't deserialize as builtin names, so they are resolved apart from the
+    // rest of the program
+    let mut program_json: serde_json::Value = serde_json::from_slice(reader)?;
+    let builtins = program_json
+        .get_mut("builtins")
+        .map(|builtins| core::mem::replace(builtins, serde_json::Value::Array(Vec::new())))
+        .unwrap_or_default();
+    let builtins = serde_json::from_value::<Vec<String>>(builtins)?
+        .into_iter()
+        .map(|name| {
+            BuiltinName::from_str_or_custom(&name, custom_builtins.iter().copied())
+                .ok_or_else(|| ProgramError::UnknownBuiltin(name.into()))
+        })
+        .collect::<Result<_, _>>()?;
+    let mut program_json: ProgramJson = serde_json::from_value(program_json)?;
+    program_json.builtins = builtins;
+    parse_program_json(program_json, entrypoint)
+}
+
 pub fn parse_program_json(
     program_json: ProgramJson,
     entrypoint: Option<&str>,
@@ -0,0 +1,745 @@
+//! Binary cache format for [Program], which loads much faster than the compiled JSON as there is
+//! nothing left to parse: references are stored as [HintReference]s, hints are grouped by pc and
+//! the program's bytecode is a table of fixed-size words that can be read in place.
+//!
+//! A cache is laid out as follows, with integers in little-endian:
+//!
+//! | Field        | Size            | Contents                                                    |
+//! |--------------|-----------------|-------------------------------------------------------------|
+//! | magic        | 8               | `CAIROPRG`                                                  |
+//! | version      | 4               | [PROGRAM_CACHE_VERSION]                                     |
+//! | content hash | 32              | SHA-256 of everything after this field                      |
+//! | source hash  | 32              | SHA-256 of the compiled JSON the program was loaded from     |
+//! | data length  | 8               | Number of words in the program's bytecode                   |
+//! | data         | 33 * length     | A tag (0: felt, 1: relocatable) followed by 32 bytes        |
+//! | metadata     | rest            | Builtins, entrypoints, hints, references, identifiers, etc. |
+//!
+//! Loading a cache fails if it was written by another version of the format or if its contents
+//! don't match its hash, so that corrupted caches aren't used. Stale caches are detected by
+//! comparing the source hash with the hash of the current compiled JSON, see
+//! [Program::from_cache_bytes_checked].
+
+use crate::stdlib::{
+    collections::{BTreeMap, HashMap},
+    prelude::*,
+    sync::Arc,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    hint_processor::hint_processor_definition::HintReference,
+    serde::deserialize_program::{
+        ApTracking, Attribute, FlowTrackingData, HintLocation, HintParams, Identifier, InputFile,
+        InstructionLocation, Location, Member, OffsetValue,
+    },
+    types::{
+        builtin_name::BuiltinName,
+        errors::program_errors::ProgramError,
+        instruction::Register,
+        program::{HintsCollection, Program, SharedProgramData},
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    Felt252,
+};
+
+/// Version of the cache format, bumped whenever the layout changes
+pub const PROGRAM_CACHE_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 8] = b"CAIROPRG";
+// Offset of the bytes covered by the content hash
+const CONTENT_OFFSET: usize = 8 + 4 + 32;
+const WORD_LEN: usize = 33;
+
+/// A view over the bytes of a program cache, checked against its content hash.
+///
+/// The program's bytecode is read straight from the cache, without copying it.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgramCache<'a> {
+    content_hash: &'a [u8; 32],
+    source_hash: &'a [u8; 32],
+    data: &'a [u8],
+    metadata: &'a [u8],
+}
+
+impl<'a> ProgramCache<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, ProgramError> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ProgramError::InvalidCache("missing magic bytes".into()));
+        }
+        let version = u32::from_le_bytes(reader.array()?);
+        if version != PROGRAM_CACHE_VERSION {
+            return Err(ProgramError::CacheVersionMismatch(
+                version,
+                PROGRAM_CACHE_VERSION,
+            ));
+        }
+        let content_hash = reader.hash()?;
+        if Sha256::digest(reader.bytes).as_slice() != content_hash {
+            return Err(ProgramError::CacheHashMismatch);
+        }
+        let source_hash = reader.hash()?;
+        let data_len = usize::read(&mut reader)?;
+        let data = reader.take(
+            data_len
+                .checked_mul(WORD_LEN)
+                .ok_or_else(|| ProgramError::InvalidCache("data length overflows".into()))?,
+        )?;
+        Ok(ProgramCache {
+            content_hash,
+            source_hash,
+            data,
+            metadata: reader.bytes,
+        })
+    }
+
+    /// SHA-256 hash of the cached program
+    pub fn content_hash(&self) -> &'a [u8; 32] {
+        self.content_hash
+    }
+
+    /// SHA-256 hash of the compiled JSON the cache was built from
+    pub fn source_hash(&self) -> &'a [u8; 32] {
+        self.source_hash
+    }
+
+    /// Fails if the cache wasn't built from the given compiled JSON
+    pub fn check_source(&self, source: &[u8]) -> Result<(), ProgramError> {
+        if Sha256::digest(source).as_slice() != self.source_hash {
+            return Err(ProgramError::StaleCache);
+        }
+        Ok(())
+    }
+
+    pub fn data_len(&self) -> usize {
+        self.data.len() / WORD_LEN
+    }
+
+    pub fn get_data(&self, index: usize) -> Option<MaybeRelocatable> {
+        let start = index.checked_mul(WORD_LEN)?;
+        let word = self.data.get(start..start.checked_add(WORD_LEN)?)?;
+        decode_word(word).ok()
+    }
+
+    pub fn iter_data(&self) -> impl Iterator<Item = Result<MaybeRelocatable, ProgramError>> + 'a {
+        self.data.chunks_exact(WORD_LEN).map(decode_word)
+    }
+
+    pub fn to_program(&self) -> Result<Program, ProgramError> {
+        let data = self.iter_data().collect::<Result<Vec<_>, _>>()?;
+        let mut reader = Reader {
+            bytes: self.metadata,
+        };
+        let builtins = Vec::<BuiltinName>::read(&mut reader)?;
+        let main = Option::<usize>::read(&mut reader)?;
+        let start = Option::<usize>::read(&mut reader)?;
+        let end = Option::<usize>::read(&mut reader)?;
+        let hints = BTreeMap::<usize, Vec<HintParams>>::read(&mut reader)?;
+        let reference_manager = Vec::<HintReference>::read(&mut reader)?;
+        let identifiers = HashMap::<String, Identifier>::read(&mut reader)?;
+        let constants = HashMap::<String, Felt252>::read(&mut reader)?;
+        let error_message_attributes = Vec::<Attribute>::read(&mut reader)?;
+        let instruction_locations =
+            Option::<HashMap<usize, InstructionLocation>>::read(&mut reader)?;
+        if !reader.bytes.is_empty() {
+            return Err(ProgramError::InvalidCache("trailing bytes".into()));
+        }
+
+        let hints_collection = HintsCollection::new(&hints, data.len())?;
+        Ok(Program {
+            shared_program_data: Arc::new(SharedProgramData {
+                data,
+                hints_collection,
+                main,
+                start,
+                end,
+                error_message_attributes,
+                instruction_locations,
+                identifiers,
+                reference_manager,
+            }),
+            constants,
+            builtins,
+        })
+    }
+}
+
+/// Serializes a program in the cache format, see [ProgramCache]. `source` is the compiled JSON the
+/// program was loaded from.
+pub fn to_cache_bytes(program: &Program, source: &[u8]) -> Vec<u8> {
+    let shared = &program.shared_program_data;
+    let mut content = Vec::new();
+    content.extend_from_slice(&Sha256::digest(source));
+    shared.data.len().write(&mut content);
+    for word in shared.data.iter() {
+        encode_word(word, &mut content);
+    }
+    program.builtins.write(&mut content);
+    shared.main.write(&mut content);
+    shared.start.write(&mut content);
+    shared.end.write(&mut content);
+    BTreeMap::<usize, Vec<HintParams>>::from(&shared.hints_collection).write(&mut content);
+    shared.reference_manager.write(&mut content);
+    shared.identifiers.write(&mut content);
+    program.constants.write(&mut content);
+    shared.error_message_attributes.write(&mut content);
+    shared.instruction_locations.write(&mut content);
+
+    let mut bytes = Vec::with_capacity(CONTENT_OFFSET + content.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&PROGRAM_CACHE_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&Sha256::digest(&content));
+    bytes.extend_from_slice(&content);
+    bytes
+}
+
+fn encode_word(word: &MaybeRelocatable, bytes: &mut Vec<u8>) {
+    match word {
+        MaybeRelocatable::Int(felt) => {
+            bytes.push(0);
+            bytes.extend_from_slice(&felt.to_bytes_be());
+        }
+        MaybeRelocatable::RelocatableValue(relocatable) => {
+            bytes.push(1);
+            bytes.extend_from_slice(&(relocatable.segment_index as i64).to_le_bytes());
+            bytes.extend_from_slice(&(relocatable.offset as u64).to_le_bytes());
+            bytes.extend_from_slice(&[0; 16]);
+        }
+    }
+}
+
+fn decode_word(word: &[u8]) -> Result<MaybeRelocatable, ProgramError> {
+    let mut reader = Reader { bytes: &word[1..] };
+    match word[0] {
+        0 => Ok(Felt252::read(&mut reader)?.into()),
+        1 => {
+            let segment_index = i64::from_le_bytes(reader.array()?);
+            let offset = u64::from_le_bytes(reader.array()?);
+            Ok(Relocatable::from((segment_index as isize, offset as usize)).into())
+        }
+        tag => Err(ProgramError::InvalidCache(
+            format!("invalid data tag {tag}").into(),
+        )),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ProgramError> {
+        if self.bytes.len() < len {
+            return Err(ProgramError::InvalidCache("unexpected end of cache".into()));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ProgramError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn hash(&mut self) -> Result<&'a [u8; 32], ProgramError> {
+        self.take(32)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidCache("truncated header".into()))
+    }
+}
+
+// Binary encoding of the types stored in the metadata section
+trait CacheEncoding: Sized {
+    fn write(&self, bytes: &mut Vec<u8>);
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError>;
+}
+
+impl CacheEncoding for usize {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&(*self as u64).to_le_bytes());
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        usize::try_from(u64::from_le_bytes(reader.array()?))
+            .map_err(|_| ProgramError::InvalidCache("length doesn't fit in usize".into()))
+    }
+}
+
+impl CacheEncoding for u32 {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        Ok(u32::from_le_bytes(reader.array()?))
+    }
+}
+
+impl CacheEncoding for i32 {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        Ok(i32::from_le_bytes(reader.array()?))
+    }
+}
+
+impl CacheEncoding for bool {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.push(*self as u8);
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        match reader.take(1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ProgramError::InvalidCache("invalid bool".into())),
+        }
+    }
+}
+
+impl CacheEncoding for Felt252 {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_bytes_be());
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        Ok(Felt252::from_bytes_be(&reader.array()?))
+    }
+}
+
+impl CacheEncoding for String {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        self.len().write(bytes);
+        bytes.extend_from_slice(self.as_bytes());
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        let len = usize::read(reader)?;
+        String::from_utf8(reader.take(len)?.to_vec())
+            .map_err(|_| ProgramError::InvalidCache("invalid utf-8 string".into()))
+    }
+}
+
+impl<T: CacheEncoding> CacheEncoding for Option<T> {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        self.is_some().write(bytes);
+        if let Some(value) = self {
+            value.write(bytes);
+        }
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        Ok(match bool::read(reader)? {
+            true => Some(T::read(reader)?),
+            false => None,
+        })
+    }
+}
+
+impl<T: CacheEncoding> CacheEncoding for Box<T> {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        self.as_ref().write(bytes)
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        T::read(reader).map(Box::new)
+    }
+}
+
+impl<A: CacheEncoding, B: CacheEncoding> CacheEncoding for (A, B) {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        self.0.write(bytes);
+        self.1.write(bytes);
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        Ok((A::read(reader)?, B::read(reader)?))
+    }
+}
+
+impl<T: CacheEncoding> CacheEncoding for Vec<T> {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        self.len().write(bytes);
+        self.iter().for_each(|item| item.write(bytes));
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        let len = usize::read(reader)?;
+        // Don't trust the length to preallocate, a corrupted one would exhaust the memory
+        (0..len).map(|_| T::read(reader)).collect()
+    }
+}
+
+impl<K: CacheEncoding + Ord, V: CacheEncoding> CacheEncoding for BTreeMap<K, V> {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        self.len().write(bytes);
+        for (key, value) in self.iter() {
+            key.write(bytes);
+            value.write(bytes);
+        }
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        let len = usize::read(reader)?;
+        (0..len).map(|_| <(K, V)>::read(reader)).collect()
+    }
+}
+
+impl<K, V> CacheEncoding for HashMap<K, V>
+where
+    K: CacheEncoding + Ord + core::hash::Hash,
+    V: CacheEncoding,
+{
+    // Entries are sorted so that the same program always has the same content hash
+    fn write(&self, bytes: &mut Vec<u8>) {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        entries.len().write(bytes);
+        for (key, value) in entries {
+            key.write(bytes);
+            value.write(bytes);
+        }
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        let len = usize::read(reader)?;
+        (0..len).map(|_| <(K, V)>::read(reader)).collect()
+    }
+}
+
+impl CacheEncoding for BuiltinName {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        self.to_str().to_string().write(bytes)
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        let name = String::read(reader)?;
+        BuiltinName::from_str(&name)
+            .ok_or_else(|| ProgramError::InvalidCache(format!("unknown builtin {name}").into()))
+    }
+}
+
+impl CacheEncoding for Register {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        (*self == Register::FP).write(bytes)
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        Ok(match bool::read(reader)? {
+            true => Register::FP,
+            false => Register::AP,
+        })
+    }
+}
+
+impl CacheEncoding for OffsetValue {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        match self {
+            OffsetValue::Immediate(value) => {
+                bytes.push(0);
+                value.write(bytes);
+            }
+            OffsetValue::Value(value) => {
+                bytes.push(1);
+                value.write(bytes);
+            }
+            OffsetValue::Reference(register, offset, dereference) => {
+                bytes.push(2);
+                register.write(bytes);
+                offset.write(bytes);
+                dereference.write(bytes);
+            }
+        }
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+        match reader.take(1)?[0] {
+            0 => Ok(OffsetValue::Immediate(Felt252::read(reader)?)),
+            1 => Ok(OffsetValue::Value(i32::read(reader)?)),
+            2 => Ok(OffsetValue::Reference(
+                Register::read(reader)?,
+                i32::read(reader)?,
+                bool::read(reader)?,
+            )),
+            _ => Err(ProgramError::InvalidCache("invalid offset value".into())),
+        }
+    }
+}
+
+// Implements CacheEncoding for a struct by encoding its fields in order
+macro_rules! impl_cache_encoding {
+    ($type:ident { $($field:ident: $field_type:ty),* $(,)? }) => {
+        impl CacheEncoding for $type {
+            fn write(&self, bytes: &mut Vec<u8>) {
+                $(self.$field.write(bytes);)*
+            }
+            fn read(reader: &mut Reader) -> Result<Self, ProgramError> {
+                Ok($type {
+                    $($field: <$field_type>::read(reader)?,)*
+                })
+            }
+        }
+    };
+}
+
+impl_cache_encoding!(ApTracking {
+    group: usize,
+    offset: usize
+});
+impl_cache_encoding!(FlowTrackingData {
+    ap_tracking: ApTracking,
+    reference_ids: HashMap<String, usize>,
+});
+impl_cache_encoding!(HintParams {
+    code: String,
+    accessible_scopes: Vec<String>,
+    flow_tracking_data: FlowTrackingData,
+});
+impl_cache_encoding!(HintReference {
+    offset1: OffsetValue,
+    offset2: OffsetValue,
+    inner_dereference: bool,
+    outer_dereference: bool,
+    ap_tracking_data: Option<ApTracking>,
+    cairo_type: Option<String>,
+});
+impl_cache_encoding!(Member {
+    cairo_type: String,
+    offset: usize
+});
+impl_cache_encoding!(Identifier {
+    pc: Option<usize>,
+    type_: Option<String>,
+    value: Option<Felt252>,
+    full_name: Option<String>,
+    members: Option<HashMap<String, Member>>,
+    cairo_type: Option<String>,
+});
+impl_cache_encoding!(Attribute {
+    name: String,
+    start_pc: usize,
+    end_pc: usize,
+    value: String,
+    flow_tracking_data: Option<FlowTrackingData>,
+});
+impl_cache_encoding!(InputFile { filename: String });
+impl_cache_encoding!(Location {
+    end_line: u32,
+    end_col: u32,
+    input_file: InputFile,
+    parent_location: Option<(Box<Location>, String)>,
+    start_line: u32,
+    start_col: u32,
+});
+impl_cache_encoding!(HintLocation {
+    location: Location,
+    n_prefix_newlines: u32,
+});
+impl_cache_encoding!(InstructionLocation {
+    inst: Location,
+    hints: Vec<HintLocation>,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        serde::deserialize_program::ReferenceManager, types::relocatable::Relocatable,
+        utils::test_utils::*,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    const VALID_PROGRAM_B: &[u8] =
+        include_bytes!("../../../cairo_programs/manually_compiled/valid_program_b.json");
+
+    fn valid_program_b() -> Program {
+        Program::from_bytes(VALID_PROGRAM_B, Some("main")).unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cache_round_trip() {
+        let program = valid_program_b();
+        let bytes = program.to_cache_bytes(VALID_PROGRAM_B);
+        assert_eq!(Program::from_cache_bytes(&bytes).unwrap(), program);
+        assert_eq!(
+            Program::from_cache_bytes_checked(&bytes, VALID_PROGRAM_B).unwrap(),
+            program
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn reject_stale_cache() {
+        let bytes = valid_program_b().to_cache_bytes(VALID_PROGRAM_B);
+        let updated_source =
+            include_bytes!("../../../cairo_programs/manually_compiled/valid_program_a.json");
+        assert_matches!(
+            Program::from_cache_bytes_checked(&bytes, updated_source),
+            Err(ProgramError::StaleCache)
+        );
+        assert_eq!(
+            ProgramCache::new(&bytes).unwrap().source_hash().as_slice(),
+            Sha256::digest(VALID_PROGRAM_B).as_slice()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cache_round_trip_with_hints_references_and_locations() {
+        let location = Location {
+            end_line: 2,
+            end_col: 13,
+            input_file: InputFile {
+                filename: "main.cairo".to_string(),
+            },
+            parent_location: Some((
+                Box::new(Location {
+                    end_line: 1,
+                    end_col: 3,
+                    input_file: InputFile {
+                        filename: "lib.cairo".to_string(),
+                    },
+                    parent_location: None,
+                    start_line: 1,
+                    start_col: 1,
+                }),
+                "While expanding the reference".to_string(),
+            )),
+            start_line: 2,
+            start_col: 5,
+        };
+        let hint = HintParams {
+            code: "memory[ap] = segments.add()".to_string(),
+            accessible_scopes: vec!["__main__".to_string(), "__main__.main".to_string()],
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking {
+                    group: 1,
+                    offset: 2,
+                },
+                reference_ids: HashMap::from([("__main__.main.x".to_string(), 0)]),
+            },
+        };
+        let reference_manager: ReferenceManager = serde_json::from_str(
+            r#"{"references": [{"ap_tracking_data": {"group": 1, "offset": 2}, "pc": 0, "value": "[cast(ap + (-1), felt*)]"}]}"#,
+        )
+        .unwrap();
+        let identifiers = HashMap::from([
+            (
+                "__main__.SIZE".to_string(),
+                Identifier {
+                    pc: None,
+                    type_: Some("const".to_string()),
+                    value: Some(Felt252::from(-3)),
+                    full_name: None,
+                    members: None,
+                    cairo_type: None,
+                },
+            ),
+            (
+                "__main__.Point".to_string(),
+                Identifier {
+                    pc: None,
+                    type_: Some("struct".to_string()),
+                    value: None,
+                    full_name: Some("__main__.Point".to_string()),
+                    members: Some(HashMap::from([(
+                        "x".to_string(),
+                        Member {
+                            cairo_type: "felt".to_string(),
+                            offset: 0,
+                        },
+                    )])),
+                    cairo_type: None,
+                },
+            ),
+        ]);
+        let program = Program::new(
+            vec![BuiltinName::output, BuiltinName::pedersen],
+            vec_data!(
+                (0x480680017fff8000_u64),
+                (5),
+                ((1, 2)),
+                (0x208b7fff7fff7ffe_u64)
+            ),
+            Some(0),
+            HashMap::from([(0, vec![hint.clone()]), (3, vec![hint.clone(), hint])]),
+            reference_manager,
+            identifiers,
+            vec![Attribute {
+                name: "error_message".to_string(),
+                start_pc: 0,
+                end_pc: 3,
+                value: "Oops".to_string(),
+                flow_tracking_data: None,
+            }],
+            Some(HashMap::from([(
+                0,
+                InstructionLocation {
+                    inst: location.clone(),
+                    hints: vec![HintLocation {
+                        location,
+                        n_prefix_newlines: 1,
+                    }],
+                },
+            )])),
+        )
+        .unwrap();
+
+        let bytes = program.to_cache_bytes(b"");
+        assert_eq!(Program::from_cache_bytes(&bytes).unwrap(), program);
+        // Maps are written in order, so that the hash doesn't depend on the iteration order
+        assert_eq!(program.clone().to_cache_bytes(b""), bytes);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_data_in_place() {
+        let program = program!(data = vec_data!((3), ((2, 5)), (7)),);
+        let bytes = program.to_cache_bytes(b"");
+        let cache = ProgramCache::new(&bytes).unwrap();
+        assert_eq!(cache.data_len(), 3);
+        assert_eq!(cache.get_data(1), Some(Relocatable::from((2, 5)).into()));
+        assert_eq!(cache.get_data(3), None);
+        assert_eq!(cache.get_data(usize::MAX / WORD_LEN + 1), None);
+        assert_eq!(cache.get_data(usize::MAX), None);
+        assert_eq!(
+            cache.iter_data().collect::<Result<Vec<_>, _>>().unwrap(),
+            program.shared_program_data.data
+        );
+        assert_eq!(
+            cache.content_hash().as_slice(),
+            Sha256::digest(&bytes[CONTENT_OFFSET..]).as_slice()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn reject_modified_cache() {
+        let mut bytes = valid_program_b().to_cache_bytes(VALID_PROGRAM_B);
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert_matches!(
+            Program::from_cache_bytes(&bytes),
+            Err(ProgramError::CacheHashMismatch)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn reject_other_version() {
+        let mut bytes = valid_program_b().to_cache_bytes(VALID_PROGRAM_B);
+        bytes[8..12].copy_from_slice(&(PROGRAM_CACHE_VERSION + 1).to_le_bytes());
+        assert_matches!(
+            Program::from_cache_bytes(&bytes),
+            Err(ProgramError::CacheVersionMismatch(found, expected))
+                if found == PROGRAM_CACHE_VERSION + 1 && expected == PROGRAM_CACHE_VERSION
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn reject_invalid_cache() {
+        let bytes = valid_program_b().to_cache_bytes(VALID_PROGRAM_B);
+        assert_matches!(
+            Program::from_cache_bytes(&bytes[..20]),
+            Err(ProgramError::InvalidCache(_))
+        );
+        assert_matches!(
+            Program::from_cache_bytes(br#"{"prime": "0x800000000000011000000000000000000000000000000000000000000000001"}"#),
+            Err(ProgramError::InvalidCache(_))
+        );
+    }
+}
@@ -658,3 +658,32 @@ fn fibonacci_with_run_resources_error() {
     .is_err());
     assert_eq!(hint_processor.run_resources(), &RunResources::new(0));
 }
+
+#[test]
+#[cfg(feature = "std")]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn fibonacci_call_contract_with_in_memory_syscall_handler() {
+    use crate::hint_processor::cairo_1_hint_processor::syscall_handler::{
+        InMemorySyscallHandler, SyscallHandler,
+    };
+
+    let program_data = include_bytes!("../../../cairo_programs/cairo-1-contracts/fib.casm");
+    let contract_class: CasmContractClass = serde_json::from_slice(program_data).unwrap();
+    let selector = Felt252::from(&contract_class.entry_points_by_type.external[0].selector);
+
+    let mut handler = InMemorySyscallHandler::new();
+    handler.declare_class(Felt252::ONE, contract_class);
+    handler.deploy_contract(Felt252::TWO, Felt252::ONE);
+
+    let mut remaining_gas = u64::MAX;
+    assert_matches!(
+        handler.call_contract(
+            Felt252::TWO,
+            selector,
+            vec![Felt252::ONE, Felt252::ONE, Felt252::from(20)],
+            &mut remaining_gas,
+        ),
+        Ok(retdata) if retdata == [Felt252::from(10946)]
+    );
+    assert!(remaining_gas < u64::MAX);
+}
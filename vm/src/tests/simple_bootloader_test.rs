@@ -0,0 +1,131 @@
+use crate::{
+    cairo_run::{cairo_run, cairo_run_program_with_initial_scope, CairoRunConfig},
+    hint_processor::builtin_hint_processor::{
+        bootloader::{simple_bootloader_initial_scope, SimpleBootloaderInput, Task},
+        builtin_hint_processor_definition::BuiltinHintProcessor,
+    },
+    hint_processor::hint_support::check_hints,
+    program_hash::compute_program_hash_chain,
+    stdlib::{collections::HashMap, prelude::*},
+    types::{
+        builtin_name::BuiltinName, layout_name::LayoutName, program::Program,
+        relocatable::MaybeRelocatable,
+    },
+    vm::runners::cairo_pie::{CairoPie, PublicMemoryPage},
+    Felt252,
+};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+fn run_pie(program_content: &[u8]) -> CairoPie {
+    let runner = cairo_run(
+        program_content,
+        &CairoRunConfig {
+            layout: LayoutName::all_cairo,
+            ..Default::default()
+        },
+        &mut BuiltinHintProcessor::new_empty(),
+    )
+    .unwrap();
+    runner.get_cairo_pie().unwrap()
+}
+
+// Returns the output of the pie's program
+fn pie_output(pie: &CairoPie) -> Vec<MaybeRelocatable> {
+    let output_segment = pie.metadata.builtin_segments[&BuiltinName::output].index as usize;
+    let mut output: Vec<_> = pie
+        .memory
+        .0
+        .iter()
+        .filter(|((segment_index, _), _)| *segment_index == output_segment)
+        .collect();
+    output.sort_by_key(|((_, offset), _)| *offset);
+    output.into_iter().map(|(_, value)| value.clone()).collect()
+}
+
+// Compiled by `make cairo_test_programs` from the sources of the cairo-lang version pinned in
+// requirements.txt
+fn simple_bootloader() -> Program {
+    Program::from_bytes(
+        include_bytes!("../../../cairo_programs/bootloader/simple_bootloader.json"),
+        Some("main"),
+    )
+    .expect("Could not load the simple bootloader. Run `make cairo_test_programs` in the root directory.")
+}
+
+// Fails if the bootloader was compiled from a cairo-lang version whose hints differ from the
+// implemented ones
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn simple_bootloader_hints_are_supported() {
+    let unsupported_hints = check_hints(&simple_bootloader(), &BuiltinHintProcessor::new_empty());
+    assert!(
+        unsupported_hints.is_empty(),
+        "{}",
+        unsupported_hints
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn simple_bootloader_runs_two_pies() {
+    let tasks = vec![
+        run_pie(include_bytes!("../../../cairo_programs/pedersen_test.json")),
+        run_pie(include_bytes!(
+            "../../../cairo_programs/bitwise_output.json"
+        )),
+    ];
+
+    let program = simple_bootloader();
+    let exec_scopes = simple_bootloader_initial_scope(
+        &program,
+        SimpleBootloaderInput {
+            tasks: tasks.iter().cloned().map(Task::from).collect(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let mut runner = cairo_run_program_with_initial_scope(
+        &program,
+        &CairoRunConfig {
+            layout: LayoutName::all_cairo,
+            ..Default::default()
+        },
+        &mut BuiltinHintProcessor::new_empty(),
+        exec_scopes,
+    )
+    .unwrap();
+
+    // The output holds the number of tasks, then the output size, program hash and output of
+    // each task, with a page for each task's output
+    let mut expected_output = vec![MaybeRelocatable::from(tasks.len())];
+    let mut expected_pages = HashMap::new();
+    for (page_id, task) in (1..).zip(tasks.iter()) {
+        let task_output = pie_output(task);
+        let program_hash = compute_program_hash_chain(&task.metadata.program, 0).unwrap();
+        expected_output.push(MaybeRelocatable::from(task_output.len() + 2));
+        expected_output.push(Felt252::from_bytes_be(&program_hash.to_bytes_be()).into());
+        expected_pages.insert(
+            page_id,
+            PublicMemoryPage {
+                start: expected_output.len(),
+                size: task_output.len(),
+            },
+        );
+        expected_output.extend(task_output);
+    }
+
+    let output_builtin = runner.vm.get_output_builtin_mut().unwrap();
+    assert_eq!(output_builtin.get_state().pages, expected_pages);
+    let output_base = output_builtin.base() as isize;
+    let output = runner
+        .vm
+        .get_continuous_range((output_base, 0).into(), expected_output.len())
+        .unwrap();
+    assert_eq!(output, expected_output);
+}
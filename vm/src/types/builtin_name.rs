@@ -1,4 +1,5 @@
-use serde::{Deserialize, Serialize};
+use crate::stdlib::prelude::*;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 
 #[cfg(feature = "test_utils")]
 use arbitrary::{self, Arbitrary};
@@ -30,9 +31,11 @@ const SEGMENT_ARENA_BUILTIN_NAME_WITH_SUFFIX: &str = "segment_arena_builtin";
 const ADD_MOD_BUILTIN_NAME_WITH_SUFFIX: &str = "add_mod_builtin";
 const MUL_MOD_BUILTIN_NAME_WITH_SUFFIX: &str = "mul_mod_builtin";
 
+const BUILTIN_NAME_SUFFIX: &str = "_builtin";
+
 /// Enum representing the name of a cairo builtin
 #[cfg_attr(feature = "test_utils", derive(Arbitrary))]
-#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash)]
 #[allow(non_camel_case_types)]
 pub enum BuiltinName {
     output,
@@ -47,6 +50,62 @@ pub enum BuiltinName {
     range_check96,
     add_mod,
     mul_mod,
+    /// Builtin implemented outside of the vm, see [`CustomBuiltin`](crate::vm::runners::builtin_runner::CustomBuiltin)
+    custom(
+        #[cfg_attr(feature = "test_utils", arbitrary(value = CustomBuiltinName::new("custom_builtin")))]
+         CustomBuiltinName,
+    ),
+}
+
+/// Name of a builtin implemented outside of the vm
+///
+/// Holds the name with the "_builtin" suffix, such as `blake2s_builtin`, so that both of its
+/// representations can be borrowed for as long as [`BuiltinName`] needs them. Programs declare the
+/// builtin by the name without the suffix.
+///
+/// ## Example
+///
+/// ```
+/// # use cairo_vm::types::builtin_name::{BuiltinName, CustomBuiltinName};
+///
+/// const BLAKE2S: CustomBuiltinName = CustomBuiltinName::new("blake2s_builtin");
+///
+/// assert_eq!(BuiltinName::custom(BLAKE2S).to_str(), "blake2s");
+/// assert_eq!(BuiltinName::custom(BLAKE2S).to_str_with_suffix(), "blake2s_builtin");
+///
+/// ```
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash)]
+pub struct CustomBuiltinName {
+    name_with_suffix: &'static str,
+}
+
+impl CustomBuiltinName {
+    /// Panics if the name doesn't end with the "_builtin" suffix, at compile time when used in a constant
+    pub const fn new(name_with_suffix: &'static str) -> Self {
+        let name = name_with_suffix.as_bytes();
+        let suffix = BUILTIN_NAME_SUFFIX.as_bytes();
+        assert!(
+            name.len() > suffix.len(),
+            "custom builtin names must end with the _builtin suffix"
+        );
+        let mut i = 0;
+        while i < suffix.len() {
+            assert!(
+                name[name.len() - suffix.len() + i] == suffix[i],
+                "custom builtin names must end with the _builtin suffix"
+            );
+            i += 1;
+        }
+        CustomBuiltinName { name_with_suffix }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        &self.name_with_suffix[..self.name_with_suffix.len() - BUILTIN_NAME_SUFFIX.len()]
+    }
+
+    pub fn to_str_with_suffix(self) -> &'static str {
+        self.name_with_suffix
+    }
 }
 
 impl BuiltinName {
@@ -75,6 +134,7 @@ impl BuiltinName {
             BuiltinName::range_check96 => RANGE_CHECK_96_BUILTIN_NAME_WITH_SUFFIX,
             BuiltinName::add_mod => ADD_MOD_BUILTIN_NAME_WITH_SUFFIX,
             BuiltinName::mul_mod => MUL_MOD_BUILTIN_NAME_WITH_SUFFIX,
+            BuiltinName::custom(name) => name.to_str_with_suffix(),
         }
     }
 
@@ -103,6 +163,7 @@ impl BuiltinName {
             BuiltinName::range_check96 => RANGE_CHECK_96_BUILTIN_NAME,
             BuiltinName::add_mod => ADD_MOD_BUILTIN_NAME,
             BuiltinName::mul_mod => MUL_MOD_BUILTIN_NAME,
+            BuiltinName::custom(name) => name.to_str(),
         }
     }
 
@@ -167,6 +228,55 @@ impl BuiltinName {
             _ => None,
         }
     }
+
+    /// Converts a [`BuiltinName`] from its string representation, resolving names unknown to the
+    /// vm against the given custom builtins
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use cairo_vm::types::builtin_name::{BuiltinName, CustomBuiltinName};
+    ///
+    /// const BLAKE2S: CustomBuiltinName = CustomBuiltinName::new("blake2s_builtin");
+    ///
+    /// assert_eq!(BuiltinName::from_str_or_custom("poseidon", [BLAKE2S]), Some(BuiltinName::poseidon));
+    ///
+    /// assert_eq!(BuiltinName::from_str_or_custom("blake2s", [BLAKE2S]), Some(BuiltinName::custom(BLAKE2S)));
+    ///
+    /// assert_eq!(BuiltinName::from_str_or_custom("blake3", [BLAKE2S]), None);
+    ///
+    /// ```
+    pub fn from_str_or_custom(
+        str: &str,
+        custom_builtins: impl IntoIterator<Item = CustomBuiltinName>,
+    ) -> Option<Self> {
+        Self::from_str(str).or_else(|| {
+            custom_builtins
+                .into_iter()
+                .find(|name| name.to_str() == str)
+                .map(BuiltinName::custom)
+        })
+    }
+
+    /// Returns true if the builtin is implemented outside of the vm
+    pub fn is_custom(self) -> bool {
+        matches!(self, BuiltinName::custom(_))
+    }
+}
+
+impl Serialize for BuiltinName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.to_str())
+    }
+}
+
+// Custom builtins can't be deserialized, as they are only known to the runner they are registered on
+impl<'de> Deserialize<'de> for BuiltinName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        BuiltinName::from_str(&name)
+            .ok_or_else(|| D::Error::custom(format!("unknown builtin {name}")))
+    }
 }
 
 /// NOTE: Adds "_builtin" suffix
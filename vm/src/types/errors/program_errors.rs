@@ -17,8 +17,18 @@ pub enum ProgramError {
     PrimeDiffers(String),
     #[error("Can't build a StrippedProgram from a Program without main")]
     StrippedProgramNoMain,
+    #[error("Unknown builtin {0}")]
+    UnknownBuiltin(Box<str>),
     #[error("Hint PC ({0}) is greater or equal to program length ({1})")]
     InvalidHintPc(usize, usize),
+    #[error("Invalid program cache: {0}")]
+    InvalidCache(Box<str>),
+    #[error("Program cache was written with format version {0}, expected version {1}")]
+    CacheVersionMismatch(u32, u32),
+    #[error("Program cache doesn't match its content hash")]
+    CacheHashMismatch,
+    #[error("Program cache was built from another version of the program")]
+    StaleCache,
 }
 
 #[cfg(test)]
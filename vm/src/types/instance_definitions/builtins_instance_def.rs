@@ -5,6 +5,7 @@ use super::{
     pedersen_instance_def::PedersenInstanceDef, poseidon_instance_def::PoseidonInstanceDef,
     range_check_instance_def::RangeCheckInstanceDef,
 };
+use crate::types::layout::CairoLayoutParams;
 
 pub(crate) const BUILTIN_INSTANCES_PER_COMPONENT: u32 = 1;
 
@@ -192,21 +193,49 @@ impl BuiltinsInstanceDef {
         }
     }
 
-    pub(crate) fn dynamic() -> BuiltinsInstanceDef {
+    pub(crate) fn dynamic(params: &CairoLayoutParams) -> BuiltinsInstanceDef {
         BuiltinsInstanceDef {
             output: true,
-            pedersen: Some(PedersenInstanceDef::new(None)),
-            range_check: Some(RangeCheckInstanceDef::new(None)),
-            ecdsa: Some(EcdsaInstanceDef::new(None)),
-            bitwise: Some(BitwiseInstanceDef::new(None)),
-            ec_op: Some(EcOpInstanceDef::new(None)),
-            keccak: None,
-            poseidon: None,
-            range_check96: None,
+            pedersen: params
+                .uses_pedersen_builtin
+                .then(|| PedersenInstanceDef::new(Some(params.pedersen_ratio))),
+            range_check: params
+                .uses_range_check_builtin
+                .then(|| RangeCheckInstanceDef::new(Some(params.range_check_ratio))),
+            ecdsa: params
+                .uses_ecdsa_builtin
+                .then(|| EcdsaInstanceDef::new(Some(params.ecdsa_ratio))),
+            bitwise: params
+                .uses_bitwise_builtin
+                .then(|| BitwiseInstanceDef::new(Some(params.bitwise_ratio))),
+            ec_op: params
+                .uses_ec_op_builtin
+                .then(|| EcOpInstanceDef::new(Some(params.ec_op_ratio))),
+            keccak: params
+                .uses_keccak_builtin
+                .then(|| KeccakInstanceDef::new(Some(params.keccak_ratio))),
+            poseidon: params
+                .uses_poseidon_builtin
+                .then(|| PoseidonInstanceDef::new(Some(params.poseidon_ratio))),
+            range_check96: params
+                .uses_range_check96_builtin
+                .then(|| RangeCheckInstanceDef::new(Some(params.range_check96_ratio))),
             #[cfg(feature = "mod_builtin")]
-            add_mod: Some(ModInstanceDef::new(None, 1, 96)),
+            add_mod: params.uses_add_mod_builtin.then(|| {
+                ModInstanceDef::new(
+                    Some(params.add_mod_ratio),
+                    params.add_mod_batch_size as usize,
+                    96,
+                )
+            }),
             #[cfg(feature = "mod_builtin")]
-            mul_mod: Some(ModInstanceDef::new(None, 1, 96)),
+            mul_mod: params.uses_mul_mod_builtin.then(|| {
+                ModInstanceDef::new(
+                    Some(params.mul_mod_ratio),
+                    params.mul_mod_batch_size as usize,
+                    96,
+                )
+            }),
             #[cfg(not(feature = "mod_builtin"))]
             add_mod: None,
             #[cfg(not(feature = "mod_builtin"))]
@@ -345,14 +374,32 @@ mod tests {
 
     #[test]
     fn get_builtins_dynamic() {
-        let builtins = BuiltinsInstanceDef::dynamic();
+        let mut params: CairoLayoutParams =
+            serde_json::from_slice(include_bytes!("../../tests/cairo_layout_params_file.json"))
+                .unwrap();
+        params.uses_keccak_builtin = false;
+        params.uses_poseidon_builtin = false;
+        params.ecdsa_ratio = 512;
+        let builtins = BuiltinsInstanceDef::dynamic(&params);
         assert!(builtins.output);
-        assert!(builtins.pedersen.is_some());
-        assert!(builtins.range_check.is_some());
-        assert!(builtins.ecdsa.is_some());
-        assert!(builtins.bitwise.is_some());
-        assert!(builtins.ec_op.is_some());
+        assert_eq!(builtins.pedersen, Some(PedersenInstanceDef::new(Some(256))));
+        assert_eq!(
+            builtins.range_check,
+            Some(RangeCheckInstanceDef::new(Some(8)))
+        );
+        assert_eq!(builtins.ecdsa, Some(EcdsaInstanceDef::new(Some(512))));
+        assert_eq!(builtins.bitwise, Some(BitwiseInstanceDef::new(Some(16))));
+        assert_eq!(builtins.ec_op, Some(EcOpInstanceDef::new(Some(1024))));
         assert!(builtins.keccak.is_none());
         assert!(builtins.poseidon.is_none());
+        assert_eq!(
+            builtins.range_check96,
+            Some(RangeCheckInstanceDef::new(Some(8)))
+        );
+        #[cfg(feature = "mod_builtin")]
+        assert_eq!(
+            builtins.mul_mod,
+            Some(ModInstanceDef::new(Some(256), 1, 96))
+        );
     }
 }
@@ -2,7 +2,9 @@ use serde::Serialize;
 
 #[derive(Serialize, Debug, PartialEq)]
 pub(crate) struct DilutedPoolInstanceDef {
-    pub(crate) units_per_step: u32, // 2 ^ log_units_per_step (for cairo_lang comparison)
+    pub(crate) units_per_step: u32, // 2 ^ |log_units_per_step| (for cairo_lang comparison)
+    // When set, the pool has one unit every `units_per_step` steps (log_units_per_step < 0)
+    pub(crate) fractional_units_per_step: bool,
     pub(crate) spacing: u32,
     pub(crate) n_bits: u32,
 }
@@ -11,6 +13,7 @@ impl DilutedPoolInstanceDef {
     pub(crate) fn default() -> Self {
         DilutedPoolInstanceDef {
             units_per_step: 16,
+            fractional_units_per_step: false,
             spacing: 4,
             n_bits: 16,
         }
@@ -19,10 +22,35 @@ impl DilutedPoolInstanceDef {
     pub(crate) fn new(units_per_step: u32, spacing: u32, n_bits: u32) -> Self {
         DilutedPoolInstanceDef {
             units_per_step,
+            fractional_units_per_step: false,
             spacing,
             n_bits,
         }
     }
+
+    pub(crate) fn from_log_units_per_step(
+        log_units_per_step: i32,
+        spacing: u32,
+        n_bits: u32,
+    ) -> Self {
+        DilutedPoolInstanceDef {
+            units_per_step: 2_u32.pow(log_units_per_step.unsigned_abs()),
+            fractional_units_per_step: log_units_per_step < 0,
+            spacing,
+            n_bits,
+        }
+    }
+
+    /// Number of diluted units available for a trace of `n_steps` steps, `None` if a fractional
+    /// pool's units don't evenly divide the steps
+    pub(crate) fn units(&self, n_steps: usize) -> Option<usize> {
+        if self.fractional_units_per_step {
+            (n_steps % self.units_per_step as usize == 0)
+                .then(|| n_steps / self.units_per_step as usize)
+        } else {
+            (self.units_per_step as usize).checked_mul(n_steps)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -49,4 +77,18 @@ mod tests {
         assert_eq!(diluted_pool.spacing, 1);
         assert_eq!(diluted_pool.n_bits, 1);
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_from_log_units_per_step() {
+        let diluted_pool = DilutedPoolInstanceDef::from_log_units_per_step(3, 4, 16);
+        assert_eq!(diluted_pool, DilutedPoolInstanceDef::new(8, 4, 16));
+        assert_eq!(diluted_pool.units(16), Some(128));
+
+        let diluted_pool = DilutedPoolInstanceDef::from_log_units_per_step(-2, 4, 16);
+        assert_eq!(diluted_pool.units_per_step, 4);
+        assert!(diluted_pool.fractional_units_per_step);
+        assert_eq!(diluted_pool.units(16), Some(4));
+        assert_eq!(diluted_pool.units(2), None);
+    }
 }
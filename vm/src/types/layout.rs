@@ -1,4 +1,6 @@
+use crate::stdlib::prelude::*;
 use crate::types::layout_name::LayoutName;
+use crate::vm::errors::runner_errors::RunnerError;
 
 use super::instance_definitions::{
     builtins_instance_def::BuiltinsInstanceDef, diluted_pool_instance_def::DilutedPoolInstanceDef,
@@ -6,15 +8,204 @@ use super::instance_definitions::{
 
 pub(crate) const MEMORY_UNITS_PER_STEP: u32 = 8;
 
-use serde::Serialize;
+#[cfg(feature = "test_utils")]
+use arbitrary::{self, Arbitrary};
+use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Debug)]
 pub struct CairoLayout {
     pub(crate) name: LayoutName,
     pub(crate) rc_units: u32,
+    pub(crate) memory_units_per_step: u32,
     pub(crate) builtins: BuiltinsInstanceDef,
     pub(crate) public_memory_fraction: u32,
     pub(crate) diluted_pool_instance_def: Option<DilutedPoolInstanceDef>,
+    /// The parameters the layout was built from, only set for the dynamic layout
+    pub(crate) dynamic_params: Option<CairoLayoutParams>,
+}
+
+/// Parameters of the dynamic layout, following the layout params file accepted by the prover.
+/// Each builtin is only included in the layout if its `uses_<builtin>_builtin` flag is set,
+/// the flags are serialized as 0 or 1 as the prover expects, but can also be read from booleans
+#[cfg_attr(feature = "test_utils", derive(Arbitrary))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CairoLayoutParams {
+    pub rc_units: u32,
+    pub cpu_component_step: u32,
+    pub memory_units_per_step: u32,
+    pub log_diluted_units_per_step: i32,
+    pub diluted_spacing: u32,
+    pub diluted_n_bits: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_pedersen_builtin: bool,
+    pub pedersen_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_range_check_builtin: bool,
+    pub range_check_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_ecdsa_builtin: bool,
+    pub ecdsa_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_bitwise_builtin: bool,
+    pub bitwise_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_ec_op_builtin: bool,
+    pub ec_op_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_keccak_builtin: bool,
+    pub keccak_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_poseidon_builtin: bool,
+    pub poseidon_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_range_check96_builtin: bool,
+    pub range_check96_ratio: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_add_mod_builtin: bool,
+    pub add_mod_ratio: u32,
+    pub add_mod_batch_size: u32,
+    #[serde(with = "bool_as_int")]
+    pub uses_mul_mod_builtin: bool,
+    pub mul_mod_ratio: u32,
+    pub mul_mod_batch_size: u32,
+}
+
+// Serializes a flag as 0 or 1, accepting either integers or booleans when deserializing
+mod bool_as_int {
+    use core::fmt;
+
+    use serde::{de, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(*value as u32)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<bool, D::Error> {
+        d.deserialize_any(BoolVisitor)
+    }
+
+    struct BoolVisitor;
+
+    impl<'de> de::Visitor<'de> for BoolVisitor {
+        type Value = bool;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("0, 1 or a boolean")
+        }
+
+        fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(value)
+        }
+
+        fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            match value {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err(E::invalid_value(de::Unexpected::Unsigned(value), &self)),
+            }
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            match value {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err(E::invalid_value(de::Unexpected::Signed(value), &self)),
+            }
+        }
+    }
+}
+
+impl CairoLayoutParams {
+    /// Loads the parameters from a JSON layout params file
+    #[cfg(feature = "std")]
+    pub fn from_file(params_path: &std::path::Path) -> std::io::Result<Self> {
+        let params_file = std::fs::File::open(params_path)?;
+        let params = serde_json::from_reader(std::io::BufReader::new(params_file))?;
+        Ok(params)
+    }
+
+    // Returns the name of the first parameter that can't be used to build a layout
+    fn invalid_param(&self) -> Option<&'static str> {
+        let ratios = [
+            (
+                self.uses_pedersen_builtin,
+                self.pedersen_ratio,
+                "pedersen_ratio",
+            ),
+            (
+                self.uses_range_check_builtin,
+                self.range_check_ratio,
+                "range_check_ratio",
+            ),
+            (self.uses_ecdsa_builtin, self.ecdsa_ratio, "ecdsa_ratio"),
+            (
+                self.uses_bitwise_builtin,
+                self.bitwise_ratio,
+                "bitwise_ratio",
+            ),
+            (self.uses_ec_op_builtin, self.ec_op_ratio, "ec_op_ratio"),
+            (self.uses_keccak_builtin, self.keccak_ratio, "keccak_ratio"),
+            (
+                self.uses_poseidon_builtin,
+                self.poseidon_ratio,
+                "poseidon_ratio",
+            ),
+            (
+                self.uses_range_check96_builtin,
+                self.range_check96_ratio,
+                "range_check96_ratio",
+            ),
+            (
+                self.uses_add_mod_builtin,
+                self.add_mod_ratio,
+                "add_mod_ratio",
+            ),
+            (
+                self.uses_add_mod_builtin,
+                self.add_mod_batch_size,
+                "add_mod_batch_size",
+            ),
+            (
+                self.uses_mul_mod_builtin,
+                self.mul_mod_ratio,
+                "mul_mod_ratio",
+            ),
+            (
+                self.uses_mul_mod_builtin,
+                self.mul_mod_batch_size,
+                "mul_mod_batch_size",
+            ),
+        ];
+        if let Some((_, _, name)) = ratios.iter().find(|(used, value, _)| *used && *value == 0) {
+            return Some(name);
+        }
+        // The instruction uses 3 range check units per step
+        if self.rc_units < 3 {
+            return Some("rc_units");
+        }
+        // The instruction uses 4 memory units per step
+        if self.memory_units_per_step < 4 {
+            return Some("memory_units_per_step");
+        }
+        // The vm executes a single instruction per cpu component, as the layouts it provides
+        if self.cpu_component_step != 1 {
+            return Some("cpu_component_step");
+        }
+        // Negative values give the pool one unit every 2^-log_diluted_units_per_step steps
+        if self.log_diluted_units_per_step.unsigned_abs() >= u32::BITS {
+            return Some("log_diluted_units_per_step");
+        }
+        None
+    }
 }
 
 impl CairoLayout {
@@ -22,9 +213,11 @@ impl CairoLayout {
         CairoLayout {
             name: LayoutName::plain,
             rc_units: 16,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
             builtins: BuiltinsInstanceDef::plain(),
             public_memory_fraction: 4,
             diluted_pool_instance_def: None,
+            dynamic_params: None,
         }
     }
 
@@ -32,9 +225,11 @@ impl CairoLayout {
         CairoLayout {
             name: LayoutName::small,
             rc_units: 16,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
             builtins: BuiltinsInstanceDef::small(),
             public_memory_fraction: 4,
             diluted_pool_instance_def: None,
+            dynamic_params: None,
         }
     }
 
@@ -42,9 +237,11 @@ impl CairoLayout {
         CairoLayout {
             name: LayoutName::dex,
             rc_units: 4,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
             builtins: BuiltinsInstanceDef::dex(),
             public_memory_fraction: 4,
             diluted_pool_instance_def: None,
+            dynamic_params: None,
         }
     }
 
@@ -52,9 +249,11 @@ impl CairoLayout {
         CairoLayout {
             name: LayoutName::recursive,
             rc_units: 4,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
             builtins: BuiltinsInstanceDef::recursive(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            dynamic_params: None,
         }
     }
 
@@ -62,9 +261,11 @@ impl CairoLayout {
         CairoLayout {
             name: LayoutName::starknet,
             rc_units: 4,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
             builtins: BuiltinsInstanceDef::starknet(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::new(2, 4, 16)),
+            dynamic_params: None,
         }
     }
 
@@ -72,9 +273,11 @@ impl CairoLayout {
         CairoLayout {
             name: LayoutName::starknet_with_keccak,
             rc_units: 4,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
             builtins: BuiltinsInstanceDef::starknet_with_keccak(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            dynamic_params: None,
         }
     }
 
@@ -82,18 +285,22 @@ impl CairoLayout {
         CairoLayout {
             name: LayoutName::recursive_large_output,
             rc_units: 4,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
             builtins: BuiltinsInstanceDef::recursive_large_output(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            dynamic_params: None,
         }
     }
     pub(crate) fn recursive_with_poseidon() -> CairoLayout {
         CairoLayout {
             name: LayoutName::recursive_with_poseidon,
             rc_units: 4,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
             builtins: BuiltinsInstanceDef::recursive_with_poseidon(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::new(8, 4, 16)),
+            dynamic_params: None,
         }
     }
 
@@ -101,9 +308,11 @@ impl CairoLayout {
         CairoLayout {
             name: LayoutName::all_cairo,
             rc_units: 4,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
             builtins: BuiltinsInstanceDef::all_cairo(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            dynamic_params: None,
         }
     }
 
@@ -111,26 +320,39 @@ impl CairoLayout {
         CairoLayout {
             name: LayoutName::all_solidity,
             rc_units: 8,
+            memory_units_per_step: MEMORY_UNITS_PER_STEP,
             builtins: BuiltinsInstanceDef::all_solidity(),
             public_memory_fraction: 8,
             diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
+            dynamic_params: None,
         }
     }
 
-    pub(crate) fn dynamic_instance() -> CairoLayout {
-        CairoLayout {
+    pub(crate) fn dynamic_instance(params: CairoLayoutParams) -> Result<CairoLayout, RunnerError> {
+        if let Some(param) = params.invalid_param() {
+            return Err(RunnerError::InvalidDynamicLayoutParams(param));
+        }
+        Ok(CairoLayout {
             name: LayoutName::dynamic,
-            rc_units: 16,
-            builtins: BuiltinsInstanceDef::dynamic(),
+            rc_units: params.rc_units,
+            memory_units_per_step: params.memory_units_per_step,
+            builtins: BuiltinsInstanceDef::dynamic(&params),
             public_memory_fraction: 8,
-            diluted_pool_instance_def: Some(DilutedPoolInstanceDef::default()),
-        }
+            diluted_pool_instance_def: Some(DilutedPoolInstanceDef::from_log_units_per_step(
+                params.log_diluted_units_per_step,
+                params.diluted_spacing,
+                params.diluted_n_bits,
+            )),
+            dynamic_params: Some(params),
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use assert_matches::assert_matches;
+    use rstest::rstest;
 
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::*;
@@ -255,17 +477,130 @@ mod tests {
         );
     }
 
+    fn layout_params() -> CairoLayoutParams {
+        serde_json::from_slice(include_bytes!("../tests/cairo_layout_params_file.json")).unwrap()
+    }
+
     #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_dynamic_instance() {
-        let layout = CairoLayout::dynamic_instance();
-        let builtins = BuiltinsInstanceDef::dynamic();
+        let params = CairoLayoutParams {
+            rc_units: 16,
+            memory_units_per_step: 16,
+            log_diluted_units_per_step: 3,
+            ..layout_params()
+        };
+        let layout = CairoLayout::dynamic_instance(params.clone()).unwrap();
+        let builtins = BuiltinsInstanceDef::dynamic(&params);
         assert_eq!(layout.name, LayoutName::dynamic);
         assert_eq!(layout.rc_units, 16);
+        assert_eq!(layout.memory_units_per_step, 16);
         assert_eq!(layout.builtins, builtins);
         assert_eq!(layout.public_memory_fraction, 8);
         assert_eq!(
             layout.diluted_pool_instance_def,
-            Some(DilutedPoolInstanceDef::default())
+            Some(DilutedPoolInstanceDef::new(8, 4, 16))
+        );
+        assert_eq!(layout.dynamic_params, Some(params));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn dynamic_instance_fractional_diluted_units() {
+        let mut value = serde_json::to_value(layout_params()).unwrap();
+        value["log_diluted_units_per_step"] = serde_json::json!(-2);
+        let params: CairoLayoutParams = serde_json::from_value(value).unwrap();
+        assert_eq!(params.log_diluted_units_per_step, -2);
+        let layout = CairoLayout::dynamic_instance(params).unwrap();
+        assert_matches!(
+            layout.diluted_pool_instance_def,
+            Some(DilutedPoolInstanceDef {
+                units_per_step: 4,
+                fractional_units_per_step: true,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn dynamic_instance_matches_all_cairo() {
+        let layout = CairoLayout::dynamic_instance(layout_params()).unwrap();
+        let all_cairo = CairoLayout::all_cairo_instance();
+        assert_eq!(layout.rc_units, all_cairo.rc_units);
+        assert_eq!(layout.builtins, all_cairo.builtins);
+        assert_eq!(
+            layout.diluted_pool_instance_def,
+            all_cairo.diluted_pool_instance_def
+        );
+    }
+
+    #[rstest]
+    #[case::zero_ratio(CairoLayoutParams { bitwise_ratio: 0, ..layout_params() }, "bitwise_ratio")]
+    #[case::zero_batch_size(CairoLayoutParams { add_mod_batch_size: 0, ..layout_params() }, "add_mod_batch_size")]
+    #[case::rc_units(CairoLayoutParams { rc_units: 2, ..layout_params() }, "rc_units")]
+    #[case::memory_units(CairoLayoutParams { memory_units_per_step: 3, ..layout_params() }, "memory_units_per_step")]
+    #[case::diluted_units(CairoLayoutParams { log_diluted_units_per_step: 32, ..layout_params() }, "log_diluted_units_per_step")]
+    #[case::negative_diluted_units(CairoLayoutParams { log_diluted_units_per_step: -32, ..layout_params() }, "log_diluted_units_per_step")]
+    #[case::cpu_component_step(CairoLayoutParams { cpu_component_step: 2, ..layout_params() }, "cpu_component_step")]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn dynamic_instance_invalid_params(#[case] params: CairoLayoutParams, #[case] param: &str) {
+        assert_matches!(
+            CairoLayout::dynamic_instance(params),
+            Err(RunnerError::InvalidDynamicLayoutParams(name)) if name == param
         );
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn unused_builtin_ratio_can_be_zero() {
+        let params = CairoLayoutParams {
+            uses_keccak_builtin: false,
+            keccak_ratio: 0,
+            ..layout_params()
+        };
+        let layout = CairoLayout::dynamic_instance(params).unwrap();
+        assert_eq!(layout.builtins.keccak, None);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn layout_params_flags_serialized_as_integers() {
+        let params = CairoLayoutParams {
+            uses_keccak_builtin: false,
+            ..layout_params()
+        };
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(value["uses_pedersen_builtin"], serde_json::json!(1));
+        assert_eq!(value["uses_keccak_builtin"], serde_json::json!(0));
+        assert_eq!(
+            serde_json::from_value::<CairoLayoutParams>(value).unwrap(),
+            params
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn layout_params_flags_from_booleans() {
+        let mut value = serde_json::to_value(layout_params()).unwrap();
+        value["uses_pedersen_builtin"] = serde_json::json!(true);
+        value["uses_keccak_builtin"] = serde_json::json!(false);
+        let params: CairoLayoutParams = serde_json::from_value(value.clone()).unwrap();
+        assert!(params.uses_pedersen_builtin);
+        assert!(!params.uses_keccak_builtin);
+
+        value["uses_keccak_builtin"] = serde_json::json!(2);
+        assert!(serde_json::from_value::<CairoLayoutParams>(value).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn layout_params_from_file() {
+        let params = CairoLayoutParams::from_file(std::path::Path::new(
+            "src/tests/cairo_layout_params_file.json",
+        ))
+        .unwrap();
+        assert_eq!(params, layout_params());
+        assert!(CairoLayoutParams::from_file(std::path::Path::new("missing.json")).is_err());
+    }
 }
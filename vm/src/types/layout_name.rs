@@ -24,6 +24,21 @@ pub enum LayoutName {
 }
 
 impl LayoutName {
+    /// Every layout, roughly ordered from the smallest to the largest
+    pub const ALL: [LayoutName; 11] = [
+        Self::plain,
+        Self::small,
+        Self::dex,
+        Self::recursive,
+        Self::starknet,
+        Self::starknet_with_keccak,
+        Self::recursive_large_output,
+        Self::recursive_with_poseidon,
+        Self::all_solidity,
+        Self::all_cairo,
+        Self::dynamic,
+    ];
+
     pub fn to_str(self) -> &'static str {
         match self {
             LayoutName::plain => "plain",
@@ -36,7 +51,7 @@ impl LayoutName {
             LayoutName::recursive_with_poseidon => "recursive_with_poseidon",
             LayoutName::all_solidity => "all_solidity",
             LayoutName::all_cairo => "all_cairo",
-            LayoutName::dynamic => "all_cairo",
+            LayoutName::dynamic => "dynamic",
         }
     }
 }
@@ -50,19 +65,7 @@ impl Display for LayoutName {
 #[cfg(all(feature = "clap", feature = "std"))]
 impl ValueEnum for LayoutName {
     fn value_variants<'a>() -> &'a [Self] {
-        &[
-            Self::plain,
-            Self::small,
-            Self::dex,
-            Self::recursive,
-            Self::starknet,
-            Self::starknet_with_keccak,
-            Self::recursive_large_output,
-            Self::recursive_with_poseidon,
-            Self::all_solidity,
-            Self::all_cairo,
-            Self::dynamic,
-        ]
+        &Self::ALL
     }
 
     fn to_possible_value(&self) -> Option<PossibleValue> {
@@ -1,6 +1,7 @@
 use crate::{
     serde::{
         deserialize_program::{parse_program_json, ProgramJson},
+        program_cache::{self, ProgramCache},
         serialize_program::ProgramSerializer,
     },
     stdlib::{
@@ -18,8 +19,8 @@ use crate::Felt252;
 use crate::{
     hint_processor::hint_processor_definition::HintReference,
     serde::deserialize_program::{
-        deserialize_and_parse_program, Attribute, HintParams, Identifier, InstructionLocation,
-        OffsetValue, ReferenceManager,
+        deserialize_and_parse_program, deserialize_and_parse_program_with_custom_builtins,
+        Attribute, HintParams, Identifier, InstructionLocation, OffsetValue, ReferenceManager,
     },
     types::{
         errors::program_errors::ProgramError, instruction::Register, relocatable::MaybeRelocatable,
@@ -32,7 +33,7 @@ use core::num::NonZeroUsize;
 #[cfg(feature = "std")]
 use std::path::Path;
 
-use super::builtin_name::BuiltinName;
+use super::builtin_name::{BuiltinName, CustomBuiltinName};
 #[cfg(feature = "extensive_hints")]
 use super::relocatable::Relocatable;
 #[cfg(feature = "test_utils")]
@@ -166,10 +167,56 @@ impl HintsCollection {
         self.hints.iter()
     }
 
+    /// Returns the pc of the hint at the given position of `iter_hints`
+    pub fn get_pc_for_hint(&self, index: usize) -> Option<usize> {
+        let contains =
+            |&(start, len): &(usize, NonZeroUsize)| (start..start + len.get()).contains(&index);
+        #[cfg(not(feature = "extensive_hints"))]
+        let pc = self
+            .hints_ranges
+            .iter()
+            .position(|range| range.as_ref().is_some_and(contains));
+        #[cfg(feature = "extensive_hints")]
+        let pc = self
+            .hints_ranges
+            .iter()
+            .find(|(_, range)| contains(range))
+            .map(|(pc, _)| pc.offset);
+        pc
+    }
+
     #[cfg(not(feature = "extensive_hints"))]
     pub fn get_hint_range_for_pc(&self, pc: usize) -> Option<HintRange> {
         self.hints_ranges.get(pc).cloned()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &[HintParams])> {
+        #[cfg(not(feature = "extensive_hints"))]
+        let iter = self
+            .hints_ranges
+            .iter()
+            .enumerate()
+            .filter_map(|(pc, range)| {
+                range.and_then(|(start, len)| {
+                    let end = start + len.get();
+                    if end <= self.hints.len() {
+                        Some((pc, &self.hints[start..end]))
+                    } else {
+                        None
+                    }
+                })
+            });
+        #[cfg(feature = "extensive_hints")]
+        let iter = self.hints_ranges.iter().filter_map(|(pc, (start, len))| {
+            let end = start + len.get();
+            if end <= self.hints.len() {
+                Some((pc.offset, &self.hints[*start..end]))
+            } else {
+                None
+            }
+        });
+        iter
+    }
 }
 
 impl From<&HintsCollection> for BTreeMap<usize, Vec<HintParams>> {
@@ -284,6 +331,15 @@ impl Program {
         deserialize_and_parse_program(bytes, entrypoint)
     }
 
+    /// Loads a program that may use the given builtins implemented outside of the vm
+    pub fn from_bytes_with_custom_builtins(
+        bytes: &[u8],
+        entrypoint: Option<&str>,
+        custom_builtins: &[CustomBuiltinName],
+    ) -> Result<Program, ProgramError> {
+        deserialize_and_parse_program_with_custom_builtins(bytes, entrypoint, custom_builtins)
+    }
+
     pub fn prime(&self) -> &str {
         _ = self;
         PRIME_STR
@@ -325,6 +381,11 @@ impl Program {
         Some(relocated_instructions)
     }
 
+    /// Iterates over the hints of the program along with the pc they run at
+    pub fn iter_hints(&self) -> impl Iterator<Item = (usize, &[HintParams])> {
+        self.shared_program_data.hints_collection.iter()
+    }
+
     pub fn iter_identifiers(&self) -> impl Iterator<Item = (&str, &Identifier)> {
         self.shared_program_data
             .identifiers
@@ -414,6 +475,27 @@ impl Program {
         let program = parse_program_json(program_json, entrypoint)?;
         Ok(program)
     }
+
+    /// Serializes the program in a versioned binary format which is much faster to load than
+    /// the compiled JSON, see [crate::serde::program_cache]. `source` is the compiled JSON the
+    /// program was loaded from, whose hash is stored to detect stale caches.
+    pub fn to_cache_bytes(&self, source: &[u8]) -> Vec<u8> {
+        program_cache::to_cache_bytes(self, source)
+    }
+
+    /// Loads a program serialized by [Program::to_cache_bytes], failing if the cache was written
+    /// by another version of the format or if it doesn't match its content hash
+    pub fn from_cache_bytes(bytes: &[u8]) -> Result<Program, ProgramError> {
+        ProgramCache::new(bytes)?.to_program()
+    }
+
+    /// Same as [Program::from_cache_bytes], also failing if the cache wasn't built from `source`,
+    /// the current compiled JSON of the program
+    pub fn from_cache_bytes_checked(bytes: &[u8], source: &[u8]) -> Result<Program, ProgramError> {
+        let cache = ProgramCache::new(bytes)?;
+        cache.check_source(source)?;
+        cache.to_program()
+    }
 }
 
 impl Default for Program {
@@ -472,37 +554,6 @@ impl TryFrom<CasmContractClass> for Program {
     }
 }
 
-#[cfg(test)]
-impl HintsCollection {
-    pub fn iter(&self) -> impl Iterator<Item = (usize, &[HintParams])> {
-        #[cfg(not(feature = "extensive_hints"))]
-        let iter = self
-            .hints_ranges
-            .iter()
-            .enumerate()
-            .filter_map(|(pc, range)| {
-                range.and_then(|(start, len)| {
-                    let end = start + len.get();
-                    if end <= self.hints.len() {
-                        Some((pc, &self.hints[start..end]))
-                    } else {
-                        None
-                    }
-                })
-            });
-        #[cfg(feature = "extensive_hints")]
-        let iter = self.hints_ranges.iter().filter_map(|(pc, (start, len))| {
-            let end = start + len.get();
-            if end <= self.hints.len() {
-                Some((pc.offset, &self.hints[*start..end]))
-            } else {
-                None
-            }
-        });
-        iter
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use core::ops::Neg;
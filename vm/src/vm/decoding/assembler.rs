@@ -0,0 +1,806 @@
+//! Assembler building a [Program] from textual CASM, written in the syntax printed by the
+//! [disassembler](crate::vm::decoding::disassembler):
+//!
+//! ```text
+//! %builtins output
+//! __main__.main:
+//!     [ap + 0] = 21, ap++
+//!     call rel __main__.double
+//!     [ap + -1] = [[fp + -3] + 0]
+//!     [ap + 0] = [fp + -3] + 1, ap++
+//!     %{ memory[ap] = segments.add() %}
+//!     ret
+//! __main__.double:
+//!     [ap + 0] = [fp + -3] + [fp + -3], ap++
+//!     ret
+//! ```
+//!
+//! Lines hold a label (`name:`), a hint (`%{ code %}`, or a block of lines between `%{` and `%}`),
+//! a `%builtins` declaration, an instruction or a data word (`dw <value>`). Instructions may be
+//! prefixed by their pc (`13: ret`), which is ignored, and everything after a `#` is a comment.
+//! Relative jumps and calls can be given a label instead of an offset, as can `call <label>` and
+//! `jmp <label>`. Hints run before the instruction that follows them.
+
+use crate::stdlib::{collections::HashMap, prelude::*};
+use thiserror_no_std::Error;
+
+use crate::{
+    serde::deserialize_program::{
+        ApTracking, FlowTrackingData, HintParams, Identifier, ReferenceManager,
+    },
+    types::{
+        builtin_name::BuiltinName,
+        errors::program_errors::ProgramError,
+        instruction::{ApUpdate, FpUpdate, Instruction, Op1Addr, Opcode, PcUpdate, Register, Res},
+        program::Program,
+        relocatable::MaybeRelocatable,
+    },
+    vm::decoding::decoder::encode_instruction,
+    Felt252,
+};
+
+#[derive(Debug, Error)]
+pub enum AssemblerError {
+    #[error("Line {0}: invalid syntax: {1}")]
+    InvalidSyntax(usize, Box<str>),
+    #[error("Line {0}: offset {1} is out of range")]
+    OffsetOutOfRange(usize, isize),
+    #[error("Line {0}: unknown label {1}")]
+    UnknownLabel(usize, Box<str>),
+    #[error("Line {0}: label {1} is already defined")]
+    DuplicateLabel(usize, Box<str>),
+    #[error("Line {0}: unknown builtin {1}")]
+    UnknownBuiltin(usize, Box<str>),
+    #[error("Line {0}: hint block is never closed")]
+    UnterminatedHint(usize),
+    #[error("Line {0}: ap += must add the result of the instruction")]
+    InvalidApUpdate(usize),
+    #[error(transparent)]
+    Program(#[from] ProgramError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Deref {
+    register: Register,
+    offset: isize,
+}
+
+// Cairo's default for the operands an instruction doesn't use
+const UNUSED_DEREF: Deref = Deref {
+    register: Register::FP,
+    offset: -1,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Immediate {
+    Value(Felt252),
+    // Relative jump or call to a label
+    Label(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Op1 {
+    Immediate(Immediate),
+    Deref(Deref),
+    // [[op0] + offset]
+    DoubleDeref(Deref, isize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ResExpr {
+    res: Res,
+    op0: Option<Deref>,
+    op1: Op1,
+}
+
+enum Statement {
+    Instruction(Instruction, Option<Immediate>),
+    Word(Felt252),
+}
+
+struct Assembler {
+    line: usize,
+    pc: usize,
+    builtins: Vec<BuiltinName>,
+    labels: HashMap<String, usize>,
+    // Name of the last label, used as the scope of the hints that follow it
+    current_label: Option<String>,
+    hints: HashMap<usize, Vec<HintParams>>,
+    // Statements along with the line and pc they are at
+    statements: Vec<(usize, usize, Statement)>,
+}
+
+/// Assembles a program, which is run from the label given as entrypoint (or from
+/// `__main__.<entrypoint>` if there isn't a label with that name)
+pub fn assemble(casm: &str, entrypoint: Option<&str>) -> Result<Program, AssemblerError> {
+    let assembler = Assembler::parse(casm)?;
+    let main = entrypoint
+        .map(|entrypoint| {
+            assembler
+                .find_label(entrypoint)
+                .ok_or_else(|| ProgramError::EntrypointNotFound(entrypoint.to_string()))
+        })
+        .transpose()?;
+    let (builtins, data, hints, identifiers) = assembler.assemble(main)?;
+    Ok(Program::new(
+        builtins,
+        data,
+        main,
+        hints,
+        ReferenceManager {
+            references: Vec::new(),
+        },
+        identifiers,
+        Vec::new(),
+        None,
+    )?)
+}
+
+/// Assembles a program to be run in proof mode, from the `__start__` label to the `__end__` one
+pub fn assemble_for_proof(casm: &str) -> Result<Program, AssemblerError> {
+    let assembler = Assembler::parse(casm)?;
+    let label = |name: &str| {
+        assembler
+            .find_label(name)
+            .ok_or_else(|| ProgramError::EntrypointNotFound(name.to_string()))
+    };
+    let (start, end) = (label("__start__")?, label("__end__")?);
+    let (builtins, data, hints, identifiers) = assembler.assemble(Some(start))?;
+    Ok(Program::new_for_proof(
+        builtins,
+        data,
+        start,
+        end,
+        hints,
+        ReferenceManager {
+            references: Vec::new(),
+        },
+        identifiers,
+        Vec::new(),
+        None,
+    )?)
+}
+
+type AssembledProgram = (
+    Vec<BuiltinName>,
+    Vec<MaybeRelocatable>,
+    HashMap<usize, Vec<HintParams>>,
+    HashMap<String, Identifier>,
+);
+
+impl Assembler {
+    fn parse(casm: &str) -> Result<Self, AssemblerError> {
+        let mut assembler = Assembler {
+            line: 0,
+            pc: 0,
+            builtins: Vec::new(),
+            labels: HashMap::new(),
+            current_label: None,
+            hints: HashMap::new(),
+            statements: Vec::new(),
+        };
+        let mut lines = casm.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            assembler.line = i + 1;
+            let trimmed = line.trim();
+            if let Some(hint) = trimmed.strip_prefix("%{") {
+                let code = match hint.strip_suffix("%}") {
+                    Some(code) => code.trim().to_string(),
+                    None => {
+                        let mut block = vec![hint];
+                        loop {
+                            let (_, line) = lines
+                                .next()
+                                .ok_or(AssemblerError::UnterminatedHint(assembler.line))?;
+                            match line.trim_end().strip_suffix("%}") {
+                                Some(last) => {
+                                    block.push(last);
+                                    break;
+                                }
+                                None => block.push(line),
+                            }
+                        }
+                        dedent(&block)
+                    }
+                };
+                assembler.add_hint(code);
+                continue;
+            }
+            let statement = strip_comment(trimmed);
+            if statement.is_empty() {
+                continue;
+            }
+            if let Some(builtins) = statement.strip_prefix("%builtins") {
+                for name in builtins.split_whitespace() {
+                    let builtin = BuiltinName::from_str(name).ok_or_else(|| {
+                        AssemblerError::UnknownBuiltin(assembler.line, name.into())
+                    })?;
+                    assembler.builtins.push(builtin);
+                }
+                continue;
+            }
+            if let Some(label) = statement
+                .strip_suffix(':')
+                .filter(|label| is_identifier(label))
+            {
+                assembler.add_label(label)?;
+                continue;
+            }
+            // Skip the pc printed by the disassembler
+            let statement = match statement.split_once(':') {
+                Some((pc, rest)) if !pc.is_empty() && pc.chars().all(|c| c.is_ascii_digit()) => {
+                    rest.trim()
+                }
+                _ => statement,
+            };
+            let statement = assembler.parse_statement(statement)?;
+            let size = match &statement {
+                Statement::Instruction(instruction, _) => instruction.size(),
+                Statement::Word(_) => 1,
+            };
+            assembler
+                .statements
+                .push((assembler.line, assembler.pc, statement));
+            assembler.pc += size;
+        }
+        Ok(assembler)
+    }
+
+    fn find_label(&self, name: &str) -> Option<usize> {
+        self.labels
+            .get(name)
+            .or_else(|| self.labels.get(&format!("__main__.{name}")))
+            .copied()
+    }
+
+    fn add_label(&mut self, name: &str) -> Result<(), AssemblerError> {
+        if self.labels.insert(name.to_string(), self.pc).is_some() {
+            return Err(AssemblerError::DuplicateLabel(self.line, name.into()));
+        }
+        self.current_label = Some(name.to_string());
+        Ok(())
+    }
+
+    fn add_hint(&mut self, code: String) {
+        // The hint can access the scopes of the label it is in, e.g. __main__ and __main__.main
+        let accessible_scopes = self
+            .current_label
+            .iter()
+            .flat_map(|label| {
+                label
+                    .match_indices('.')
+                    .map(|(i, _)| label[..i].to_string())
+                    .chain([label.clone()])
+            })
+            .collect();
+        self.hints.entry(self.pc).or_default().push(HintParams {
+            code,
+            accessible_scopes,
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking::default(),
+                reference_ids: HashMap::new(),
+            },
+        });
+    }
+
+    fn assemble(self, main: Option<usize>) -> Result<AssembledProgram, AssemblerError> {
+        let mut data = Vec::new();
+        // Labels that are called, or the entrypoint, are functions
+        let mut functions: Vec<usize> = main.into_iter().collect();
+        for (line, pc, statement) in self.statements.iter() {
+            match statement {
+                Statement::Word(value) => data.push(MaybeRelocatable::from(*value)),
+                Statement::Instruction(instruction, imm) => {
+                    data.push(Felt252::from(encode_instruction(instruction)).into());
+                    match imm {
+                        Some(Immediate::Value(value)) => data.push((*value).into()),
+                        Some(Immediate::Label(label)) => {
+                            let target = *self.labels.get(label).ok_or_else(|| {
+                                AssemblerError::UnknownLabel(*line, label.as_str().into())
+                            })?;
+                            if instruction.opcode == Opcode::Call {
+                                functions.push(target);
+                            }
+                            let offset = Felt252::from(target) - Felt252::from(*pc);
+                            data.push(offset.into());
+                        }
+                        None => (),
+                    }
+                }
+            }
+        }
+        let identifiers = self
+            .labels
+            .into_iter()
+            .map(|(name, pc)| {
+                let type_ = if functions.contains(&pc) {
+                    "function"
+                } else {
+                    "label"
+                };
+                let identifier = Identifier {
+                    pc: Some(pc),
+                    type_: Some(type_.to_string()),
+                    value: None,
+                    full_name: Some(name.clone()),
+                    members: None,
+                    cairo_type: None,
+                };
+                (name, identifier)
+            })
+            .collect();
+        Ok((self.builtins, data, self.hints, identifiers))
+    }
+
+    fn invalid(&self, statement: &str) -> AssemblerError {
+        AssemblerError::InvalidSyntax(self.line, statement.into())
+    }
+
+    fn parse_statement(&self, statement: &str) -> Result<Statement, AssemblerError> {
+        if let Some(value) = statement.strip_prefix("dw ") {
+            return parse_felt(value.trim())
+                .map(Statement::Word)
+                .ok_or_else(|| self.invalid(statement));
+        }
+        let mut parts = split_top_level(statement, ',').into_iter();
+        let main = parts.next().unwrap_or_default();
+
+        let mut opcode = Opcode::NOp;
+        let mut pc_update = PcUpdate::Regular;
+        let mut ap_update = ApUpdate::Regular;
+        let mut dst = None;
+        let mut res_expr = None;
+        if main == "ret" {
+            opcode = Opcode::Ret;
+            pc_update = PcUpdate::Jump;
+        } else if main == "nop" {
+        } else if let Some(target) = main.strip_prefix("call ") {
+            opcode = Opcode::Call;
+            ap_update = ApUpdate::Add2;
+            dst = Some(Deref {
+                register: Register::AP,
+                offset: 0,
+            });
+            let (update, res) = self.parse_jump_target(target)?;
+            pc_update = update;
+            res_expr = Some(res);
+        } else if let Some(jump) = main.strip_prefix("jmp ") {
+            match jump.split_once(" if ") {
+                Some((target, condition)) => {
+                    let target = target.trim();
+                    let target = target.strip_prefix("rel ").unwrap_or(target);
+                    let condition = condition
+                        .trim()
+                        .strip_suffix("!= 0")
+                        .ok_or_else(|| self.invalid(statement))?;
+                    pc_update = PcUpdate::Jnz;
+                    dst = Some(self.parse_deref(condition.trim())?);
+                    res_expr = Some(ResExpr {
+                        res: Res::Unconstrained,
+                        op0: None,
+                        op1: self.parse_op1(target.trim(), true)?,
+                    });
+                }
+                None => {
+                    let (update, res) = self.parse_jump_target(jump)?;
+                    pc_update = update;
+                    res_expr = Some(res);
+                }
+            }
+        } else if let Some(res) = main.strip_prefix("ap += ") {
+            ap_update = ApUpdate::Add;
+            res_expr = Some(self.parse_res(res.trim(), false)?);
+        } else if let Some((left, right)) = main.split_once(" = ") {
+            opcode = Opcode::AssertEq;
+            dst = Some(self.parse_deref(left.trim())?);
+            res_expr = Some(self.parse_res(right.trim(), false)?);
+        } else {
+            return Err(self.invalid(statement));
+        }
+
+        for part in parts {
+            if part == "ap++" && ap_update == ApUpdate::Regular {
+                ap_update = ApUpdate::Add1;
+            } else if let Some(res) = part.strip_prefix("ap += ") {
+                if ap_update != ApUpdate::Regular
+                    || res_expr.as_ref() != Some(&self.parse_res(res.trim(), false)?)
+                {
+                    return Err(AssemblerError::InvalidApUpdate(self.line));
+                }
+                ap_update = ApUpdate::Add;
+            } else {
+                return Err(self.invalid(statement));
+            }
+        }
+
+        let (dst, op0, op1) = match opcode {
+            // ret jumps to the return pc at [fp - 1] and restores fp from [fp - 2]
+            Opcode::Ret => (
+                Deref {
+                    register: Register::FP,
+                    offset: -2,
+                },
+                UNUSED_DEREF,
+                Op1::Deref(UNUSED_DEREF),
+            ),
+            _ => {
+                let ResExpr { res: _, op0, op1 } = res_expr.clone().unwrap_or(ResExpr {
+                    res: Res::Op1,
+                    op0: None,
+                    op1: Op1::Deref(UNUSED_DEREF),
+                });
+                let op0 = match (&op1, op0) {
+                    (Op1::DoubleDeref(inner, _), Some(op0)) if *inner != op0 => {
+                        return Err(self.invalid(statement))
+                    }
+                    (Op1::DoubleDeref(inner, _), _) => *inner,
+                    // call stores the return pc at [ap + 1]
+                    (_, None) if opcode == Opcode::Call => Deref {
+                        register: Register::AP,
+                        offset: 1,
+                    },
+                    (_, op0) => op0.unwrap_or(UNUSED_DEREF),
+                };
+                (dst.unwrap_or(UNUSED_DEREF), op0, op1)
+            }
+        };
+        let (op1_addr, off2, imm) = match op1 {
+            Op1::Immediate(imm) => (Op1Addr::Imm, 1, Some(imm)),
+            Op1::Deref(Deref {
+                register: Register::AP,
+                offset,
+            }) => (Op1Addr::AP, offset, None),
+            Op1::Deref(Deref {
+                register: Register::FP,
+                offset,
+            }) => (Op1Addr::FP, offset, None),
+            Op1::DoubleDeref(_, offset) => (Op1Addr::Op0, offset, None),
+        };
+        for offset in [dst.offset, op0.offset, off2] {
+            if !(-(1 << 15)..(1 << 15)).contains(&offset) {
+                return Err(AssemblerError::OffsetOutOfRange(self.line, offset));
+            }
+        }
+        let instruction = Instruction {
+            off0: dst.offset,
+            off1: op0.offset,
+            off2,
+            dst_register: dst.register,
+            op0_register: op0.register,
+            op1_addr,
+            res: res_expr.map(|res| res.res).unwrap_or(Res::Op1),
+            pc_update,
+            ap_update,
+            fp_update: match opcode {
+                Opcode::Call => FpUpdate::APPlus2,
+                Opcode::Ret => FpUpdate::Dst,
+                _ => FpUpdate::Regular,
+            },
+            opcode,
+        };
+        Ok(Statement::Instruction(instruction, imm))
+    }
+
+    // Parses `abs <res>`, `rel <res>` or `<label>`
+    fn parse_jump_target(&self, target: &str) -> Result<(PcUpdate, ResExpr), AssemblerError> {
+        let target = target.trim();
+        if let Some(res) = target.strip_prefix("abs ") {
+            Ok((PcUpdate::Jump, self.parse_res(res.trim(), false)?))
+        } else {
+            let res = target.strip_prefix("rel ").unwrap_or(target);
+            Ok((PcUpdate::JumpRel, self.parse_res(res.trim(), true)?))
+        }
+    }
+
+    // Parses `<op1>`, `<op0> + <op1>` or `<op0> * <op1>`
+    fn parse_res(&self, res: &str, relative: bool) -> Result<ResExpr, AssemblerError> {
+        let mut depth = 0;
+        for (i, c) in res.char_indices() {
+            match c {
+                '[' | '(' => depth += 1,
+                ']' | ')' => depth -= 1,
+                '+' | '*' if depth == 0 => {
+                    return Ok(ResExpr {
+                        res: if c == '+' { Res::Add } else { Res::Mul },
+                        op0: Some(self.parse_deref(res[..i].trim())?),
+                        op1: self.parse_op1(res[i + 1..].trim(), relative)?,
+                    });
+                }
+                _ => (),
+            }
+        }
+        Ok(ResExpr {
+            res: Res::Op1,
+            op0: None,
+            op1: self.parse_op1(res, relative)?,
+        })
+    }
+
+    fn parse_op1(&self, op1: &str, relative: bool) -> Result<Op1, AssemblerError> {
+        let Some(inner) = op1.strip_prefix('[').and_then(|op1| op1.strip_suffix(']')) else {
+            if let Some(value) = parse_felt(op1) {
+                return Ok(Op1::Immediate(Immediate::Value(value)));
+            }
+            if relative && is_identifier(op1) {
+                return Ok(Op1::Immediate(Immediate::Label(op1.to_string())));
+            }
+            return Err(self.invalid(op1));
+        };
+        let inner = inner.trim();
+        if inner.starts_with('[') {
+            let end = inner.find(']').ok_or_else(|| self.invalid(op1))?;
+            let op0 = self.parse_deref(&inner[..=end])?;
+            let offset = self.parse_offset(&inner[end + 1..])?;
+            return Ok(Op1::DoubleDeref(op0, offset));
+        }
+        Ok(Op1::Deref(self.parse_deref(op1)?))
+    }
+
+    // Parses `[ap]`, `[fp + 3]`, `[ap + -3]`, `[ap - 3]` or `[ap + (-3)]`
+    fn parse_deref(&self, deref: &str) -> Result<Deref, AssemblerError> {
+        let inner = deref
+            .strip_prefix('[')
+            .and_then(|deref| deref.strip_suffix(']'))
+            .ok_or_else(|| self.invalid(deref))?
+            .trim();
+        let register = match inner.get(..2) {
+            Some("ap") => Register::AP,
+            Some("fp") => Register::FP,
+            _ => return Err(self.invalid(deref)),
+        };
+        Ok(Deref {
+            register,
+            offset: self.parse_offset(&inner[2..])?,
+        })
+    }
+
+    // Parses an empty string, `+ 3`, `+ -3`, `- 3` or `+ (-3)`
+    fn parse_offset(&self, offset: &str) -> Result<isize, AssemblerError> {
+        let compact: String = offset
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '(' && *c != ')')
+            .collect();
+        let parsed = if compact.is_empty() {
+            Some(0)
+        } else if let Some(value) = compact.strip_prefix('+') {
+            value.parse::<isize>().ok()
+        } else if let Some(value) = compact.strip_prefix('-') {
+            value.parse::<isize>().ok().map(|value| -value)
+        } else {
+            None
+        };
+        parsed.ok_or_else(|| self.invalid(offset))
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    line.split_once('#')
+        .map(|(statement, _)| statement)
+        .unwrap_or(line)
+        .trim()
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+// Parses a decimal or hexadecimal integer, which may be negative
+fn parse_felt(value: &str) -> Option<Felt252> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value.trim()),
+        None => (false, value),
+    };
+    if !value.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let felt = if value.starts_with("0x") {
+        Felt252::from_hex(value).ok()?
+    } else {
+        Felt252::from_dec_str(value).ok()?
+    };
+    Some(if negative { -felt } else { felt })
+}
+
+// Splits at the separators that aren't inside brackets
+fn split_top_level(statement: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in statement.char_indices() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(statement[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(statement[start..].trim());
+    parts
+}
+
+// Removes the indentation shared by the lines of a hint block, along with its blank first and last lines
+fn dedent(lines: &[&str]) -> String {
+    let lines: Vec<&str> = lines
+        .iter()
+        .map(|line| line.trim_end())
+        .skip_while(|line| line.is_empty())
+        .collect();
+    let end = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |i| i + 1);
+    let indent = lines[..end]
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines[..end]
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::layout_name::LayoutName,
+        vm::{decoding::disassembler::disassemble, runners::cairo_runner::CairoRunner},
+    };
+    use assert_matches::assert_matches;
+    use rstest::rstest;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    const DOUBLE: &str = "
+        %builtins output
+        __main__.main:
+            [ap] = 21, ap++
+            call __main__.double  # calls can take a label
+            [ap - 1] = [[fp + (-3)]]
+            [ap] = [fp + -3] + 1, ap++
+            %{
+                memory[ap] = segments.add()
+            %}
+            ret
+        __main__.double:
+            [ap] = [fp + -3] + [fp + -3], ap++
+            ret
+    ";
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn assemble_program() {
+        let program = assemble(DOUBLE, Some("main")).unwrap();
+        assert_eq!(program.builtins, vec![BuiltinName::output]);
+        assert_eq!(program.shared_program_data.main, Some(0));
+        assert_eq!(program.data_len(), 10);
+        let double = program.get_identifier("__main__.double").unwrap();
+        assert_eq!(double.pc, Some(8));
+        assert_eq!(double.type_.as_deref(), Some("function"));
+        assert_eq!(
+            disassemble(&program),
+            "__main__.main:\n    \
+             0: [ap + 0] = 21, ap++\n    \
+             2: call rel 6 # __main__.double\n    \
+             4: [ap + -1] = [[fp + -3] + 0]\n    \
+             5: [ap + 0] = [fp + -3] + 1, ap++\n    \
+             %{ memory[ap] = segments.add() %}\n    \
+             7: ret\n\
+             __main__.double:\n    \
+             8: [ap + 0] = [fp + -3] + [fp + -3], ap++\n    \
+             9: ret\n"
+        );
+        let hints = crate::stdlib::collections::BTreeMap::<usize, Vec<HintParams>>::from(
+            &program.shared_program_data.hints_collection,
+        );
+        assert_eq!(
+            hints[&7][0].accessible_scopes,
+            vec!["__main__", "__main__.main"]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_assembled_program() {
+        let program = assemble(DOUBLE, Some("main")).unwrap();
+        let mut runner = CairoRunner::new(&program, LayoutName::all_cairo, false, false).unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let end = runner.initialize(false).unwrap();
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        let mut output = String::new();
+        runner.vm.write_output(&mut output).unwrap();
+        assert_eq!(output, "42\n");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn reassemble_disassembled_program() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let reassembled = assemble(&disassemble(&program), Some("main")).unwrap();
+        assert_eq!(
+            reassembled.shared_program_data.data,
+            program.shared_program_data.data
+        );
+        assert_eq!(
+            reassembled.shared_program_data.main,
+            program.shared_program_data.main
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn assemble_for_proof_mode() {
+        let program = assemble_for_proof(
+            "
+            __start__:
+                ap += 0
+                call rel main
+            __end__:
+                jmp rel 0
+            main:
+                dw 0x208b7fff7fff7ffe
+            ",
+        )
+        .unwrap();
+        assert_eq!(program.shared_program_data.start, Some(0));
+        assert_eq!(program.shared_program_data.end, Some(4));
+        assert_eq!(
+            program.shared_program_data.data,
+            vec![
+                Felt252::from(0x40780017fff7fff_u64).into(),
+                Felt252::ZERO.into(),
+                Felt252::from(0x1104800180018000_u64).into(),
+                Felt252::from(4).into(),
+                Felt252::from(0x10780017fff7fff_u64).into(),
+                Felt252::ZERO.into(),
+                Felt252::from(0x208b7fff7fff7ffe_u64).into(),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("[ap] = ", AssemblerError::InvalidSyntax(1, "[ap] =".into()))]
+    #[case("[sp] = 1", AssemblerError::InvalidSyntax(1, "[sp]".into()))]
+    #[case("[ap + 40000] = 1", AssemblerError::OffsetOutOfRange(1, 40000))]
+    #[case("jmp rel nowhere", AssemblerError::UnknownLabel(1, "nowhere".into()))]
+    #[case("a:\na:", AssemblerError::DuplicateLabel(2, "a".into()))]
+    #[case("%builtins output pedersen2", AssemblerError::UnknownBuiltin(1, "pedersen2".into()))]
+    #[case("ret\n%{\nx = 1", AssemblerError::UnterminatedHint(2))]
+    #[case("[ap] = [fp] + 1, ap += 2", AssemblerError::InvalidApUpdate(1))]
+    #[case("[ap] = [fp] + [[ap] + 2]", AssemblerError::InvalidSyntax(1, "[ap] = [fp] + [[ap] + 2]".into()))]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn assemble_errors(#[case] casm: &str, #[case] error: AssemblerError) {
+        assert_eq!(
+            assemble(casm, None).unwrap_err().to_string(),
+            error.to_string()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn assemble_missing_entrypoint() {
+        assert_matches!(
+            assemble("ret", Some("main")),
+            Err(AssemblerError::Program(ProgramError::EntrypointNotFound(name))) if name == "main"
+        );
+        assert_matches!(
+            assemble_for_proof("__start__:\nret"),
+            Err(AssemblerError::Program(ProgramError::EntrypointNotFound(name))) if name == "__end__"
+        );
+    }
+}
@@ -127,6 +127,64 @@ pub fn decode_instruction(encoded_instr: u64) -> Result<Instruction, VirtualMach
     })
 }
 
+/// Encodes an instruction, the inverse of [decode_instruction].
+/// The offsets must be in the range [-2^15, 2^15).
+pub fn encode_instruction(instruction: &Instruction) -> u64 {
+    let dst_reg_num = match instruction.dst_register {
+        Register::AP => 0,
+        Register::FP => 1,
+    };
+    let op0_reg_num = match instruction.op0_register {
+        Register::AP => 0,
+        Register::FP => 1,
+    };
+    let op1_src_num = match instruction.op1_addr {
+        Op1Addr::Op0 => 0,
+        Op1Addr::Imm => 1,
+        Op1Addr::FP => 2,
+        Op1Addr::AP => 4,
+    };
+    let res_logic_num = match instruction.res {
+        Res::Op1 | Res::Unconstrained => 0,
+        Res::Add => 1,
+        Res::Mul => 2,
+    };
+    let pc_update_num = match instruction.pc_update {
+        PcUpdate::Regular => 0,
+        PcUpdate::Jump => 1,
+        PcUpdate::JumpRel => 2,
+        PcUpdate::Jnz => 4,
+    };
+    // Calls advance ap by 2 without setting any flag
+    let ap_update_num = match instruction.ap_update {
+        ApUpdate::Regular | ApUpdate::Add2 => 0,
+        ApUpdate::Add => 1,
+        ApUpdate::Add1 => 2,
+    };
+    let opcode_num = match instruction.opcode {
+        Opcode::NOp => 0,
+        Opcode::Call => 1,
+        Opcode::Ret => 2,
+        Opcode::AssertEq => 4,
+    };
+    let flags: u64 = dst_reg_num
+        | op0_reg_num << 1
+        | op1_src_num << 2
+        | res_logic_num << 5
+        | pc_update_num << 7
+        | ap_update_num << 10
+        | opcode_num << 12;
+
+    flags << 48
+        | encode_offset(instruction.off2) << 32
+        | encode_offset(instruction.off1) << 16
+        | encode_offset(instruction.off0)
+}
+
+fn encode_offset(offset: isize) -> u64 {
+    (offset as i16 as u16 ^ 0x8000) as u64
+}
+
 fn decode_offset(offset: u64) -> isize {
     let vectorized_offset: [u8; 8] = offset.to_le_bytes();
     let offset_16b_encoded = u16::from_le_bytes([vectorized_offset[0], vectorized_offset[1]]);
@@ -140,6 +198,7 @@ mod decoder_test {
     use super::*;
     use crate::stdlib::string::ToString;
     use assert_matches::assert_matches;
+    use rstest::rstest;
 
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::*;
@@ -305,4 +364,20 @@ mod decoder_test {
         assert_eq!(inst.off1, 0);
         assert_eq!(inst.off2, 1);
     }
+
+    #[rstest]
+    #[case(0x480680017fff8000)]
+    #[case(0x482680017ffd8000)]
+    #[case(0x40497ffd80007ffc)]
+    #[case(0x1104800180018000)]
+    #[case(0x208b7fff7fff7ffe)]
+    #[case(0x10780017fff7fff)]
+    #[case(0x20680017fff7fff)]
+    #[case(0x40780017fff7fff)]
+    #[case(0x0000800180007FFF)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn encode_decoded_instruction(#[case] encoded: u64) {
+        let instruction = decode_instruction(encoded).unwrap();
+        assert_eq!(encode_instruction(&instruction), encoded);
+    }
 }
@@ -0,0 +1,302 @@
+//! Disassembler rendering the bytecode of compiled Cairo 0 programs as textual CASM, such as
+//! `[ap + 0] = [fp + -3] + 5, ap++` or `call rel 12`.
+
+use crate::stdlib::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Write},
+    prelude::*,
+};
+use num_traits::ToPrimitive;
+
+use crate::{
+    math_utils::signed_felt,
+    serde::deserialize_program::HintParams,
+    types::{
+        instruction::{ApUpdate, Instruction, Op1Addr, Opcode, PcUpdate, Register, Res},
+        program::Program,
+        relocatable::MaybeRelocatable,
+    },
+    vm::decoding::decoder::decode_instruction,
+    Felt252,
+};
+
+/// A word of a program's bytecode rendered as CASM, along with the labels pointing at it and the
+/// hints run before it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisassembledInstruction {
+    pub pc: usize,
+    /// Number of words taken by the instruction, 2 if it has an immediate
+    pub size: usize,
+    pub labels: Vec<String>,
+    pub hints: Vec<String>,
+    pub casm: String,
+}
+
+impl fmt::Display for DisassembledInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for label in self.labels.iter() {
+            writeln!(f, "{label}:")?;
+        }
+        for hint in self.hints.iter() {
+            if hint.lines().count() > 1 {
+                writeln!(f, "    %{{")?;
+                for line in hint.lines() {
+                    writeln!(f, "        {line}")?;
+                }
+                writeln!(f, "    %}}")?;
+            } else {
+                writeln!(f, "    %{{ {hint} %}}")?;
+            }
+        }
+        write!(f, "    {}: {}", self.pc, self.casm)
+    }
+}
+
+fn deref(register: Register, offset: isize) -> String {
+    match register {
+        Register::AP => format!("[ap + {offset}]"),
+        Register::FP => format!("[fp + {offset}]"),
+    }
+}
+
+/// Renders a decoded instruction as CASM, `imm` being the word that follows it in the bytecode.
+/// Immediates are shown as signed integers.
+pub fn instruction_to_casm(instruction: &Instruction, imm: Option<&Felt252>) -> String {
+    let dst = deref(instruction.dst_register, instruction.off0);
+    let op0 = deref(instruction.op0_register, instruction.off1);
+    let op1 = match instruction.op1_addr {
+        Op1Addr::Imm => imm
+            .map(|imm| signed_felt(*imm).to_string())
+            .unwrap_or_else(|| "?".to_string()),
+        Op1Addr::AP => deref(Register::AP, instruction.off2),
+        Op1Addr::FP => deref(Register::FP, instruction.off2),
+        Op1Addr::Op0 => format!("[{op0} + {}]", instruction.off2),
+    };
+    let res = match instruction.res {
+        Res::Op1 | Res::Unconstrained => op1.clone(),
+        Res::Add => format!("{op0} + {op1}"),
+        Res::Mul => format!("{op0} * {op1}"),
+    };
+
+    let mut parts = Vec::new();
+    match (instruction.opcode, instruction.pc_update) {
+        // The return address is implied by the opcode
+        (Opcode::Ret, _) => parts.push("ret".to_string()),
+        (Opcode::Call, PcUpdate::Jump) => parts.push(format!("call abs {res}")),
+        (Opcode::Call, PcUpdate::JumpRel) => parts.push(format!("call rel {res}")),
+        (opcode, pc_update) => {
+            if opcode == Opcode::AssertEq {
+                parts.push(format!("{dst} = {res}"));
+            }
+            match pc_update {
+                PcUpdate::Regular => (),
+                PcUpdate::Jump => parts.push(format!("jmp abs {res}")),
+                PcUpdate::JumpRel => parts.push(format!("jmp rel {res}")),
+                PcUpdate::Jnz => parts.push(format!("jmp rel {op1} if {dst} != 0")),
+            }
+        }
+    }
+    match instruction.ap_update {
+        ApUpdate::Regular => (),
+        ApUpdate::Add => parts.push(format!("ap += {res}")),
+        ApUpdate::Add1 => parts.push("ap++".to_string()),
+        // Calls always advance ap by 2
+        ApUpdate::Add2 if instruction.opcode == Opcode::Call => (),
+        ApUpdate::Add2 => parts.push("ap += 2".to_string()),
+    }
+    if parts.is_empty() {
+        return "nop".to_string();
+    }
+    parts.join(", ")
+}
+
+// Returns the pc an instruction jumps to, if it is given by an immediate
+fn jump_target(instruction: &Instruction, pc: usize, imm: &Felt252) -> Option<usize> {
+    if instruction.op1_addr != Op1Addr::Imm || instruction.opcode == Opcode::Ret {
+        return None;
+    }
+    let imm = signed_felt(*imm).to_isize()?;
+    match instruction.pc_update {
+        PcUpdate::Jump => usize::try_from(imm).ok(),
+        PcUpdate::JumpRel | PcUpdate::Jnz => pc.checked_add_signed(imm),
+        PcUpdate::Regular => None,
+    }
+}
+
+/// Disassembles the bytecode of a program.
+///
+/// Labels and functions are resolved from the program's identifiers, both to mark the pcs they
+/// point at and to annotate the jumps and calls to them. Words that can't be decoded as an
+/// instruction are rendered as `dw <value>`.
+pub fn disassemble_program(program: &Program) -> Vec<DisassembledInstruction> {
+    let mut labels = HashMap::<usize, Vec<String>>::new();
+    for (name, identifier) in program.iter_identifiers() {
+        if let (Some("function" | "label"), Some(pc)) = (identifier.type_.as_deref(), identifier.pc)
+        {
+            labels.entry(pc).or_default().push(name.to_string());
+        }
+    }
+    labels.values_mut().for_each(|names| names.sort());
+    let hints =
+        BTreeMap::<usize, Vec<HintParams>>::from(&program.shared_program_data.hints_collection);
+
+    let data = &program.shared_program_data.data;
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+    while pc < data.len() {
+        let imm = data.get(pc + 1).and_then(MaybeRelocatable::get_int_ref);
+        let decoded = data[pc]
+            .get_int_ref()
+            .and_then(Felt252::to_u64)
+            .and_then(|encoded| decode_instruction(encoded).ok())
+            .filter(|instruction| instruction.size() == 1 || imm.is_some());
+        let (size, casm) = match decoded {
+            Some(instruction) => {
+                let mut casm = instruction_to_casm(&instruction, imm);
+                let target_label = imm
+                    .and_then(|imm| jump_target(&instruction, pc, imm))
+                    .and_then(|target| labels.get(&target)?.first());
+                if let Some(label) = target_label {
+                    casm.push_str(&format!(" # {label}"));
+                }
+                (instruction.size(), casm)
+            }
+            None => (1, format!("dw {}", data[pc])),
+        };
+        instructions.push(DisassembledInstruction {
+            pc,
+            size,
+            labels: labels.get(&pc).cloned().unwrap_or_default(),
+            hints: hints
+                .get(&pc)
+                .map(|hints| hints.iter().map(|hint| hint.code.clone()).collect())
+                .unwrap_or_default(),
+            casm,
+        });
+        pc += size;
+    }
+    instructions
+}
+
+/// Disassembles the bytecode of a program into a CASM listing, see [disassemble_program]
+pub fn disassemble(program: &Program) -> String {
+    let mut listing = String::new();
+    for instruction in disassemble_program(program) {
+        // Writing into a String can't fail
+        let _ = writeln!(listing, "{instruction}");
+    }
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde::deserialize_program::{ApTracking, FlowTrackingData, Identifier};
+    use crate::{relocatable, types::relocatable::Relocatable, utils::test_utils::*};
+    use rstest::rstest;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[rstest]
+    #[case(0x480680017fff8000, Some(5), "[ap + 0] = 5, ap++")]
+    #[case(0x482680017ffd8000, Some(5), "[ap + 0] = [fp + -3] + 5, ap++")]
+    #[case(0x40497ffd80007ffc, None, "[fp + -4] = [ap + 0] * [fp + -3]")]
+    #[case(0x48127ffe7fff8000, None, "[ap + 0] = [ap + -2], ap++")]
+    #[case(0x1104800180018000, Some(12), "call rel 12")]
+    #[case(0x208b7fff7fff7ffe, None, "ret")]
+    #[case(0x10780017fff7fff, Some(-4), "jmp rel -4")]
+    #[case(0x20680017fff7fff, Some(6), "jmp rel 6 if [ap + -1] != 0")]
+    #[case(0x40780017fff7fff, Some(3), "ap += 3")]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn instruction_to_casm_cases(
+        #[case] encoded: u64,
+        #[case] imm: Option<i64>,
+        #[case] expected: &str,
+    ) {
+        let instruction = decode_instruction(encoded).unwrap();
+        let imm = imm.map(Felt252::from);
+        assert_eq!(instruction_to_casm(&instruction, imm.as_ref()), expected);
+    }
+
+    fn function(pc: usize) -> Identifier {
+        Identifier {
+            pc: Some(pc),
+            type_: Some(String::from("function")),
+            value: None,
+            full_name: None,
+            members: None,
+            cairo_type: None,
+        }
+    }
+
+    fn program_with_labels() -> Program {
+        let hint = HintParams {
+            code: "a = 1\nb = 2".to_string(),
+            accessible_scopes: vec![],
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking::default(),
+                reference_ids: HashMap::new(),
+            },
+        };
+        program!(
+            data = vec_data!(
+                (0x1104800180018000_u64),
+                (3),
+                (0x208b7fff7fff7ffe_u64),
+                (0x480680017fff8000_u64),
+                (7),
+                (0x208b7fff7fff7ffe_u64),
+                ((1, 0))
+            ),
+            hints = BTreeMap::from([(3, vec![hint])]),
+            identifiers = HashMap::from([
+                (String::from("__main__.main"), function(0)),
+                (String::from("__main__.seven"), function(3)),
+            ]),
+        )
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn disassemble_program_with_labels_and_hints() {
+        let instructions = disassemble_program(&program_with_labels());
+        assert_eq!(
+            instructions
+                .iter()
+                .map(|instruction| (instruction.pc, instruction.size))
+                .collect::<Vec<_>>(),
+            vec![(0, 2), (2, 1), (3, 2), (5, 1), (6, 1)]
+        );
+        assert_eq!(instructions[0].labels, vec!["__main__.main"]);
+        assert_eq!(instructions[0].casm, "call rel 3 # __main__.seven");
+        assert_eq!(instructions[2].hints, vec!["a = 1\nb = 2"]);
+        assert_eq!(instructions[4].casm, format!("dw {}", relocatable!(1, 0)));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn disassemble_listing() {
+        assert_eq!(
+            disassemble(&program_with_labels()),
+            "__main__.main:\n    \
+             0: call rel 3 # __main__.seven\n    \
+             2: ret\n\
+             __main__.seven:\n    \
+             %{\n        a = 1\n        b = 2\n    %}\n    \
+             3: [ap + 0] = 7, ap++\n    \
+             5: ret\n    \
+             6: dw 1:0\n"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn disassemble_truncated_immediate() {
+        let program = program!(data = vec_data!((0x480680017fff8000_u64)),);
+        let instructions = disassemble_program(&program);
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].size, 1);
+        assert!(instructions[0].casm.starts_with("dw "));
+    }
+}
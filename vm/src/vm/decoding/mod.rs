@@ -1 +1,3 @@
+pub mod assembler;
 pub mod decoder;
+pub mod disassembler;
@@ -132,6 +132,20 @@ pub enum RunnerError {
     CairoPieProofMode,
     #[error("{0}: Invalid additional data")]
     InvalidAdditionalData(BuiltinName),
+    #[error("{0}: The snapshot doesn't match the runner's builtin")]
+    SnapshotBuiltinMismatch(BuiltinName),
+    #[error("The snapshot has {0} builtins but the runner has {1}")]
+    SnapshotBuiltinCountMismatch(usize, usize),
+    #[error("The snapshot has no dict manager at index {0}")]
+    SnapshotMissingDictManager(usize),
+    #[error("Can't step back once the run has ended")]
+    StepBackAfterEndRun,
+    #[error("The dynamic layout requires layout params")]
+    MissingDynamicLayoutParams,
+    #[error("Invalid dynamic layout param: {0}")]
+    InvalidDynamicLayoutParams(&'static str),
+    #[error("{0}: The custom builtin was not added to the runner")]
+    MissingCustomBuiltin(BuiltinName),
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
 use thiserror_no_std::Error;
 
-use crate::vm::errors::memory_errors::MemoryError;
+use crate::{stdlib::prelude::*, vm::errors::memory_errors::MemoryError};
 
 #[derive(Debug, PartialEq, Error)]
 pub enum TraceError {
@@ -14,6 +14,8 @@ pub enum TraceError {
     MemoryError(#[from] MemoryError),
     #[error("Trace not relocated")]
     TraceNotRelocated,
+    #[error("Failed to write the trace to its sink: {0}")]
+    SinkFailure(Box<str>),
 }
 
 #[cfg(test)]
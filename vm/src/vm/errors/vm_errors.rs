@@ -12,9 +12,12 @@ use crate::{
         errors::math_errors::MathError,
         relocatable::{MaybeRelocatable, Relocatable},
     },
-    vm::errors::{
-        exec_scope_errors::ExecScopeError, hint_errors::HintError, memory_errors::MemoryError,
-        runner_errors::RunnerError, trace_errors::TraceError,
+    vm::{
+        errors::{
+            exec_scope_errors::ExecScopeError, hint_errors::HintError, memory_errors::MemoryError,
+            runner_errors::RunnerError, trace_errors::TraceError,
+        },
+        runners::cairo_runner::RunLimit,
     },
 };
 
@@ -104,6 +107,8 @@ pub enum VirtualMachineError {
     SliceToArrayError,
     #[error("Failed to compile hint: {0}")]
     CompileHintFail(Box<str>),
+    #[error("Hint{} is not whitelisted:\n%{{\n{}\n%}}", (*.0).0.map(|pc| format!(" at pc {pc}")).unwrap_or_default(), (*.0).1)]
+    HintNotWhitelisted(Box<(Option<usize>, Box<str>)>),
     #[error("op1_addr is Op1Addr.IMM, but no immediate was given")]
     NoImm,
     #[error("Execution reached the end of the program. Requested remaining steps: {0}.")]
@@ -112,6 +117,8 @@ pub enum VirtualMachineError {
     StepsLimit(u64),
     #[error("Could not reach the end of the program. RunResources has no remaining steps.")]
     UnfinishedExecution,
+    #[error("Could not reach the end of the program: {0}")]
+    RunInterrupted(RunLimit),
     #[error("Current run is not finished")]
     RunNotFinished,
     #[error("Invalid argument count, expected {} but got {}", (*.0).0, (*.0).1)]
@@ -134,6 +141,10 @@ pub enum VirtualMachineError {
     FailedToWriteOutput,
     #[error("Failed to find index {0} in the vm's relocation table")]
     RelocationNotFound(usize),
+    #[error("The vm's journal is not enabled")]
+    JournalNotEnabled,
+    #[error("Can't step back {} instructions, only {} were recorded", (*.0).0, (*.0).1)]
+    StepBackOutOfRange(Box<(usize, usize)>),
     #[error("{} batch size is not {}", (*.0).0, (*.0).1)]
     ModBuiltinBatchSize(Box<(BuiltinName, usize)>),
 }
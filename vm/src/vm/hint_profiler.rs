@@ -0,0 +1,416 @@
+//! Hint profiler, counting the executions of each hint and the time spent in them, both per
+//! distinct hint code and per pc.
+//!
+//! Time is only measured with the `std` feature. Each execution can also be logged as a JSON
+//! line holding its pc, the hash of its code and the values of its ids when the hint starts.
+
+use crate::stdlib::{any::Any, borrow::Cow, collections::HashMap, fmt::Write as _, prelude::*};
+use core::time::Duration;
+
+use crate::{
+    hint_processor::builtin_hint_processor::builtin_hint_processor_definition::HintProcessorData,
+    types::relocatable::Relocatable,
+    vm::{errors::vm_errors::VirtualMachineError, vm_core::VirtualMachine},
+};
+
+/// Label used for the hints whose code can't be recovered from their hint data
+pub const UNKNOWN_HINT_CODE: &str = "<unknown hint>";
+
+/// Executions of a hint and the time spent in them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HintStats {
+    pub count: u64,
+    pub time: Duration,
+}
+
+impl HintStats {
+    fn record(&mut self, time: Duration) {
+        self.count += 1;
+        self.time += time;
+    }
+}
+
+/// Collects hint statistics during a run, see [`VirtualMachine::set_hint_profiler`]
+#[derive(Default)]
+pub struct HintProfiler {
+    by_code: HashMap<String, HintStats>,
+    by_pc: HashMap<Relocatable, HintStats>,
+    #[cfg(feature = "std")]
+    log: Option<Box<dyn std::io::Write + Send + Sync>>,
+}
+
+/// Start of a hint execution, as returned by [`HintProfiler::start_hint`]
+pub(crate) struct HintStart {
+    pc: Relocatable,
+    #[cfg(feature = "std")]
+    instant: std::time::Instant,
+}
+
+impl HintProfiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes a JSON line to `log` for each hint execution
+    #[cfg(feature = "std")]
+    pub fn with_log(mut self, log: Box<dyn std::io::Write + Send + Sync>) -> Self {
+        self.log = Some(log);
+        self
+    }
+
+    /// Called right before executing a hint at the vm's current pc, logs it if needed
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    pub(crate) fn start_hint(
+        &mut self,
+        vm: &VirtualMachine,
+        hint_data: &dyn Any,
+    ) -> Result<HintStart, VirtualMachineError> {
+        let pc = vm.get_pc();
+        #[cfg(feature = "std")]
+        if let Some(log) = self.log.as_mut() {
+            let mut line = serde_json::to_vec(&log_entry(vm, pc, hint_data))
+                .map_err(|err| VirtualMachineError::Other(err.into()))?;
+            line.push(b'\n');
+            log.write_all(&line)
+                .map_err(|err| VirtualMachineError::Other(err.into()))?;
+        }
+        Ok(HintStart {
+            pc,
+            #[cfg(feature = "std")]
+            instant: std::time::Instant::now(),
+        })
+    }
+
+    /// Called once the hint started with `start` has been executed, successfully or not
+    pub(crate) fn end_hint(&mut self, start: HintStart, hint_data: &dyn Any) {
+        #[cfg(feature = "std")]
+        let time = start.instant.elapsed();
+        #[cfg(not(feature = "std"))]
+        let time = Duration::ZERO;
+        let code = hint_code(hint_data);
+        match self.by_code.get_mut(code.as_ref()) {
+            Some(stats) => stats.record(time),
+            None => {
+                let mut stats = HintStats::default();
+                stats.record(time);
+                self.by_code.insert(code.into_owned(), stats);
+            }
+        }
+        self.by_pc.entry(start.pc).or_default().record(time);
+    }
+
+    /// Flushes the hint log, which is otherwise only flushed when the profiler is dropped
+    #[cfg(feature = "std")]
+    pub fn flush(&mut self) -> Result<(), VirtualMachineError> {
+        match self.log.as_mut() {
+            Some(log) => log
+                .flush()
+                .map_err(|err| VirtualMachineError::Other(err.into())),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the statistics of each distinct hint code, most time consuming first
+    pub fn get_stats_by_code(&self) -> Vec<(&str, HintStats)> {
+        let mut stats: Vec<_> = self
+            .by_code
+            .iter()
+            .map(|(code, stats)| (code.as_str(), *stats))
+            .collect();
+        stats.sort_by(|(code_a, a), (code_b, b)| by_cost(a, b).then(code_a.cmp(code_b)));
+        stats
+    }
+
+    /// Returns the statistics of the hints run at each pc, most time consuming first
+    pub fn get_stats_by_pc(&self) -> Vec<(Relocatable, HintStats)> {
+        let mut stats: Vec<_> = self.by_pc.iter().map(|(pc, stats)| (*pc, *stats)).collect();
+        stats.sort_by(|(pc_a, a), (pc_b, b)| by_cost(a, b).then(pc_a.cmp(pc_b)));
+        stats
+    }
+
+    /// Formats the `top` most time consuming hint codes and pcs
+    pub fn summary(&self, top: usize) -> String {
+        let mut summary = String::new();
+        let _ = writeln!(summary, "Hints by code (top {top}):");
+        for (code, stats) in self.get_stats_by_code().into_iter().take(top) {
+            // Keep each entry in one line
+            let first_line = code.lines().next().unwrap_or_default();
+            let ellipsis = if code.contains('\n') { " ..." } else { "" };
+            let _ = writeln!(
+                summary,
+                "  {:>8} calls {:>12?}  {first_line}{ellipsis}",
+                stats.count, stats.time
+            );
+        }
+        let _ = writeln!(summary, "Hints by pc (top {top}):");
+        for (pc, stats) in self.get_stats_by_pc().into_iter().take(top) {
+            let _ = writeln!(
+                summary,
+                "  {:>8} calls {:>12?}  pc {pc}",
+                stats.count, stats.time
+            );
+        }
+        summary
+    }
+}
+
+fn by_cost(a: &HintStats, b: &HintStats) -> core::cmp::Ordering {
+    b.time.cmp(&a.time).then(b.count.cmp(&a.count))
+}
+
+/// Returns the code of a compiled hint, for the hint data of the builtin and cairo 1 hint processors
+fn hint_code(hint_data: &dyn Any) -> Cow<str> {
+    if let Some(data) = hint_data.downcast_ref::<HintProcessorData>() {
+        return Cow::Borrowed(&data.code);
+    }
+    #[cfg(feature = "cairo-1-hints")]
+    if let Some(hints) = hint_data.downcast_ref::<Vec<cairo_lang_casm::hints::Hint>>() {
+        return Cow::Owned(
+            hints
+                .iter()
+                .map(|hint| hint.representing_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+    Cow::Borrowed(UNKNOWN_HINT_CODE)
+}
+
+#[cfg(feature = "std")]
+fn log_entry(vm: &VirtualMachine, pc: Relocatable, hint_data: &dyn Any) -> serde_json::Value {
+    use crate::hint_processor::hint_processor_utils::get_maybe_relocatable_from_reference;
+    use sha2::{Digest, Sha256};
+
+    let code = hint_code(hint_data);
+    let code_hash = Sha256::digest(code.as_bytes())
+        .iter()
+        .fold(String::new(), |mut hash, byte| {
+            let _ = write!(hash, "{byte:02x}");
+            hash
+        });
+    let ids: serde_json::Map<_, _> = hint_data
+        .downcast_ref::<HintProcessorData>()
+        .map(|hint_data| {
+            let mut ids: Vec<_> = hint_data.ids_data.iter().collect();
+            ids.sort_by_key(|(name, _)| *name);
+            ids.into_iter()
+                .map(|(name, reference)| {
+                    let value =
+                        get_maybe_relocatable_from_reference(vm, reference, &hint_data.ap_tracking)
+                            .map_or(serde_json::Value::Null, |value| value.to_string().into());
+                    (name.clone(), value)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    serde_json::json!({
+        "pc": pc.to_string(),
+        "code_hash": code_hash,
+        "ids": ids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::{
+            builtin_hint_processor::{
+                builtin_hint_processor_definition::BuiltinHintProcessor, hint_code,
+            },
+            hint_processor_definition::HintReference,
+        },
+        stdlib::sync::{Arc, Mutex},
+        types::layout_name::LayoutName,
+        utils::test_utils::*,
+        vm::{decoding::assembler::assemble, runners::cairo_runner::CairoRunner},
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    // Runs the hint at pc 0 once and the one at pc 7 twice
+    const PROGRAM: &str = "
+        __main__.main:
+            %{
+                memory[ap] = segments.add()
+            %}
+            ap += 1
+            call __main__.f
+            call __main__.f
+            ret
+        __main__.f:
+            %{
+                memory[ap] = segments.add()
+            %}
+            ap += 1
+            ret
+    ";
+
+    fn profiled_run(hint_profiler: HintProfiler) -> CairoRunner {
+        let program = assemble(PROGRAM, Some("main")).unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runner = CairoRunner::new(&program, LayoutName::plain, false, false).unwrap();
+        let end = runner.initialize(false).unwrap();
+        runner.vm.set_hint_profiler(hint_profiler);
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        runner
+    }
+
+    /// Writer whose content can be read while the vm owns it
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hint_profiler_counts_executions() {
+        let runner = profiled_run(HintProfiler::new());
+        let hint_profiler = runner.vm.get_hint_profiler().unwrap();
+        let by_code = hint_profiler.get_stats_by_code();
+        assert_eq!(by_code.len(), 1);
+        assert_eq!(by_code[0].0, hint_code::ADD_SEGMENT);
+        assert_eq!(by_code[0].1.count, 3);
+        let mut by_pc: Vec<_> = hint_profiler
+            .get_stats_by_pc()
+            .into_iter()
+            .map(|(pc, stats)| (pc, stats.count))
+            .collect();
+        by_pc.sort();
+        assert_eq!(
+            by_pc,
+            vec![
+                (Relocatable::from((0, 0)), 1),
+                (Relocatable::from((0, 7)), 2)
+            ]
+        );
+        let time: Duration = by_pc
+            .iter()
+            .map(|(pc, _)| hint_profiler.by_pc[pc].time)
+            .sum();
+        assert_eq!(by_code[0].1.time, time);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hint_profiler_not_enabled() {
+        let program = assemble(PROGRAM, Some("main")).unwrap();
+        let runner = CairoRunner::new(&program, LayoutName::plain, false, false).unwrap();
+        assert!(runner.vm.get_hint_profiler().is_none());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hint_profiler_logs_executions() {
+        let log = SharedBuffer::default();
+        profiled_run(HintProfiler::new().with_log(Box::new(log.clone())));
+        let log = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+        let entries: Vec<serde_json::Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let pcs: Vec<_> = entries.iter().map(|entry| entry["pc"].clone()).collect();
+        assert_eq!(pcs, vec!["0:0", "0:7", "0:7"]);
+        assert_eq!(entries[0]["code_hash"], entries[1]["code_hash"]);
+        assert_eq!(entries[0]["code_hash"].as_str().unwrap().len(), 64);
+        assert_eq!(entries[0]["ids"], serde_json::json!({}));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hint_profiler_flushes_log() {
+        let log = SharedBuffer::default();
+        let mut runner = profiled_run(
+            HintProfiler::new().with_log(Box::new(std::io::BufWriter::new(log.clone()))),
+        );
+        assert!(log.0.lock().unwrap().is_empty());
+        runner.vm.hint_profiler.as_mut().unwrap().flush().unwrap();
+        let log = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(log.lines().count(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "cairo-1-hints")]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cairo_1_hints_keyed_by_representation() {
+        use cairo_lang_casm::{
+            hints::{CoreHint, Hint},
+            operand::{CellRef, Register},
+        };
+
+        let hints: Vec<Hint> = vec![CoreHint::AllocSegment {
+            dst: CellRef {
+                register: Register::AP,
+                offset: 0,
+            },
+        }
+        .into()];
+        let mut hint_profiler = HintProfiler::new();
+        let start = hint_profiler.start_hint(&vm!(), &hints).unwrap();
+        hint_profiler.end_hint(start, &hints);
+        let by_code = hint_profiler.get_stats_by_code();
+        assert_eq!(by_code.len(), 1);
+        assert_eq!(by_code[0].0, hints[0].representing_string());
+        assert_ne!(by_code[0].0, UNKNOWN_HINT_CODE);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn log_entry_reads_ids() {
+        let mut vm = vm!();
+        vm.set_pc(Relocatable::from((0, 3)));
+        vm.segments = segments![((1, 0), 5), ((1, 1), (2, 3))];
+        let hint_data = HintProcessorData::new_default(
+            "x = 1".to_string(),
+            non_continuous_ids_data![("a", 0), ("b", 1), ("c", 2)],
+        );
+        assert_eq!(
+            log_entry(&vm, vm.get_pc(), &hint_data),
+            serde_json::json!({
+                "pc": "0:3",
+                "code_hash": "8ff436def1451285599a1b1ad70800493b8dcafde2912e1a38345633054e4c26",
+                "ids": {"a": "5", "b": "2:3", "c": null},
+            })
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn summary_lists_top_hints() {
+        let mut hint_profiler = HintProfiler::new();
+        for (pc, code, count) in [(3, "x = 1", 2), (5, "y = 2\nz = 3", 1), (9, "w = 4", 4)] {
+            hint_profiler.by_code.insert(
+                code.to_string(),
+                HintStats {
+                    count,
+                    time: Duration::from_millis(count),
+                },
+            );
+            hint_profiler.by_pc.insert(
+                Relocatable::from((0, pc)),
+                HintStats {
+                    count,
+                    time: Duration::from_millis(count),
+                },
+            );
+        }
+        assert_eq!(
+            hint_profiler.summary(2),
+            "Hints by code (top 2):\n\
+             \x20        4 calls          4ms  w = 4\n\
+             \x20        2 calls          2ms  x = 1\n\
+             Hints by pc (top 2):\n\
+             \x20        4 calls          4ms  pc 0:9\n\
+             \x20        2 calls          2ms  pc 0:3\n"
+        );
+    }
+}
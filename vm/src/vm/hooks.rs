@@ -8,17 +8,34 @@
 //! - before_first_step, executed before entering the execution loop in [run_until_pc](CairoRunner::run_until_pc)
 //! - pre_step_instruction, executed before each instruction_step in [step](VirtualMachine::step)
 //! - post_step_instruction, executed after each instruction_step in [step](VirtualMachine::step)
+//! - pre_hint, executed before each hint in [step_hint](VirtualMachine::step_hint), receives the hint code and pc
+//! - post_hint, executed after each hint in [step_hint](VirtualMachine::step_hint), receives the hint code and pc
+//! - memory_insert, executed on each successful insertion into the [Memory](crate::vm::vm_memory::memory::Memory), receives the address and value
+//! - segment_creation, executed each time a segment or temporary segment is added, receives its base
+//! - end_run, executed at the end of [end_run](CairoRunner::end_run)
+//!
+//! Hooks are set on the VM with [`VirtualMachine::set_hooks`] or [`VirtualMachineBuilder::hooks`](crate::vm::vm_core::VirtualMachineBuilder::hooks)
 
-use crate::stdlib::{any::Any, collections::HashMap, prelude::*, sync::Arc};
+use crate::stdlib::{any::Any, borrow::Cow, collections::HashMap, prelude::*, sync::Arc};
 
 use crate::Felt252;
 
 use crate::{
-    hint_processor::hint_processor_definition::HintProcessor, types::exec_scope::ExecutionScopes,
+    hint_processor::{
+        builtin_hint_processor::builtin_hint_processor_definition::HintProcessorData,
+        hint_processor_definition::HintProcessor,
+    },
+    types::{
+        exec_scope::ExecutionScopes,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
 };
 
 use super::{errors::vm_errors::VirtualMachineError, vm_core::VirtualMachine};
 
+#[cfg(doc)]
+use super::runners::cairo_runner::CairoRunner;
+
 type BeforeFirstStepHookFunc = Arc<
     dyn Fn(&mut VirtualMachine, &[Box<dyn Any>]) -> Result<(), VirtualMachineError> + Sync + Send,
 >;
@@ -35,6 +52,25 @@ type StepHookFunc = Arc<
         + Send,
 >;
 
+type HintHookFunc = Arc<
+    dyn Fn(
+            &mut VirtualMachine,
+            &mut ExecutionScopes,
+            &str,
+            Relocatable,
+        ) -> Result<(), VirtualMachineError>
+        + Sync
+        + Send,
+>;
+
+pub(crate) type MemoryInsertHookFunc = Arc<dyn Fn(Relocatable, &MaybeRelocatable) + Sync + Send>;
+
+pub(crate) type SegmentCreationHookFunc = Arc<dyn Fn(Relocatable) + Sync + Send>;
+
+type EndRunHookFunc = Arc<
+    dyn Fn(&mut VirtualMachine, &ExecutionScopes) -> Result<(), VirtualMachineError> + Sync + Send,
+>;
+
 /// The hooks to be executed during the VM run
 ///
 /// They can be individually ignored by setting them to [None]
@@ -43,6 +79,11 @@ pub struct Hooks {
     before_first_step: Option<BeforeFirstStepHookFunc>,
     pre_step_instruction: Option<StepHookFunc>,
     post_step_instruction: Option<StepHookFunc>,
+    pre_hint: Option<HintHookFunc>,
+    post_hint: Option<HintHookFunc>,
+    pub(crate) memory_insert: Option<MemoryInsertHookFunc>,
+    pub(crate) segment_creation: Option<SegmentCreationHookFunc>,
+    end_run: Option<EndRunHookFunc>,
 }
 
 impl Hooks {
@@ -55,11 +96,64 @@ impl Hooks {
             before_first_step,
             pre_step_instruction,
             post_step_instruction,
+            ..Default::default()
         }
     }
+
+    pub fn pre_hint(mut self, pre_hint: HintHookFunc) -> Self {
+        self.pre_hint = Some(pre_hint);
+        self
+    }
+
+    pub fn post_hint(mut self, post_hint: HintHookFunc) -> Self {
+        self.post_hint = Some(post_hint);
+        self
+    }
+
+    /// Memory insertion hooks can't access the VM, and can't fail
+    pub fn memory_insert(mut self, memory_insert: MemoryInsertHookFunc) -> Self {
+        self.memory_insert = Some(memory_insert);
+        self
+    }
+
+    /// Segment creation hooks can't access the VM, and can't fail
+    pub fn segment_creation(mut self, segment_creation: SegmentCreationHookFunc) -> Self {
+        self.segment_creation = Some(segment_creation);
+        self
+    }
+
+    pub fn end_run(mut self, end_run: EndRunHookFunc) -> Self {
+        self.end_run = Some(end_run);
+        self
+    }
+}
+
+/// Returns the code of a compiled hint, for the hint data of the builtin and cairo 1 hint processors
+fn hint_code(hint_data: &dyn Any) -> Cow<str> {
+    if let Some(data) = hint_data.downcast_ref::<HintProcessorData>() {
+        return Cow::Borrowed(&data.code);
+    }
+    #[cfg(feature = "cairo-1-hints")]
+    if let Some(hints) = hint_data.downcast_ref::<Vec<cairo_lang_casm::hints::Hint>>() {
+        return Cow::Owned(
+            hints
+                .iter()
+                .map(|hint| hint.representing_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+    Cow::Borrowed("")
 }
 
 impl VirtualMachine {
+    /// Sets the hooks to be executed during the run, replacing the previous ones
+    pub fn set_hooks(&mut self, hooks: Hooks) {
+        self.segments.memory.insert_hook = hooks.memory_insert.clone();
+        self.segments.segment_creation_hook = hooks.segment_creation.clone();
+        self.hooks = hooks;
+    }
+
     pub fn execute_before_first_step(
         &mut self,
         hint_data: &[Box<dyn Any>],
@@ -98,6 +192,43 @@ impl VirtualMachine {
 
         Ok(())
     }
+
+    pub fn execute_pre_hint(
+        &mut self,
+        exec_scope: &mut ExecutionScopes,
+        hint_data: &dyn Any,
+    ) -> Result<(), VirtualMachineError> {
+        if let Some(hook_func) = self.hooks.clone().pre_hint {
+            let pc = self.get_pc();
+            (hook_func)(self, exec_scope, &hint_code(hint_data), pc)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn execute_post_hint(
+        &mut self,
+        exec_scope: &mut ExecutionScopes,
+        hint_data: &dyn Any,
+    ) -> Result<(), VirtualMachineError> {
+        if let Some(hook_func) = self.hooks.clone().post_hint {
+            let pc = self.get_pc();
+            (hook_func)(self, exec_scope, &hint_code(hint_data), pc)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn execute_end_run(
+        &mut self,
+        exec_scope: &ExecutionScopes,
+    ) -> Result<(), VirtualMachineError> {
+        if let Some(hook_func) = self.hooks.clone().end_run {
+            (hook_func)(self, exec_scope)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -229,4 +360,130 @@ mod tests {
         let end = cairo_runner.initialize(false).unwrap();
         assert!(cairo_runner.run_until_pc(end, &mut hint_processor).is_ok());
     }
+
+    #[test]
+    fn hint_memory_segment_and_end_run_hooks() {
+        use crate::{
+            hint_processor::builtin_hint_processor::hint_code::ADD_SEGMENT,
+            serde::deserialize_program::{
+                ApTracking, FlowTrackingData, HintParams, ReferenceManager,
+            },
+            types::layout_name::LayoutName,
+            vm::runners::cairo_runner::CairoRunner,
+        };
+        use std::sync::Mutex;
+
+        // %{ memory[ap] = segments.add() %}
+        // ap += 1
+        // ret
+        let hint = HintParams {
+            code: ADD_SEGMENT.to_string(),
+            accessible_scopes: vec![],
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking::default(),
+                reference_ids: HashMap::new(),
+            },
+        };
+        let program = Program::new(
+            vec![],
+            vec![
+                Felt252::from(0x40780017fff7fff_i64).into(),
+                Felt252::ONE.into(),
+                Felt252::from(0x208b7fff7fff7ffe_i64).into(),
+            ],
+            Some(0),
+            HashMap::from([(0, vec![hint])]),
+            ReferenceManager {
+                references: Vec::new(),
+            },
+            HashMap::new(),
+            vec![],
+            None,
+        )
+        .unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::<String>::new()));
+        let (pre_hint, post_hint, memory_insert, segment_creation, end_run) = (
+            events.clone(),
+            events.clone(),
+            events.clone(),
+            events.clone(),
+            events.clone(),
+        );
+        let hooks = Hooks::default()
+            .pre_hint(Arc::new(move |_, _, code, pc| {
+                pre_hint
+                    .lock()
+                    .unwrap()
+                    .push(format!("pre_hint {pc} {code}"));
+                Ok(())
+            }))
+            .post_hint(Arc::new(move |_, _, code, pc| {
+                post_hint
+                    .lock()
+                    .unwrap()
+                    .push(format!("post_hint {pc} {code}"));
+                Ok(())
+            }))
+            .memory_insert(Arc::new(move |addr, value| {
+                memory_insert
+                    .lock()
+                    .unwrap()
+                    .push(format!("memory_insert {addr} {value}"));
+            }))
+            .segment_creation(Arc::new(move |base| {
+                segment_creation
+                    .lock()
+                    .unwrap()
+                    .push(format!("segment_creation {base}"));
+            }))
+            .end_run(Arc::new(move |vm, _| {
+                end_run
+                    .lock()
+                    .unwrap()
+                    .push(format!("end_run {}", vm.get_current_step()));
+                Ok(())
+            }));
+
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut cairo_runner = CairoRunner::new(&program, LayoutName::plain, false, false).unwrap();
+        cairo_runner.initialize(false).unwrap();
+        cairo_runner.vm.set_hooks(hooks);
+        let ap = cairo_runner.vm.get_ap();
+        cairo_runner.run_for_steps(1, &mut hint_processor).unwrap();
+        cairo_runner
+            .end_run(false, false, &mut hint_processor)
+            .unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                format!("pre_hint 0:0 {ADD_SEGMENT}"),
+                "segment_creation 4:0".to_string(),
+                format!("memory_insert {ap} 4:0"),
+                format!("post_hint 0:0 {ADD_SEGMENT}"),
+                "end_run 1".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn hint_hook_failure() {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_hooks(
+            Hooks::default().pre_hint(Arc::new(|_, _, _, _| Err(VirtualMachineError::Unexpected))),
+        );
+        let hint_data: Box<dyn Any> = Box::new(HintProcessorData::new_default(
+            "pass".to_string(),
+            HashMap::new(),
+        ));
+        assert_eq!(
+            vm.execute_pre_hint(&mut ExecutionScopes::new(), hint_data.as_ref())
+                .map_err(|e| e.to_string()),
+            Err(VirtualMachineError::Unexpected.to_string())
+        );
+        assert!(vm
+            .execute_post_hint(&mut ExecutionScopes::new(), hint_data.as_ref())
+            .is_ok());
+    }
 }
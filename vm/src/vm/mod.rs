@@ -1,12 +1,18 @@
 pub mod context;
 pub mod decoding;
 pub mod errors;
+#[cfg(feature = "profiler")]
+#[cfg_attr(docsrs, doc(cfg(feature = "profiler")))]
+pub mod hint_profiler;
+#[cfg(feature = "profiler")]
+#[cfg_attr(docsrs, doc(cfg(feature = "profiler")))]
+pub mod profiler;
 pub mod runners;
 pub mod security;
 pub mod trace;
 pub mod vm_core;
 pub mod vm_memory;
 
-#[cfg(feature = "test_utils")]
-#[cfg_attr(docsrs, doc(cfg(feature = "test_utils")))]
+#[cfg(feature = "hooks")]
+#[cfg_attr(docsrs, doc(cfg(feature = "hooks")))]
 pub mod hooks;
@@ -0,0 +1,550 @@
+//! Execution profiler, attributing each executed step and the builtin instances it uses to the
+//! Cairo call stack it was executed in.
+//!
+//! The call stack is tracked through `call` and `ret` instructions, and kept in sync with the fp
+//! chain. Function names are taken from the program's `function` and `label` identifiers.
+
+use crate::stdlib::{collections::HashMap, prelude::*};
+
+use crate::{
+    types::relocatable::Relocatable,
+    types::{builtin_name::BuiltinName, instruction::Opcode, program::Program},
+    vm::vm_core::VirtualMachine,
+};
+
+/// A frame of the call stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Frame {
+    // Frame pointer of the function's frame
+    fp: usize,
+    // First instruction of the function
+    pc: Relocatable,
+}
+
+/// Values accumulated for a call stack
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Sample {
+    steps: u64,
+    builtin_instances: Vec<u64>,
+}
+
+/// Builtin segment tracked by the profiler
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrackedBuiltin {
+    name: BuiltinName,
+    segment_index: usize,
+    cells_per_instance: usize,
+    // Segment length after the last recorded step
+    len: usize,
+}
+
+/// Collects samples during a run, see [`VirtualMachine::enable_profiler`]
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    stack: Vec<Frame>,
+    builtins: Option<Vec<TrackedBuiltin>>,
+    samples: HashMap<Vec<Relocatable>, Sample>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a step, called once the instruction at `pc` (executed with frame pointer `fp`) has been run
+    pub(crate) fn record_step(
+        &mut self,
+        vm: &VirtualMachine,
+        pc: Relocatable,
+        fp: usize,
+        opcode: &Opcode,
+    ) {
+        let builtins = self.builtins.get_or_insert_with(|| {
+            vm.builtin_runners
+                .iter()
+                .filter(|builtin| builtin.cells_per_instance() != 0)
+                .map(|builtin| TrackedBuiltin {
+                    name: builtin.name(),
+                    segment_index: builtin.base(),
+                    cells_per_instance: builtin.cells_per_instance() as usize,
+                    len: 0,
+                })
+                .collect()
+        });
+        if self.stack.is_empty() {
+            self.stack.push(Frame { fp, pc });
+        }
+        // Drop the frames left behind if the fp chain was unwound without going through `ret`
+        while self.stack.len() > 1 && self.stack.last().is_some_and(|frame| frame.fp != fp) {
+            self.stack.pop();
+        }
+
+        let key = self.stack.iter().map(|frame| frame.pc).collect();
+        let sample = self.samples.entry(key).or_insert_with(|| Sample {
+            steps: 0,
+            builtin_instances: vec![0; builtins.len()],
+        });
+        sample.steps += 1;
+        for (builtin, instances) in builtins.iter_mut().zip(sample.builtin_instances.iter_mut()) {
+            let len = vm
+                .segments
+                .memory
+                .data
+                .get(builtin.segment_index)
+                .map_or(0, Vec::len);
+            // Count the instances whose first cell was written since the last step
+            let started = |len: usize| len.div_ceil(builtin.cells_per_instance);
+            *instances += (started(len) - started(builtin.len.min(len))) as u64;
+            builtin.len = len;
+        }
+
+        match opcode {
+            Opcode::Call => self.stack.push(Frame {
+                fp: vm.get_fp().offset,
+                pc: vm.get_pc(),
+            }),
+            Opcode::Ret if self.stack.len() > 1 => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    /// Builds the profile of the run, resolving function names through the program's identifiers
+    pub fn get_profile(&self, program: &Program) -> Profile {
+        let names = FunctionNames::new(program);
+        let locations = program.shared_program_data.instruction_locations.as_ref();
+        let builtins = self.builtins.as_deref().unwrap_or_default();
+
+        let mut sample_types = vec![("steps".to_string(), "count".to_string())];
+        sample_types.extend(builtins.iter().map(|builtin| {
+            (
+                builtin.name.to_str_with_suffix().to_string(),
+                "count".to_string(),
+            )
+        }));
+
+        let mut functions = Vec::<ProfileFunction>::new();
+        let mut function_ids = HashMap::<Relocatable, usize>::new();
+        let mut stacks: Vec<_> = self.samples.iter().collect();
+        stacks.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let samples = stacks
+            .into_iter()
+            .map(|(stack, sample)| {
+                let stack = stack
+                    .iter()
+                    .map(|pc| {
+                        *function_ids.entry(*pc).or_insert_with(|| {
+                            let location = (pc.segment_index == 0)
+                                .then(|| locations.and_then(|l| l.get(&pc.offset)))
+                                .flatten();
+                            functions.push(ProfileFunction {
+                                name: names.get(*pc),
+                                pc: *pc,
+                                filename: location.map(|l| l.inst.input_file.filename.clone()),
+                                start_line: location.map(|l| l.inst.start_line),
+                            });
+                            functions.len() - 1
+                        })
+                    })
+                    .collect();
+                let mut values = vec![sample.steps];
+                values.extend(sample.builtin_instances.iter());
+                ProfileSample { stack, values }
+            })
+            .collect();
+
+        Profile {
+            sample_types,
+            functions,
+            samples,
+        }
+    }
+}
+
+/// Maps pcs in the program segment to the name of the function or label they belong to
+struct FunctionNames {
+    // (pc, name) sorted by pc, functions come before labels sharing their pc
+    entries: Vec<(usize, bool, String)>,
+}
+
+impl FunctionNames {
+    fn new(program: &Program) -> Self {
+        let mut entries: Vec<_> = program
+            .shared_program_data
+            .identifiers
+            .iter()
+            .filter_map(|(name, identifier)| {
+                let is_label = match identifier.type_.as_deref() {
+                    Some("function") => false,
+                    Some("label") => true,
+                    _ => return None,
+                };
+                Some((identifier.pc?, is_label, name.clone()))
+            })
+            .collect();
+        entries.sort();
+        Self { entries }
+    }
+
+    fn get(&self, pc: Relocatable) -> String {
+        if pc.segment_index != 0 {
+            return format!("{pc}");
+        }
+        // Use the closest function or label at or before the pc
+        let end = self
+            .entries
+            .partition_point(|(entry_pc, _, _)| *entry_pc <= pc.offset);
+        let Some(entry_pc) = end.checked_sub(1).map(|i| self.entries[i].0) else {
+            return format!("{pc}");
+        };
+        let (_, _, name) = &self.entries[self
+            .entries
+            .partition_point(|(other_pc, _, _)| *other_pc < entry_pc)];
+        if entry_pc == pc.offset {
+            name.clone()
+        } else {
+            format!("{name}+{}", pc.offset - entry_pc)
+        }
+    }
+}
+
+/// A function of the profiled program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileFunction {
+    pub name: String,
+    pub pc: Relocatable,
+    pub filename: Option<String>,
+    pub start_line: Option<u32>,
+}
+
+/// Values attributed to a call stack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSample {
+    /// Indexes into [`Profile::functions`], outermost function first
+    pub stack: Vec<usize>,
+    /// Values matching [`Profile::sample_types`]
+    pub values: Vec<u64>,
+}
+
+/// Profile of a run, as built by [`Profiler::get_profile`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// (type, unit) of each sample value, the first one being the executed steps
+    pub sample_types: Vec<(String, String)>,
+    pub functions: Vec<ProfileFunction>,
+    pub samples: Vec<ProfileSample>,
+}
+
+impl Profile {
+    /// Returns the executed steps in folded stack format, one `outer;inner steps` line per call stack,
+    /// as taken by flamegraph tools
+    pub fn to_folded(&self) -> String {
+        let mut folded = String::new();
+        for sample in self.samples.iter() {
+            let stack: Vec<_> = sample
+                .stack
+                .iter()
+                .map(|i| self.functions[*i].name.as_str())
+                .collect();
+            folded.push_str(&format!("{} {}\n", stack.join(";"), sample.values[0]));
+        }
+        folded
+    }
+
+    /// Writes the profile to `path`, in pprof format if the file has a `.pb` or `.pprof` extension,
+    /// and as folded stacks otherwise
+    #[cfg(feature = "std")]
+    pub fn write_to_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("pb" | "pprof") => std::fs::write(path, self.to_pprof()),
+            _ => std::fs::write(path, self.to_folded()),
+        }
+    }
+
+    /// Encodes the profile in pprof's protobuf format (uncompressed)
+    pub fn to_pprof(&self) -> Vec<u8> {
+        let mut strings = StringTable::default();
+        let mut profile = Vec::new();
+        for (type_, unit) in self.sample_types.iter() {
+            let mut value_type = Vec::new();
+            put_uint(&mut value_type, 1, strings.index(type_));
+            put_uint(&mut value_type, 2, strings.index(unit));
+            put_bytes(&mut profile, 1, &value_type);
+        }
+        for sample in self.samples.iter() {
+            // Ids are 1-based, and the leaf comes first
+            let location_ids: Vec<_> = sample.stack.iter().rev().map(|i| *i as u64 + 1).collect();
+            let mut message = Vec::new();
+            put_packed(&mut message, 1, &location_ids);
+            put_packed(&mut message, 2, &sample.values);
+            put_bytes(&mut profile, 2, &message);
+        }
+        for (i, function) in self.functions.iter().enumerate() {
+            let id = i as u64 + 1;
+            let mut line = Vec::new();
+            put_uint(&mut line, 1, id);
+            put_uint(&mut line, 2, function.start_line.unwrap_or_default().into());
+            let mut location = Vec::new();
+            put_uint(&mut location, 1, id);
+            put_uint(&mut location, 3, function.pc.offset as u64);
+            put_bytes(&mut location, 4, &line);
+            put_bytes(&mut profile, 4, &location);
+        }
+        for (i, function) in self.functions.iter().enumerate() {
+            let mut message = Vec::new();
+            put_uint(&mut message, 1, i as u64 + 1);
+            put_uint(&mut message, 2, strings.index(&function.name));
+            put_uint(&mut message, 3, strings.index(&function.name));
+            if let Some(filename) = &function.filename {
+                put_uint(&mut message, 4, strings.index(filename));
+            }
+            put_uint(
+                &mut message,
+                5,
+                function.start_line.unwrap_or_default().into(),
+            );
+            put_bytes(&mut profile, 5, &message);
+        }
+        for string in strings.strings.iter() {
+            put_bytes(&mut profile, 6, string.as_bytes());
+        }
+        profile
+    }
+}
+
+/// pprof's string table, the first entry must be the empty string
+struct StringTable {
+    strings: Vec<String>,
+    indexes: HashMap<String, u64>,
+}
+
+impl Default for StringTable {
+    fn default() -> Self {
+        Self {
+            strings: vec![String::new()],
+            indexes: HashMap::from([(String::new(), 0)]),
+        }
+    }
+}
+
+impl StringTable {
+    fn index(&mut self, string: &str) -> u64 {
+        if let Some(index) = self.indexes.get(string) {
+            return *index;
+        }
+        let index = self.strings.len() as u64;
+        self.strings.push(string.to_string());
+        self.indexes.insert(string.to_string(), index);
+        index
+    }
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_uint(buf: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(buf, field << 3);
+    put_varint(buf, value);
+}
+
+fn put_bytes(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(buf, field << 3 | 2);
+    put_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn put_packed(buf: &mut Vec<u8>, field: u64, values: &[u64]) {
+    let mut packed = Vec::new();
+    for value in values {
+        put_varint(&mut packed, *value);
+    }
+    put_bytes(buf, field, &packed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        serde::deserialize_program::{Identifier, ReferenceManager},
+        types::layout_name::LayoutName,
+        vm::runners::cairo_runner::CairoRunner,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn profiled_run() -> CairoRunner {
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runner = CairoRunner::new(&program, LayoutName::all_cairo, false, false).unwrap();
+        let end = runner.initialize(false).unwrap();
+        runner.vm.enable_profiler();
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        runner
+    }
+
+    fn function(pc: usize) -> Identifier {
+        Identifier {
+            pc: Some(pc),
+            type_: Some("function".to_string()),
+            value: None,
+            full_name: None,
+            members: None,
+            cairo_type: None,
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn profile_attributes_steps_to_call_stacks() {
+        let runner = profiled_run();
+        let profile = runner.get_profile().unwrap();
+        assert_eq!(
+            profile.to_folded(),
+            "__main__.main 8\n\
+             __main__.main;starkware.cairo.common.serialize.serialize_word 3\n\
+             __main__.main;__main__.check_range 7\n"
+        );
+        let steps: u64 = profile.samples.iter().map(|sample| sample.values[0]).sum();
+        assert_eq!(steps, runner.vm.get_current_step() as u64);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn profile_counts_builtin_instances() {
+        let mut runner = profiled_run();
+        runner.vm.segments.compute_effective_sizes();
+        let profile = runner.get_profile().unwrap();
+        assert_eq!(
+            profile.sample_types,
+            vec![
+                ("steps".to_string(), "count".to_string()),
+                ("range_check_builtin".to_string(), "count".to_string()),
+            ]
+        );
+        // The output builtin has no fixed size instances and isn't tracked
+        let range_check = &runner.vm.builtin_runners[1];
+        let instances: u64 = profile.samples.iter().map(|sample| sample.values[1]).sum();
+        assert_eq!(
+            instances,
+            range_check.get_used_instances(&runner.vm.segments).unwrap() as u64
+        );
+        assert_eq!(profile.samples[2].values, vec![7, 2]);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn profile_not_enabled() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let runner = CairoRunner::new(&program, LayoutName::all_cairo, false, false).unwrap();
+        assert_eq!(runner.get_profile(), None);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn function_names_use_closest_preceding_identifier() {
+        let identifiers = HashMap::from([
+            ("main".to_string(), function(0)),
+            ("main.loop".to_string(), {
+                let mut label = function(0);
+                label.type_ = Some("label".to_string());
+                label
+            }),
+            ("foo".to_string(), function(4)),
+            (
+                "SIZE".to_string(),
+                Identifier {
+                    type_: Some("const".to_string()),
+                    value: Some(6.into()),
+                    ..function(6)
+                },
+            ),
+        ]);
+        let program = Program::new(
+            vec![],
+            vec![],
+            None,
+            HashMap::new(),
+            ReferenceManager {
+                references: Vec::new(),
+            },
+            identifiers,
+            vec![],
+            None,
+        )
+        .unwrap();
+        let names = FunctionNames::new(&program);
+        assert_eq!(names.get((0, 0).into()), "main");
+        assert_eq!(names.get((0, 2).into()), "main+2");
+        assert_eq!(names.get((0, 4).into()), "foo");
+        assert_eq!(names.get((0, 7).into()), "foo+3");
+        assert_eq!(names.get((1, 7).into()), "1:7");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn pprof_encoding() {
+        let profile = Profile {
+            sample_types: vec![("steps".to_string(), "count".to_string())],
+            functions: vec![ProfileFunction {
+                name: "main".to_string(),
+                pc: (0, 1).into(),
+                filename: None,
+                start_line: Some(3),
+            }],
+            samples: vec![ProfileSample {
+                stack: vec![0],
+                values: vec![200],
+            }],
+        };
+        assert_eq!(
+            profile.to_pprof(),
+            vec![
+                // sample_type { type: 1, unit: 2 }
+                0x0a, 0x04, 0x08, 0x01, 0x10, 0x02,
+                // sample { location_id: [1], value: [200] }
+                0x12, 0x07, 0x0a, 0x01, 0x01, 0x12, 0x02, 0xc8, 0x01,
+                // location { id: 1, address: 1, line { function_id: 1, line: 3 } }
+                0x22, 0x0a, 0x08, 0x01, 0x18, 0x01, 0x22, 0x04, 0x08, 0x01, 0x10, 0x03,
+                // function { id: 1, name: 3, system_name: 3, start_line: 3 }
+                0x2a, 0x08, 0x08, 0x01, 0x10, 0x03, 0x18, 0x03, 0x28, 0x03,
+                // string_table: ["", "steps", "count", "main"]
+                0x32, 0x00, 0x32, 0x05, b's', b't', b'e', b'p', b's', 0x32, 0x05, b'c', b'o', b'u',
+                b'n', b't', 0x32, 0x04, b'm', b'a', b'i', b'n',
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write_profile_to_file() {
+        let profile = profiled_run().get_profile().unwrap();
+        let dir = std::env::temp_dir();
+        let folded_path = dir.join(format!("cairo-vm-profile-{}.folded", std::process::id()));
+        let pprof_path = dir.join(format!("cairo-vm-profile-{}.pb", std::process::id()));
+        profile.write_to_file(&folded_path).unwrap();
+        profile.write_to_file(&pprof_path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&folded_path).unwrap(),
+            profile.to_folded()
+        );
+        assert_eq!(std::fs::read(&pprof_path).unwrap(), profile.to_pprof());
+        std::fs::remove_file(folded_path).unwrap();
+        std::fs::remove_file(pprof_path).unwrap();
+    }
+}
@@ -0,0 +1,389 @@
+use crate::air_private_input::PrivateInput;
+use crate::stdlib::{boxed::Box, fmt, vec::Vec};
+use crate::types::builtin_name::{BuiltinName, CustomBuiltinName};
+use crate::types::relocatable::{MaybeRelocatable, Relocatable};
+use crate::vm::errors::{memory_errors::MemoryError, runner_errors::RunnerError};
+use crate::vm::runners::cairo_pie::BuiltinAdditionalData;
+use crate::vm::vm_memory::{memory::Memory, memory_segments::MemorySegmentManager};
+use num_integer::div_ceil;
+
+/// A builtin implemented outside of the vm, used to prototype new builtins in execution mode.
+///
+/// The builtin is registered on the runner with [`CairoRunner::add_custom_builtin`](crate::vm::runners::cairo_runner::CairoRunner::add_custom_builtin)
+/// and used by programs that declare it by [`name`](CustomBuiltin::name) in their `%builtins`
+/// directive, after all of the vm's builtins. Programs using it have to be loaded with
+/// [`Program::from_bytes_with_custom_builtins`](crate::types::program::Program::from_bytes_with_custom_builtins),
+/// or run with [`cairo_run`](crate::cairo_run::cairo_run) and the builtin in [`CairoRunConfig::custom_builtins`](crate::cairo_run::CairoRunConfig::custom_builtins).
+/// The segment, its base and stop pointer are handled by the vm: the final stack is checked
+/// against [`cells_per_instance`](CustomBuiltin::cells_per_instance) as for any other builtin.
+pub trait CustomBuiltin: fmt::Debug + Send {
+    /// Name used by programs to declare the builtin
+    fn name(&self) -> CustomBuiltinName;
+
+    /// Number of steps per builtin instance, `None` if the builtin isn't limited by the trace length
+    fn ratio(&self) -> Option<u32> {
+        None
+    }
+
+    fn cells_per_instance(&self) -> u32;
+
+    /// Number of cells written by the program on each instance, the rest are deduced by the builtin
+    fn n_input_cells(&self) -> u32 {
+        self.cells_per_instance()
+    }
+
+    /// Deduces the value of an output cell of the builtin's segment
+    fn deduce_memory_cell(
+        &self,
+        _address: Relocatable,
+        _memory: &Memory,
+    ) -> Result<Option<MaybeRelocatable>, RunnerError> {
+        Ok(None)
+    }
+
+    /// Adds the rules cells of the builtin's segment have to comply with, see [`Memory::add_validation_rule`]
+    fn add_validation_rule(&self, _segment_index: usize, _memory: &mut Memory) {}
+
+    /// Information about the builtin's segment that should be added to the AIR private input
+    fn air_private_input(&self, _segment_index: usize, _memory: &Memory) -> Vec<PrivateInput> {
+        vec![]
+    }
+
+    /// Data stored internally by the builtin needed to re-execute from a cairo pie
+    fn get_additional_data(&self) -> BuiltinAdditionalData {
+        BuiltinAdditionalData::None
+    }
+
+    /// Extends the builtin's internal data with the data obtained from a previous execution
+    fn extend_additional_data(
+        &mut self,
+        _additional_data: &BuiltinAdditionalData,
+    ) -> Result<(), RunnerError> {
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn CustomBuiltin>;
+}
+
+impl Clone for Box<dyn CustomBuiltin> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomBuiltinRunner {
+    name: CustomBuiltinName,
+    builtin: Box<dyn CustomBuiltin>,
+    pub base: usize,
+    pub(crate) stop_ptr: Option<usize>,
+    pub(crate) included: bool,
+}
+
+impl CustomBuiltinRunner {
+    pub(crate) fn new(builtin: Box<dyn CustomBuiltin>, included: bool) -> Self {
+        CustomBuiltinRunner {
+            name: builtin.name(),
+            builtin,
+            base: 0,
+            stop_ptr: None,
+            included,
+        }
+    }
+
+    pub fn name(&self) -> BuiltinName {
+        BuiltinName::custom(self.name)
+    }
+
+    /// Returns the builtin implementation
+    pub fn builtin(&self) -> &dyn CustomBuiltin {
+        self.builtin.as_ref()
+    }
+
+    pub fn initialize_segments(&mut self, segments: &mut MemorySegmentManager) {
+        self.base = segments.add().segment_index as usize // segments.add() always returns a positive index
+    }
+
+    pub fn initial_stack(&self) -> Vec<MaybeRelocatable> {
+        if self.included {
+            vec![MaybeRelocatable::from((self.base as isize, 0))]
+        } else {
+            vec![]
+        }
+    }
+
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    pub fn ratio(&self) -> Option<u32> {
+        self.builtin.ratio()
+    }
+
+    pub fn cells_per_instance(&self) -> u32 {
+        self.builtin.cells_per_instance()
+    }
+
+    pub fn n_input_cells(&self) -> u32 {
+        self.builtin.n_input_cells()
+    }
+
+    pub fn add_validation_rule(&self, memory: &mut Memory) {
+        self.builtin.add_validation_rule(self.base, memory)
+    }
+
+    pub fn deduce_memory_cell(
+        &self,
+        address: Relocatable,
+        memory: &Memory,
+    ) -> Result<Option<MaybeRelocatable>, RunnerError> {
+        self.builtin.deduce_memory_cell(address, memory)
+    }
+
+    pub fn get_used_cells(&self, segments: &MemorySegmentManager) -> Result<usize, MemoryError> {
+        segments
+            .get_segment_used_size(self.base)
+            .ok_or(MemoryError::MissingSegmentUsedSizes)
+    }
+
+    pub fn get_used_instances(
+        &self,
+        segments: &MemorySegmentManager,
+    ) -> Result<usize, MemoryError> {
+        let used_cells = self.get_used_cells(segments)?;
+        Ok(div_ceil(used_cells, self.cells_per_instance() as usize))
+    }
+
+    pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
+        self.builtin.air_private_input(self.base, memory)
+    }
+
+    pub fn get_additional_data(&self) -> BuiltinAdditionalData {
+        self.builtin.get_additional_data()
+    }
+
+    pub fn extend_additional_data(
+        &mut self,
+        additional_data: &BuiltinAdditionalData,
+    ) -> Result<(), RunnerError> {
+        self.builtin.extend_additional_data(additional_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cairo_run::{cairo_run_program, CairoRunConfig};
+    use crate::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+    use crate::types::errors::program_errors::ProgramError;
+    use crate::types::layout_name::LayoutName;
+    use crate::types::program::Program;
+    use crate::vm::decoding::assembler::assemble;
+    use crate::vm::errors::cairo_run_errors::CairoRunError;
+    use crate::vm::runners::builtin_runner::BuiltinRunner;
+    use crate::vm::runners::cairo_pie::CairoPieAdditionalData;
+    use crate::vm::runners::cairo_runner::CairoRunner;
+    use crate::Felt252;
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    // Writes the double of each input cell on the cell that follows it
+    #[derive(Debug, Clone)]
+    struct DoubleBuiltin;
+
+    impl CustomBuiltin for DoubleBuiltin {
+        fn name(&self) -> CustomBuiltinName {
+            DOUBLE
+        }
+
+        fn cells_per_instance(&self) -> u32 {
+            2
+        }
+
+        fn n_input_cells(&self) -> u32 {
+            1
+        }
+
+        fn deduce_memory_cell(
+            &self,
+            address: Relocatable,
+            memory: &Memory,
+        ) -> Result<Option<MaybeRelocatable>, RunnerError> {
+            if address.offset % 2 == 0 {
+                return Ok(None);
+            }
+            let Ok(value) = memory.get_integer((address - 1)?) else {
+                return Ok(None);
+            };
+            Ok(Some((Felt252::TWO * value.as_ref()).into()))
+        }
+
+        fn get_additional_data(&self) -> BuiltinAdditionalData {
+            BuiltinAdditionalData::Hash(vec![Relocatable::from((0, 1))])
+        }
+
+        fn clone_box(&self) -> Box<dyn CustomBuiltin> {
+            Box::new(self.clone())
+        }
+    }
+
+    const DOUBLE: CustomBuiltinName = CustomBuiltinName::new("double_builtin");
+
+    fn double() -> BuiltinName {
+        BuiltinName::custom(DOUBLE)
+    }
+
+    // Doubles 21 with the custom builtin and outputs the result
+    fn program() -> Program {
+        let mut program = assemble(
+            "%builtins output
+            __main__.main:
+                [ap + 0] = 21, ap++
+                [ap + -1] = [[fp + -3] + 0]
+                [ap + 0] = [[fp + -3] + 1], ap++
+                [ap + -1] = [[fp + -4] + 0]
+                [ap + 0] = [fp + -4] + 1, ap++
+                [ap + 0] = [fp + -3] + 2, ap++
+                ret",
+            Some("main"),
+        )
+        .unwrap();
+        program.builtins.push(double());
+        program
+    }
+
+    fn run(
+        program: &Program,
+        custom_builtins: Vec<Box<dyn CustomBuiltin>>,
+    ) -> Result<CairoRunner, CairoRunError> {
+        cairo_run_program(
+            program,
+            &CairoRunConfig {
+                layout: LayoutName::all_cairo,
+                custom_builtins,
+                ..Default::default()
+            },
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_program_with_custom_builtin() {
+        let mut runner = run(&program(), vec![Box::new(DoubleBuiltin)]).unwrap();
+        let mut output = String::new();
+        runner.vm.write_output(&mut output).unwrap();
+        assert_eq!(output, "42\n");
+
+        let builtin = runner.vm.builtin_runners.last().unwrap();
+        assert_matches!(builtin, BuiltinRunner::Custom(_));
+        assert_eq!(builtin.name(), double());
+        assert_eq!(builtin.name().to_str_with_suffix(), "double_builtin");
+        assert_eq!(builtin.get_memory_segment_addresses(), (3, Some(2)));
+
+        let resources = runner.get_execution_resources().unwrap();
+        assert_eq!(resources.builtin_instance_counter[&double()], 1);
+        assert!(serde_json::to_string(&resources)
+            .unwrap()
+            .contains(r#""double_builtin":1"#));
+
+        let pie = runner.get_cairo_pie().unwrap();
+        assert_eq!(
+            pie.additional_data.0[&double()],
+            BuiltinAdditionalData::Hash(vec![Relocatable::from((0, 1))])
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_program_with_unregistered_custom_builtin() {
+        assert_matches!(
+            run(&program(), vec![]).err(),
+            Some(CairoRunError::Runner(RunnerError::NoBuiltinForInstance(_)))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn custom_builtin_before_vm_builtins() {
+        let mut program = program();
+        program.builtins.reverse();
+        assert_matches!(
+            run(&program, vec![Box::new(DoubleBuiltin)]).err(),
+            Some(CairoRunError::Runner(RunnerError::DisorderedBuiltins))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn custom_builtin_not_in_proof_mode() {
+        let mut runner = CairoRunner::new(&program(), LayoutName::all_cairo, true, false).unwrap();
+        runner.add_custom_builtin(Box::new(DoubleBuiltin));
+        assert_matches!(
+            runner.initialize_builtins(false),
+            Err(RunnerError::NoBuiltinForInstance(_))
+        );
+    }
+
+    // Declares the output, range_check and double builtins
+    fn program_json(builtins: &[&str]) -> Vec<u8> {
+        let mut program_json: serde_json::Value = serde_json::from_slice(include_bytes!(
+            "../../../../../cairo_programs/manually_compiled/valid_program_b.json"
+        ))
+        .unwrap();
+        program_json["builtins"] = serde_json::json!(builtins);
+        serde_json::to_vec(&program_json).unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn load_program_with_custom_builtin() {
+        let program_json = program_json(&["output", "range_check", "double"]);
+        let program =
+            Program::from_bytes_with_custom_builtins(&program_json, Some("main"), &[DOUBLE])
+                .unwrap();
+        assert_eq!(
+            program.builtins,
+            vec![BuiltinName::output, BuiltinName::range_check, double()]
+        );
+        assert_matches!(
+            Program::from_bytes(&program_json, Some("main")),
+            Err(ProgramError::Parse(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn load_program_with_unknown_builtin() {
+        let program_json = program_json(&["output", "rangecheck"]);
+        assert_matches!(
+            Program::from_bytes_with_custom_builtins(&program_json, Some("main"), &[DOUBLE]),
+            Err(ProgramError::UnknownBuiltin(name)) if &*name == "rangecheck"
+        );
+        assert_matches!(
+            Program::from_bytes(&program_json, Some("main")),
+            Err(ProgramError::Parse(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deserialize_unknown_builtin_name() {
+        assert!(serde_json::from_str::<Vec<BuiltinName>>(r#"["output", "double"]"#).is_err());
+        assert!(
+            serde_json::from_str::<CairoPieAdditionalData>(r#"{"double_builtin": []}"#).is_err()
+        );
+        assert_eq!(
+            serde_json::to_string(&[BuiltinName::output, double()]).unwrap(),
+            r#"["output","double"]"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "custom builtin names must end with the _builtin suffix")]
+    fn custom_builtin_name_without_suffix() {
+        CustomBuiltinName::new("double");
+    }
+}
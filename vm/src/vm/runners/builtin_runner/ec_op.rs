@@ -206,6 +206,11 @@ impl EcOpBuiltinRunner {
     Q = {q:?}.")
     }
 
+    /// Drops the coordinates of the computed result points
+    pub(crate) fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
     pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
         let mut private_inputs = vec![];
         if let Some(segment) = memory.data.get(self.base) {
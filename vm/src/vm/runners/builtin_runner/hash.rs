@@ -109,6 +109,11 @@ impl HashBuiltinRunner {
         Ok(div_ceil(used_cells, CELLS_PER_HASH as usize))
     }
 
+    /// Drops the addresses whose hash was already computed
+    pub(crate) fn clear_cache(&self) {
+        self.verified_addresses.borrow_mut().clear();
+    }
+
     pub fn get_additional_data(&self) -> BuiltinAdditionalData {
         let mut verified_addresses = Vec::new();
         for (offset, is_verified) in self.verified_addresses.borrow().iter().enumerate() {
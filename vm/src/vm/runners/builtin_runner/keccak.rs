@@ -162,6 +162,11 @@ impl KeccakBuiltinRunner {
         Ok(keccak_input.iter().flat_map(|x| x.to_le_bytes()).collect())
     }
 
+    /// Drops the output cells computed from each hashed input
+    pub(crate) fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
     pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
         let mut private_inputs = vec![];
         if let Some(segment) = memory.data.get(self.base) {
@@ -25,11 +25,13 @@ use crate::types::relocatable::{MaybeRelocatable, Relocatable};
 use crate::vm::errors::memory_errors::{self, InsufficientAllocatedCellsError, MemoryError};
 use crate::vm::errors::runner_errors::RunnerError;
 use crate::vm::errors::vm_errors::VirtualMachineError;
+use crate::vm::runners::snapshot::BuiltinRunnerSnapshot;
 use crate::vm::vm_core::VirtualMachine;
 use crate::vm::vm_memory::memory::Memory;
 use crate::vm::vm_memory::memory_segments::MemorySegmentManager;
 
 mod bitwise;
+mod custom;
 mod ec_op;
 mod hash;
 mod keccak;
@@ -44,6 +46,7 @@ pub use self::keccak::KeccakBuiltinRunner;
 pub(crate) use self::range_check::{RC_N_PARTS_96, RC_N_PARTS_STANDARD};
 use self::segment_arena::ARENA_BUILTIN_SIZE;
 pub use bitwise::BitwiseBuiltinRunner;
+pub use custom::{CustomBuiltin, CustomBuiltinRunner};
 pub use ec_op::EcOpBuiltinRunner;
 pub use hash::HashBuiltinRunner;
 pub use modulo::ModBuiltinRunner;
@@ -61,8 +64,8 @@ use super::cairo_pie::BuiltinAdditionalData;
  * are either storing a `dyn Trait` inside an `Arc<Mutex<&dyn Trait>>` or
  * making the type itself `Send`. We opted for not complicating the user nor
  * moving the guarantees to runtime by using an `enum` rather than a `Trait`.
- * This works under the assumption that we don't expect downstream users to
- * extend Cairo by adding new builtin runners.
+ * Builtins implemented downstream are held by the `Custom` variant, which
+ * requires them to be `Send`, see `CustomBuiltin`.
  */
 #[derive(Debug, Clone)]
 pub enum BuiltinRunner {
@@ -77,6 +80,7 @@ pub enum BuiltinRunner {
     Poseidon(PoseidonBuiltinRunner),
     SegmentArena(SegmentArenaBuiltinRunner),
     Mod(ModBuiltinRunner),
+    Custom(CustomBuiltinRunner),
 }
 
 impl BuiltinRunner {
@@ -100,6 +104,7 @@ impl BuiltinRunner {
                 segment_arena.initialize_segments(segments)
             }
             BuiltinRunner::Mod(ref mut modulo) => modulo.initialize_segments(segments),
+            BuiltinRunner::Custom(ref mut custom) => custom.initialize_segments(segments),
         }
     }
 
@@ -116,6 +121,7 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(ref poseidon) => poseidon.initial_stack(),
             BuiltinRunner::SegmentArena(ref segment_arena) => segment_arena.initial_stack(),
             BuiltinRunner::Mod(ref modulo) => modulo.initial_stack(),
+            BuiltinRunner::Custom(ref custom) => custom.initial_stack(),
         }
     }
 
@@ -216,6 +222,7 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(ref poseidon) => poseidon.included,
             BuiltinRunner::SegmentArena(ref segment_arena) => segment_arena.included,
             BuiltinRunner::Mod(ref modulo) => modulo.included,
+            BuiltinRunner::Custom(ref custom) => custom.included,
         }
     }
 
@@ -234,6 +241,7 @@ impl BuiltinRunner {
             //Warning, returns only the segment index, base offset will be 3
             BuiltinRunner::SegmentArena(ref segment_arena) => segment_arena.base(),
             BuiltinRunner::Mod(ref modulo) => modulo.base(),
+            BuiltinRunner::Custom(ref custom) => custom.base(),
         }
     }
 
@@ -249,6 +257,7 @@ impl BuiltinRunner {
             BuiltinRunner::Signature(ref signature) => signature.ratio(),
             BuiltinRunner::Poseidon(poseidon) => poseidon.ratio(),
             BuiltinRunner::Mod(ref modulo) => modulo.ratio(),
+            BuiltinRunner::Custom(ref custom) => custom.ratio(),
         }
     }
 
@@ -258,6 +267,7 @@ impl BuiltinRunner {
             BuiltinRunner::RangeCheck96(ref range_check) => range_check.add_validation_rule(memory),
             BuiltinRunner::Signature(ref signature) => signature.add_validation_rule(memory),
             BuiltinRunner::Poseidon(ref poseidon) => poseidon.add_validation_rule(memory),
+            BuiltinRunner::Custom(ref custom) => custom.add_validation_rule(memory),
             _ => {}
         }
     }
@@ -273,6 +283,7 @@ impl BuiltinRunner {
             BuiltinRunner::Hash(ref hash) => hash.deduce_memory_cell(address, memory),
             BuiltinRunner::Keccak(ref keccak) => keccak.deduce_memory_cell(address, memory),
             BuiltinRunner::Poseidon(ref poseidon) => poseidon.deduce_memory_cell(address, memory),
+            BuiltinRunner::Custom(ref custom) => custom.deduce_memory_cell(address, memory),
             _ => Ok(None),
         }
     }
@@ -296,6 +307,7 @@ impl BuiltinRunner {
                 segment_arena.get_used_cells(segments)
             }
             BuiltinRunner::Mod(ref modulo) => modulo.get_used_cells(segments),
+            BuiltinRunner::Custom(ref custom) => custom.get_used_cells(segments),
         }
     }
 
@@ -319,6 +331,7 @@ impl BuiltinRunner {
                 segment_arena.get_used_instances(segments)
             }
             BuiltinRunner::Mod(modulo) => modulo.get_used_instances(segments),
+            BuiltinRunner::Custom(custom) => custom.get_used_instances(segments),
         }
     }
 
@@ -362,7 +375,7 @@ impl BuiltinRunner {
         }
     }
 
-    fn cells_per_instance(&self) -> u32 {
+    pub(crate) fn cells_per_instance(&self) -> u32 {
         match self {
             BuiltinRunner::Bitwise(_) => CELLS_PER_BITWISE,
             BuiltinRunner::EcOp(_) => CELLS_PER_EC_OP,
@@ -374,6 +387,7 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(_) => CELLS_PER_POSEIDON,
             BuiltinRunner::SegmentArena(_) => ARENA_BUILTIN_SIZE,
             BuiltinRunner::Mod(_) => CELLS_PER_MOD,
+            BuiltinRunner::Custom(custom) => custom.cells_per_instance(),
         }
     }
 
@@ -389,10 +403,11 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(_) => INPUT_CELLS_PER_POSEIDON,
             BuiltinRunner::SegmentArena(_) => ARENA_BUILTIN_SIZE,
             BuiltinRunner::Mod(_) => CELLS_PER_MOD,
+            BuiltinRunner::Custom(custom) => custom.n_input_cells(),
         }
     }
 
-    fn instances_per_component(&self) -> u32 {
+    pub(crate) fn instances_per_component(&self) -> u32 {
         match self {
             BuiltinRunner::Keccak(_) => KECCAK_INSTANCES_PER_COMPONENT,
             _ => BUILTIN_INSTANCES_PER_COMPONENT,
@@ -412,6 +427,7 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(_) => BuiltinName::poseidon,
             BuiltinRunner::SegmentArena(_) => BuiltinName::segment_arena,
             BuiltinRunner::Mod(b) => b.name(),
+            BuiltinRunner::Custom(b) => b.name(),
         }
     }
 
@@ -519,6 +535,7 @@ impl BuiltinRunner {
             BuiltinRunner::Hash(builtin) => builtin.get_additional_data(),
             BuiltinRunner::Output(builtin) => builtin.get_additional_data(),
             BuiltinRunner::Signature(builtin) => builtin.get_additional_data(),
+            BuiltinRunner::Custom(builtin) => builtin.get_additional_data(),
             _ => BuiltinAdditionalData::None,
         }
     }
@@ -533,6 +550,7 @@ impl BuiltinRunner {
             BuiltinRunner::Hash(builtin) => builtin.extend_additional_data(additional_data),
             BuiltinRunner::Output(builtin) => builtin.extend_additional_data(additional_data),
             BuiltinRunner::Signature(builtin) => builtin.extend_additional_data(additional_data),
+            BuiltinRunner::Custom(builtin) => builtin.extend_additional_data(additional_data),
             _ => Ok(()),
         }
     }
@@ -549,10 +567,86 @@ impl BuiltinRunner {
             BuiltinRunner::Signature(builtin) => builtin.air_private_input(&segments.memory),
             BuiltinRunner::Keccak(builtin) => builtin.air_private_input(&segments.memory),
             BuiltinRunner::Mod(builtin) => builtin.air_private_input(segments),
+            BuiltinRunner::Custom(builtin) => builtin.air_private_input(&segments.memory),
             _ => vec![],
         }
     }
 
+    /// Captures the builtin's state so that it can be restored via [`BuiltinRunner::restore_snapshot`]
+    pub(crate) fn snapshot(&self) -> BuiltinRunnerSnapshot {
+        BuiltinRunnerSnapshot {
+            name: self.name(),
+            base: self.base(),
+            stop_ptr: self.stop_ptr(),
+            additional_data: self.get_additional_data(),
+        }
+    }
+
+    /// Replaces the builtin's state with the one captured in the snapshot
+    /// Cached deductions are dropped, as they may not hold for the restored memory
+    /// Checks that the snapshot was taken from this builtin
+    pub(crate) fn check_snapshot(
+        &self,
+        snapshot: &BuiltinRunnerSnapshot,
+    ) -> Result<(), RunnerError> {
+        if snapshot.name != self.name() || snapshot.base != self.base() {
+            return Err(RunnerError::SnapshotBuiltinMismatch(self.name()));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn restore_snapshot(
+        &mut self,
+        snapshot: &BuiltinRunnerSnapshot,
+    ) -> Result<(), RunnerError> {
+        self.check_snapshot(snapshot)?;
+        self.clear_cache();
+        match self {
+            BuiltinRunner::Signature(builtin) => builtin.clear_signatures(),
+            BuiltinRunner::Output(builtin) => {
+                builtin.pages.clear();
+                builtin.attributes.clear();
+            }
+            _ => {}
+        }
+        match snapshot.stop_ptr {
+            Some(stop_ptr) => self.set_stop_ptr(stop_ptr),
+            None => self.clear_stop_ptr(),
+        }
+        self.extend_additional_data(&snapshot.additional_data)
+    }
+
+    /// Drops the values the builtin deduced and cached from its input cells. Rewinding the vm,
+    /// either by restoring a snapshot or by going back to a previous step, can remove or change
+    /// those inputs, so it must call this on every builtin before the run resumes. Builtins that
+    /// don't cache deductions have nothing to drop
+    pub(crate) fn clear_cache(&self) {
+        match self {
+            BuiltinRunner::Hash(builtin) => builtin.clear_cache(),
+            BuiltinRunner::EcOp(builtin) => builtin.clear_cache(),
+            BuiltinRunner::Keccak(builtin) => builtin.clear_cache(),
+            BuiltinRunner::Poseidon(builtin) => builtin.clear_cache(),
+            _ => {}
+        }
+    }
+
+    fn clear_stop_ptr(&mut self) {
+        match self {
+            BuiltinRunner::Bitwise(ref mut bitwise) => bitwise.stop_ptr = None,
+            BuiltinRunner::EcOp(ref mut ec) => ec.stop_ptr = None,
+            BuiltinRunner::Hash(ref mut hash) => hash.stop_ptr = None,
+            BuiltinRunner::Output(ref mut output) => output.stop_ptr = None,
+            BuiltinRunner::RangeCheck(ref mut range_check) => range_check.stop_ptr = None,
+            BuiltinRunner::RangeCheck96(ref mut range_check) => range_check.stop_ptr = None,
+            BuiltinRunner::Keccak(ref mut keccak) => keccak.stop_ptr = None,
+            BuiltinRunner::Signature(ref mut signature) => signature.stop_ptr = None,
+            BuiltinRunner::Poseidon(ref mut poseidon) => poseidon.stop_ptr = None,
+            BuiltinRunner::SegmentArena(ref mut segment_arena) => segment_arena.stop_ptr = None,
+            BuiltinRunner::Mod(modulo) => modulo.stop_ptr = None,
+            BuiltinRunner::Custom(custom) => custom.stop_ptr = None,
+        }
+    }
+
     pub(crate) fn set_stop_ptr(&mut self, stop_ptr: usize) {
         match self {
             BuiltinRunner::Bitwise(ref mut bitwise) => bitwise.stop_ptr = Some(stop_ptr),
@@ -570,6 +664,7 @@ impl BuiltinRunner {
                 segment_arena.stop_ptr = Some(stop_ptr)
             }
             BuiltinRunner::Mod(modulo) => modulo.stop_ptr = Some(stop_ptr),
+            BuiltinRunner::Custom(custom) => custom.stop_ptr = Some(stop_ptr),
         }
     }
 
@@ -586,6 +681,7 @@ impl BuiltinRunner {
             BuiltinRunner::Poseidon(ref poseidon) => poseidon.stop_ptr,
             BuiltinRunner::SegmentArena(ref segment_arena) => segment_arena.stop_ptr,
             BuiltinRunner::Mod(ref modulo) => modulo.stop_ptr,
+            BuiltinRunner::Custom(ref custom) => custom.stop_ptr,
         }
     }
 }
@@ -656,6 +752,12 @@ impl From<ModBuiltinRunner> for BuiltinRunner {
     }
 }
 
+impl From<CustomBuiltinRunner> for BuiltinRunner {
+    fn from(runner: CustomBuiltinRunner) -> Self {
+        BuiltinRunner::Custom(runner)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
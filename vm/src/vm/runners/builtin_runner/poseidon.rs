@@ -111,6 +111,11 @@ impl PoseidonBuiltinRunner {
         Ok(div_ceil(used_cells, CELLS_PER_POSEIDON as usize))
     }
 
+    /// Drops the permutation outputs computed from each input state
+    pub(crate) fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
     pub fn air_private_input(&self, memory: &Memory) -> Vec<PrivateInput> {
         let mut private_inputs = vec![];
         if let Some(segment) = memory.data.get(self.base) {
@@ -119,27 +119,28 @@ impl<const N_PARTS: u64> RangeCheckBuiltinRunner<N_PARTS> {
         let mut rc_bounds =
             (!range_check_segment.is_empty()).then_some((usize::MAX, usize::MIN))?;
 
-        // Split value into n_parts parts of less than _INNER_RC_BOUND size.
         for value in range_check_segment {
-            rc_bounds = value
-                .get_value()?
-                .get_int_ref()?
-                .to_le_digits()
-                // TODO: maybe skip leading zeros
-                .into_iter()
-                .flat_map(|digit| {
-                    (0..=3)
-                        .rev()
-                        .map(move |i| ((digit >> (i * INNER_RC_BOUND_SHIFT)) & INNER_RC_BOUND_MASK))
-                })
-                .take(N_PARTS as usize)
-                .fold(rc_bounds, |mm, x| {
-                    (min(mm.0, x as usize), max(mm.1, x as usize))
-                });
+            rc_bounds = Self::range_check_parts(value.get_value()?.get_int_ref()?)
+                .fold(rc_bounds, |mm, x| (min(mm.0, x), max(mm.1, x)));
         }
         Some(rc_bounds)
     }
 
+    /// Splits a value into the n_parts parts of less than _INNER_RC_BOUND size that are range checked
+    pub(crate) fn range_check_parts(value: &Felt252) -> impl Iterator<Item = usize> {
+        value
+            .to_le_digits()
+            // TODO: maybe skip leading zeros
+            .into_iter()
+            .flat_map(|digit| {
+                (0..=3)
+                    .rev()
+                    .map(move |i| ((digit >> (i * INNER_RC_BOUND_SHIFT)) & INNER_RC_BOUND_MASK))
+            })
+            .take(N_PARTS as usize)
+            .map(|part| part as usize)
+    }
+
     pub fn get_used_instances(
         &self,
         segments: &MemorySegmentManager,
@@ -163,6 +163,11 @@ impl SignatureBuiltinRunner {
         Ok(div_ceil(used_cells, CELLS_PER_SIGNATURE as usize))
     }
 
+    /// Drops the stored signatures, so that a restored snapshot can replace them with its own
+    pub(crate) fn clear_signatures(&self) {
+        self.signatures.borrow_mut().clear();
+    }
+
     pub fn get_additional_data(&self) -> BuiltinAdditionalData {
         // Convert signatures to Felt tuple
         let signatures: HashMap<Relocatable, (Felt252, Felt252)> = self
@@ -2,12 +2,16 @@ use super::cairo_runner::ExecutionResources;
 use crate::stdlib::prelude::{String, Vec};
 use crate::types::builtin_name::BuiltinName;
 use crate::vm::errors::cairo_pie_errors::CairoPieValidationError;
+use crate::vm::{
+    decoding::decoder::decode_instruction,
+    runners::builtin_runner::{RangeCheckBuiltinRunner, RC_N_PARTS_96, RC_N_PARTS_STANDARD},
+};
 use crate::{
     stdlib::{collections::HashMap, prelude::*},
     types::relocatable::{MaybeRelocatable, Relocatable},
     Felt252,
 };
-use num_traits::{One, Zero};
+use num_traits::{One, ToPrimitive, Zero};
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "std")]
 use {
@@ -270,6 +274,65 @@ impl CairoPie {
         Ok(())
     }
 
+    /// Returns the bounds of the range checked values of the run, as
+    /// [`CairoRunner::get_perm_range_check_limits`](super::cairo_runner::CairoRunner::get_perm_range_check_limits)
+    /// does, out of the PIE's memory.
+    /// As the PIE doesn't record which instructions were executed, the offsets of every instruction
+    /// in the program segment are taken into account, which can only widen the bounds
+    pub fn get_perm_range_check_limits(&self) -> Option<(isize, isize)> {
+        // Offsets are encoded with a bias of 2^15, as range checked by the vm
+        const OFFSET_BIAS: isize = 1 << 15;
+        let segment_index = |name| {
+            self.metadata
+                .builtin_segments
+                .get(&name)
+                .map(|segment| segment.index)
+        };
+        let range_check_segment = segment_index(BuiltinName::range_check);
+        let range_check96_segment = segment_index(BuiltinName::range_check96);
+
+        let mut program = vec![None; self.metadata.program_segment.size];
+        let mut rc_values = Vec::new();
+        for ((index, offset), value) in self.memory.0.iter() {
+            let index = Some(*index as isize);
+            let Some(value) = value.get_int_ref() else {
+                continue;
+            };
+            if index == Some(self.metadata.program_segment.index) {
+                if let Some(cell) = program.get_mut(*offset) {
+                    *cell = value.to_u64();
+                }
+            } else if index == range_check_segment {
+                rc_values.extend(
+                    RangeCheckBuiltinRunner::<RC_N_PARTS_STANDARD>::range_check_parts(value)
+                        .map(|part| part as isize),
+                );
+            } else if index == range_check96_segment {
+                rc_values.extend(
+                    RangeCheckBuiltinRunner::<RC_N_PARTS_96>::range_check_parts(value)
+                        .map(|part| part as isize),
+                );
+            }
+        }
+
+        let mut pc = 0;
+        while pc < program.len() {
+            match program[pc].map(decode_instruction) {
+                Some(Ok(instruction)) => {
+                    rc_values.extend([
+                        instruction.off0 + OFFSET_BIAS,
+                        instruction.off1 + OFFSET_BIAS,
+                        instruction.off2 + OFFSET_BIAS,
+                    ]);
+                    pc += instruction.size();
+                }
+                // Data that isn't an instruction
+                _ => pc += 1,
+            }
+        }
+        Some((*rc_values.iter().min()?, *rc_values.iter().max()?))
+    }
+
     /// Checks that the pie received is identical to self, skipping the fields execution_resources.n_steps, and additional_data[pedersen]
     /// Stricter runs check more Pedersen addresses leading to different address lists
     pub fn check_pie_compatibility(&self, pie: &CairoPie) -> Result<(), CairoPieValidationError> {
@@ -881,4 +944,31 @@ mod test {
         // Remove zip file created by the test
         std::fs::remove_file(file_path).unwrap();
     }
+
+    #[test]
+    fn pie_range_check_limits_cover_the_run() {
+        use crate::{
+            cairo_run::CairoRunConfig,
+            hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+            types::layout_name::LayoutName,
+        };
+        let cairo_run_config = CairoRunConfig {
+            layout: LayoutName::all_cairo,
+            ..Default::default()
+        };
+        let runner = crate::cairo_run::cairo_run(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let (run_min, run_max) = runner.get_perm_range_check_limits().unwrap();
+        let (pie_min, pie_max) = runner
+            .get_cairo_pie()
+            .unwrap()
+            .get_perm_range_check_limits()
+            .unwrap();
+        assert!(pie_min <= run_min);
+        assert!(pie_max >= run_max);
+    }
 }
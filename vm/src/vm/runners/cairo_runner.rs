@@ -1,13 +1,16 @@
 use crate::{
     air_private_input::AirPrivateInput,
     air_public_input::{PublicInput, PublicInputError},
+    coverage::{Coverage, CoverageError},
     stdlib::{
         any::Any,
         collections::{HashMap, HashSet},
+        fmt,
         ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign},
         prelude::*,
+        sync::Arc,
     },
-    types::{builtin_name::BuiltinName, layout::MEMORY_UNITS_PER_STEP, layout_name::LayoutName},
+    types::{builtin_name::BuiltinName, layout::CairoLayoutParams, layout_name::LayoutName},
     vm::{
         runners::builtin_runner::SegmentArenaBuiltinRunner,
         trace::trace_entry::{relocate_trace_register, RelocatedTraceEntry},
@@ -45,6 +48,8 @@ use crate::{
         },
     },
 };
+use bincode::enc::write::Writer;
+use core::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use num_integer::div_rem;
 use num_traits::{ToPrimitive, Zero};
 use serde::{Deserialize, Serialize};
@@ -52,11 +57,16 @@ use serde::{Deserialize, Serialize};
 use super::{builtin_runner::ModBuiltinRunner, cairo_pie::CairoPieAdditionalData};
 use super::{
     builtin_runner::{
-        KeccakBuiltinRunner, PoseidonBuiltinRunner, RC_N_PARTS_96, RC_N_PARTS_STANDARD,
+        CustomBuiltin, CustomBuiltinRunner, KeccakBuiltinRunner, PoseidonBuiltinRunner,
+        RC_N_PARTS_96, RC_N_PARTS_STANDARD,
     },
     cairo_pie::{self, CairoPie, CairoPieMetadata, CairoPieVersion},
+    layout_selection::{select_layout, LayoutSelection},
+    snapshot::{ExecutionScopesSnapshot, RunnerSnapshot},
 };
 use crate::types::instance_definitions::mod_instance_def::ModInstanceDef;
+#[cfg(feature = "extensive_hints")]
+use crate::types::program::HintRange;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum CairoArg {
@@ -82,11 +92,67 @@ impl From<Vec<MaybeRelocatable>> for CairoArg {
 // ================
 
 /// Maintains the resources of a cairo run. Can be used across multiple runners.
+///
+/// Besides the step limit, a run can be given a wall-clock deadline and a [CancellationFlag], which
+/// are checked before each step and between hint executions. Clones share the same cancellation flag.
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct RunResources {
     n_steps: Option<usize>,
+    #[cfg(feature = "std")]
+    deadline: Option<std::time::Instant>,
+    cancellation: Option<CancellationFlag>,
 }
 
+/// A flag that can be raised from any thread to stop the runs whose [RunResources] hold it
+#[derive(Clone, Default, Debug)]
+pub struct CancellationFlag(Arc<AtomicBool>);
+
+impl CancellationFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the cancellation of the runs holding this flag
+    pub fn cancel(&self) {
+        self.0.store(true, AtomicOrdering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(AtomicOrdering::Relaxed)
+    }
+}
+
+impl PartialEq for CancellationFlag {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Limit, other than the step count, that stopped a run before it could reach its end
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunLimit {
+    /// The deadline of the run's resources has passed
+    Deadline,
+    /// The run's cancellation flag was raised
+    Cancelled,
+}
+
+impl fmt::Display for RunLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunLimit::Deadline => write!(f, "the deadline has passed"),
+            RunLimit::Cancelled => write!(f, "the run was cancelled"),
+        }
+    }
+}
+
+static UNLIMITED_RESOURCES: RunResources = RunResources {
+    n_steps: None,
+    #[cfg(feature = "std")]
+    deadline: None,
+    cancellation: None,
+};
+
 /// This trait is in charge of overseeing the VM's step usage in contexts where a limited amount of steps are available
 /// for a single execution (which may or not involve other executions taking place in the duration of it ).
 /// This is mostly used in the context of starknet, where contracts can call other contracts while sharing the same step limit.
@@ -103,9 +169,13 @@ pub trait ResourceTracker {
     fn get_n_steps(&self) -> Option<usize> {
         None
     }
+    /// Returns the limit that should stop the run right away, if any was hit
+    fn interrupted(&self) -> Option<RunLimit> {
+        self.run_resources().interrupted()
+    }
     /// Returns a reference to the available resources
     fn run_resources(&self) -> &RunResources {
-        &RunResources { n_steps: None }
+        &UNLIMITED_RESOURCES
     }
 }
 
@@ -113,8 +183,31 @@ impl RunResources {
     pub fn new(n_steps: usize) -> Self {
         Self {
             n_steps: Some(n_steps),
+            ..Default::default()
         }
     }
+
+    /// Stops the run once `deadline` has passed, regardless of the steps left
+    #[cfg(feature = "std")]
+    pub fn with_deadline(mut self, deadline: std::time::Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stops the run once `flag` is cancelled
+    pub fn with_cancellation_flag(mut self, flag: CancellationFlag) -> Self {
+        self.cancellation = Some(flag);
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn get_deadline(&self) -> Option<std::time::Instant> {
+        self.deadline
+    }
+
+    pub fn get_cancellation_flag(&self) -> Option<&CancellationFlag> {
+        self.cancellation.as_ref()
+    }
 }
 
 impl ResourceTracker for RunResources {
@@ -135,15 +228,66 @@ impl ResourceTracker for RunResources {
         self.n_steps
     }
 
+    fn interrupted(&self) -> Option<RunLimit> {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationFlag::is_cancelled)
+        {
+            return Some(RunLimit::Cancelled);
+        }
+        #[cfg(feature = "std")]
+        if self
+            .deadline
+            .is_some_and(|deadline| std::time::Instant::now() >= deadline)
+        {
+            return Some(RunLimit::Deadline);
+        }
+        None
+    }
+
     fn run_resources(&self) -> &RunResources {
         self
     }
 }
 
+// ================
+//     Execution
+// ================
+
+/// State of an [Execution] after it was resumed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    /// The execution hasn't reached its end yet
+    Running,
+    /// The execution reached its end
+    Finished,
+}
+
+/// A run until a given pc that can be performed in chunks of steps, so that it can be interleaved
+/// with other work.
+///
+/// Created by [CairoRunner::start_execution] and resumed by [CairoRunner::resume_execution].
+/// It holds the hints compiled by the hint processor, so it must be resumed with the same runner and
+/// hint processor that started it.
+pub struct Execution {
+    end: Relocatable,
+    hint_data: Vec<Box<dyn Any>>,
+    #[cfg(feature = "extensive_hints")]
+    hint_ranges: HashMap<Relocatable, HintRange>,
+}
+
+impl Execution {
+    /// Returns the pc at which the execution ends
+    pub fn end(&self) -> Relocatable {
+        self.end
+    }
+}
+
 pub struct CairoRunner {
     pub vm: VirtualMachine,
     pub(crate) program: Program,
-    layout: CairoLayout,
+    pub(crate) layout: CairoLayout,
     final_pc: Option<Relocatable>,
     pub program_base: Option<Relocatable>,
     execution_base: Option<Relocatable>,
@@ -158,6 +302,7 @@ pub struct CairoRunner {
     pub relocated_memory: Vec<Option<Felt252>>,
     pub exec_scopes: ExecutionScopes,
     pub relocated_trace: Option<Vec<RelocatedTraceEntry>>,
+    custom_builtins: Vec<Box<dyn CustomBuiltin>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -168,9 +313,12 @@ pub enum RunnerMode {
 }
 
 impl CairoRunner {
+    /// Creates a runner for the given layout.
+    /// `dynamic_layout_params` is required by the dynamic layout and ignored by the fixed ones.
     pub fn new_v2(
         program: &Program,
         layout: LayoutName,
+        dynamic_layout_params: Option<CairoLayoutParams>,
         mode: RunnerMode,
         trace_enabled: bool,
     ) -> Result<CairoRunner, RunnerError> {
@@ -185,7 +333,9 @@ impl CairoRunner {
             LayoutName::recursive_with_poseidon => CairoLayout::recursive_with_poseidon(),
             LayoutName::all_cairo => CairoLayout::all_cairo_instance(),
             LayoutName::all_solidity => CairoLayout::all_solidity_instance(),
-            LayoutName::dynamic => CairoLayout::dynamic_instance(),
+            LayoutName::dynamic => CairoLayout::dynamic_instance(
+                dynamic_layout_params.ok_or(RunnerError::MissingDynamicLayoutParams)?,
+            )?,
         };
         Ok(CairoRunner {
             program: program.clone(),
@@ -209,6 +359,7 @@ impl CairoRunner {
                 None
             },
             relocated_trace: None,
+            custom_builtins: Vec::new(),
         })
     }
 
@@ -222,14 +373,30 @@ impl CairoRunner {
             Self::new_v2(
                 program,
                 layout,
+                None,
                 RunnerMode::ProofModeCanonical,
                 trace_enabled,
             )
         } else {
-            Self::new_v2(program, layout, RunnerMode::ExecutionMode, trace_enabled)
+            Self::new_v2(
+                program,
+                layout,
+                None,
+                RunnerMode::ExecutionMode,
+                trace_enabled,
+            )
         }
     }
 
+    /// Registers a builtin implemented outside of the vm, which is used in execution mode by programs
+    /// that declare it in their `%builtins` directive. It replaces any builtin registered with the same name.
+    /// Must be called before the builtins are initialized.
+    pub fn add_custom_builtin(&mut self, builtin: Box<dyn CustomBuiltin>) {
+        self.custom_builtins
+            .retain(|custom| custom.name() != builtin.name());
+        self.custom_builtins.push(builtin);
+    }
+
     pub fn initialize(&mut self, allow_missing_builtins: bool) -> Result<Relocatable, RunnerError> {
         self.initialize_builtins(allow_missing_builtins)?;
         self.initialize_segments(None);
@@ -261,7 +428,17 @@ impl CairoRunner {
             BuiltinName::add_mod,
             BuiltinName::mul_mod,
         ];
-        if !is_subsequence(&self.program.builtins, &builtin_ordered_list) {
+        // Custom builtins come after the ones known to the vm, in any order
+        let (vm_builtins, custom_builtins) = self.program.builtins.split_at(
+            self.program
+                .builtins
+                .iter()
+                .position(|name| name.is_custom())
+                .unwrap_or(self.program.builtins.len()),
+        );
+        if !is_subsequence(vm_builtins, &builtin_ordered_list)
+            || !custom_builtins.iter().all(|name| name.is_custom())
+        {
             return Err(RunnerError::DisorderedBuiltins);
         };
         let mut program_builtins: HashSet<&BuiltinName> = self.program.builtins.iter().collect();
@@ -367,6 +544,15 @@ impl CairoRunner {
                     .push(ModBuiltinRunner::new_mul_mod(instance_def, included).into());
             }
         }
+        // Layouts have no room for custom builtins, so they can't be proven
+        if !self.is_proof_mode() {
+            for name in custom_builtins {
+                if let Some(builtin) = custom_builtin_runner(&self.custom_builtins, *name) {
+                    program_builtins.remove(name);
+                    self.vm.builtin_runners.push(builtin.into());
+                }
+            }
+        }
         if !program_builtins.is_empty() && !allow_missing_builtins {
             return Err(RunnerError::NoBuiltinForInstance(Box::new((
                 program_builtins.iter().map(|n| **n).collect(),
@@ -385,7 +571,11 @@ impl CairoRunner {
     // Initialize all program builtins. Values used are the original one from the CairoFunctionRunner
     // Values extracted from here: https://github.com/starkware-libs/cairo-lang/blob/4fb83010ab77aa7ead0c9df4b0c05e030bc70b87/src/starkware/cairo/common/cairo_function_runner.py#L28
     pub fn initialize_program_builtins(&mut self) -> Result<(), RunnerError> {
-        fn initialize_builtin(name: BuiltinName, vm: &mut VirtualMachine) {
+        fn initialize_builtin(
+            name: BuiltinName,
+            vm: &mut VirtualMachine,
+            custom_builtins: &[Box<dyn CustomBuiltin>],
+        ) -> Result<(), RunnerError> {
             match name {
                 BuiltinName::pedersen => vm
                     .builtin_runners
@@ -425,11 +615,17 @@ impl CairoRunner {
                     ModBuiltinRunner::new_mul_mod(&ModInstanceDef::new(Some(1), 1, 96), true)
                         .into(),
                 ),
+                BuiltinName::custom(_) => vm.builtin_runners.push(
+                    custom_builtin_runner(custom_builtins, name)
+                        .ok_or(RunnerError::MissingCustomBuiltin(name))?
+                        .into(),
+                ),
             }
+            Ok(())
         }
 
         for builtin_name in &self.program.builtins {
-            initialize_builtin(*builtin_name, &mut self.vm);
+            initialize_builtin(*builtin_name, &mut self.vm, &self.custom_builtins)?;
         }
         Ok(())
     }
@@ -612,11 +808,11 @@ impl CairoRunner {
         references: &[HintReference],
         hint_executor: &mut dyn HintProcessor,
     ) -> Result<Vec<Box<dyn Any>>, VirtualMachineError> {
-        self.program
-            .shared_program_data
-            .hints_collection
+        let hints_collection = &self.program.shared_program_data.hints_collection;
+        hints_collection
             .iter_hints()
-            .map(|hint| {
+            .enumerate()
+            .map(|(index, hint)| {
                 hint_executor
                     .compile_hint(
                         &hint.code,
@@ -624,7 +820,15 @@ impl CairoRunner {
                         &hint.flow_tracking_data.reference_ids,
                         references,
                     )
-                    .map_err(|_| VirtualMachineError::CompileHintFail(hint.code.clone().into()))
+                    .map_err(|error| match error {
+                        VirtualMachineError::HintNotWhitelisted(hint) => {
+                            VirtualMachineError::HintNotWhitelisted(Box::new((
+                                hints_collection.get_pc_for_hint(index),
+                                hint.1,
+                            )))
+                        }
+                        _ => VirtualMachineError::CompileHintFail(hint.code.clone().into()),
+                    })
             })
             .collect()
     }
@@ -642,48 +846,85 @@ impl CairoRunner {
         address: Relocatable,
         hint_processor: &mut dyn HintProcessor,
     ) -> Result<(), VirtualMachineError> {
+        let mut execution = self.start_execution(address, hint_processor)?;
+        while self.resume_execution(&mut execution, usize::MAX, hint_processor)?
+            == Progress::Running
+        {}
+        Ok(())
+    }
+
+    /// Prepares a run until `address`, which is then performed by calls to [CairoRunner::resume_execution]
+    pub fn start_execution(
+        &mut self,
+        address: Relocatable,
+        hint_processor: &mut dyn HintProcessor,
+    ) -> Result<Execution, VirtualMachineError> {
         let references = &self.program.shared_program_data.reference_manager;
-        #[cfg(not(feature = "extensive_hints"))]
         let hint_data = self.get_hint_data(references, hint_processor)?;
-        #[cfg(feature = "extensive_hints")]
-        let mut hint_data = self.get_hint_data(references, hint_processor)?;
-        #[cfg(feature = "extensive_hints")]
-        let mut hint_ranges = self
-            .program
-            .shared_program_data
-            .hints_collection
-            .hints_ranges
-            .clone();
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         self.vm.execute_before_first_step(&hint_data)?;
-        while self.vm.get_pc() != address && !hint_processor.consumed() {
+        Ok(Execution {
+            end: address,
+            hint_data,
+            #[cfg(feature = "extensive_hints")]
+            hint_ranges: self
+                .program
+                .shared_program_data
+                .hints_collection
+                .hints_ranges
+                .clone(),
+        })
+    }
+
+    /// Executes at most `max_steps` steps of `execution`, stopping early if its end is reached.
+    ///
+    /// Fails with [VirtualMachineError::UnfinishedExecution] if the hint processor runs out of
+    /// steps before the end is reached.
+    pub fn resume_execution(
+        &mut self,
+        execution: &mut Execution,
+        max_steps: usize,
+        hint_processor: &mut dyn HintProcessor,
+    ) -> Result<Progress, VirtualMachineError> {
+        for _ in 0..max_steps {
+            if self.vm.get_pc() == execution.end {
+                return Ok(Progress::Finished);
+            }
+            if hint_processor.consumed() {
+                return Err(VirtualMachineError::UnfinishedExecution);
+            }
+            if let Some(limit) = hint_processor.interrupted() {
+                return Err(VirtualMachineError::RunInterrupted(limit));
+            }
             self.vm.step(
                 hint_processor,
                 &mut self.exec_scopes,
                 #[cfg(feature = "extensive_hints")]
-                &mut hint_data,
+                &mut execution.hint_data,
                 #[cfg(not(feature = "extensive_hints"))]
                 self.program
                     .shared_program_data
                     .hints_collection
                     .get_hint_range_for_pc(self.vm.get_pc().offset)
                     .and_then(|range| {
-                        range.and_then(|(start, length)| hint_data.get(start..start + length.get()))
+                        range.and_then(|(start, length)| {
+                            execution.hint_data.get(start..start + length.get())
+                        })
                     })
                     .unwrap_or(&[]),
                 #[cfg(feature = "extensive_hints")]
-                &mut hint_ranges,
+                &mut execution.hint_ranges,
                 &self.program.constants,
             )?;
 
             hint_processor.consume_step();
         }
 
-        if self.vm.get_pc() != address {
-            return Err(VirtualMachineError::UnfinishedExecution);
+        if self.vm.get_pc() == execution.end {
+            Ok(Progress::Finished)
+        } else {
+            Ok(Progress::Running)
         }
-
-        Ok(())
     }
 
     /// Execute an exact number of steps on the program from the actual position.
@@ -736,6 +977,15 @@ impl CairoRunner {
         Ok(())
     }
 
+    /// Undoes the last `n` executed steps, see [`VirtualMachine::step_back`].
+    /// Requires the vm's journal to be enabled (via [`VirtualMachine::enable_journal`]) before running them.
+    pub fn step_back(&mut self, n: usize) -> Result<(), VirtualMachineError> {
+        if self.run_ended {
+            return Err(RunnerError::StepBackAfterEndRun.into());
+        }
+        self.vm.step_back(n)
+    }
+
     /// Execute steps until a number of steps since the start of the program is reached.
     pub fn run_until_steps(
         &mut self,
@@ -829,7 +1079,14 @@ impl CairoRunner {
             used_units_by_builtins += used_units * multiplier;
         }
 
-        let diluted_units = diluted_pool_instance.units_per_step as usize * self.vm.current_step;
+        let diluted_units = if diluted_pool_instance.fractional_units_per_step {
+            safe_div_usize(
+                self.vm.current_step,
+                diluted_pool_instance.units_per_step as usize,
+            )?
+        } else {
+            diluted_pool_instance.units_per_step as usize * self.vm.current_step
+        };
         let unused_diluted_units = diluted_units.saturating_sub(used_units_by_builtins);
 
         let diluted_usage_upper_bound = 1usize << diluted_pool_instance.n_bits;
@@ -860,6 +1117,8 @@ impl CairoRunner {
         self.vm.end_run(&self.exec_scopes)?;
 
         if disable_finalize_all {
+            #[cfg(feature = "hooks")]
+            self.vm.execute_end_run(&self.exec_scopes)?;
             return Ok(());
         }
 
@@ -882,6 +1141,8 @@ impl CairoRunner {
         }
 
         self.run_ended = true;
+        #[cfg(feature = "hooks")]
+        self.vm.execute_end_run(&self.exec_scopes)?;
         Ok(())
     }
 
@@ -961,13 +1222,31 @@ impl CairoRunner {
                 return Err(TraceError::MemoryError(memory_error));
             }
         }
-        if self.vm.trace.is_some() {
+        if self.vm.trace_sink.is_some() {
+            // The trace is relocated as it's written, see `write_relocated_trace`
+            self.vm.flush_trace_sink()?;
+        } else if self.vm.trace.is_some() {
             self.relocate_trace(&relocation_table)?;
         }
         self.vm.relocation_table = Some(relocation_table);
         Ok(())
     }
 
+    /// Writes the trace handed over to the VM's trace sink, relocated and encoded as by
+    /// [`write_encoded_trace`](crate::cairo_run::write_encoded_trace). Can only be called after [`CairoRunner::relocate`]
+    pub fn write_relocated_trace(&mut self, dest: &mut impl Writer) -> Result<(), TraceError> {
+        let relocation_table = self
+            .vm
+            .relocation_table
+            .as_ref()
+            .ok_or(TraceError::TraceNotRelocated)?;
+        self.vm
+            .trace_sink
+            .as_mut()
+            .ok_or(TraceError::TraceNotEnabled)?
+            .write_relocated(relocation_table, dest)
+    }
+
     // Returns a map from builtin base's segment index to stop_ptr offset
     // Aka the builtin's segment number and its maximum offset
     pub fn get_builtin_segments_info(&self) -> Result<Vec<(usize, usize)>, RunnerError> {
@@ -1032,6 +1311,19 @@ impl CairoRunner {
         })
     }
 
+    /// Evaluates in which layouts the run could be proven, see [`select_layout`]
+    pub fn select_layout(
+        &self,
+        dynamic_layout_params: Option<&CairoLayoutParams>,
+    ) -> Result<LayoutSelection, RunnerError> {
+        select_layout(
+            &self.get_execution_resources()?,
+            &self.program.builtins,
+            self.get_perm_range_check_limits(),
+            dynamic_layout_params,
+        )
+    }
+
     // Finalizes the segments.
     //     Note:
     //     1.  end_run() must precede a call to this method.
@@ -1157,7 +1449,7 @@ impl CairoRunner {
 
         // Out of the memory units available per step, a fraction is used for public memory, and
         // four are used for the instruction.
-        let total_memory_units = MEMORY_UNITS_PER_STEP * vm_current_step_u32;
+        let total_memory_units = instance.memory_units_per_step * vm_current_step_u32;
         let (public_memory_units, rem) =
             div_rem(total_memory_units, instance.public_memory_fraction);
         if rem != 0 {
@@ -1294,6 +1586,46 @@ impl CairoRunner {
         &self.program
     }
 
+    /// Returns the profile of the run, if the profiler was enabled via [`VirtualMachine::enable_profiler`]
+    #[cfg(feature = "profiler")]
+    pub fn get_profile(&self) -> Option<crate::vm::profiler::Profile> {
+        self.vm
+            .get_profiler()
+            .map(|profiler| profiler.get_profile(&self.program))
+    }
+
+    /// Captures the current execution state, so that the run can be rewound to it via [`CairoRunner::restore`].
+    /// The snapshot can be serialized to checkpoint long runs to disk.
+    /// Execution scope variables of types not supported by [`ScopeValue`](super::snapshot::ScopeValue) are left out
+    pub fn snapshot(&self) -> RunnerSnapshot {
+        RunnerSnapshot {
+            vm: self.vm.snapshot(),
+            exec_scopes: ExecutionScopesSnapshot::new(&self.exec_scopes),
+            final_pc: self.final_pc,
+            run_ended: self.run_ended,
+            segments_finalized: self.segments_finalized,
+            execution_public_memory: self.execution_public_memory.clone(),
+        }
+    }
+
+    /// Rewinds the runner to the execution state captured in `snapshot`.
+    /// The runner must either be the one the snapshot was taken from, or a runner initialized
+    /// (see [`CairoRunner::initialize`]) with the same program, layout and entrypoint.
+    /// Any relocation results are discarded
+    pub fn restore(&mut self, snapshot: &RunnerSnapshot) -> Result<(), RunnerError> {
+        let exec_scopes = snapshot.exec_scopes.restore()?;
+        self.vm.restore_snapshot(&snapshot.vm)?;
+        self.exec_scopes = exec_scopes;
+        self.final_pc = snapshot.final_pc;
+        self.run_ended = snapshot.run_ended;
+        self.segments_finalized = snapshot.segments_finalized;
+        self.execution_public_memory
+            .clone_from(&snapshot.execution_public_memory);
+        self.relocated_memory.clear();
+        self.relocated_trace = None;
+        Ok(())
+    }
+
     // Constructs and returns a CairoPie representing the current VM run.
     pub fn get_cairo_pie(&self) -> Result<CairoPie, RunnerError> {
         let program_base = self.program_base.ok_or(RunnerError::NoProgBase)?;
@@ -1394,10 +1726,18 @@ impl CairoRunner {
         })
     }
 
+    /// Returns the source lines executed by the run, the runner must have been created with the trace enabled
+    pub fn get_coverage(&self) -> Result<Coverage, CoverageError> {
+        let mut coverage = Coverage::new();
+        coverage.add_run(self)?;
+        Ok(coverage)
+    }
+
     pub fn get_air_public_input(&self) -> Result<PublicInput, PublicInputError> {
         PublicInput::new(
             &self.relocated_memory,
             self.layout.name.to_str(),
+            self.layout.dynamic_params.as_ref(),
             &self.vm.get_public_memory_addresses()?,
             self.get_memory_segment_addresses()?,
             self.relocated_trace
@@ -1450,6 +1790,17 @@ impl CairoRunner {
     }
 }
 
+// Returns a runner for the registered custom builtin with the given name
+fn custom_builtin_runner(
+    custom_builtins: &[Box<dyn CustomBuiltin>],
+    name: BuiltinName,
+) -> Option<CustomBuiltinRunner> {
+    custom_builtins
+        .iter()
+        .find(|builtin| BuiltinName::custom(builtin.name()) == name)
+        .map(|builtin| CustomBuiltinRunner::new(builtin.clone(), true))
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SegmentInfo {
     pub index: isize,
@@ -1569,6 +1920,78 @@ mod tests {
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::*;
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn new_v2_dynamic_layout_without_params() {
+        let program = program!();
+        assert_matches!(
+            CairoRunner::new_v2(
+                &program,
+                LayoutName::dynamic,
+                None,
+                RunnerMode::ExecutionMode,
+                false
+            )
+            .err(),
+            Some(RunnerError::MissingDynamicLayoutParams)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_memory_usage_dynamic_layout() {
+        let params: CairoLayoutParams =
+            serde_json::from_slice(include_bytes!("../../tests/cairo_layout_params_file.json"))
+                .unwrap();
+        let program = program!();
+        let mut cairo_runner = CairoRunner::new_v2(
+            &program,
+            LayoutName::dynamic,
+            Some(CairoLayoutParams {
+                memory_units_per_step: 5,
+                ..params
+            }),
+            RunnerMode::ExecutionMode,
+            false,
+        )
+        .unwrap();
+        // A single accessed cell in a segment of size 8 leaves 7 memory holes
+        cairo_runner.vm.segments.memory = memory![((0, 0), 9)];
+        cairo_runner
+            .vm
+            .segments
+            .memory
+            .mark_as_accessed((0, 0).into());
+        cairo_runner.vm.segments.segment_used_sizes = Some(vec![8]);
+        cairo_runner.vm.current_step = 8;
+        // 8 steps with 5 units per step leave 3 unused units after the instructions and the public memory
+        assert_matches!(
+            cairo_runner.check_memory_usage(),
+            Err(VirtualMachineError::Memory(
+                MemoryError::InsufficientAllocatedCells(_)
+            ))
+        );
+        cairo_runner.layout.memory_units_per_step = 8;
+        assert_matches!(cairo_runner.check_memory_usage(), Ok(()));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn select_layout_after_run() {
+        let program = program![BuiltinName::output];
+        let mut cairo_runner = cairo_runner!(program);
+        cairo_runner.initialize_builtins(false).unwrap();
+        cairo_runner.initialize_segments(None);
+        cairo_runner.vm.segments.segment_used_sizes = Some(vec![0, 0, 0]);
+        cairo_runner.vm.current_step = 40;
+        cairo_runner.vm.rc_limits = Some((32764, 32769));
+        let selection = cairo_runner.select_layout(None).unwrap();
+        assert_eq!(
+            selection.best().map(|fit| (fit.layout, fit.trace_length)),
+            Some((LayoutName::dex, Some(512)))
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn check_memory_usage_ok_case() {
@@ -5150,6 +5573,179 @@ mod tests {
         assert_eq!(hint_processor.run_resources(), &RunResources::new(0));
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_until_pc_cancelled() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut runner = cairo_runner!(program);
+        let end = runner.initialize(false).unwrap();
+        let flag = CancellationFlag::new();
+        let run_resources = RunResources::default().with_cancellation_flag(flag.clone());
+        // Clones of the resources share the flag
+        assert_eq!(run_resources.clone().get_cancellation_flag(), Some(&flag));
+        let mut hint_processor = BuiltinHintProcessor::new(HashMap::new(), run_resources);
+        flag.cancel();
+        let pc = runner.vm.get_pc();
+        assert_matches!(
+            runner.run_until_pc(end, &mut hint_processor),
+            Err(VirtualMachineError::RunInterrupted(RunLimit::Cancelled))
+        );
+        assert_eq!(runner.vm.get_pc(), pc);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn interrupted_defaults_to_run_resources() {
+        struct Tracker(RunResources);
+        impl ResourceTracker for Tracker {
+            fn run_resources(&self) -> &RunResources {
+                &self.0
+            }
+        }
+        let flag = CancellationFlag::new();
+        let tracker = Tracker(RunResources::default().with_cancellation_flag(flag.clone()));
+        assert_eq!(tracker.interrupted(), None);
+        flag.cancel();
+        assert_eq!(tracker.interrupted(), Some(RunLimit::Cancelled));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn run_until_pc_deadline() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut runner = cairo_runner!(program);
+        let end = runner.initialize(false).unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new(
+            HashMap::new(),
+            RunResources::new(100).with_deadline(std::time::Instant::now()),
+        );
+        assert_matches!(
+            runner.run_until_pc(end, &mut hint_processor),
+            Err(VirtualMachineError::RunInterrupted(RunLimit::Deadline))
+        );
+        assert_eq!(hint_processor.get_n_steps(), Some(100));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn run_until_pc_before_deadline() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut runner = cairo_runner!(program);
+        let end = runner.initialize(false).unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(3600);
+        let mut hint_processor = BuiltinHintProcessor::new(
+            HashMap::new(),
+            RunResources::default()
+                .with_deadline(deadline)
+                .with_cancellation_flag(CancellationFlag::new()),
+        );
+        assert_matches!(runner.run_until_pc(end, &mut hint_processor), Ok(()));
+        assert_eq!(hint_processor.interrupted(), None);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn resume_execution_in_chunks() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut expected_runner = cairo_runner!(program, LayoutName::all_cairo, false, true);
+        let end = expected_runner.initialize(false).unwrap();
+        expected_runner
+            .run_until_pc(end, &mut hint_processor)
+            .unwrap();
+
+        let mut runner = cairo_runner!(program, LayoutName::all_cairo, false, true);
+        let end = runner.initialize(false).unwrap();
+        let mut execution = runner.start_execution(end, &mut hint_processor).unwrap();
+        assert_eq!(execution.end(), end);
+        assert_matches!(
+            runner.resume_execution(&mut execution, 5, &mut hint_processor),
+            Ok(Progress::Running)
+        );
+        assert_eq!(runner.vm.current_step, 5);
+        assert_matches!(
+            runner.resume_execution(&mut execution, 100, &mut hint_processor),
+            Ok(Progress::Finished)
+        );
+        assert_eq!(runner.vm.current_step, expected_runner.vm.current_step);
+        assert_eq!(runner.vm.trace, expected_runner.vm.trace);
+        // Resuming a finished execution doesn't run any step
+        assert_matches!(
+            runner.resume_execution(&mut execution, 1, &mut hint_processor),
+            Ok(Progress::Finished)
+        );
+        assert_eq!(runner.vm.current_step, expected_runner.vm.current_step);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn resume_execution_interleaved() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runners = [cairo_runner!(program), cairo_runner!(program)];
+        let mut executions = runners
+            .iter_mut()
+            .map(|runner| {
+                let end = runner.initialize(false).unwrap();
+                runner.start_execution(end, &mut hint_processor).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let mut finished = [false, false];
+        while !finished.iter().all(|f| *f) {
+            for (i, (runner, execution)) in runners.iter_mut().zip(&mut executions).enumerate() {
+                finished[i] = runner
+                    .resume_execution(execution, 4, &mut hint_processor)
+                    .unwrap()
+                    == Progress::Finished;
+            }
+        }
+        assert_eq!(runners[0].vm.current_step, runners[1].vm.current_step);
+        assert_eq!(runners[0].vm.get_ap(), runners[1].vm.get_ap());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn resume_execution_out_of_steps() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new(HashMap::new(), RunResources::new(6));
+        let mut runner = cairo_runner!(program);
+        let end = runner.initialize(false).unwrap();
+        let mut execution = runner.start_execution(end, &mut hint_processor).unwrap();
+        assert_matches!(
+            runner.resume_execution(&mut execution, 6, &mut hint_processor),
+            Ok(Progress::Running)
+        );
+        assert_matches!(
+            runner.resume_execution(&mut execution, 6, &mut hint_processor),
+            Err(VirtualMachineError::UnfinishedExecution)
+        );
+        assert_eq!(runner.vm.current_step, 6);
+    }
+
     #[test]
     fn get_cairo_pie_no_program_base() {
         let runner = cairo_runner!(Default::default());
@@ -5366,4 +5962,57 @@ mod tests {
             })]
         );
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn step_back_rewinds_steps() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut cairo_runner =
+            CairoRunner::new(&program, LayoutName::all_cairo, false, true).unwrap();
+        let end = cairo_runner.initialize(false).unwrap();
+        cairo_runner.vm.enable_journal();
+        cairo_runner.run_for_steps(3, &mut hint_processor).unwrap();
+        let snapshot = cairo_runner.snapshot();
+        cairo_runner.run_until_pc(end, &mut hint_processor).unwrap();
+        let finished = cairo_runner.snapshot();
+        let steps = cairo_runner.vm.get_current_step();
+        assert_eq!(cairo_runner.vm.journal_len(), Some(steps));
+
+        cairo_runner.step_back(steps - 3).unwrap();
+        assert_eq!(cairo_runner.snapshot(), snapshot);
+        assert_eq!(cairo_runner.vm.journal_len(), Some(3));
+
+        cairo_runner.run_until_pc(end, &mut hint_processor).unwrap();
+        assert_eq!(cairo_runner.snapshot(), finished);
+        assert_matches!(
+            cairo_runner.step_back(steps + 1),
+            Err(VirtualMachineError::StepBackOutOfRange(bx)) if *bx == (steps + 1, steps)
+        );
+
+        cairo_runner
+            .end_run(false, false, &mut hint_processor)
+            .unwrap();
+        assert_matches!(
+            cairo_runner.step_back(1),
+            Err(VirtualMachineError::RunnerError(
+                RunnerError::StepBackAfterEndRun
+            ))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn step_back_without_journal() {
+        let program = program!();
+        let mut cairo_runner = cairo_runner!(program);
+        assert_matches!(
+            cairo_runner.step_back(1),
+            Err(VirtualMachineError::JournalNotEnabled)
+        );
+    }
 }
@@ -0,0 +1,381 @@
+use crate::stdlib::prelude::*;
+
+use crate::types::{
+    builtin_name::BuiltinName, layout::CairoLayoutParams, layout_name::LayoutName, program::Program,
+};
+use crate::vm::errors::runner_errors::RunnerError;
+use crate::vm::runners::builtin_runner::BuiltinRunner;
+use crate::vm::runners::cairo_runner::{CairoRunner, ExecutionResources, RunnerMode};
+
+/// Largest trace length considered when fitting a run in a layout
+pub const MAX_TRACE_LENGTH: usize = 1 << 30;
+
+/// Number of steps a run needs when proven with a given layout
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutFit {
+    pub layout: LayoutName,
+    /// Power of two the trace has to be padded to for the run to fit in the layout.
+    /// `None` if the layout lacks one of the program's builtins or the run doesn't fit in
+    /// [`MAX_TRACE_LENGTH`] steps
+    pub trace_length: Option<usize>,
+    /// Memory, range check and diluted units the layout allocates for each step, which make up
+    /// the width of its trace
+    pub units_per_step: u64,
+}
+
+/// Result of [`select_layout`], with the fit of each layout in the order of [`LayoutName::ALL`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutSelection {
+    pub fits: Vec<LayoutFit>,
+}
+
+impl LayoutSelection {
+    /// Returns the cheapest layout the run fits in: the one with the shortest trace, and out of
+    /// those the one with the fewest units per step. Ties go to the smallest layout
+    pub fn best(&self) -> Option<&LayoutFit> {
+        self.fits
+            .iter()
+            .filter(|fit| fit.trace_length.is_some())
+            .min_by_key(|fit| (fit.trace_length, fit.units_per_step))
+    }
+}
+
+/// Evaluates in which layouts a run could be proven, given its execution resources, the
+/// program's builtins and its range check limits (see [`CairoRunner::get_perm_range_check_limits`]).
+/// The dynamic layout is only evaluated if its params are given.
+///
+/// Each layout is given the smallest power of two number of steps that passes the checks done at
+/// the end of a proof mode run: the builtins' used cells, range check units, diluted units and
+/// memory units. Layouts are then ranked by that number of steps and by their units per step, see
+/// [`LayoutSelection::best`].
+pub fn select_layout(
+    resources: &ExecutionResources,
+    builtins: &[BuiltinName],
+    rc_limits: Option<(isize, isize)>,
+    dynamic_layout_params: Option<&CairoLayoutParams>,
+) -> Result<LayoutSelection, RunnerError> {
+    let program = Program {
+        builtins: builtins.to_vec(),
+        ..Default::default()
+    };
+    let mut fits = Vec::new();
+    for layout in LayoutName::ALL {
+        let params = match (layout, dynamic_layout_params) {
+            (LayoutName::dynamic, None) => continue,
+            (LayoutName::dynamic, Some(params)) => Some(params.clone()),
+            _ => None,
+        };
+        let mut runner = CairoRunner::new_v2(
+            &program,
+            layout,
+            params,
+            RunnerMode::ProofModeCanonical,
+            false,
+        )?;
+        let trace_length = match runner.initialize_builtins(false) {
+            Ok(()) => fit_trace_length(&runner, resources, rc_limits),
+            Err(RunnerError::NoBuiltinForInstance(_)) => None,
+            Err(error) => return Err(error),
+        };
+        fits.push(LayoutFit {
+            layout,
+            trace_length,
+            units_per_step: units_per_step(&runner),
+        });
+    }
+    Ok(LayoutSelection { fits })
+}
+
+// Returns the smallest power of two number of steps for which the run fits in the runner's layout
+fn fit_trace_length(
+    runner: &CairoRunner,
+    resources: &ExecutionResources,
+    rc_limits: Option<(isize, isize)>,
+) -> Option<usize> {
+    let mut n_steps = resources.n_steps.max(1).next_power_of_two();
+    while n_steps <= MAX_TRACE_LENGTH {
+        if fits_in_steps(runner, resources, rc_limits, n_steps as u64) {
+            return Some(n_steps);
+        }
+        n_steps *= 2;
+    }
+    None
+}
+
+fn units_per_step(runner: &CairoRunner) -> u64 {
+    let layout = &runner.layout;
+    // A fractional diluted pool has less than one unit per step, which rounds down to none
+    let diluted_units_per_step = layout
+        .diluted_pool_instance_def
+        .as_ref()
+        .filter(|diluted_pool| !diluted_pool.fractional_units_per_step)
+        .map_or(0, |diluted_pool| diluted_pool.units_per_step);
+    (layout.memory_units_per_step + layout.rc_units + diluted_units_per_step) as u64
+}
+
+// Replays CairoRunner's check_used_cells, check_range_check_usage, check_diluted_check_usage and
+// check_memory_usage for a run padded to n_steps
+fn fits_in_steps(
+    runner: &CairoRunner,
+    resources: &ExecutionResources,
+    rc_limits: Option<(isize, isize)>,
+    n_steps: u64,
+) -> bool {
+    let layout = &runner.layout;
+    let builtin_runners = &runner.vm.builtin_runners;
+    let used_instances = |builtin: &BuiltinRunner| {
+        resources
+            .builtin_instance_counter
+            .get(&builtin.name())
+            .copied()
+            .unwrap_or_default() as u64
+    };
+
+    // Builtins with a ratio allocate one instance every `ratio` steps
+    let mut builtins_memory_units = 0;
+    for builtin in builtin_runners {
+        let cells_per_instance = builtin.cells_per_instance() as u64;
+        let instances_per_component = builtin.instances_per_component() as u64;
+        let instances = used_instances(builtin);
+        builtins_memory_units += match (builtin, builtin.ratio()) {
+            (BuiltinRunner::Output(_) | BuiltinRunner::SegmentArena(_), _) => 0,
+            (_, Some(ratio)) => {
+                let ratio = ratio as u64;
+                if n_steps < ratio * instances_per_component
+                    || n_steps % ratio != 0
+                    || instances > n_steps / ratio
+                {
+                    return false;
+                }
+                cells_per_instance * (n_steps / ratio)
+            }
+            (_, None) => {
+                cells_per_instance
+                    * instances_per_component
+                    * (instances / instances_per_component).next_power_of_two()
+            }
+        };
+    }
+
+    if let Some((rc_min, rc_max)) = rc_limits {
+        let rc_units_used_by_builtins: u64 = builtin_runners
+            .iter()
+            .map(|builtin| match builtin {
+                BuiltinRunner::RangeCheck(range_check) => {
+                    used_instances(builtin) * range_check.n_parts()
+                }
+                BuiltinRunner::RangeCheck96(range_check) => {
+                    used_instances(builtin) * range_check.n_parts()
+                }
+                _ => 0,
+            })
+            .sum();
+        let unused_rc_units = ((layout.rc_units as u64).saturating_sub(3) * n_steps)
+            .saturating_sub(rc_units_used_by_builtins);
+        if unused_rc_units < (rc_max - rc_min) as u64 {
+            return false;
+        }
+    }
+
+    if let Some(diluted_pool) = &layout.diluted_pool_instance_def {
+        let used_units_by_builtins: u64 = builtin_runners
+            .iter()
+            .map(|builtin| {
+                builtin.get_used_diluted_check_units(diluted_pool.spacing, diluted_pool.n_bits)
+                    as u64
+                    * (n_steps / builtin.ratio().unwrap_or(1) as u64)
+            })
+            .sum();
+        // As in check_diluted_check_usage, a fractional pool must evenly divide the steps
+        let Some(diluted_units) = diluted_pool.units(n_steps as usize) else {
+            return false;
+        };
+        let unused_diluted_units = (diluted_units as u64).saturating_sub(used_units_by_builtins);
+        let diluted_usage_upper_bound = 1u64.checked_shl(diluted_pool.n_bits).unwrap_or(u64::MAX);
+        if unused_diluted_units < diluted_usage_upper_bound {
+            return false;
+        }
+    }
+
+    // Out of the memory units available per step, a fraction is used for public memory, and
+    // four are used for the instruction.
+    let total_memory_units = layout.memory_units_per_step as u64 * n_steps;
+    let public_memory_fraction = layout.public_memory_fraction as u64;
+    if total_memory_units % public_memory_fraction != 0 {
+        return false;
+    }
+    let used_memory_units =
+        total_memory_units / public_memory_fraction + 4 * n_steps + builtins_memory_units;
+    total_memory_units
+        .checked_sub(used_memory_units)
+        .is_some_and(|unused_memory_units| unused_memory_units >= resources.n_memory_holes as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::collections::HashMap;
+    use assert_matches::assert_matches;
+    use rstest::rstest;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn resources(
+        n_steps: usize,
+        n_memory_holes: usize,
+        builtins: &[(BuiltinName, usize)],
+    ) -> ExecutionResources {
+        ExecutionResources {
+            n_steps,
+            n_memory_holes,
+            builtin_instance_counter: builtins.iter().copied().collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn trace_length(selection: &LayoutSelection, layout: LayoutName) -> Option<usize> {
+        selection
+            .fits
+            .iter()
+            .find(|fit| fit.layout == layout)
+            .and_then(|fit| fit.trace_length)
+    }
+
+    #[rstest]
+    #[case::no_builtins(resources(10, 0, &[]), &[], Some((32764, 32769)), LayoutName::plain, 16)]
+    #[case::memory_holes(resources(10, 100, &[]), &[], None, LayoutName::plain, 64)]
+    // plain and small need the same trace length, the smaller layout wins
+    #[case::range_check_units(resources(10, 0, &[]), &[], Some((0, 65535)), LayoutName::plain, 8192)]
+    // small and dex allocate an ecdsa instance every 512 steps, dex has fewer range check units
+    #[case::min_step(resources(10, 0, &[(BuiltinName::pedersen, 3)]), &[BuiltinName::pedersen], None, LayoutName::dex, 512)]
+    // small and dex fit in 4096 steps, recursive needs 16384
+    #[case::used_cells(
+        resources(1000, 0, &[(BuiltinName::pedersen, 100), (BuiltinName::range_check, 300)]),
+        &[BuiltinName::pedersen, BuiltinName::range_check],
+        None,
+        LayoutName::dex,
+        4096
+    )]
+    // starknet_with_keccak and all_cairo need the same trace length and units per step
+    #[case::keccak(resources(100, 0, &[(BuiltinName::keccak, 1)]), &[BuiltinName::keccak], None, LayoutName::starknet_with_keccak, 32768)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn select_layout_best(
+        #[case] resources: ExecutionResources,
+        #[case] builtins: &[BuiltinName],
+        #[case] rc_limits: Option<(isize, isize)>,
+        #[case] layout: LayoutName,
+        #[case] trace_length: usize,
+    ) {
+        let selection = select_layout(&resources, builtins, rc_limits, None).unwrap();
+        assert_eq!(
+            selection.best().map(|fit| (fit.layout, fit.trace_length)),
+            Some((layout, Some(trace_length)))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn select_layout_missing_builtins() {
+        let selection = select_layout(
+            &resources(100, 0, &[(BuiltinName::bitwise, 10)]),
+            &[BuiltinName::bitwise],
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(trace_length(&selection, LayoutName::plain), None);
+        assert_eq!(trace_length(&selection, LayoutName::small), None);
+        assert_eq!(trace_length(&selection, LayoutName::dex), None);
+        // The diluted pool runs out before the bitwise instances do
+        assert_eq!(trace_length(&selection, LayoutName::recursive), Some(16384));
+        // all_solidity has more units per step, but fits in a shorter trace
+        assert_eq!(
+            trace_length(&selection, LayoutName::all_solidity),
+            Some(8192)
+        );
+        assert_eq!(
+            selection.best().map(|fit| fit.layout),
+            Some(LayoutName::all_solidity)
+        );
+    }
+
+    #[rstest]
+    #[case(LayoutName::plain, 24)]
+    #[case(LayoutName::dex, 12)]
+    #[case(LayoutName::recursive, 28)]
+    #[case(LayoutName::starknet, 14)]
+    #[case(LayoutName::all_solidity, 32)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn select_layout_units_per_step(#[case] layout: LayoutName, #[case] units_per_step: u64) {
+        let selection = select_layout(&resources(10, 0, &[]), &[], None, None).unwrap();
+        let fit = selection.fits.iter().find(|fit| fit.layout == layout);
+        assert_eq!(fit.map(|fit| fit.units_per_step), Some(units_per_step));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn select_layout_dynamic() {
+        let params: CairoLayoutParams =
+            serde_json::from_slice(include_bytes!("../../tests/cairo_layout_params_file.json"))
+                .unwrap();
+        let resources = resources(100, 0, &[(BuiltinName::keccak, 1)]);
+        let builtins = [BuiltinName::keccak];
+        let selection = select_layout(&resources, &builtins, None, None).unwrap();
+        assert_eq!(selection.fits.len(), LayoutName::ALL.len() - 1);
+        assert_eq!(trace_length(&selection, LayoutName::dynamic), None);
+
+        // The params in the file match the all_cairo layout
+        let selection = select_layout(&resources, &builtins, None, Some(&params)).unwrap();
+        assert_eq!(selection.fits.len(), LayoutName::ALL.len());
+        assert_eq!(
+            trace_length(&selection, LayoutName::dynamic),
+            trace_length(&selection, LayoutName::all_cairo)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn select_layout_dynamic_fractional_diluted_units() {
+        let params = CairoLayoutParams {
+            log_diluted_units_per_step: -2,
+            uses_bitwise_builtin: false,
+            uses_keccak_builtin: false,
+            ..serde_json::from_slice(include_bytes!("../../tests/cairo_layout_params_file.json"))
+                .unwrap()
+        };
+        let selection = select_layout(&resources(10, 0, &[]), &[], None, Some(&params)).unwrap();
+        // No builtin uses diluted units, the pool needs 2^16 of them with one every 4 steps
+        assert_eq!(trace_length(&selection, LayoutName::dynamic), Some(1 << 18));
+        let fit = selection
+            .fits
+            .iter()
+            .find(|fit| fit.layout == LayoutName::dynamic);
+        assert_eq!(
+            fit.map(|fit| fit.units_per_step),
+            Some((params.memory_units_per_step + params.rc_units) as u64)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn select_layout_too_many_steps() {
+        let selection =
+            select_layout(&resources(MAX_TRACE_LENGTH + 1, 0, &[]), &[], None, None).unwrap();
+        assert!(selection.fits.iter().all(|fit| fit.trace_length.is_none()));
+        assert_eq!(selection.best(), None);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn select_layout_disordered_builtins() {
+        assert_matches!(
+            select_layout(
+                &resources(10, 0, &[]),
+                &[BuiltinName::range_check, BuiltinName::output],
+                None,
+                None
+            ),
+            Err(RunnerError::DisorderedBuiltins)
+        );
+    }
+}
@@ -1,3 +1,5 @@
 pub mod builtin_runner;
 pub mod cairo_pie;
 pub mod cairo_runner;
+pub mod layout_selection;
+pub mod snapshot;
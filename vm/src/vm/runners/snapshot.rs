@@ -0,0 +1,534 @@
+//! Serializable snapshots of a [`CairoRunner`](super::cairo_runner::CairoRunner)'s execution state.
+//!
+//! A snapshot is taken via [`CairoRunner::snapshot`](super::cairo_runner::CairoRunner::snapshot) and
+//! rewound to via [`CairoRunner::restore`](super::cairo_runner::CairoRunner::restore), either on the
+//! same runner or on a new runner initialized from the same program and layout.
+
+use crate::stdlib::{any::Any, cell::RefCell, collections::HashMap, prelude::*, rc::Rc};
+
+use num_bigint::{BigInt, BigUint};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    hint_processor::builtin_hint_processor::dict_manager::{DictManager, DictTracker, Dictionary},
+    types::{
+        builtin_name::BuiltinName,
+        exec_scope::ExecutionScopes,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{
+        errors::runner_errors::RunnerError,
+        trace::trace_entry::TraceEntry,
+        vm_memory::{
+            memory::{Memory, MemoryCell},
+            memory_segments::MemorySegmentManager,
+        },
+    },
+    Felt252,
+};
+
+use super::cairo_pie::BuiltinAdditionalData;
+
+/// Execution state of a [`CairoRunner`](super::cairo_runner::CairoRunner)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RunnerSnapshot {
+    pub vm: VirtualMachineSnapshot,
+    pub exec_scopes: ExecutionScopesSnapshot,
+    pub final_pc: Option<Relocatable>,
+    pub run_ended: bool,
+    pub segments_finalized: bool,
+    pub execution_public_memory: Option<Vec<usize>>,
+}
+
+/// Execution state of a [`VirtualMachine`](crate::vm::vm_core::VirtualMachine)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VirtualMachineSnapshot {
+    pub pc: Relocatable,
+    pub ap: usize,
+    pub fp: usize,
+    pub segments: SegmentsSnapshot,
+    pub builtin_runners: Vec<BuiltinRunnerSnapshot>,
+    pub trace: Option<Vec<TraceEntry>>,
+    pub current_step: usize,
+    pub rc_limits: Option<(isize, isize)>,
+    pub skip_instruction_execution: bool,
+    pub run_finished: bool,
+}
+
+/// Contents of a [`MemorySegmentManager`], validation rules are not included as they belong to
+/// the builtins and are re-applied on restore
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SegmentsSnapshot {
+    pub data: Vec<Vec<Option<MemoryCellSnapshot>>>,
+    pub temp_data: Vec<Vec<Option<MemoryCellSnapshot>>>,
+    pub relocation_rules: HashMap<usize, Relocatable>,
+    pub segment_sizes: HashMap<usize, usize>,
+    pub segment_used_sizes: Option<Vec<usize>>,
+    pub public_memory_offsets: HashMap<usize, Vec<(usize, usize)>>,
+    pub zero_segment_index: usize,
+    pub zero_segment_size: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MemoryCellSnapshot {
+    pub value: MaybeRelocatable,
+    pub accessed: bool,
+}
+
+/// State of a single builtin runner, the builtin itself is rebuilt from the program and layout
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BuiltinRunnerSnapshot {
+    pub name: BuiltinName,
+    pub base: usize,
+    pub stop_ptr: Option<usize>,
+    pub additional_data: BuiltinAdditionalData,
+}
+
+/// Contents of the [`ExecutionScopes`].
+/// Only variables whose type is covered by [`ScopeValue`] can be captured, the names of the
+/// remaining ones are listed in `skipped_variables` and will be missing after a restore.
+/// Each [`DictManager`] is stored once in `dict_managers`, so that the variables sharing it
+/// before the snapshot still share it after a restore.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct ExecutionScopesSnapshot {
+    pub scopes: Vec<HashMap<String, ScopeValue>>,
+    pub dict_managers: Vec<Vec<DictTrackerSnapshot>>,
+    pub skipped_variables: Vec<String>,
+}
+
+/// Execution scope variable types supported by snapshots
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ScopeValue {
+    Felt(Felt252),
+    BigInt(BigInt),
+    BigUint(BigUint),
+    Usize(usize),
+    U64(u64),
+    Bool(bool),
+    Relocatable(Relocatable),
+    MaybeRelocatable(MaybeRelocatable),
+    FeltList(Vec<Felt252>),
+    MaybeRelocatableList(Vec<MaybeRelocatable>),
+    /// Index of the [`DictManager`] in [`ExecutionScopesSnapshot::dict_managers`]
+    DictManager(usize),
+}
+
+/// A [`DictTracker`] along with the segment index it is registered with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DictTrackerSnapshot {
+    pub segment_index: isize,
+    pub current_ptr: Relocatable,
+    pub data: Vec<(MaybeRelocatable, MaybeRelocatable)>,
+    pub default_value: Option<MaybeRelocatable>,
+}
+
+impl SegmentsSnapshot {
+    pub(crate) fn new(segments: &MemorySegmentManager) -> Self {
+        let memory = &segments.memory;
+        SegmentsSnapshot {
+            data: memory.data.iter().map(|s| snapshot_segment(s)).collect(),
+            temp_data: memory
+                .temp_data
+                .iter()
+                .map(|s| snapshot_segment(s))
+                .collect(),
+            relocation_rules: memory.relocation_rules.clone(),
+            segment_sizes: segments.segment_sizes.clone(),
+            segment_used_sizes: segments.segment_used_sizes.clone(),
+            public_memory_offsets: segments.public_memory_offsets.clone(),
+            zero_segment_index: segments.zero_segment_index,
+            zero_segment_size: segments.zero_segment_size,
+        }
+    }
+
+    /// Replaces the segments' contents, keeping the memory's validation rules.
+    /// The caller is responsible for re-validating the memory once the builtins are restored
+    pub(crate) fn restore(&self, segments: &mut MemorySegmentManager) {
+        let memory: &mut Memory = &mut segments.memory;
+        memory.data = self.data.iter().map(|s| restore_segment(s)).collect();
+        memory.temp_data = self.temp_data.iter().map(|s| restore_segment(s)).collect();
+        memory.relocation_rules = self.relocation_rules.clone();
+        segments.segment_sizes = self.segment_sizes.clone();
+        segments.segment_used_sizes = self.segment_used_sizes.clone();
+        segments.public_memory_offsets = self.public_memory_offsets.clone();
+        segments.zero_segment_index = self.zero_segment_index;
+        segments.zero_segment_size = self.zero_segment_size;
+    }
+}
+
+fn snapshot_segment(segment: &[MemoryCell]) -> Vec<Option<MemoryCellSnapshot>> {
+    segment
+        .iter()
+        .map(|cell| {
+            cell.get_value().map(|value| MemoryCellSnapshot {
+                value,
+                accessed: cell.is_accessed(),
+            })
+        })
+        .collect()
+}
+
+fn restore_segment(segment: &[Option<MemoryCellSnapshot>]) -> Vec<MemoryCell> {
+    segment
+        .iter()
+        .map(|cell| match cell {
+            Some(cell) => {
+                let mut memory_cell = MemoryCell::new(cell.value.clone());
+                if cell.accessed {
+                    memory_cell.mark_accessed();
+                }
+                memory_cell
+            }
+            None => MemoryCell::NONE,
+        })
+        .collect()
+}
+
+impl ExecutionScopesSnapshot {
+    pub(crate) fn new(exec_scopes: &ExecutionScopes) -> Self {
+        let mut skipped_variables = Vec::new();
+        let mut dict_managers = DictManagers::default();
+        let scopes = exec_scopes
+            .data
+            .iter()
+            .map(|scope| {
+                let mut variables = HashMap::new();
+                for (name, value) in scope {
+                    match ScopeValue::from_any(value.as_ref(), &mut dict_managers) {
+                        Some(value) => {
+                            variables.insert(name.clone(), value);
+                        }
+                        None => skipped_variables.push(name.clone()),
+                    }
+                }
+                variables
+            })
+            .collect();
+        skipped_variables.sort();
+        ExecutionScopesSnapshot {
+            scopes,
+            dict_managers: dict_managers.snapshots,
+            skipped_variables,
+        }
+    }
+
+    pub(crate) fn restore(&self) -> Result<ExecutionScopes, RunnerError> {
+        let dict_managers: Vec<_> = self
+            .dict_managers
+            .iter()
+            .map(|trackers| Rc::new(RefCell::new(restore_dict_manager(trackers))))
+            .collect();
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.data = self
+            .scopes
+            .iter()
+            .map(|scope| {
+                scope
+                    .iter()
+                    .map(|(name, value)| Ok((name.clone(), value.to_any(&dict_managers)?)))
+                    .collect()
+            })
+            .collect::<Result<_, RunnerError>>()?;
+        if exec_scopes.data.is_empty() {
+            // The main scope should always be present
+            exec_scopes.data.push(HashMap::new());
+        }
+        Ok(exec_scopes)
+    }
+}
+
+/// The dict managers found while taking a snapshot, identified by their address
+#[derive(Default)]
+struct DictManagers {
+    addresses: Vec<*const RefCell<DictManager>>,
+    snapshots: Vec<Vec<DictTrackerSnapshot>>,
+}
+
+impl DictManagers {
+    /// Returns the index of the dict manager's snapshot, taking it on its first occurrence
+    fn index_of(&mut self, dict_manager: &Rc<RefCell<DictManager>>) -> usize {
+        let address = Rc::as_ptr(dict_manager);
+        match self.addresses.iter().position(|a| *a == address) {
+            Some(index) => index,
+            None => {
+                self.addresses.push(address);
+                self.snapshots.push(snapshot_dict_manager(dict_manager));
+                self.snapshots.len() - 1
+            }
+        }
+    }
+}
+
+impl ScopeValue {
+    fn from_any(value: &dyn Any, dict_managers: &mut DictManagers) -> Option<Self> {
+        if let Some(value) = value.downcast_ref::<Felt252>() {
+            Some(ScopeValue::Felt(*value))
+        } else if let Some(value) = value.downcast_ref::<BigInt>() {
+            Some(ScopeValue::BigInt(value.clone()))
+        } else if let Some(value) = value.downcast_ref::<BigUint>() {
+            Some(ScopeValue::BigUint(value.clone()))
+        } else if let Some(value) = value.downcast_ref::<usize>() {
+            Some(ScopeValue::Usize(*value))
+        } else if let Some(value) = value.downcast_ref::<u64>() {
+            Some(ScopeValue::U64(*value))
+        } else if let Some(value) = value.downcast_ref::<bool>() {
+            Some(ScopeValue::Bool(*value))
+        } else if let Some(value) = value.downcast_ref::<Relocatable>() {
+            Some(ScopeValue::Relocatable(*value))
+        } else if let Some(value) = value.downcast_ref::<MaybeRelocatable>() {
+            Some(ScopeValue::MaybeRelocatable(value.clone()))
+        } else if let Some(value) = value.downcast_ref::<Vec<Felt252>>() {
+            Some(ScopeValue::FeltList(value.clone()))
+        } else if let Some(value) = value.downcast_ref::<Vec<MaybeRelocatable>>() {
+            Some(ScopeValue::MaybeRelocatableList(value.clone()))
+        } else {
+            value
+                .downcast_ref::<Rc<RefCell<DictManager>>>()
+                .map(|dict_manager| ScopeValue::DictManager(dict_managers.index_of(dict_manager)))
+        }
+    }
+
+    fn to_any(
+        &self,
+        dict_managers: &[Rc<RefCell<DictManager>>],
+    ) -> Result<Box<dyn Any>, RunnerError> {
+        Ok(match self {
+            ScopeValue::Felt(value) => Box::new(*value),
+            ScopeValue::BigInt(value) => Box::new(value.clone()),
+            ScopeValue::BigUint(value) => Box::new(value.clone()),
+            ScopeValue::Usize(value) => Box::new(*value),
+            ScopeValue::U64(value) => Box::new(*value),
+            ScopeValue::Bool(value) => Box::new(*value),
+            ScopeValue::Relocatable(value) => Box::new(*value),
+            ScopeValue::MaybeRelocatable(value) => Box::new(value.clone()),
+            ScopeValue::FeltList(value) => Box::new(value.clone()),
+            ScopeValue::MaybeRelocatableList(value) => Box::new(value.clone()),
+            ScopeValue::DictManager(index) => Box::new(
+                dict_managers
+                    .get(*index)
+                    .ok_or(RunnerError::SnapshotMissingDictManager(*index))?
+                    .clone(),
+            ),
+        })
+    }
+}
+
+fn snapshot_dict_manager(dict_manager: &RefCell<DictManager>) -> Vec<DictTrackerSnapshot> {
+    let mut trackers: Vec<_> = dict_manager
+        .borrow()
+        .trackers
+        .iter()
+        .map(|(segment_index, tracker)| {
+            let (dict, default_value) = match &tracker.data {
+                Dictionary::SimpleDictionary(dict) => (dict, None),
+                Dictionary::DefaultDictionary {
+                    dict,
+                    default_value,
+                } => (dict, Some(default_value.clone())),
+            };
+            let mut data: Vec<_> = dict
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            data.sort();
+            DictTrackerSnapshot {
+                segment_index: *segment_index,
+                current_ptr: tracker.current_ptr,
+                data,
+                default_value,
+            }
+        })
+        .collect();
+    trackers.sort_by_key(|tracker| tracker.segment_index);
+    trackers
+}
+
+fn restore_dict_manager(trackers: &[DictTrackerSnapshot]) -> DictManager {
+    let mut dict_manager = DictManager::new();
+    for tracker in trackers {
+        let dict = tracker.data.iter().cloned().collect();
+        let data = match &tracker.default_value {
+            Some(default_value) => Dictionary::DefaultDictionary {
+                dict,
+                default_value: default_value.clone(),
+            },
+            None => Dictionary::SimpleDictionary(dict),
+        };
+        dict_manager.trackers.insert(
+            tracker.segment_index,
+            DictTracker {
+                data,
+                current_ptr: tracker.current_ptr,
+            },
+        );
+    }
+    dict_manager
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{any_box, relocatable};
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::{layout_name::LayoutName, program::Program},
+        utils::test_utils::*,
+        vm::runners::cairo_runner::CairoRunner,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn initialized_runner() -> (CairoRunner, Relocatable) {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut runner = CairoRunner::new(&program, LayoutName::all_cairo, false, true).unwrap();
+        let end = runner.initialize(false).unwrap();
+        (runner, end)
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn restore_rewinds_run() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let (mut runner, end) = initialized_runner();
+        runner.run_for_steps(5, &mut hint_processor).unwrap();
+        let snapshot = runner.snapshot();
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        runner.end_run(false, false, &mut hint_processor).unwrap();
+        let finished = runner.snapshot();
+        assert!(finished.run_ended);
+
+        runner.restore(&snapshot).unwrap();
+        assert_eq!(runner.vm.get_current_step(), 5);
+        assert_eq!(runner.vm.trace.as_ref().map(Vec::len), Some(5));
+        assert_eq!(runner.snapshot(), snapshot);
+
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        runner.end_run(false, false, &mut hint_processor).unwrap();
+        assert_eq!(runner.snapshot(), finished);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn restore_serialized_snapshot_into_new_runner() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let (mut runner, end) = initialized_runner();
+        runner.run_for_steps(7, &mut hint_processor).unwrap();
+        let serialized = serde_json::to_string(&runner.snapshot()).unwrap();
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+
+        let snapshot: RunnerSnapshot = serde_json::from_str(&serialized).unwrap();
+        let (mut new_runner, _) = initialized_runner();
+        new_runner.restore(&snapshot).unwrap();
+        assert_eq!(new_runner.vm.get_current_step(), 7);
+        new_runner.run_until_pc(end, &mut hint_processor).unwrap();
+        assert_eq!(new_runner.snapshot(), runner.snapshot());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn restore_with_mismatched_builtins() {
+        let (runner, _) = initialized_runner();
+        let snapshot = runner.snapshot();
+        let program = program!(main = Some(0),);
+        let mut other_runner = cairo_runner!(program);
+        other_runner.initialize(false).unwrap();
+        assert_matches!(
+            other_runner.restore(&snapshot),
+            Err(RunnerError::SnapshotBuiltinCountMismatch(2, 0))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn restore_with_mismatched_builtin_leaves_runner_untouched() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let (mut runner, end) = initialized_runner();
+        runner.run_for_steps(5, &mut hint_processor).unwrap();
+        let mut snapshot = runner.snapshot();
+        snapshot.vm.builtin_runners[1].name = BuiltinName::bitwise;
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        runner.vm.builtin_runners[0].set_stop_ptr(1);
+        let current = runner.snapshot();
+
+        assert_matches!(
+            runner.restore(&snapshot),
+            Err(RunnerError::SnapshotBuiltinMismatch(_))
+        );
+        assert_eq!(runner.snapshot(), current);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn exec_scopes_snapshot_roundtrip() {
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value("n", 3_usize);
+        exec_scopes.insert_value("value", bigint!(-7));
+        exec_scopes.insert_value("unsupported", vec![1_u32]);
+        let mut dict_manager = DictManager::new();
+        dict_manager.trackers.insert(
+            2,
+            DictTracker::new_with_initial(
+                relocatable!(2, 0),
+                HashMap::from([(MaybeRelocatable::from(1), MaybeRelocatable::from((4, 0)))]),
+            ),
+        );
+        let shared_dict_manager = Rc::new(RefCell::new(dict_manager.clone()));
+        exec_scopes.insert_value("dict_manager", shared_dict_manager.clone());
+        exec_scopes.enter_scope(HashMap::from([(
+            "dict_manager".to_string(),
+            any_box!(shared_dict_manager),
+        )]));
+
+        let snapshot: ExecutionScopesSnapshot = serde_json::from_str(
+            &serde_json::to_string(&ExecutionScopesSnapshot::new(&exec_scopes)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(snapshot.skipped_variables, vec!["unsupported".to_string()]);
+        assert_eq!(snapshot.dict_managers.len(), 1);
+
+        let restored = snapshot.restore().unwrap();
+        assert_eq!(restored.data.len(), 2);
+        assert_eq!(restored.data[0].len(), 3);
+        assert_eq!(restored.data[0]["n"].downcast_ref::<usize>(), Some(&3));
+        assert_eq!(
+            restored.data[0]["value"].downcast_ref::<BigInt>(),
+            Some(&bigint!(-7))
+        );
+        assert_eq!(
+            restored.data[1]["dict_manager"]
+                .downcast_ref::<Rc<RefCell<DictManager>>>()
+                .map(|dict_manager| dict_manager.borrow().clone()),
+            Some(dict_manager)
+        );
+        let outer = restored.data[0]["dict_manager"]
+            .downcast_ref::<Rc<RefCell<DictManager>>>()
+            .unwrap();
+        let inner = restored.data[1]["dict_manager"]
+            .downcast_ref::<Rc<RefCell<DictManager>>>()
+            .unwrap();
+        assert!(Rc::ptr_eq(outer, inner));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn exec_scopes_snapshot_missing_dict_manager() {
+        let snapshot = ExecutionScopesSnapshot {
+            scopes: vec![HashMap::from([(
+                "dict_manager".to_string(),
+                ScopeValue::DictManager(1),
+            )])],
+            dict_managers: vec![vec![]],
+            skipped_variables: vec![],
+        };
+        assert_matches!(
+            snapshot.restore(),
+            Err(RunnerError::SnapshotMissingDictManager(1))
+        );
+    }
+}
@@ -40,3 +40,5 @@ pub mod trace_entry {
         Ok(relocation_table[segment_index] + value.offset)
     }
 }
+
+pub mod trace_sink;
@@ -0,0 +1,251 @@
+//! Trace sinks, which take the trace entries of a run as it goes, so that the whole trace doesn't
+//! need to be kept in memory during long executions.
+//!
+//! A sink is set with [`VirtualMachine::set_trace_sink`](crate::vm::vm_core::VirtualMachine::set_trace_sink),
+//! after which the VM hands its trace entries over to it every [`TRACE_SINK_CHUNK_SIZE`] steps.
+//! The relocated trace is then written in a streaming pass by
+//! [`CairoRunner::write_relocated_trace`](crate::vm::runners::cairo_runner::CairoRunner::write_relocated_trace).
+
+use bincode::enc::write::Writer;
+
+use super::trace_entry::TraceEntry;
+use crate::vm::errors::trace_errors::TraceError;
+
+#[cfg(feature = "std")]
+use {
+    super::trace_entry::relocate_trace_register,
+    crate::stdlib::prelude::*,
+    std::{
+        fs::{File, OpenOptions},
+        io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// Number of entries the VM keeps in its trace before handing them over to the sink
+pub const TRACE_SINK_CHUNK_SIZE: usize = 1 << 16;
+
+pub trait TraceSink: Send + Sync {
+    /// Stores trace entries, which are given in execution order and haven't been relocated
+    fn write_entries(&mut self, entries: &[TraceEntry]) -> Result<(), TraceError>;
+
+    /// Relocates the entries stored so far and writes them to `dest`, encoded as by
+    /// [`write_encoded_trace`](crate::cairo_run::write_encoded_trace)
+    fn write_relocated(
+        &mut self,
+        relocation_table: &[usize],
+        dest: &mut dyn Writer,
+    ) -> Result<(), TraceError>;
+}
+
+/// Trace sink spilling the entries into a file, keyed by their segment-local pc
+///
+/// Each entry takes 32 bytes: the pc's segment index and offset, ap and fp, as little endian 64 bit integers.
+#[cfg(feature = "std")]
+pub struct FileTraceSink {
+    file: BufWriter<File>,
+    // Path of the file, if it was created by the sink and should be removed along with it
+    temp_path: Option<PathBuf>,
+}
+
+#[cfg(feature = "std")]
+impl FileTraceSink {
+    /// Spills the entries into a new file in the temporary directory, removed when the sink is dropped
+    pub fn new() -> io::Result<Self> {
+        static SINK_COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "cairo-vm-trace-{}-{}",
+            std::process::id(),
+            SINK_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(Self {
+            file: BufWriter::new(file),
+            temp_path: Some(path),
+        })
+    }
+
+    /// Spills the entries into the given file, which must be empty and opened for both reading and writing
+    pub fn from_file(file: File) -> Self {
+        Self {
+            file: BufWriter::new(file),
+            temp_path: None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn sink_failure(err: impl ToString) -> TraceError {
+    TraceError::SinkFailure(err.to_string().into_boxed_str())
+}
+
+#[cfg(feature = "std")]
+impl TraceSink for FileTraceSink {
+    fn write_entries(&mut self, entries: &[TraceEntry]) -> Result<(), TraceError> {
+        entries
+            .iter()
+            .try_for_each(|entry| {
+                self.file
+                    .write_all(&(entry.pc.segment_index as i64).to_le_bytes())?;
+                self.file
+                    .write_all(&(entry.pc.offset as u64).to_le_bytes())?;
+                self.file.write_all(&(entry.ap as u64).to_le_bytes())?;
+                self.file.write_all(&(entry.fp as u64).to_le_bytes())
+            })
+            .map_err(sink_failure)
+    }
+
+    fn write_relocated(
+        &mut self,
+        relocation_table: &[usize],
+        dest: &mut dyn Writer,
+    ) -> Result<(), TraceError> {
+        let segment_1_base = *relocation_table
+            .get(1)
+            .ok_or(TraceError::NoRelocationFound)?;
+        self.file.flush().map_err(sink_failure)?;
+        let file = self.file.get_mut();
+        file.seek(SeekFrom::Start(0)).map_err(sink_failure)?;
+        let mut reader = BufReader::new(&*file);
+        let mut entry = [0; 32];
+        loop {
+            match reader.read_exact(&mut entry) {
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                res => res.map_err(sink_failure)?,
+            }
+            let field = |i: usize| {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(&entry[i * 8..(i + 1) * 8]);
+                u64::from_le_bytes(bytes)
+            };
+            let pc = (field(0) as i64 as isize, field(1) as usize).into();
+            let pc = relocate_trace_register(pc, relocation_table)?;
+            for value in [
+                field(2) as usize + segment_1_base,
+                field(3) as usize + segment_1_base,
+                pc,
+            ] {
+                dest.write(&(value as u64).to_le_bytes())
+                    .map_err(sink_failure)?;
+            }
+        }
+        drop(reader);
+        // Further entries are appended after the current ones
+        file.seek(SeekFrom::End(0)).map_err(sink_failure)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for FileTraceSink {
+    fn drop(&mut self) {
+        if let Some(path) = &self.temp_path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        cairo_run::write_encoded_trace,
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::{layout_name::LayoutName, program::Program},
+        vm::runners::cairo_runner::CairoRunner,
+    };
+
+    struct VecWriter(Vec<u8>);
+
+    impl Writer for VecWriter {
+        fn write(&mut self, bytes: &[u8]) -> Result<(), bincode::error::EncodeError> {
+            self.0.extend_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    fn run(stream_trace: bool) -> CairoRunner {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/manually_compiled/valid_program_b.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut runner = CairoRunner::new(&program, LayoutName::all_cairo, false, true).unwrap();
+        if stream_trace {
+            runner
+                .vm
+                .set_trace_sink(Box::new(FileTraceSink::new().unwrap()))
+                .unwrap();
+        }
+        let end = runner.initialize(false).unwrap();
+        runner.run_until_pc(end, &mut hint_processor).unwrap();
+        runner.end_run(false, false, &mut hint_processor).unwrap();
+        runner.relocate(false).unwrap();
+        runner
+    }
+
+    #[test]
+    fn streamed_trace_matches_relocated_trace() {
+        let runner = run(false);
+        let mut expected = VecWriter(Vec::new());
+        write_encoded_trace(runner.relocated_trace.as_ref().unwrap(), &mut expected).unwrap();
+
+        let mut runner = run(true);
+        assert_eq!(runner.relocated_trace, None);
+        assert_eq!(runner.vm.trace.as_ref().map(Vec::len), Some(0));
+        let mut streamed = VecWriter(Vec::new());
+        runner.write_relocated_trace(&mut streamed).unwrap();
+        assert!(!expected.0.is_empty());
+        assert_eq!(streamed.0, expected.0);
+    }
+
+    #[test]
+    fn file_sink_appends_after_relocation() {
+        let entry = |pc: usize, ap: usize| TraceEntry {
+            pc: (0, pc).into(),
+            ap,
+            fp: 1,
+        };
+        let relocation_table = [1, 10];
+        let mut sink = FileTraceSink::new().unwrap();
+        sink.write_entries(&[entry(0, 2), entry(2, 3)]).unwrap();
+        let mut first = VecWriter(Vec::new());
+        sink.write_relocated(&relocation_table, &mut first).unwrap();
+        assert_eq!(first.0.len(), 2 * 24);
+
+        sink.write_entries(&[entry(3, 4)]).unwrap();
+        let mut second = VecWriter(Vec::new());
+        sink.write_relocated(&relocation_table, &mut second)
+            .unwrap();
+        assert_eq!(second.0[..48], first.0);
+        let last: Vec<_> = second.0[48..]
+            .chunks(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(last, vec![14, 11, 4]);
+    }
+
+    #[test]
+    fn file_sink_removes_temp_file() {
+        let sink = FileTraceSink::new().unwrap();
+        let path = sink.temp_path.clone().unwrap();
+        assert!(path.exists());
+        drop(sink);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn set_trace_sink_without_trace() {
+        let mut vm = crate::vm::vm_core::VirtualMachine::new(false);
+        assert_eq!(
+            vm.set_trace_sink(Box::new(FileTraceSink::new().unwrap())),
+            Err(TraceError::TraceNotEnabled)
+        );
+    }
+}
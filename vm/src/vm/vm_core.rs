@@ -18,12 +18,15 @@ use crate::{
         decoding::decoder::decode_instruction,
         errors::{
             exec_scope_errors::ExecScopeError, memory_errors::MemoryError,
-            vm_errors::VirtualMachineError,
+            trace_errors::TraceError, vm_errors::VirtualMachineError,
         },
         runners::builtin_runner::{
             BuiltinRunner, OutputBuiltinRunner, RangeCheckBuiltinRunner, SignatureBuiltinRunner,
         },
-        trace::trace_entry::TraceEntry,
+        trace::{
+            trace_entry::TraceEntry,
+            trace_sink::{TraceSink, TRACE_SINK_CHUNK_SIZE},
+        },
         vm_memory::memory_segments::MemorySegmentManager,
     },
 };
@@ -37,6 +40,8 @@ use num_traits::{ToPrimitive, Zero};
 use super::errors::runner_errors::RunnerError;
 use super::runners::builtin_runner::{ModBuiltinRunner, RC_N_PARTS_STANDARD};
 use super::runners::cairo_pie::CairoPie;
+use super::runners::snapshot::{SegmentsSnapshot, VirtualMachineSnapshot};
+use super::vm_memory::memory::AddressSet;
 
 const MAX_TRACEBACK_ENTRIES: u32 = 20;
 
@@ -80,6 +85,35 @@ impl DeducedOperands {
     }
 }
 
+/// Position in the vm's history, an instruction's effects are the changes made after its start mark
+#[derive(Debug, Clone, Copy)]
+struct JournalMark {
+    memory_journal_len: usize,
+    n_segments: usize,
+    n_temp_segments: usize,
+}
+
+/// State of the vm before executing an instruction
+#[derive(Debug)]
+struct JournalStep {
+    pc: Relocatable,
+    ap: usize,
+    fp: usize,
+    current_step: usize,
+    rc_limits: Option<(isize, isize)>,
+    skip_instruction_execution: bool,
+    trace_len: Option<usize>,
+    start: JournalMark,
+}
+
+/// Record of the executed instructions, used to step back
+#[derive(Debug)]
+struct Journal {
+    steps: Vec<JournalStep>,
+    // Changes made after the last instruction (ie: by hints) belong to the next one
+    next_step_start: JournalMark,
+}
+
 pub struct VirtualMachine {
     pub(crate) run_context: RunContext,
     pub builtin_runners: Vec<BuiltinRunner>,
@@ -90,9 +124,15 @@ pub struct VirtualMachine {
     skip_instruction_execution: bool,
     run_finished: bool,
     instruction_cache: Vec<Option<Instruction>>,
-    #[cfg(feature = "test_utils")]
+    #[cfg(feature = "hooks")]
     pub(crate) hooks: crate::vm::hooks::Hooks,
     pub(crate) relocation_table: Option<Vec<usize>>,
+    journal: Option<Journal>,
+    pub(crate) trace_sink: Option<Box<dyn TraceSink>>,
+    #[cfg(feature = "profiler")]
+    pub(crate) profiler: Option<crate::vm::profiler::Profiler>,
+    #[cfg(feature = "profiler")]
+    pub(crate) hint_profiler: Option<crate::vm::hint_profiler::HintProfiler>,
 }
 
 impl VirtualMachine {
@@ -119,9 +159,15 @@ impl VirtualMachine {
             rc_limits: None,
             run_finished: false,
             instruction_cache: Vec::new(),
-            #[cfg(feature = "test_utils")]
+            #[cfg(feature = "hooks")]
             hooks: Default::default(),
             relocation_table: None,
+            journal: None,
+            trace_sink: None,
+            #[cfg(feature = "profiler")]
+            profiler: None,
+            #[cfg(feature = "profiler")]
+            hint_profiler: None,
         }
     }
 
@@ -413,6 +459,12 @@ impl VirtualMachine {
                 ap: self.run_context.ap,
                 fp: self.run_context.fp,
             });
+            if let Some(sink) = &mut self.trace_sink {
+                if trace.len() >= TRACE_SINK_CHUNK_SIZE {
+                    sink.write_entries(trace)?;
+                    trace.clear();
+                }
+            }
         }
 
         // Update range check limits
@@ -438,9 +490,17 @@ impl VirtualMachine {
             .memory
             .mark_as_accessed(operands_addresses.op1_addr);
 
+        #[cfg(feature = "profiler")]
+        let (pc, fp) = (self.run_context.pc, self.run_context.fp);
         self.update_registers(instruction, operands)?;
         self.current_step += 1;
 
+        #[cfg(feature = "profiler")]
+        if let Some(mut profiler) = self.profiler.take() {
+            profiler.record_step(self, pc, fp, &instruction.opcode);
+            self.profiler = Some(profiler);
+        }
+
         Ok(())
     }
 
@@ -463,9 +523,22 @@ impl VirtualMachine {
         constants: &HashMap<String, Felt252>,
     ) -> Result<(), VirtualMachineError> {
         for (hint_index, hint_data) in hint_datas.iter().enumerate() {
-            hint_processor
-                .execute_hint(self, exec_scopes, hint_data, constants)
-                .map_err(|err| VirtualMachineError::Hint(Box::new((hint_index, err))))?
+            if hint_index != 0 {
+                if let Some(limit) = hint_processor.interrupted() {
+                    return Err(VirtualMachineError::RunInterrupted(limit));
+                }
+            }
+            #[cfg(feature = "hooks")]
+            self.execute_pre_hint(exec_scopes, hint_data.as_ref())?;
+            #[cfg(feature = "profiler")]
+            let result = self.profile_hint(hint_data.as_ref(), |vm| {
+                hint_processor.execute_hint(vm, exec_scopes, hint_data, constants)
+            })?;
+            #[cfg(not(feature = "profiler"))]
+            let result = hint_processor.execute_hint(self, exec_scopes, hint_data, constants);
+            result.map_err(|err| VirtualMachineError::Hint(Box::new((hint_index, err))))?;
+            #[cfg(feature = "hooks")]
+            self.execute_post_hint(exec_scopes, hint_data.as_ref())?;
         }
         Ok(())
     }
@@ -485,14 +558,25 @@ impl VirtualMachine {
             let s = *s;
             // Execute each hint for the given range
             for idx in s..(s + l.get()) {
-                let hint_extension = hint_processor
-                    .execute_hint_extensive(
-                        self,
-                        exec_scopes,
-                        hint_datas.get(idx).ok_or(VirtualMachineError::Unexpected)?,
-                        constants,
-                    )
-                    .map_err(|err| VirtualMachineError::Hint(Box::new((idx - s, err))))?;
+                if idx != s {
+                    if let Some(limit) = hint_processor.interrupted() {
+                        return Err(VirtualMachineError::RunInterrupted(limit));
+                    }
+                }
+                let hint_data = hint_datas.get(idx).ok_or(VirtualMachineError::Unexpected)?;
+                #[cfg(feature = "hooks")]
+                self.execute_pre_hint(exec_scopes, hint_data.as_ref())?;
+                #[cfg(feature = "profiler")]
+                let result = self.profile_hint(hint_data.as_ref(), |vm| {
+                    hint_processor.execute_hint_extensive(vm, exec_scopes, hint_data, constants)
+                })?;
+                #[cfg(not(feature = "profiler"))]
+                let result =
+                    hint_processor.execute_hint_extensive(self, exec_scopes, hint_data, constants);
+                let hint_extension =
+                    result.map_err(|err| VirtualMachineError::Hint(Box::new((idx - s, err))))?;
+                #[cfg(feature = "hooks")]
+                self.execute_post_hint(exec_scopes, hint_datas[idx].as_ref())?;
                 // Update the hint_ranges & hint_datas with the hints added by the executed hint
                 for (hint_pc, hints) in hint_extension {
                     if let Ok(len) = NonZeroUsize::try_from(hints.len()) {
@@ -505,7 +589,56 @@ impl VirtualMachine {
         Ok(())
     }
 
+    /// Runs `execute`, recording it in the hint profiler if enabled
+    #[cfg(feature = "profiler")]
+    fn profile_hint<T>(
+        &mut self,
+        hint_data: &dyn Any,
+        execute: impl FnOnce(&mut Self) -> T,
+    ) -> Result<T, VirtualMachineError> {
+        let Some(mut hint_profiler) = self.hint_profiler.take() else {
+            return Ok(execute(self));
+        };
+        let start = match hint_profiler.start_hint(self, hint_data) {
+            Ok(start) => start,
+            Err(error) => {
+                self.hint_profiler = Some(hint_profiler);
+                return Err(error);
+            }
+        };
+        let result = execute(self);
+        hint_profiler.end_hint(start, hint_data);
+        self.hint_profiler = Some(hint_profiler);
+        Ok(result)
+    }
+
     pub fn step_instruction(&mut self) -> Result<(), VirtualMachineError> {
+        let Some(start) = self.journal.as_ref().map(|journal| journal.next_step_start) else {
+            return self.decode_and_run_instruction();
+        };
+        let step = JournalStep {
+            pc: self.run_context.pc,
+            ap: self.run_context.ap,
+            fp: self.run_context.fp,
+            current_step: self.current_step,
+            rc_limits: self.rc_limits,
+            skip_instruction_execution: self.skip_instruction_execution,
+            trace_len: self.trace.as_ref().map(Vec::len),
+            start,
+        };
+        let res = self.decode_and_run_instruction();
+        let next_step_start = self.journal_mark();
+        if let Some(journal) = &mut self.journal {
+            // Failed instructions are not recorded, their changes will be undone along with the previous step
+            if res.is_ok() {
+                journal.steps.push(step);
+                journal.next_step_start = next_step_start;
+            }
+        }
+        res
+    }
+
+    fn decode_and_run_instruction(&mut self) -> Result<(), VirtualMachineError> {
         if self.run_context.pc.segment_index == 0 {
             // Run instructions from program segment, using instruction cache
             let pc = self.run_context.pc.offset;
@@ -562,10 +695,10 @@ impl VirtualMachine {
             constants,
         )?;
 
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         self.execute_pre_step_instruction(hint_processor, exec_scopes, hint_datas, constants)?;
         self.step_instruction()?;
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         self.execute_post_step_instruction(hint_processor, exec_scopes, hint_datas, constants)?;
 
         Ok(())
@@ -756,9 +889,9 @@ impl VirtualMachine {
         Ok(())
     }
 
-    // Returns the values (fp, pc) corresponding to each call instruction in the traceback.
-    // Returns the most recent call last.
-    pub(crate) fn get_traceback_entries(&self) -> Vec<(Relocatable, Relocatable)> {
+    /// Returns the values (fp, pc) corresponding to each call instruction in the traceback,
+    /// obtained by walking the fp chain. Returns the most recent call last.
+    pub fn get_traceback_entries(&self) -> Vec<(Relocatable, Relocatable)> {
         let mut entries = Vec::<(Relocatable, Relocatable)>::new();
         let mut fp = Relocatable::from((1, self.run_context.fp));
         // Fetch the fp and pc traceback entries
@@ -830,6 +963,194 @@ impl VirtualMachine {
         self.run_context.get_pc()
     }
 
+    /// Returns the number of instructions executed so far
+    pub fn get_current_step(&self) -> usize {
+        self.current_step
+    }
+
+    /// Captures the vm's execution state, see [`CairoRunner::snapshot`](crate::vm::runners::cairo_runner::CairoRunner::snapshot)
+    pub fn snapshot(&self) -> VirtualMachineSnapshot {
+        VirtualMachineSnapshot {
+            pc: self.run_context.pc,
+            ap: self.run_context.ap,
+            fp: self.run_context.fp,
+            segments: SegmentsSnapshot::new(&self.segments),
+            builtin_runners: self
+                .builtin_runners
+                .iter()
+                .map(BuiltinRunner::snapshot)
+                .collect(),
+            trace: self.trace.clone(),
+            current_step: self.current_step,
+            rc_limits: self.rc_limits,
+            skip_instruction_execution: self.skip_instruction_execution,
+            run_finished: self.run_finished,
+        }
+    }
+
+    /// Rewinds the vm to a previously captured execution state.
+    /// The vm's builtins must match the ones the snapshot was taken with, as their validation rules
+    /// are kept and re-applied to the restored memory
+    pub fn restore_snapshot(
+        &mut self,
+        snapshot: &VirtualMachineSnapshot,
+    ) -> Result<(), RunnerError> {
+        if snapshot.builtin_runners.len() != self.builtin_runners.len() {
+            return Err(RunnerError::SnapshotBuiltinCountMismatch(
+                snapshot.builtin_runners.len(),
+                self.builtin_runners.len(),
+            ));
+        }
+        // Check every builtin before restoring any of them, so that a mismatch leaves the vm untouched
+        for (builtin, builtin_snapshot) in self
+            .builtin_runners
+            .iter()
+            .zip(snapshot.builtin_runners.iter())
+        {
+            builtin.check_snapshot(builtin_snapshot)?;
+        }
+        for (builtin, builtin_snapshot) in self
+            .builtin_runners
+            .iter_mut()
+            .zip(snapshot.builtin_runners.iter())
+        {
+            builtin.restore_snapshot(builtin_snapshot)?;
+        }
+        snapshot.segments.restore(&mut self.segments);
+        self.segments.memory.validated_addresses = AddressSet::new();
+        self.segments.memory.validate_existing_memory()?;
+        self.run_context.pc = snapshot.pc;
+        self.run_context.ap = snapshot.ap;
+        self.run_context.fp = snapshot.fp;
+        self.trace.clone_from(&snapshot.trace);
+        self.current_step = snapshot.current_step;
+        self.rc_limits = snapshot.rc_limits;
+        self.skip_instruction_execution = snapshot.skip_instruction_execution;
+        self.run_finished = snapshot.run_finished;
+        self.relocation_table = None;
+        if self.journal.is_some() {
+            // Recorded instructions don't apply to the restored state
+            self.enable_journal();
+        }
+        Ok(())
+    }
+
+    /// Starts attributing each executed step to the current call stack, see [`Profiler`](crate::vm::profiler::Profiler)
+    #[cfg(feature = "profiler")]
+    pub fn enable_profiler(&mut self) {
+        self.profiler = Some(crate::vm::profiler::Profiler::new());
+    }
+
+    /// Returns the profiler, if enabled via [`VirtualMachine::enable_profiler`]
+    #[cfg(feature = "profiler")]
+    pub fn get_profiler(&self) -> Option<&crate::vm::profiler::Profiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Records the executions of each hint, see [`HintProfiler`](crate::vm::hint_profiler::HintProfiler)
+    #[cfg(feature = "profiler")]
+    pub fn set_hint_profiler(&mut self, hint_profiler: crate::vm::hint_profiler::HintProfiler) {
+        self.hint_profiler = Some(hint_profiler);
+    }
+
+    /// Returns the hint profiler, if set via [`VirtualMachine::set_hint_profiler`]
+    #[cfg(feature = "profiler")]
+    pub fn get_hint_profiler(&self) -> Option<&crate::vm::hint_profiler::HintProfiler> {
+        self.hint_profiler.as_ref()
+    }
+
+    /// Hands the trace over to `sink` every [`TRACE_SINK_CHUNK_SIZE`] steps instead of keeping it all in memory.
+    /// The trace must be enabled. Entries handed over to the sink are no longer part of the VM's trace,
+    /// so they can't be undone via [`VirtualMachine::step_back`] nor restored from a snapshot
+    pub fn set_trace_sink(&mut self, sink: Box<dyn TraceSink>) -> Result<(), TraceError> {
+        if self.trace.is_none() {
+            return Err(TraceError::TraceNotEnabled);
+        }
+        self.trace_sink = Some(sink);
+        Ok(())
+    }
+
+    /// Hands the entries still in the trace over to the trace sink, if there is one
+    pub fn flush_trace_sink(&mut self) -> Result<(), TraceError> {
+        if let (Some(sink), Some(trace)) = (&mut self.trace_sink, &mut self.trace) {
+            sink.write_entries(trace)?;
+            trace.clear();
+        }
+        Ok(())
+    }
+
+    /// Starts recording the effects of each executed instruction, so that they can be undone via [`VirtualMachine::step_back`].
+    /// Instructions executed before the journal is enabled can't be undone
+    pub fn enable_journal(&mut self) {
+        self.segments.memory.journal = Some(Vec::new());
+        self.journal = Some(Journal {
+            steps: Vec::new(),
+            next_step_start: self.journal_mark(),
+        });
+    }
+
+    /// Stops recording the effects of executed instructions and drops the recorded ones
+    pub fn disable_journal(&mut self) {
+        self.segments.memory.journal = None;
+        self.journal = None;
+    }
+
+    /// Returns the number of instructions that can be undone via [`VirtualMachine::step_back`]
+    pub fn journal_len(&self) -> Option<usize> {
+        self.journal.as_ref().map(|journal| journal.steps.len())
+    }
+
+    fn journal_mark(&self) -> JournalMark {
+        JournalMark {
+            memory_journal_len: self.segments.memory.journal.as_ref().map_or(0, Vec::len),
+            n_segments: self.segments.memory.data.len(),
+            n_temp_segments: self.segments.memory.temp_data.len(),
+        }
+    }
+
+    /// Undoes the last `n` executed instructions, along with the hints executed since then.
+    /// Registers, memory, segments, trace and step count are rewound, while state kept outside of the vm,
+    /// such as execution scopes, is not.
+    /// The journal must have been enabled via [`VirtualMachine::enable_journal`] before executing them
+    pub fn step_back(&mut self, n: usize) -> Result<(), VirtualMachineError> {
+        let journal = self
+            .journal
+            .as_mut()
+            .ok_or(VirtualMachineError::JournalNotEnabled)?;
+        if n > journal.steps.len() {
+            return Err(VirtualMachineError::StepBackOutOfRange(Box::new((
+                n,
+                journal.steps.len(),
+            ))));
+        }
+        let Some(step) = journal.steps.drain(journal.steps.len() - n..).next() else {
+            return Ok(());
+        };
+        journal.next_step_start = step.start;
+        self.segments
+            .memory
+            .undo_journal(step.start.memory_journal_len);
+        self.segments.memory.data.truncate(step.start.n_segments);
+        self.segments
+            .memory
+            .temp_data
+            .truncate(step.start.n_temp_segments);
+        self.run_context.pc = step.pc;
+        self.run_context.ap = step.ap;
+        self.run_context.fp = step.fp;
+        self.current_step = step.current_step;
+        self.rc_limits = step.rc_limits;
+        self.skip_instruction_execution = step.skip_instruction_execution;
+        if let (Some(trace), Some(trace_len)) = (&mut self.trace, step.trace_len) {
+            trace.truncate(trace_len);
+        }
+        self.run_finished = false;
+        for builtin in self.builtin_runners.iter() {
+            builtin.clear_cache();
+        }
+        Ok(())
+    }
+
     ///Gets the integer value corresponding to the Relocatable address
     pub fn get_integer(&self, key: Relocatable) -> Result<Cow<Felt252>, MemoryError> {
         self.segments.memory.get_integer(key)
@@ -1165,7 +1486,7 @@ pub struct VirtualMachineBuilder {
     pub(crate) current_step: usize,
     skip_instruction_execution: bool,
     run_finished: bool,
-    #[cfg(feature = "test_utils")]
+    #[cfg(feature = "hooks")]
     pub(crate) hooks: crate::vm::hooks::Hooks,
 }
 
@@ -1185,7 +1506,7 @@ impl Default for VirtualMachineBuilder {
             skip_instruction_execution: false,
             segments: MemorySegmentManager::new(),
             run_finished: false,
-            #[cfg(feature = "test_utils")]
+            #[cfg(feature = "hooks")]
             hooks: Default::default(),
         }
     }
@@ -1230,14 +1551,15 @@ impl VirtualMachineBuilder {
         self
     }
 
-    #[cfg(feature = "test_utils")]
+    #[cfg(feature = "hooks")]
     pub fn hooks(mut self, hooks: crate::vm::hooks::Hooks) -> VirtualMachineBuilder {
         self.hooks = hooks;
         self
     }
 
     pub fn build(self) -> VirtualMachine {
-        VirtualMachine {
+        #[allow(unused_mut)]
+        let mut vm = VirtualMachine {
             run_context: self.run_context,
             builtin_runners: self.builtin_runners,
             trace: self.trace,
@@ -1247,10 +1569,19 @@ impl VirtualMachineBuilder {
             rc_limits: None,
             run_finished: self.run_finished,
             instruction_cache: Vec::new(),
-            #[cfg(feature = "test_utils")]
-            hooks: self.hooks,
+            #[cfg(feature = "hooks")]
+            hooks: Default::default(),
             relocation_table: None,
-        }
+            journal: None,
+            trace_sink: None,
+            #[cfg(feature = "profiler")]
+            profiler: None,
+            #[cfg(feature = "profiler")]
+            hint_profiler: None,
+        };
+        #[cfg(feature = "hooks")]
+        vm.set_hooks(self.hooks);
+        vm
     }
 }
 
@@ -4375,14 +4706,14 @@ mod tests {
                 fp: 1,
             }]));
 
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         fn before_first_step_hook(
             _vm: &mut VirtualMachine,
             _hint_data: &[Box<dyn Any>],
         ) -> Result<(), VirtualMachineError> {
             Err(VirtualMachineError::Unexpected)
         }
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         let virtual_machine_builder = virtual_machine_builder.hooks(crate::vm::hooks::Hooks::new(
             Some(std::sync::Arc::new(before_first_step_hook)),
             None,
@@ -4416,7 +4747,7 @@ mod tests {
                 fp: 1,
             }])
         );
-        #[cfg(feature = "test_utils")]
+        #[cfg(feature = "hooks")]
         {
             let program = crate::types::program::Program::from_bytes(
                 include_bytes!("../../../cairo_programs/sqrt.json"),
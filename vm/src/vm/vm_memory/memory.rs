@@ -66,6 +66,10 @@ impl MemoryCell {
         self.0[0] & Self::ACCESS_MASK == Self::ACCESS_MASK
     }
 
+    pub fn clear_accessed(&mut self) {
+        self.0[0] &= !Self::ACCESS_MASK;
+    }
+
     pub fn get_value(&self) -> Option<MaybeRelocatable> {
         self.is_some().then(|| (*self).into())
     }
@@ -144,6 +148,30 @@ impl AddressSet {
             self.0[segment].replace(offset, true);
         }
     }
+
+    pub(crate) fn remove(&mut self, addr: &Relocatable) {
+        if addr.segment_index.is_negative() {
+            return;
+        }
+        if let Some(segment) = self.0.get_mut(addr.segment_index as usize) {
+            if addr.offset < segment.len() {
+                segment.set(addr.offset, false);
+            }
+        }
+    }
+}
+
+/// A change made to the [`Memory`], recorded so that it can be undone
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum MemoryJournalEntry {
+    /// A value was written to a previously empty cell
+    Insert(Relocatable),
+    /// A cell was marked as accessed
+    Access(Relocatable),
+    /// Addresses were marked as validated by a validation rule
+    Validate(Vec<Relocatable>),
+    /// A relocation rule was added for the temporary segment with the given key
+    RelocationRule(usize),
 }
 
 #[cfg(test)]
@@ -164,6 +192,10 @@ pub struct Memory {
     pub(crate) relocation_rules: HashMap<usize, Relocatable>,
     pub validated_addresses: AddressSet,
     validation_rules: Vec<Option<ValidationRule>>,
+    // Changes made to the memory, only recorded when the journal is enabled
+    pub(crate) journal: Option<Vec<MemoryJournalEntry>>,
+    #[cfg(feature = "hooks")]
+    pub(crate) insert_hook: Option<crate::vm::hooks::MemoryInsertHookFunc>,
 }
 
 impl Memory {
@@ -174,6 +206,9 @@ impl Memory {
             relocation_rules: HashMap::new(),
             validated_addresses: AddressSet::new(),
             validation_rules: Vec::with_capacity(7),
+            journal: None,
+            #[cfg(feature = "hooks")]
+            insert_hook: None,
         }
     }
 
@@ -213,8 +248,15 @@ impl Memory {
         }
         // At this point there's *something* in there
 
+        #[cfg(feature = "hooks")]
+        let hooked_val = self.insert_hook.is_some().then(|| val.clone());
         match segment[value_offset].get_value() {
-            None => segment[value_offset] = MemoryCell::new(val),
+            None => {
+                segment[value_offset] = MemoryCell::new(val);
+                if let Some(journal) = &mut self.journal {
+                    journal.push(MemoryJournalEntry::Insert(key));
+                }
+            }
             Some(current_cell) => {
                 if current_cell != val {
                     //Existing memory cannot be changed
@@ -226,7 +268,12 @@ impl Memory {
                 }
             }
         };
-        self.validate_memory_cell(key)
+        self.validate_memory_cell(key)?;
+        #[cfg(feature = "hooks")]
+        if let (Some(hook_func), Some(val)) = (&self.insert_hook, hooked_val) {
+            (hook_func)(key, &val);
+        }
+        Ok(())
     }
 
     /// Retrieve a value from memory (either normal or temporary) and apply relocation rules
@@ -339,6 +386,9 @@ impl Memory {
         }
 
         self.relocation_rules.insert(segment_index, dst_ptr);
+        if let Some(journal) = &mut self.journal {
+            journal.push(MemoryJournalEntry::RelocationRule(segment_index));
+        }
         Ok(())
     }
 
@@ -402,8 +452,16 @@ impl Memory {
             .and_then(|x| self.validation_rules.get(x))
         {
             if !self.validated_addresses.contains(&addr) {
-                self.validated_addresses
-                    .extend(rule.0(self, addr)?.as_slice());
+                let validated = rule.0(self, addr)?;
+                if let Some(journal) = &mut self.journal {
+                    let new_addresses: Vec<_> = validated
+                        .iter()
+                        .filter(|addr| !self.validated_addresses.contains(addr))
+                        .copied()
+                        .collect();
+                    journal.push(MemoryJournalEntry::Validate(new_addresses));
+                }
+                self.validated_addresses.extend(validated.as_slice());
             }
         }
         Ok(())
@@ -573,10 +631,63 @@ impl Memory {
         };
         let cell = data.get_mut(i).and_then(|x| x.get_mut(j));
         if let Some(cell) = cell {
+            if let Some(journal) = &mut self.journal {
+                if !cell.is_accessed() {
+                    journal.push(MemoryJournalEntry::Access(addr));
+                }
+            }
             cell.mark_accessed()
         }
     }
 
+    /// Undoes the changes recorded in the journal after its first `len` entries
+    pub(crate) fn undo_journal(&mut self, len: usize) {
+        let Some(mut journal) = self.journal.take() else {
+            return;
+        };
+        for entry in journal.drain(len.min(journal.len())..).rev() {
+            match entry {
+                MemoryJournalEntry::Insert(addr) => {
+                    let (i, j) = from_relocatable_to_indexes(addr);
+                    let data = if addr.segment_index < 0 {
+                        &mut self.temp_data
+                    } else {
+                        &mut self.data
+                    };
+                    if let Some(segment) = data.get_mut(i) {
+                        if let Some(cell) = segment.get_mut(j) {
+                            *cell = MemoryCell::NONE;
+                        }
+                        // Segments only grow through insertions, so trailing gaps were added by them
+                        while segment.last().is_some_and(MemoryCell::is_none) {
+                            segment.pop();
+                        }
+                    }
+                }
+                MemoryJournalEntry::Access(addr) => {
+                    let (i, j) = from_relocatable_to_indexes(addr);
+                    let data = if addr.segment_index < 0 {
+                        &mut self.temp_data
+                    } else {
+                        &mut self.data
+                    };
+                    if let Some(cell) = data.get_mut(i).and_then(|x| x.get_mut(j)) {
+                        cell.clear_accessed();
+                    }
+                }
+                MemoryJournalEntry::Validate(addresses) => {
+                    for addr in addresses.iter() {
+                        self.validated_addresses.remove(addr);
+                    }
+                }
+                MemoryJournalEntry::RelocationRule(segment_index) => {
+                    self.relocation_rules.remove(&segment_index);
+                }
+            }
+        }
+        self.journal = Some(journal);
+    }
+
     pub fn get_amount_of_accessed_addresses_for_segment(
         &self,
         segment_index: usize,
@@ -1739,4 +1850,56 @@ mod memory_tests {
             ])
         )
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn undo_journal_insert_and_access() {
+        let mut memory = memory![((0, 0), 1)];
+        memory.journal = Some(Vec::new());
+        memory.insert((0, 3).into(), 5).unwrap();
+        memory.insert((0, 0).into(), 1).unwrap();
+        memory.mark_as_accessed((0, 0).into());
+        assert_eq!(
+            memory.journal,
+            Some(vec![
+                MemoryJournalEntry::Insert((0, 3).into()),
+                MemoryJournalEntry::Access((0, 0).into()),
+            ])
+        );
+
+        memory.undo_journal(1);
+        assert!(!memory.data[0][0].is_accessed());
+        assert_eq!(memory.data[0].len(), 4);
+
+        memory.undo_journal(0);
+        assert_eq!(memory.data[0].len(), 1);
+        assert_eq!(memory.get(&relocatable!(0, 3)), None);
+        assert_eq!(memory.journal, Some(Vec::new()));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn undo_journal_validation() {
+        let mut builtin = RangeCheckBuiltinRunner::<RC_N_PARTS_STANDARD>::new(Some(8), true);
+        let mut segments = MemorySegmentManager::new();
+        builtin.initialize_segments(&mut segments);
+        builtin.add_validation_rule(&mut segments.memory);
+        segments.memory.journal = Some(Vec::new());
+        segments.memory.insert((0, 0).into(), 45).unwrap();
+        assert!(segments
+            .memory
+            .validated_addresses
+            .contains(&Relocatable::from((0, 0))));
+
+        segments.memory.undo_journal(0);
+        assert!(!segments
+            .memory
+            .validated_addresses
+            .contains(&Relocatable::from((0, 0))));
+        // A different value can now be written and validated
+        assert_matches!(
+            segments.memory.insert((0, 0).into(), Felt252::from(-1)),
+            Err(MemoryError::RangeCheckNumOutOfBounds(_))
+        );
+    }
 }
@@ -29,9 +29,11 @@ pub struct MemorySegmentManager {
     pub public_memory_offsets: HashMap<usize, Vec<(usize, usize)>>,
     // Segment index of the zero segment index, a memory segment filled with zeroes, used exclusively by builtin runners
     // This segment will never have index 0 so we use 0 to represent uninitialized value
-    zero_segment_index: usize,
+    pub(crate) zero_segment_index: usize,
     // Segment size of the zero segment index
-    zero_segment_size: usize,
+    pub(crate) zero_segment_size: usize,
+    #[cfg(feature = "hooks")]
+    pub(crate) segment_creation_hook: Option<crate::vm::hooks::SegmentCreationHookFunc>,
 }
 
 impl MemorySegmentManager {
@@ -48,20 +50,30 @@ impl MemorySegmentManager {
     ///Adds a new segment and returns its starting location as a Relocatable value. Its segment index will always be positive.
     pub fn add(&mut self) -> Relocatable {
         self.memory.data.push(Vec::new());
-        Relocatable {
+        let base = Relocatable {
             segment_index: (self.memory.data.len() - 1) as isize,
             offset: 0,
+        };
+        #[cfg(feature = "hooks")]
+        if let Some(hook_func) = &self.segment_creation_hook {
+            (hook_func)(base);
         }
+        base
     }
 
     /// Adds a new temporary segment and returns its starting location as a Relocatable value. Its segment index will always be negative.
     pub fn add_temporary_segment(&mut self) -> Relocatable {
         self.memory.temp_data.push(Vec::new());
-        Relocatable {
+        let base = Relocatable {
             // We dont substract 1 as we need to take into account the index shift (temporary memory begins from -1 instead of 0)
             segment_index: -((self.memory.temp_data.len()) as isize),
             offset: 0,
+        };
+        #[cfg(feature = "hooks")]
+        if let Some(hook_func) = &self.segment_creation_hook {
+            (hook_func)(base);
         }
+        base
     }
 
     ///Writes data into the memory from address ptr and returns the first address after the data.
@@ -86,6 +98,8 @@ impl MemorySegmentManager {
             memory: Memory::new(),
             zero_segment_index: 0,
             zero_segment_size: 0,
+            #[cfg(feature = "hooks")]
+            segment_creation_hook: None,
         }
     }
 